@@ -0,0 +1,28 @@
+//! Measure the throughput of a query on a document with both engines.
+//!
+//! Usage: `cargo run --release --features bench --example bench -- <QUERY> <FILE>`
+//!
+//! Rebuild with different features, e.g. `--no-default-features --features bench,simd,tail-skip`,
+//! to compare configurations on the same inputs.
+use rsonpath_lib::bench::{Benchmark, Report};
+use rsonpath_lib::engine::{main::MainEngine, recursive::RecursiveEngine};
+use rsonpath_lib::query::JsonPathQuery;
+use std::error::Error;
+use std::{env, fs};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let (Some(query_string), Some(path)) = (args.next(), args.next()) else {
+        return Err("usage: bench <QUERY> <FILE>".into());
+    };
+    let query = JsonPathQuery::parse(&query_string)?;
+    let document = fs::read_to_string(path)?;
+    let benchmark = Benchmark::new(&document);
+    let mut report = Report::new();
+
+    report.push(benchmark.measure::<MainEngine>("main", &query)?);
+    report.push(benchmark.measure::<RecursiveEngine>("recursive", &query)?);
+
+    println!("{report}");
+    Ok(())
+}
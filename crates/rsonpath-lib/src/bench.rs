@@ -0,0 +1,402 @@
+//! Throughput measurements of queries on custom inputs, for choosing the build configuration
+//! best suited for a workload.
+//!
+//! The optimizations enabled by the `simd`, `head-skip`, `tail-skip`, and `unique-labels`
+//! features are chosen at compile time, so a single build can only measure its own
+//! [`Configuration`]. To compare configurations, build the harness once per configuration
+//! and run each build on the same inputs, e.g. with the `bench` example of this crate:
+//!
+//! ```text
+//! cargo run --release --features bench --example bench -- '$..a.b' document.json
+//! cargo run --release --no-default-features --features bench,simd,tail-skip --example bench -- '$..a.b' document.json
+//! ```
+//!
+//! The module is only compiled with the `bench` feature, so it does not take part in regular builds.
+//!
+//! A [`Benchmark`] runs the query a few times to warm up the caches, then takes a number of
+//! samples, each repeating the query until a minimal duration passes to make up for the resolution
+//! of the clock. The [`Measurement`] reports the median and fastest time of a single run,
+//! and the [`Report`] prints measurements of several engines as a table.
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::bench::{Benchmark, Report};
+//! use rsonpath_lib::engine::{main::MainEngine, recursive::RecursiveEngine};
+//! use rsonpath_lib::query::JsonPathQuery;
+//! # use std::error::Error;
+//! use std::time::Duration;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let query = JsonPathQuery::parse("$.a[*]")?;
+//! let benchmark = Benchmark::new(r#"{"a": [1, {"b": 2}], "b": [3]}"#)
+//!     .with_min_sample_duration(Duration::from_micros(100));
+//! let mut report = Report::new();
+//!
+//! report.push(benchmark.measure::<MainEngine>("main", &query)?);
+//! report.push(benchmark.measure::<RecursiveEngine>("recursive", &query)?);
+//!
+//! assert!(report.measurements().iter().all(|measurement| measurement.count() == 2));
+//! println!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+use crate::engine::error::EngineError;
+use crate::engine::{Compiler, Engine, Input};
+use crate::error::QueryError;
+use crate::query::JsonPathQuery;
+use crate::result::CountResult;
+use std::fmt::{self, Display};
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+const DEFAULT_WARM_UP_RUNS: usize = 3;
+const DEFAULT_SAMPLES: usize = 10;
+const DEFAULT_MIN_SAMPLE_DURATION: Duration = Duration::from_millis(10);
+
+/// Optimization features the crate was compiled with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Configuration {
+    simd: bool,
+    head_skip: bool,
+    tail_skip: bool,
+    unique_labels: bool,
+}
+
+impl Configuration {
+    /// The configuration of the current build.
+    #[must_use]
+    #[inline]
+    pub fn current() -> Self {
+        Self {
+            simd: cfg!(feature = "simd"),
+            head_skip: cfg!(feature = "head-skip"),
+            tail_skip: cfg!(feature = "tail-skip"),
+            unique_labels: cfg!(feature = "unique-labels"),
+        }
+    }
+
+    /// Whether the `simd` feature is enabled.
+    #[must_use]
+    #[inline(always)]
+    pub fn simd(&self) -> bool {
+        self.simd
+    }
+
+    /// Whether the `head-skip` feature is enabled.
+    #[must_use]
+    #[inline(always)]
+    pub fn head_skip(&self) -> bool {
+        self.head_skip
+    }
+
+    /// Whether the `tail-skip` feature is enabled.
+    #[must_use]
+    #[inline(always)]
+    pub fn tail_skip(&self) -> bool {
+        self.tail_skip
+    }
+
+    /// Whether the `unique-labels` feature is enabled.
+    #[must_use]
+    #[inline(always)]
+    pub fn unique_labels(&self) -> bool {
+        self.unique_labels
+    }
+}
+
+impl Display for Configuration {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let features: Vec<_> = [
+            (self.simd, "simd"),
+            (self.head_skip, "head-skip"),
+            (self.tail_skip, "tail-skip"),
+            (self.unique_labels, "unique-labels"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect();
+
+        if features.is_empty() {
+            write!(f, "no optimizations")
+        } else {
+            write!(f, "{}", features.join(", "))
+        }
+    }
+}
+
+/// Harness timing runs of queries on a single document.
+pub struct Benchmark {
+    input: Input,
+    len: usize,
+    warm_up_runs: usize,
+    samples: usize,
+    min_sample_duration: Duration,
+}
+
+impl Benchmark {
+    /// Create a benchmark on the `document` with 3 warm-up runs and 10 samples
+    /// of at least 10 milliseconds each.
+    #[must_use]
+    #[inline]
+    pub fn new(document: &str) -> Self {
+        let mut contents = document.to_owned();
+
+        Self {
+            input: Input::new(&mut contents),
+            len: document.len(),
+            warm_up_runs: DEFAULT_WARM_UP_RUNS,
+            samples: DEFAULT_SAMPLES,
+            min_sample_duration: DEFAULT_MIN_SAMPLE_DURATION,
+        }
+    }
+
+    /// Set the number of untimed runs before the samples are taken.
+    ///
+    /// The query is always run at least once before sampling, even if this is zero.
+    #[must_use]
+    #[inline]
+    pub fn with_warm_up_runs(mut self, runs: usize) -> Self {
+        self.warm_up_runs = runs;
+        self
+    }
+
+    /// Set the number of samples taken.
+    #[must_use]
+    #[inline]
+    pub fn with_samples(mut self, samples: NonZeroUsize) -> Self {
+        self.samples = samples.get();
+        self
+    }
+
+    /// Set the minimal duration of every sample. The query is run repeatedly within a sample
+    /// until this much time passes, as estimated from the warm-up runs.
+    #[must_use]
+    #[inline]
+    pub fn with_min_sample_duration(mut self, duration: Duration) -> Self {
+        self.min_sample_duration = duration;
+        self
+    }
+
+    /// The input the queries are run on.
+    #[must_use]
+    #[inline(always)]
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    /// Compile the `query` with the engine `C` and measure runs of the engine counting its matches.
+    ///
+    /// # Errors
+    /// [`QueryError::Compiler`] if the engine cannot handle the query,
+    /// [`QueryError::Engine`] if any run fails.
+    #[inline]
+    pub fn measure<C: Compiler>(
+        &self,
+        name: &str,
+        query: &JsonPathQuery,
+    ) -> Result<Measurement, QueryError> {
+        let engine = C::compile_query(query)?;
+        let measurement =
+            self.measure_with(name, |input| Ok(engine.run::<CountResult>(input)?.get()))?;
+
+        Ok(measurement)
+    }
+
+    /// Measure runs of any function on the input returning the number of matches,
+    /// e.g. a run of an engine with non-default settings.
+    ///
+    /// # Errors
+    /// The first error returned by the `run`.
+    #[inline]
+    pub fn measure_with<F>(&self, name: &str, mut run: F) -> Result<Measurement, EngineError>
+    where
+        F: FnMut(&Input) -> Result<usize, EngineError>,
+    {
+        let warm_up_runs = self.warm_up_runs.max(1);
+        let start = Instant::now();
+        let mut count = 0;
+        for _ in 0..warm_up_runs {
+            count = run(&self.input)?;
+        }
+        let run_nanos = (start.elapsed().as_nanos() / warm_up_runs as u128).max(1);
+        let iterations = (self.min_sample_duration.as_nanos() / run_nanos).max(1);
+        let iterations = usize::try_from(iterations).unwrap_or(usize::MAX);
+
+        let mut samples = Vec::with_capacity(self.samples);
+        for _ in 0..self.samples {
+            let start = Instant::now();
+            for _ in 0..iterations {
+                run(&self.input)?;
+            }
+            samples.push(Duration::from_secs_f64(
+                start.elapsed().as_secs_f64() / iterations as f64,
+            ));
+        }
+        samples.sort_unstable();
+
+        Ok(Measurement {
+            name: name.to_owned(),
+            configuration: Configuration::current(),
+            bytes: self.len,
+            count,
+            iterations,
+            samples,
+        })
+    }
+}
+
+/// Times of runs of a single engine on a single document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    name: String,
+    configuration: Configuration,
+    bytes: usize,
+    count: usize,
+    iterations: usize,
+    samples: Vec<Duration>,
+}
+
+impl Measurement {
+    /// Name given to the measured engine.
+    #[must_use]
+    #[inline(always)]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Configuration of the build that took the measurement.
+    #[must_use]
+    #[inline(always)]
+    pub fn configuration(&self) -> Configuration {
+        self.configuration
+    }
+
+    /// Length of the document in bytes.
+    #[must_use]
+    #[inline(always)]
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Number of matches found by a run.
+    #[must_use]
+    #[inline(always)]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Number of runs within every sample.
+    #[must_use]
+    #[inline(always)]
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Average time of a single run in every sample, from the fastest to the slowest sample.
+    #[must_use]
+    #[inline(always)]
+    pub fn samples(&self) -> &[Duration] {
+        &self.samples
+    }
+
+    /// Median time of a single run over all samples.
+    #[must_use]
+    #[inline]
+    pub fn median(&self) -> Duration {
+        let middle = self.samples.len() / 2;
+        if self.samples.len() % 2 == 0 {
+            (self.samples[middle - 1] + self.samples[middle]) / 2
+        } else {
+            self.samples[middle]
+        }
+    }
+
+    /// Time of a single run in the fastest sample.
+    #[must_use]
+    #[inline]
+    pub fn fastest(&self) -> Duration {
+        self.samples[0]
+    }
+
+    /// Time of a single run in the slowest sample.
+    #[must_use]
+    #[inline]
+    pub fn slowest(&self) -> Duration {
+        self.samples[self.samples.len() - 1]
+    }
+
+    /// Bytes of the document processed per second in the median run.
+    #[must_use]
+    #[inline]
+    pub fn throughput(&self) -> f64 {
+        self.bytes as f64 / self.median().as_secs_f64()
+    }
+}
+
+/// Comparison table of [`Measurements`](Measurement) of several engines.
+///
+/// Throughput is printed in megabytes per second, where a megabyte is 10<sup>6</sup> bytes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    measurements: Vec<Measurement>,
+}
+
+impl Report {
+    /// Create an empty report.
+    #[must_use]
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a row to the report.
+    #[inline]
+    pub fn push(&mut self, measurement: Measurement) {
+        self.measurements.push(measurement);
+    }
+
+    /// All measurements in the report, in the order they were added.
+    #[must_use]
+    #[inline(always)]
+    pub fn measurements(&self) -> &[Measurement] {
+        &self.measurements
+    }
+}
+
+impl Display for Report {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let configuration = self
+            .measurements
+            .first()
+            .map_or_else(Configuration::current, Measurement::configuration);
+        let width = self
+            .measurements
+            .iter()
+            .map(|measurement| measurement.name.len())
+            .chain(std::iter::once("engine".len()))
+            .max()
+            .unwrap_or_default();
+
+        writeln!(f, "configuration: {configuration}")?;
+        write!(
+            f,
+            "{:width$}  {:>10}  {:>12}  {:>12}  {:>12}  {:>12}",
+            "engine", "bytes", "matches", "median", "fastest", "MB/s"
+        )?;
+        for measurement in &self.measurements {
+            write!(
+                f,
+                "\n{:width$}  {:>10}  {:>12}  {:>12}  {:>12}  {:>12.2}",
+                measurement.name,
+                measurement.bytes,
+                measurement.count,
+                format!("{:.3?}", measurement.median()),
+                format!("{:.3?}", measurement.fastest()),
+                measurement.throughput() / 1e6
+            )?;
+        }
+        Ok(())
+    }
+}
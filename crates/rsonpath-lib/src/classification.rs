@@ -56,9 +56,11 @@
 pub mod depth;
 pub mod quotes;
 pub mod structural;
+pub(crate) mod whitespace;
 
 use crate::debug;
-use quotes::{QuoteClassifiedBlock, QuoteClassifiedIterator};
+use aligners::{alignment, AlignedBytes};
+use quotes::{classify_quoted_sequences, QuoteClassifiedBlock, QuoteClassifiedIterator};
 
 /// State allowing resumption of a classifier from a particular place
 /// in the input along with the stopped [`QuoteClassifiedIterator`].
@@ -132,3 +134,34 @@ impl<'a, I: QuoteClassifiedIterator<'a>> ResumeClassifierState<'a, I> {
         );
     }
 }
+
+/// Create a classifier state positioned at the character at `idx`,
+/// which must not be within a string.
+pub(crate) fn classifier_state_at(
+    bytes: &AlignedBytes<alignment::Page>,
+    idx: usize,
+) -> ResumeClassifierState<'_, impl QuoteClassifiedIterator<'_>> {
+    let mut state = ResumeClassifierState {
+        iter: classify_quoted_sequences(bytes.relax_alignment()),
+        block: None,
+        are_commas_on: false,
+        are_colons_on: false,
+    };
+
+    if idx > 0 {
+        state.offset_bytes(idx as isize);
+    }
+
+    // The skipped blocks are not classified, so the block at `idx` is classified
+    // as if it started outside of all strings. If that is wrong, the character at `idx`
+    // is marked as within quotes and the whole block has to be flipped.
+    if let Some(block) = state.block.as_mut() {
+        if (block.block.within_quotes_mask & (1_u64 << block.idx)) != 0 {
+            debug!("Mask needs flipping!");
+            block.block.within_quotes_mask = !block.block.within_quotes_mask;
+            state.iter.flip_quotes_bit();
+        }
+    }
+
+    state
+}
@@ -83,6 +83,8 @@
 use crate::classification::{quotes::QuoteClassifiedIterator, ResumeClassifierState};
 use cfg_if::cfg_if;
 
+pub(crate) mod profile;
+
 /// Common trait for structs that enrich a byte block with JSON depth information.
 #[allow(clippy::len_without_is_empty)]
 pub trait DepthBlock<'a>: Sized {
@@ -0,0 +1,120 @@
+//! Precomputed depth information of an entire document, used to skip subtrees
+//! without classifying their depth again.
+use crate::classification::quotes::QuoteClassifiedIterator;
+use crate::classification::structural::{classify_structural_characters, Structural};
+
+/// Positions of all brackets of a document, each with the position of the closing bracket
+/// of the innermost object or array that contains it.
+///
+/// Skipping to the end of the current object or array then takes a single binary search
+/// for the first bracket after the current position. If it is a closing bracket, it ends
+/// the current object or array; if it is an opening one, the current one ends with the
+/// closing bracket of its parent.
+///
+/// Takes up two [`usize`] values for every bracket in the document.
+pub(crate) struct DepthProfile {
+    brackets: Vec<(usize, usize)>,
+    #[cfg_attr(not(feature = "tail-skip"), allow(dead_code))]
+    len: usize,
+}
+
+impl DepthProfile {
+    /// Compute the profile of a document of length `len` classified by the `quote_classifier`.
+    ///
+    /// Brackets that are never closed end at `len`.
+    pub(crate) fn new<'a, Q: QuoteClassifiedIterator<'a>>(quote_classifier: Q, len: usize) -> Self {
+        const NO_PARENT: usize = usize::MAX;
+        // For openings, the second element is the position of their own closing
+        // until all brackets are collected.
+        let mut brackets = vec![];
+        let mut parents = vec![];
+        let mut open = vec![];
+
+        for structural in classify_structural_characters(quote_classifier) {
+            match structural {
+                Structural::Opening(_, idx) => {
+                    parents.push(open.last().copied().unwrap_or(NO_PARENT));
+                    open.push(brackets.len());
+                    brackets.push((idx, len));
+                }
+                Structural::Closing(_, idx) => {
+                    if let Some(opening) = open.pop() {
+                        brackets[opening].1 = idx;
+                    }
+                    parents.push(NO_PARENT);
+                    brackets.push((idx, idx));
+                }
+                Structural::Colon(_) | Structural::Comma(_) => (),
+            }
+        }
+
+        // Parents precede their children, so going backwards every child reads
+        // the closing of its parent before it is replaced.
+        for i in (0..brackets.len()).rev() {
+            if brackets[i].0 != brackets[i].1 {
+                let parent = parents[i];
+                brackets[i].1 = if parent == NO_PARENT {
+                    len
+                } else {
+                    brackets[parent].1
+                };
+            }
+        }
+
+        Self { brackets, len }
+    }
+
+    /// Position of the closing bracket of the innermost object or array containing
+    /// the position `idx`, or the length of the document if there is none.
+    #[cfg_attr(not(feature = "tail-skip"), allow(dead_code))]
+    pub(crate) fn enclosing_closing(&self, idx: usize) -> usize {
+        let next = self.brackets.partition_point(|&(bracket, _)| bracket < idx);
+        self.brackets
+            .get(next)
+            .map_or(self.len, |&(_, closing)| closing)
+    }
+
+    /// Number of bytes of heap memory taken up by the profile.
+    pub(crate) fn approx_memory_bytes(&self) -> usize {
+        self.brackets.len() * std::mem::size_of::<(usize, usize)>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classification::quotes::classify_quoted_sequences;
+    use aligners::AlignedBytes;
+    use test_case::test_case;
+
+    fn profile(json: &str) -> DepthProfile {
+        let bytes = AlignedBytes::new_padded(json.as_bytes());
+        DepthProfile::new(classify_quoted_sequences(&bytes), json.len())
+    }
+
+    #[test_case(r#"{"a": [1, 2]}"#, 1 => 12; "from the start of an object")]
+    #[test_case(r#"{"a": [1, 2]}"#, 7 => 11; "from the start of an array")]
+    #[test_case(r#"{"a": [1, 2]}"#, 12 => 12; "at the closing")]
+    #[test_case(r#"[{}, [[]], {"b": []}]"#, 2 => 2; "from an empty object")]
+    #[test_case(r#"[{}, [[]], {"b": []}]"#, 3 => 20; "between siblings")]
+    #[test_case(r#"[{}, [[]], {"b": []}]"#, 6 => 8; "inside nested arrays")]
+    #[test_case(r#"[{"]": "}"}, 1]"#, 2 => 10; "brackets in strings")]
+    #[test_case(r#"[[1], [2"#, 7 => 8; "unclosed")]
+    fn enclosing_closing(json: &str, idx: usize) -> usize {
+        profile(json).enclosing_closing(idx)
+    }
+
+    #[test]
+    fn enclosing_closing_across_blocks() {
+        let json = format!(
+            r#"{{"a": [{}], "b": {{"c": [1, 2]}}}}"#,
+            "[0], ".repeat(40) + "[0]"
+        );
+        let closing = json.find("], \"b\"").unwrap();
+        let profile = profile(&json);
+
+        assert_eq!(profile.enclosing_closing(7), closing);
+        assert_eq!(profile.enclosing_closing(json.len() - 10), json.len() - 2);
+        assert_eq!(profile.approx_memory_bytes(), 90 * 16);
+    }
+}
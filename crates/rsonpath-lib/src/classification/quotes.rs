@@ -32,6 +32,9 @@ use crate::BlockAlignment;
 use aligners::{alignment::Twice, AlignedBlock, AlignedSlice};
 use cfg_if::cfg_if;
 
+mod cached;
+pub(crate) use cached::{quote_masks, CachedQuoteClassifier};
+
 /// Input block with a bitmask signifying which characters are within quotes.
 ///
 /// Characters within quotes in the input are guaranteed to have their corresponding
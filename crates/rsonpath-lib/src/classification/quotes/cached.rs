@@ -0,0 +1,86 @@
+use super::*;
+use aligners::alignment::{self, Alignment};
+use aligners::{AlignedBlockIterator, AlignedSlice};
+
+/// Classify the whole `bytes` and return the quote masks of all blocks, in order.
+pub(crate) fn quote_masks(bytes: &AlignedSlice<alignment::Twice<BlockAlignment>>) -> Vec<u64> {
+    classify_quoted_sequences(bytes)
+        .map(|block| block.within_quotes_mask)
+        .collect()
+}
+
+/// Quote classifier replaying the masks computed earlier with [`quote_masks`]
+/// instead of classifying the blocks again.
+///
+/// The masks are exact for every block, so unlike the other classifiers this one
+/// does not lose track of the quote state when blocks are skipped.
+pub(crate) struct CachedQuoteClassifier<'a> {
+    iter: AlignedBlockIterator<'a, alignment::Twice<BlockAlignment>>,
+    masks: &'a [u64],
+    offset: Option<usize>,
+}
+
+impl<'a> CachedQuoteClassifier<'a> {
+    /// Replay the `masks`, which must have been computed from the same `bytes`.
+    #[inline(always)]
+    pub(crate) fn new(
+        bytes: &'a AlignedSlice<alignment::Twice<BlockAlignment>>,
+        masks: &'a [u64],
+    ) -> Self {
+        debug_assert_eq!(bytes.len(), masks.len() * Self::block_size());
+
+        Self {
+            iter: bytes.iter_blocks(),
+            masks,
+            offset: None,
+        }
+    }
+}
+
+impl<'a> Iterator for CachedQuoteClassifier<'a> {
+    type Item = QuoteClassifiedBlock<'a>;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<QuoteClassifiedBlock<'a>> {
+        let block = self.iter.next()?;
+        let offset = self.offset.map_or(0, |offset| offset + Self::block_size());
+        self.offset = Some(offset);
+
+        Some(QuoteClassifiedBlock {
+            block,
+            within_quotes_mask: self.masks[offset / Self::block_size()],
+        })
+    }
+}
+
+impl<'a> std::iter::FusedIterator for CachedQuoteClassifier<'a> {}
+
+impl<'a> QuoteClassifiedIterator<'a> for CachedQuoteClassifier<'a> {
+    fn block_size() -> usize {
+        Twice::<BlockAlignment>::size()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.iter.len() == 0
+    }
+
+    fn get_offset(&self) -> usize {
+        self.offset.unwrap_or(0)
+    }
+
+    fn offset(&mut self, count: isize) {
+        if count == 0 {
+            return;
+        }
+
+        self.iter.offset(count);
+        self.offset = Some(match self.offset {
+            None => (count as usize - 1) * Self::block_size(),
+            Some(offset) => offset + (count as usize) * Self::block_size(),
+        });
+    }
+
+    fn flip_quotes_bit(&mut self) {
+        // The masks are exact, so there is no state that could have been damaged.
+    }
+}
@@ -48,7 +48,12 @@
 //! let actual = classify_structural_characters(quote_classifier).collect::<Vec<Structural>>();
 //! assert_eq!(expected, actual);
 //! ```
-use crate::classification::{quotes::QuoteClassifiedIterator, ResumeClassifierState};
+use crate::classification::{
+    quotes::{classify_quoted_sequences, QuoteClassifiedIterator},
+    ResumeClassifierState,
+};
+use crate::BlockAlignment;
+use aligners::{alignment, AlignedSlice};
 use cfg_if::cfg_if;
 
 /// Defines the kinds of brackets that can be identified as structural.
@@ -237,10 +242,100 @@ cfg_if! {
     }
 }
 
+/// Number of structural characters of every kind in a JSON document,
+/// as computed by [`count_structural_characters`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StructuralCounts {
+    openings: usize,
+    closings: usize,
+    colons: usize,
+    commas: usize,
+}
+
+impl StructuralCounts {
+    /// Number of opening brackets, `[` and `{`.
+    #[must_use]
+    #[inline(always)]
+    pub fn openings(&self) -> usize {
+        self.openings
+    }
+
+    /// Number of closing brackets, `]` and `}`.
+    #[must_use]
+    #[inline(always)]
+    pub fn closings(&self) -> usize {
+        self.closings
+    }
+
+    /// Number of colons.
+    #[must_use]
+    #[inline(always)]
+    pub fn colons(&self) -> usize {
+        self.colons
+    }
+
+    /// Number of commas.
+    #[must_use]
+    #[inline(always)]
+    pub fn commas(&self) -> usize {
+        self.commas
+    }
+
+    /// Number of all structural characters.
+    #[must_use]
+    #[inline(always)]
+    pub fn total(&self) -> usize {
+        self.openings + self.closings + self.colons + self.commas
+    }
+}
+
+/// Count the structural characters in the JSON document represented by `bytes`,
+/// skipping those within strings.
+///
+/// Only the quote and structural classifiers are run, without any query, so this is
+/// a cheap proxy for the amount of work an engine has to do on the document.
+/// Engines usually classify fewer characters, since they only turn commas and colons on
+/// where they are needed and skip over irrelevant subtrees.
+///
+/// # Examples
+/// ```rust
+/// use rsonpath_lib::classification::structural::count_structural_characters;
+/// use aligners::AlignedBytes;
+///
+/// let json = r#"{"x": [1, "[,]", {"y": 2}]}"#;
+/// let aligned = AlignedBytes::new_padded(json.as_bytes());
+/// let counts = count_structural_characters(&aligned);
+///
+/// assert_eq!(counts.openings(), 3);
+/// assert_eq!(counts.closings(), 3);
+/// assert_eq!(counts.colons(), 2);
+/// assert_eq!(counts.commas(), 2);
+/// assert_eq!(counts.total(), 10);
+/// ```
+#[must_use]
+#[inline]
+pub fn count_structural_characters(
+    bytes: &AlignedSlice<alignment::Twice<BlockAlignment>>,
+) -> StructuralCounts {
+    let mut classifier = classify_structural_characters(classify_quoted_sequences(bytes));
+    classifier.turn_colons_on(0);
+    classifier.turn_commas_on(0);
+
+    let mut counts = StructuralCounts::default();
+    for event in classifier {
+        match event {
+            Opening(_, _) => counts.openings += 1,
+            Closing(_, _) => counts.closings += 1,
+            Colon(_) => counts.colons += 1,
+            Comma(_) => counts.commas += 1,
+        }
+    }
+
+    counts
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::classification::quotes::classify_quoted_sequences;
-
     use super::*;
     use aligners::AlignedBytes;
 
@@ -347,4 +442,17 @@ mod tests {
         assert_eq!(Some(Colon(27)), resumed_classifier.next());
         assert_eq!(Some(Comma(30)), resumed_classifier.next());
     }
+
+    #[test]
+    fn counting_across_blocks() {
+        let json = r#"{"a": [42, "{[:,]}", { "b\"": { "c": 1, "d": 2 } }], "e": "x"}"#.repeat(20);
+        let bytes = AlignedBytes::new_padded(json.as_bytes());
+
+        let counts = count_structural_characters(&bytes);
+
+        assert_eq!(counts.openings(), 4 * 20);
+        assert_eq!(counts.closings(), 4 * 20);
+        assert_eq!(counts.colons(), 5 * 20);
+        assert_eq!(counts.commas(), 4 * 20);
+    }
 }
@@ -0,0 +1,72 @@
+//! Skipping over runs of JSON whitespace backwards through the input.
+//!
+//! Used by the engines to find the character preceding a value or a key,
+//! like the colon before the value of an object member. In pretty-printed documents
+//! these are often separated by long runs of indentation, which are skipped
+//! a block at a time with SIMD.
+use cfg_if::cfg_if;
+
+/// Returns whether the `byte` is JSON whitespace, i.e. a space, tab, line feed, or carriage return.
+#[inline(always)]
+pub(crate) fn is_whitespace(byte: u8) -> bool {
+    matches!(byte, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+cfg_if! {
+    if #[cfg(any(doc, not(feature = "simd")))] {
+        mod nosimd;
+
+        /// Find the index of the last byte in `bytes` that is not JSON whitespace,
+        /// or [`None`] if all of them are.
+        #[inline(always)]
+        pub(crate) fn last_non_whitespace(bytes: &[u8]) -> Option<usize> {
+            nosimd::last_non_whitespace(bytes)
+        }
+    }
+    else if #[cfg(simd = "avx2")] {
+        mod avx2;
+
+        /// Find the index of the last byte in `bytes` that is not JSON whitespace,
+        /// or [`None`] if all of them are.
+        #[inline(always)]
+        pub(crate) fn last_non_whitespace(bytes: &[u8]) -> Option<usize> {
+            // SAFETY: target_feature invariant
+            unsafe { avx2::last_non_whitespace(bytes) }
+        }
+    }
+    else {
+        compile_error!("Target architecture is not supported by SIMD features of this crate. Disable the default `simd` feature.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(b"" => None; "empty")]
+    #[test_case(b"   \t\n\r" => None; "only whitespace")]
+    #[test_case(b"a" => Some(0); "single byte")]
+    #[test_case(b"{\"a\":" => Some(4); "no whitespace")]
+    #[test_case(b"\"a\" :   " => Some(4); "trailing whitespace")]
+    #[test_case(b"\x0c" => Some(0); "form feed is not JSON whitespace")]
+    fn short(bytes: &[u8]) -> Option<usize> {
+        last_non_whitespace(bytes)
+    }
+
+    #[test]
+    fn runs_across_many_blocks() {
+        for prefix in 0..70 {
+            for run in 0..200 {
+                let mut bytes = vec![b'x'; prefix];
+                bytes.extend((0..run).map(|i| b" \t\n\r"[i % 4]));
+
+                assert_eq!(
+                    last_non_whitespace(&bytes),
+                    prefix.checked_sub(1),
+                    "prefix {prefix}, run {run}"
+                );
+            }
+        }
+    }
+}
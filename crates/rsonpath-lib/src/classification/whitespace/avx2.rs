@@ -0,0 +1,67 @@
+//! This module can only be included if the code is compiled with AVX2 support
+//! and on x86/x86_64 architecture for safety.
+cfg_if::cfg_if! {
+    if #[cfg(not(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        simd = "avx2")
+    ))] {
+        compile_error!{
+            "internal error: AVX2 code included on unsupported target; \
+            please report this issue at https://github.com/V0ldek/rsonpath/issues/new?template=bug_report.md"
+        }
+    }
+}
+
+use super::is_whitespace;
+
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+const BLOCK_SIZE: usize = 32;
+
+#[target_feature(enable = "avx2")]
+#[inline]
+pub(crate) unsafe fn last_non_whitespace(bytes: &[u8]) -> Option<usize> {
+    let mut end = bytes.len();
+
+    // In compact documents there is usually no whitespace at all,
+    // so check the last byte before classifying entire blocks.
+    match end.checked_sub(1) {
+        None => return None,
+        Some(last) if !is_whitespace(bytes[last]) => return Some(last),
+        Some(_) => (),
+    }
+
+    let spaces = _mm256_set1_epi8(b' ' as i8);
+    let tabs = _mm256_set1_epi8(b'\t' as i8);
+    let line_feeds = _mm256_set1_epi8(b'\n' as i8);
+    let carriage_returns = _mm256_set1_epi8(b'\r' as i8);
+
+    while end >= BLOCK_SIZE {
+        let start = end - BLOCK_SIZE;
+        // The block lies entirely within `bytes`, and the load does not require alignment.
+        let block = _mm256_loadu_si256(bytes.as_ptr().add(start).cast());
+        let whitespace_vector = _mm256_or_si256(
+            _mm256_or_si256(
+                _mm256_cmpeq_epi8(block, spaces),
+                _mm256_cmpeq_epi8(block, tabs),
+            ),
+            _mm256_or_si256(
+                _mm256_cmpeq_epi8(block, line_feeds),
+                _mm256_cmpeq_epi8(block, carriage_returns),
+            ),
+        );
+        let non_whitespace = !(_mm256_movemask_epi8(whitespace_vector) as u32);
+
+        if non_whitespace != 0 {
+            let last_in_block = BLOCK_SIZE - 1 - non_whitespace.leading_zeros() as usize;
+            return Some(start + last_in_block);
+        }
+
+        end = start;
+    }
+
+    bytes[..end].iter().rposition(|&b| !is_whitespace(b))
+}
@@ -0,0 +1,6 @@
+use super::is_whitespace;
+
+#[inline(always)]
+pub(crate) fn last_non_whitespace(bytes: &[u8]) -> Option<usize> {
+    bytes.iter().rposition(|&b| !is_whitespace(b))
+}
@@ -7,21 +7,35 @@ mod depth;
 pub mod error;
 #[cfg(feature = "head-skip")]
 mod head_skipping;
+pub mod incremental;
 pub mod main;
+mod member_buffer;
+pub mod recovery;
 pub mod recursive;
+pub mod session;
 #[cfg(feature = "tail-skip")]
-mod tail_skipping;
+pub(crate) mod tail_skipping;
+#[cfg(feature = "timing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "timing")))]
+pub mod timing;
+mod token_counter;
+#[cfg(feature = "trace")]
+#[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
+pub mod trace;
 
 pub use main::MainEngine as RsonpathEngine;
 
-use self::error::EngineError;
+use self::error::{EngineError, InvalidPaddingError};
+use self::recovery::Recovered;
+use crate::error::UnsupportedFeatureError;
 use crate::query::{automaton::Automaton, error::CompilerError, JsonPathQuery};
-use crate::result::QueryResult;
+use crate::result::scalar::{self, JsonScalar, ScalarError};
+use crate::result::{NthResult, QueryResult, Tagged};
 use aligners::{
     alignment::{self},
     AlignedBytes,
 };
-use cfg_if::cfg_if;
+use std::ops::Range;
 
 /// Input into a query engine.
 pub struct Input {
@@ -66,32 +80,102 @@ impl Input {
     /// The buffer must know its length, may be extended by auxiliary bytes.
     #[inline]
     pub fn new_bytes<T: Extend<u8> + AsRef<[u8]>>(src: &mut T) -> Self {
-        cfg_if! {
-            if #[cfg(feature = "simd")] {
-                use aligners::alignment::Alignment;
-                type A = alignment::Twice::<crate::BlockAlignment>;
-                let contents = src;
-                let rem = contents.as_ref().len() % A::size();
-                let pad = if rem == 0 {
-                    0
-                } else {
-                    A::size() - rem
-                };
-
-                let extension = std::iter::repeat(0).take(pad + A::size());
-                contents.extend(extension);
-
-                debug_assert_eq!(contents.as_ref().len() % A::size(), 0);
-
-                Self {
-                    bytes: AlignedBytes::<alignment::Page>::from(contents.as_ref()),
-                }
-            }
-            else {
-                Self {
-                    bytes: AlignedBytes::<alignment::Page>::from(src.as_ref()),
-                }
-            }
+        Self::pad_bytes(src, 0)
+    }
+
+    /// Read the entire contents of the `reader` into an input.
+    ///
+    /// The contents are read into a buffer until the end of the stream,
+    /// and then padded as required by the engines. An empty stream results in
+    /// a valid input of an empty document.
+    ///
+    /// # Errors
+    /// Returns any [`io::Error`](std::io::Error) raised by the `reader`,
+    /// apart from [`Interrupted`](std::io::ErrorKind::Interrupted), on which the read is retried.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+    /// use rsonpath_lib::query::JsonPathQuery;
+    /// use rsonpath_lib::result::CountResult;
+    /// # use std::error::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let query = JsonPathQuery::parse("$..a")?;
+    /// let mut reader: &[u8] = br#"{"a": [{"a": 1}]}"#;
+    /// let input = Input::from_reader(&mut reader)?;
+    /// let engine = RsonpathEngine::compile_query(&query)?;
+    ///
+    /// assert_eq!(engine.run::<CountResult>(&input)?.get(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn from_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        let mut contents = vec![];
+        reader.read_to_end(&mut contents)?;
+
+        Ok(Self::new_bytes(&mut contents))
+    }
+
+    /// Transmute a buffer into an input, extending it with the given `padding` byte
+    /// instead of zeroes.
+    ///
+    /// The padding is placed after the end of the document, so it is never reported
+    /// as a match and does not change the indices of any matches. The `padding` must be
+    /// JSON whitespace (space, `\t`, `\n`, or `\r`) or a zero byte. Any other byte
+    /// could either affect classification of the document, like structural characters
+    /// and quotes, or be taken as a continuation of a trailing atomic value, e.g. a root `123`
+    /// padded with `0` would be read as `12300...`.
+    ///
+    /// # Errors
+    /// Returns an [`InvalidPaddingError`] if the `padding` is not whitespace or zero.
+    /// The `src` buffer is not modified in that case.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+    /// use rsonpath_lib::query::JsonPathQuery;
+    /// use rsonpath_lib::result::CountResult;
+    /// # use std::error::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let query = JsonPathQuery::parse("$..a")?;
+    /// let mut contents = br#"{"a": [{"a": 1}]}"#.to_vec();
+    /// let input = Input::new_bytes_with_padding(&mut contents, b' ')?;
+    /// let engine = RsonpathEngine::compile_query(&query)?;
+    ///
+    /// assert_eq!(engine.run::<CountResult>(&input)?.get(), 2);
+    /// assert!(Input::new_bytes_with_padding(&mut contents, b'#').is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn new_bytes_with_padding<T: Extend<u8> + AsRef<[u8]>>(
+        src: &mut T,
+        padding: u8,
+    ) -> Result<Self, InvalidPaddingError> {
+        if !matches!(padding, b' ' | b'\t' | b'\n' | b'\r' | 0) {
+            return Err(InvalidPaddingError(padding));
+        }
+
+        Ok(Self::pad_bytes(src, padding))
+    }
+
+    fn pad_bytes<T: Extend<u8> + AsRef<[u8]>>(src: &mut T, padding: u8) -> Self {
+        use aligners::alignment::Alignment;
+        type A = alignment::Twice<crate::BlockAlignment>;
+        let contents = src;
+        let rem = contents.as_ref().len() % A::size();
+        let pad = if rem == 0 { 0 } else { A::size() - rem };
+
+        let extension = std::iter::repeat(padding).take(pad + A::size());
+        contents.extend(extension);
+
+        debug_assert_eq!(contents.as_ref().len() % A::size(), 0);
+
+        Self {
+            bytes: AlignedBytes::<alignment::Page>::from(contents.as_ref()),
         }
     }
 }
@@ -108,7 +192,201 @@ pub trait Engine {
     /// Some glaring errors like mismatched braces or double quotes are raised,
     /// but in general the result of an engine run on an invalid JSON is undefined.
     /// It _is_ guaranteed that the computation terminates and does not panic.
-    fn run<R: QueryResult>(&self, input: &Input) -> Result<R, EngineError>;
+    #[inline]
+    fn run<R: QueryResult>(&self, input: &Input) -> Result<R, EngineError> {
+        let mut result = R::default();
+        self.run_into(input, &mut result)?;
+        Ok(result)
+    }
+
+    /// Find the match of the query at position `n` in the order in which the matches
+    /// are reported, counting from zero, and return its span as in [`SpanResult`](crate::result::SpanResult).
+    ///
+    /// Returns [`None`] if there are at most `n` matches. The engine stops processing
+    /// the input as soon as the match is found, so the rest of the document is not scanned.
+    ///
+    /// # Errors
+    /// Same as [`Engine::run`]. Malformed input after the selected match is not detected.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+    /// use rsonpath_lib::query::JsonPathQuery;
+    /// # use std::error::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let json = r#"{"items": [{"id": 1}, {"id": 2}, {"id": 3}]}"#;
+    /// let mut contents = json.to_owned();
+    /// let input = Input::new(&mut contents);
+    /// let query = JsonPathQuery::parse("$..id")?;
+    /// let engine = RsonpathEngine::compile_query(&query)?;
+    ///
+    /// let span = engine.run_nth(&input, 1)?.unwrap();
+    /// assert_eq!(&json[span], "2");
+    /// assert_eq!(engine.run_nth(&input, 3)?, None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn run_nth(&self, input: &Input, n: usize) -> Result<Option<Range<usize>>, EngineError> {
+        let mut result = NthResult::new(n);
+        self.run_into(input, &mut result)?;
+        Ok(result.into_span())
+    }
+
+    /// Find the first match of the query and convert it to a value of type `T`.
+    ///
+    /// Only the first match in the order in which the matches are reported is considered.
+    /// Other matches are ignored and do not cause an error, since the engine stops processing
+    /// the input as soon as the first match is found, like in [`Engine::run_nth`].
+    /// See [`JsonScalar`] for the supported types and conversions.
+    ///
+    /// # Errors
+    /// - [`ScalarError::NoMatch`] if the query has no matches.
+    /// - [`ScalarError::TypeMismatch`] if the first match cannot be converted to `T`.
+    /// - [`ScalarError::Engine`] for errors of [`Engine::run`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+    /// use rsonpath_lib::query::JsonPathQuery;
+    /// # use std::error::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut contents = r#"{"limits": {"timeout": 2.5, "retries": [3, 5]}}"#.to_owned();
+    /// let input = Input::new(&mut contents);
+    ///
+    /// let query = JsonPathQuery::parse("$.limits.timeout")?;
+    /// let engine = RsonpathEngine::compile_query(&query)?;
+    /// assert_eq!(engine.get_scalar::<f64>(&input)?, 2.5);
+    ///
+    /// let query = JsonPathQuery::parse("$..retries.*")?;
+    /// let engine = RsonpathEngine::compile_query(&query)?;
+    /// assert_eq!(engine.get_scalar::<i64>(&input)?, 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn get_scalar<T: JsonScalar>(&self, input: &Input) -> Result<T, ScalarError> {
+        let span = self.run_nth(input, 0)?.ok_or(ScalarError::NoMatch)?;
+        scalar::convert(input, span)
+    }
+
+    /// Compute the [`QueryResult`] on given [`Input`] like [`Engine::run`], and return it
+    /// with the `tag` attached.
+    ///
+    /// The tag is returned unchanged also when the run fails, so every outcome can be correlated
+    /// with the input it came from, no matter in which order the runs finish.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+    /// use rsonpath_lib::query::JsonPathQuery;
+    /// use rsonpath_lib::result::CountResult;
+    /// # use std::error::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let query = JsonPathQuery::parse("$..a")?;
+    /// let engine = RsonpathEngine::compile_query(&query)?;
+    /// let documents = [(17_u64, r#"{"a": [{"a": 1}]}"#), (42, r#"{"b": 1}"#), (7, r#"[{"a": 1}]"#)];
+    ///
+    /// let mut outcomes = std::thread::scope(|s| {
+    ///     let handles: Vec<_> = documents
+    ///         .iter()
+    ///         .map(|&(id, json)| {
+    ///             let engine = &engine;
+    ///             s.spawn(move || {
+    ///                 let mut contents = json.to_owned();
+    ///                 engine.run_tagged::<CountResult, _>(&Input::new(&mut contents), id)
+    ///             })
+    ///         })
+    ///         .collect();
+    ///     handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+    /// });
+    /// outcomes.sort_by_key(|outcome| *outcome.tag());
+    ///
+    /// let counts: Vec<_> = outcomes
+    ///     .into_iter()
+    ///     .map(|outcome| {
+    ///         let (id, result) = outcome.into_parts();
+    ///         (id, result.unwrap().get())
+    ///     })
+    ///     .collect();
+    /// assert_eq!(counts, [(7, 1), (17, 2), (42, 0)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn run_tagged<R: QueryResult, T>(
+        &self,
+        input: &Input,
+        tag: T,
+    ) -> Tagged<T, Result<R, EngineError>> {
+        Tagged::new(tag, self.run(input))
+    }
+
+    /// Compute the [`QueryResult`] on given [`Input`] consisting of many records,
+    /// the top-level objects and arrays, skipping all records with inconsistent structure
+    /// instead of failing.
+    ///
+    /// The query is executed on every record separately, and the matches in all records
+    /// that were not skipped are reported at their positions in the whole `input`.
+    /// See the [`recovery`] module for how the records are found and skipped.
+    /// Every record is treated as the root of a separate document, so e.g. the depths,
+    /// paths, and token indices in the [`MatchContext`](crate::result::MatchContext)
+    /// of its matches start at the record.
+    ///
+    /// The default implementation does not support recovery and always fails.
+    /// Both engines of this crate implement it.
+    ///
+    /// # Errors
+    /// Only [`EngineError::NotSupported`]. All other errors cause the record
+    /// in which they occurred to be skipped.
+    #[inline]
+    fn run_lenient<R: QueryResult>(&self, _input: &Input) -> Result<Recovered<R>, EngineError> {
+        Err(UnsupportedFeatureError::lenient_runs().into())
+    }
+
+    /// Report all matches of the query on given [`Input`] into an existing `result`.
+    ///
+    /// Matches are reported on top of whatever the `result` already contains,
+    /// so running several engines into the same `result` accumulates the matches of all
+    /// their queries. All indices are consistent as long as the same `input` is used.
+    /// The matches of each run are reported after the matches of all previous runs,
+    /// in the order in which the engine finds them, so e.g. an [`IndexResult`](crate::result::IndexResult)
+    /// accumulated from many runs is not sorted as a whole and can contain duplicates
+    /// if the queries overlap. Use [`QueryResult::reset`] to clear the `result` between runs.
+    ///
+    /// If the `result` is already [complete](QueryResult::is_complete), the input is not processed.
+    ///
+    /// # Errors
+    /// Same as [`Engine::run`]. Matches reported before the error was detected remain
+    /// in the `result`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+    /// use rsonpath_lib::query::JsonPathQuery;
+    /// use rsonpath_lib::result::{IndexResult, QueryResult};
+    /// # use std::error::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut contents = r#"{"a": 1, "b": {"c": 2}}"#.to_owned();
+    /// let input = Input::new(&mut contents);
+    /// let mut result = IndexResult::default();
+    ///
+    /// for query in ["$.b.c", "$.a"] {
+    ///     let query = JsonPathQuery::parse(query)?;
+    ///     RsonpathEngine::compile_query(&query)?.run_into(&input, &mut result)?;
+    /// }
+    /// assert_eq!(result.get(), &[18, 4]);
+    ///
+    /// result.reset();
+    /// assert!(result.get().is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn run_into<R: QueryResult>(&self, input: &Input, result: &mut R) -> Result<(), EngineError>;
 }
 
 /// Trait for an engine that can be created by compiling a [`JsonPathQuery`].
@@ -124,6 +402,57 @@ pub trait Compiler {
     /// cannot handle the query.
     fn compile_query(query: &JsonPathQuery) -> Result<Self::E<'_>, CompilerError>;
 
+    /// Compile a [`JsonPathQuery`] into an [`Engine`] like [`Compiler::compile_query`],
+    /// rejecting the query if it contains any descendant selector.
+    ///
+    /// See [`Automaton::new_without_descendants`] for details.
+    ///
+    /// # Errors
+    /// [`CompilerError::DescendantDisallowed`] is returned for the first descendant selector
+    /// in the query. Otherwise, same as [`Compiler::compile_query`].
+    #[inline]
+    fn compile_query_without_descendants(
+        query: &JsonPathQuery,
+    ) -> Result<Self::E<'_>, CompilerError> {
+        let automaton = Automaton::new_without_descendants(query)?;
+        Ok(Self::from_compiled_query(automaton))
+    }
+
+    /// Compile a [`JsonPathQuery`] into an [`Engine`] like [`Compiler::compile_query`],
+    /// reporting only the outermost matches of the query.
+    ///
+    /// See [`Automaton::new_outermost`] for details. Matched values are skipped entirely,
+    /// so this is also faster than filtering out nested matches from the result.
+    ///
+    /// # Errors
+    /// Same as [`Automaton::new_outermost`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+    /// use rsonpath_lib::query::JsonPathQuery;
+    /// use rsonpath_lib::result::CountResult;
+    /// # use std::error::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut contents = r#"{"a": {"a": {"a": 1}}, "b": [{"a": 2}]}"#.to_owned();
+    /// let input = Input::new(&mut contents);
+    /// let query = JsonPathQuery::parse("$..a")?;
+    ///
+    /// let all = RsonpathEngine::compile_query(&query)?;
+    /// assert_eq!(all.run::<CountResult>(&input)?.get(), 4);
+    ///
+    /// let outermost = RsonpathEngine::compile_query_outermost(&query)?;
+    /// assert_eq!(outermost.run::<CountResult>(&input)?.get(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn compile_query_outermost(query: &JsonPathQuery) -> Result<Self::E<'_>, CompilerError> {
+        let automaton = Automaton::new_outermost(query)?;
+        Ok(Self::from_compiled_query(automaton))
+    }
+
     /// Turn a compiled [`Automaton`] into an [`Engine`].
     fn from_compiled_query(automaton: Automaton<'_>) -> Self::E<'_>;
 }
@@ -1,4 +1,5 @@
 //! Error definitions and utilities for engine execution.
+use std::io;
 use thiserror::Error;
 
 /// Error enum for all types of errors that can be reported
@@ -38,6 +39,38 @@ pub enum EngineError {
     NotSupported(#[from] crate::error::UnsupportedFeatureError),
 }
 
+/// Convert into an [`io::Error`] to use `?` uniformly in code performing IO.
+///
+/// Errors caused by a known limitation are [`Unsupported`](io::ErrorKind::Unsupported),
+/// all other errors are caused by a malformed document and are
+/// [`InvalidData`](io::ErrorKind::InvalidData). The original error is kept as the
+/// inner error of the [`io::Error`], available with [`io::Error::into_inner`],
+/// and its message is used as the message of the [`io::Error`].
+impl From<EngineError> for io::Error {
+    #[inline]
+    fn from(error: EngineError) -> Self {
+        let kind = match error {
+            EngineError::NotSupported(_) => io::ErrorKind::Unsupported,
+            EngineError::DepthBelowZero(_, _)
+            | EngineError::DepthAboveLimit(_, _)
+            | EngineError::MissingClosingCharacter()
+            | EngineError::MalformedLabelQuotes(_) => io::ErrorKind::InvalidData,
+        };
+
+        Self::new(kind, error)
+    }
+}
+
+/// Error raised when an [`Input`](super::Input) is to be padded with a byte
+/// that could be read as a part of the document.
+/// The inner [`u8`] is the rejected padding byte.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error(
+    "Byte {0:#04x} cannot be used for padding, \
+    since it is neither JSON whitespace nor zero."
+)]
+pub struct InvalidPaddingError(pub u8);
+
 /// Errors in internal depth tracking of execution engines.
 #[derive(Error, Debug)]
 pub enum DepthError {
@@ -3,7 +3,7 @@
 //! This happens in queries starting with a descendant selector.
 use super::error::EngineError;
 use crate::classification::{
-    quotes::{classify_quoted_sequences, QuoteClassifiedIterator},
+    quotes::QuoteClassifiedIterator,
     structural::{resume_structural_classification, Structural, StructuralIterator},
     ResumeClassifierState,
 };
@@ -30,6 +30,9 @@ pub(super) trait CanHeadSkip<'b> {
     /// and execute the query until a matching [`Structural::Closing`] character is encountered,
     /// using `classifier` for classification and `result` for reporting query results. The `classifier`
     /// must *not* be used to classify anything past the matching [`Structural::Closing`] character.
+    ///
+    /// If the `result` becomes [complete](`QueryResult::is_complete`) the engine can stop early,
+    /// and the returned state is then unspecified.
     fn run_on_subtree<'r, R, Q, I>(
         &mut self,
         next_event: Structural,
@@ -66,7 +69,10 @@ impl<'b, 'q> HeadSkip<'b, 'q> {
     /// Syntacticly, if the [`fallback_state`](`crate::query::automaton::StateTable::fallback_state`)
     /// of the [`initial_state`](`crate::query::automaton::StateTable::initial_state`) is the same as the
     /// [`initial_state`](`crate::query::automaton::StateTable::initial_state`), and its
-    /// [`transitions`](`crate::query::automaton::StateTable::transitions`) are a single-element list.
+    /// [`transitions`](`crate::query::automaton::StateTable::transitions`) are a single-element list,
+    /// with no [pattern transitions](`crate::query::automaton::StateTable::has_pattern_transitions`).
+    /// The label must also not contain escaped control characters, since keys can escape them
+    /// differently than the label.
     ///
     /// This means that we can search for the label of the forward transition in the entire document,
     /// disregarding any additional structure &ndash; during execution we would always loop
@@ -78,35 +84,34 @@ impl<'b, 'q> HeadSkip<'b, 'q> {
         bytes: &'b AlignedBytes<alignment::Page>,
         automaton: &'b Automaton<'q>,
     ) -> Option<Self> {
-        let initial_state = automaton.initial_state();
-        let fallback_state = automaton[initial_state].fallback_state();
-        let transitions = automaton[initial_state].transitions();
-
-        if fallback_state == initial_state && transitions.len() == 1 {
-            let (label, target_state) = transitions[0];
-            debug!("Automaton starts with a descendant search, using memmem heuristic.");
-            return Some(Self {
-                bytes,
-                state: target_state,
-                is_accepting: automaton.is_accepting(target_state),
-                label,
-            });
-        }
-
-        None
+        let (label, target_state) = automaton.head_skip_transition()?;
+        debug!("Automaton starts with a descendant search, using memmem heuristic.");
+
+        Some(Self {
+            bytes,
+            state: target_state,
+            is_accepting: automaton.is_accepting(target_state),
+            label,
+        })
     }
 
     /// Run a preconfigured [`HeadSkip`] using the given `engine` and reporting
-    /// to the `result`.
-    pub(super) fn run_head_skipping<'r, E: CanHeadSkip<'b>, R: QueryResult>(
+    /// to the `result`. The `quote_classifier` must not have classified any blocks yet.
+    pub(super) fn run_head_skipping<'r, E, Q, R>(
         &self,
         engine: &mut E,
+        quote_classifier: Q,
         result: &'r mut R,
-    ) -> Result<(), EngineError> {
+    ) -> Result<(), EngineError>
+    where
+        E: CanHeadSkip<'b>,
+        Q: QuoteClassifiedIterator<'b>,
+        R: QueryResult,
+    {
         use memchr::memmem;
 
         let mut classifier_state = ResumeClassifierState {
-            iter: classify_quoted_sequences(self.bytes.relax_alignment()),
+            iter: quote_classifier,
             block: None,
             are_commas_on: false,
             are_colons_on: false,
@@ -135,7 +140,11 @@ impl<'b, 'q> HeadSkip<'b, 'q> {
                     classifier_state.offset_bytes(distance as isize);
 
                     if self.is_accepting {
-                        result.report(colon_idx);
+                        result.report(self.bytes, colon_idx);
+
+                        if result.is_complete() {
+                            return Ok(());
+                        }
                     }
 
                     // Check if the colon is marked as within quotes.
@@ -165,6 +174,10 @@ impl<'b, 'q> HeadSkip<'b, 'q> {
                         _ => classifier.stop(),
                     };
 
+                    if result.is_complete() {
+                        return Ok(());
+                    }
+
                     debug!("Quote classified up to {}", classifier_state.get_idx());
                     idx = classifier_state.get_idx();
                     continue;
@@ -0,0 +1,197 @@
+//! Keeping the results of a query up to date while the document is being edited.
+//!
+//! The [`IncrementalQuery`] remembers the indices of all matches of its query in a document.
+//! After an [`Edit`] of the document, the matches are updated without scanning the document
+//! whenever the edit cannot change which values are matched, e.g. when the text
+//! of a number or a string value is changed. All other edits cause the query to be executed
+//! again on the whole document.
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::engine::incremental::{Edit, IncrementalQuery, UpdateKind};
+//! use rsonpath_lib::engine::Input;
+//! use rsonpath_lib::query::{automaton::Automaton, JsonPathQuery};
+//! # use std::error::Error;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let query = JsonPathQuery::parse("$..price")?;
+//! let mut contents = r#"{"a": {"price": 1}, "b": {"price": 2}}"#.to_owned();
+//! let mut query = IncrementalQuery::new(Automaton::new(&query)?, &Input::new(&mut contents))?;
+//! assert_eq!(query.get(), &[14, 33]);
+//!
+//! // Change the first price from `1` to `100`.
+//! contents.replace_range(16..17, "100");
+//! let edit = Edit { start: 16, removed: b"1", inserted_len: 3 };
+//! let update = query.update(&Input::new(&mut contents), &edit)?;
+//!
+//! assert_eq!(update, UpdateKind::Shifted);
+//! assert_eq!(query.get(), &[14, 35]);
+//! # Ok(())
+//! # }
+//! ```
+use super::error::EngineError;
+use super::main::MainEngine;
+use super::{Compiler, Engine, Input};
+use crate::json;
+use crate::query::automaton::Automaton;
+use crate::result::IndexResult;
+
+/// Replacement of a contiguous range of bytes of a document with other bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edit<'a> {
+    /// Position of the first replaced byte, the same in the document before and after the edit.
+    pub start: usize,
+    /// Bytes of the document before the edit that were replaced.
+    pub removed: &'a [u8],
+    /// Number of bytes that replaced them, starting at [`start`](Edit::start)
+    /// in the document after the edit.
+    pub inserted_len: usize,
+}
+
+/// How the matches of an [`IncrementalQuery`] were brought up to date after an [`Edit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpdateKind {
+    /// The edit could not change the matched values, so the indices of the matches after
+    /// the edit were shifted by the difference in length, without scanning the document.
+    Shifted,
+    /// The query was executed again on the whole document.
+    Rerun,
+}
+
+/// Query whose matches are kept up to date across [`Edits`](Edit) of a document.
+///
+/// The matches are the same as those of an [`IndexResult`] of the [`MainEngine`].
+/// An edit is handled without scanning the document if:
+/// - neither the removed nor the inserted bytes contain structural characters,
+///   double quotes, or backslashes, so the structure of the document and the boundaries
+///   of its strings are unchanged;
+/// - the edit does not directly follow a backslash, which could escape a different character;
+/// - the edit is not within a key, since keys decide which values are matched;
+/// - the edit neither empties nor fills a value, does not touch a value right after
+///   an opening bracket or a comma, and is not whitespace right after an opening bracket,
+///   since elements of arrays are reported at positions depending on their neighbours;
+/// - the query has no filter selector, since filters depend on the matched values.
+///
+/// The check is conservative, e.g. an edit in a string value containing a colon
+/// right after its opening quote is treated as possibly changing a key,
+/// and causes the query to be executed again.
+pub struct IncrementalQuery<'q> {
+    engine: MainEngine<'q>,
+    is_value_dependent: bool,
+    indices: Vec<usize>,
+}
+
+impl<'q> IncrementalQuery<'q> {
+    /// Execute the query represented by the `automaton` on the `input`
+    /// and remember its matches.
+    ///
+    /// # Errors
+    /// Same as [`Engine::run`].
+    #[inline]
+    pub fn new(automaton: Automaton<'q>, input: &Input) -> Result<Self, EngineError> {
+        let is_value_dependent = automaton.filter().is_some();
+        let engine = MainEngine::from_compiled_query(automaton);
+        let indices = engine.run::<IndexResult>(input)?.into();
+
+        Ok(Self {
+            engine,
+            is_value_dependent,
+            indices,
+        })
+    }
+
+    /// Indices of the matches in the document after the latest edit,
+    /// as in [`IndexResult::get`].
+    #[must_use]
+    #[inline(always)]
+    pub fn get(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Bring the matches up to date after the `edit`, given the `input` with the document
+    /// after the edit.
+    ///
+    /// # Errors
+    /// Same as [`Engine::run`], if the query has to be executed again.
+    /// The matches are then left empty.
+    #[inline]
+    pub fn update(&mut self, input: &Input, edit: &Edit) -> Result<UpdateKind, EngineError> {
+        if !self.is_value_dependent && is_value_edit(input, edit) {
+            let removed_end = edit.start + edit.removed.len();
+            for idx in &mut self.indices {
+                if *idx >= removed_end {
+                    *idx = *idx - edit.removed.len() + edit.inserted_len;
+                } else if *idx > edit.start {
+                    *idx = edit.start;
+                }
+            }
+
+            return Ok(UpdateKind::Shifted);
+        }
+
+        self.indices.clear();
+        self.indices = self.engine.run::<IndexResult>(input)?.into();
+
+        Ok(UpdateKind::Rerun)
+    }
+}
+
+/// Returns whether the `edit` resulting in `bytes` certainly changes neither
+/// the structure of the document nor any of its keys.
+fn is_value_edit(bytes: &[u8], edit: &Edit) -> bool {
+    let inserted_end = edit.start + edit.inserted_len;
+    let Some(inserted) = bytes.get(edit.start..inserted_end) else {
+        return false;
+    };
+    let is_plain = |b: &u8| !matches!(b, b'{' | b'}' | b'[' | b']' | b':' | b',' | b'"' | b'\\');
+
+    if !edit.removed.iter().all(is_plain) || !inserted.iter().all(is_plain) {
+        return false;
+    }
+    if edit.start > 0 && bytes[edit.start - 1] == b'\\' {
+        return false;
+    }
+    if touches_list_element(bytes, edit, inserted) {
+        return false;
+    }
+
+    // Keys are followed by a colon, so if the first quote after the edit closes a key,
+    // the edit might be within it. If it opens a string instead, this is a false positive.
+    let next_quote = (inserted_end..bytes.len())
+        .find(|&idx| bytes[idx] == b'"' && !json::is_escaped(bytes, idx));
+    match next_quote {
+        Some(idx) => bytes[idx + 1..]
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            .map_or(true, |&b| b != b':'),
+        None => true,
+    }
+}
+
+/// Returns whether the `edit` resulting in `bytes` might change the elements of an array
+/// or move the positions at which they are reported.
+///
+/// The first element of an array is reported right after the opening bracket if more elements
+/// follow, and at its first byte otherwise, while an array with only whitespace inside
+/// has no elements at all. Objects and arrays are always reported at their opening character.
+fn touches_list_element(bytes: &[u8], edit: &Edit, inserted: &[u8]) -> bool {
+    let is_blank = |part: &[u8]| part.iter().all(u8::is_ascii_whitespace);
+    let inserted_end = edit.start + edit.inserted_len;
+    let before = bytes[..edit.start]
+        .iter()
+        .rev()
+        .find(|b| !b.is_ascii_whitespace());
+    let after = bytes[inserted_end..]
+        .iter()
+        .find(|b| !b.is_ascii_whitespace());
+    let is_whole_value =
+        matches!(before, Some(b'[' | b',' | b':')) && matches!(after, Some(b']' | b',' | b'}'));
+
+    match (is_blank(edit.removed), is_blank(inserted)) {
+        (true, true) => {
+            edit.start > 0 && bytes[edit.start - 1] == b'[' && !matches!(after, Some(b'{' | b'['))
+        }
+        (true, false) | (false, true) if is_whole_value => true,
+        _ => matches!(before, Some(b'[' | b',')),
+    }
+}
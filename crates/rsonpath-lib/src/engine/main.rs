@@ -9,10 +9,11 @@
 //! even on targets that do not support AVX2 SIMD operations.
 #[cfg(feature = "head-skip")]
 use super::head_skipping::{CanHeadSkip, HeadSkip};
+use super::recovery::{self, CanRunOnRecord, Recovered};
 use super::Compiler;
-#[cfg(feature = "unique-labels")]
+use crate::classification::depth::profile::DepthProfile;
 use crate::classification::structural::BracketType;
-#[cfg(feature = "head-skip")]
+use crate::classification::whitespace;
 use crate::classification::ResumeClassifierState;
 use crate::classification::{
     quotes::{classify_quoted_sequences, QuoteClassifiedIterator},
@@ -21,15 +22,24 @@ use crate::classification::{
 use crate::debug;
 use crate::engine::depth::Depth;
 use crate::engine::error::EngineError;
+use crate::engine::member_buffer::MemberBuffer;
 #[cfg(feature = "tail-skip")]
 use crate::engine::tail_skipping::TailSkip;
+#[cfg(feature = "timing")]
+use crate::engine::timing::RunStats;
+use crate::engine::token_counter::TokenCounter;
+#[cfg(feature = "trace")]
+use crate::engine::trace::{Trace, TraceAction};
 use crate::engine::{Engine, Input};
+use crate::json;
 use crate::query::automaton::{Automaton, State};
 use crate::query::error::CompilerError;
 use crate::query::{JsonPathQuery, Label};
-use crate::result::QueryResult;
+use crate::result::{Consumed, MatchContext, PathStep, QueryResult};
 use aligners::{alignment, AlignedBytes};
 use smallvec::{smallvec, SmallVec};
+use std::num::NonZeroUsize;
+use std::ops::Range;
 
 /// Main engine for a fixed JSONPath query.
 ///
@@ -58,29 +68,380 @@ impl Compiler for MainEngine<'_> {
 
 impl Engine for MainEngine<'_> {
     #[inline]
-    fn run<R: QueryResult>(&self, input: &Input) -> Result<R, EngineError> {
-        if self.automaton.is_empty_query() {
-            return Ok(empty_query(input));
+    fn run_into<R: QueryResult>(&self, input: &Input, result: &mut R) -> Result<(), EngineError> {
+        self.run_with_options(input, RunOptions::default(), result)?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn run_lenient<R: QueryResult>(&self, input: &Input) -> Result<Recovered<R>, EngineError> {
+        recovery::run_lenient(self, input)
+    }
+}
+
+impl CanRunOnRecord for MainEngine<'_> {
+    fn run_on_record<'b, Q, I, R>(
+        &'b self,
+        bytes: &'b AlignedBytes<alignment::Page>,
+        opening: Structural,
+        classifier: I,
+        result: &mut R,
+    ) -> Result<(), EngineError>
+    where
+        Q: QuoteClassifiedIterator<'b>,
+        I: StructuralIterator<'b, Q>,
+        R: QueryResult,
+    {
+        let initial_state = self.automaton.initial_state();
+
+        if self.automaton.is_accepting(initial_state) {
+            result.report_in_context(bytes, opening.idx(), &MatchContext::ROOT);
         }
+        if self.automaton.is_empty_query() || result.is_complete() {
+            return Ok(());
+        }
+
+        let mut executor = query_executor(&self.automaton, bytes);
+        executor.tracks_paths = R::TRACKING.is_path_tracked();
+        executor.tokens = R::TRACKING
+            .is_token_index_tracked()
+            .then(|| TokenCounter::new(bytes, opening.idx()));
+        executor.resume_on_subtree(opening, initial_state, classifier, result)?;
 
+        Ok(())
+    }
+}
+
+/// Configuration of a single execution of the main engine with [`run_with`].
+pub(crate) struct RunOptions<'b> {
+    /// Whether head-skipping can be used, if the query allows it.
+    /// Runs that need to observe every structural event of the document have to disable it.
+    pub(crate) head_skip: bool,
+    /// Whether the document has to be processed until its end even if the query cannot
+    /// match anything past the root, to find out if it is [fully consumed](Consumed).
+    pub(crate) run_to_end: bool,
+    /// Depths at which matches are reported.
+    pub(crate) depth_range: Range<usize>,
+    /// Depth profile of the input used to skip subtrees.
+    #[cfg_attr(not(feature = "tail-skip"), allow(dead_code))]
+    pub(crate) depth_profile: Option<&'b DepthProfile>,
+    #[cfg(feature = "trace")]
+    pub(crate) trace: Option<&'b mut Trace>,
+    #[cfg(feature = "timing")]
+    pub(crate) stats: Option<&'b mut RunStats>,
+}
+
+impl Default for RunOptions<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            head_skip: true,
+            run_to_end: false,
+            depth_range: 0..usize::MAX,
+            depth_profile: None,
+            #[cfg(feature = "trace")]
+            trace: None,
+            #[cfg(feature = "timing")]
+            stats: None,
+        }
+    }
+}
+
+/// Execute the query represented by the `automaton` on the `input` like [`Engine::run_into`],
+/// configured by the `options`, with quote classification provided by the `quote_classifier`,
+/// which must not have classified any blocks yet.
+///
+/// Returns whether the document was fully consumed. This is only reliable if
+/// [`run_to_end`](RunOptions::run_to_end) is set, since otherwise execution can stop
+/// as soon as the query cannot match anything more.
+pub(crate) fn run_with<'b, Q, R>(
+    automaton: &'b Automaton<'_>,
+    input: &'b Input,
+    quote_classifier: Q,
+    #[cfg_attr(not(feature = "trace"), allow(unused_mut))] mut options: RunOptions<'b>,
+    result: &mut R,
+) -> Result<bool, EngineError>
+where
+    Q: QuoteClassifiedIterator<'b>,
+    R: QueryResult,
+{
+    let initial_state = automaton.initial_state();
+
+    if result.is_complete() || options.depth_range.is_empty() {
+        return Ok(false);
+    }
+    if options.depth_range.contains(&0) && automaton.is_accepting(initial_state) {
+        #[cfg_attr(not(feature = "trace"), allow(unused_variables))]
+        let root_event = report_root(input, result);
+        #[cfg(feature = "trace")]
+        if let (Some(trace), Some(event)) = (options.trace.as_mut(), root_event) {
+            trace.start_event(event, 0, initial_state);
+            trace.record(TraceAction::Report(event.idx()));
+            trace.finish_event();
+        }
+    }
+    if result.is_complete() {
+        return Ok(false);
+    }
+    // Only the root can be within a range ending at depth 1.
+    if !options.run_to_end && (options.depth_range.end <= 1 || automaton.is_empty_query()) {
+        return Ok(false);
+    }
+
+    let mut executor = query_executor(automaton, input);
+    executor.tracks_paths = R::TRACKING.is_path_tracked();
+    executor.tokens = R::TRACKING
+        .is_token_index_tracked()
+        .then(|| TokenCounter::new(input, 0));
+    executor.depth_range = options.depth_range;
+    #[cfg(feature = "tail-skip")]
+    {
+        executor.depth_profile = options.depth_profile;
+    }
+    #[cfg(feature = "trace")]
+    {
+        executor.trace = options.trace;
+    }
+    #[cfg(feature = "timing")]
+    {
+        executor.stats = options.stats;
+    }
+
+    if options.head_skip {
+        executor.run(quote_classifier, result)?;
+        Ok(false)
+    } else {
+        executor.run_to_end(quote_classifier, result)
+    }
+}
+
+impl MainEngine<'_> {
+    /// Run the engine like [`Engine::run`], and return the result together with
+    /// whether the whole document was processed.
+    ///
+    /// The document is fully consumed if the engine reached its end with all objects and arrays
+    /// closed. It is not if the engine stopped early because the result became
+    /// [complete](QueryResult::is_complete), e.g. after the first match of an
+    /// [`ExistsResult`](crate::result::ExistsResult). Then no error is raised,
+    /// even though the rest of the document was not checked.
+    /// Head-skipping is disabled for these runs so that the depth is tracked through the entire document.
+    ///
+    /// # Errors
+    /// Same as [`Engine::run`]. In particular, a document that ends before all of its
+    /// objects and arrays are closed is an error, unless the engine stopped early.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rsonpath_lib::engine::{main::MainEngine, Compiler, Input};
+    /// use rsonpath_lib::query::JsonPathQuery;
+    /// use rsonpath_lib::result::{CountResult, ExistsResult};
+    /// # use std::error::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut contents = r#"{"a": [1, 2], "b": {"a": 3}}"#.to_owned();
+    /// let input = Input::new(&mut contents);
+    /// let query = JsonPathQuery::parse("$..a")?;
+    /// let engine = MainEngine::compile_query(&query)?;
+    ///
+    /// let count = engine.run_with_consumption::<CountResult>(&input)?;
+    /// assert_eq!(count.result().get(), 2);
+    /// assert!(count.is_fully_consumed());
+    ///
+    /// let exists = engine.run_with_consumption::<ExistsResult>(&input)?;
+    /// assert!(exists.result().get());
+    /// assert!(!exists.is_fully_consumed());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn run_with_consumption<R: QueryResult>(
+        &self,
+        input: &Input,
+    ) -> Result<Consumed<R>, EngineError> {
+        let options = RunOptions {
+            head_skip: false,
+            run_to_end: true,
+            ..RunOptions::default()
+        };
         let mut result = R::default();
-        let executor = query_executor(&self.automaton, input);
-        executor.run(&mut result)?;
+        let is_fully_consumed = self.run_with_options(input, options, &mut result)?;
+
+        Ok(Consumed::new(result, is_fully_consumed))
+    }
+
+    /// Run the engine like [`Engine::run`], reporting only the matches at a depth
+    /// within the `depth_range`.
+    ///
+    /// The depth of a value is the number of objects and arrays enclosing it,
+    /// so the root value is at depth 0, its direct children at depth 1, etc.
+    /// The range includes its start and excludes its end, so `3..6` selects the matches
+    /// at depths 3, 4, and 5. Values nested deeper than the range are skipped without
+    /// being inspected, and an empty range returns right away with no matches.
+    /// Head-skipping is disabled for these runs so that the depth is tracked through the entire document.
+    ///
+    /// # Errors
+    /// Same as [`Engine::run`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rsonpath_lib::engine::{main::MainEngine, Compiler, Input};
+    /// use rsonpath_lib::query::JsonPathQuery;
+    /// use rsonpath_lib::result::CountResult;
+    /// # use std::error::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut contents = r#"{"x": 1, "a": {"x": 2, "b": {"x": 3}}}"#.to_owned();
+    /// let input = Input::new(&mut contents);
+    /// let query = JsonPathQuery::parse("$..x")?;
+    /// let engine = MainEngine::compile_query(&query)?;
+    ///
+    /// let count = |range| engine.run_with_depth_range::<CountResult>(&input, range);
+    /// assert_eq!(count(1..2)?.get(), 1);
+    /// assert_eq!(count(2..usize::MAX)?.get(), 2);
+    /// assert_eq!(count(2..2)?.get(), 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn run_with_depth_range<R: QueryResult>(
+        &self,
+        input: &Input,
+        depth_range: Range<usize>,
+    ) -> Result<R, EngineError> {
+        let options = RunOptions {
+            head_skip: false,
+            depth_range,
+            ..RunOptions::default()
+        };
+        let mut result = R::default();
+        self.run_with_options(input, options, &mut result)?;
 
         Ok(result)
     }
+
+    fn run_with_options<'b, R: QueryResult>(
+        &'b self,
+        input: &'b Input,
+        options: RunOptions<'b>,
+        result: &mut R,
+    ) -> Result<bool, EngineError> {
+        let quote_classifier = classify_quoted_sequences(input.relax_alignment());
+        run_with(&self.automaton, input, quote_classifier, options, result)
+    }
 }
 
-fn empty_query<R: QueryResult>(bytes: &AlignedBytes<alignment::Page>) -> R {
-    let quote_classifier = classify_quoted_sequences(bytes.relax_alignment());
-    let mut block_event_source = classify_structural_characters(quote_classifier);
-    let mut result = R::default();
+#[cfg(feature = "trace")]
+#[cfg_attr(docsrs, doc(cfg(feature = "trace")))]
+impl MainEngine<'_> {
+    /// Run the engine like [`Engine::run`], recording a structured [`Trace`]
+    /// of the execution into `trace`.
+    ///
+    /// Entries are appended to `trace` even if the execution fails,
+    /// so the trace can be used to diagnose the error. Head-skipping is disabled
+    /// for traced runs so that every structural event of the document is recorded.
+    ///
+    /// # Errors
+    /// Same as [`Engine::run`].
+    #[inline]
+    pub fn run_traced<R: QueryResult>(
+        &self,
+        input: &Input,
+        trace: &mut Trace,
+    ) -> Result<R, EngineError> {
+        let options = RunOptions {
+            head_skip: false,
+            trace: Some(trace),
+            ..RunOptions::default()
+        };
+        let mut result = R::default();
+        self.run_with_options(input, options, &mut result)?;
 
-    if let Some(Structural::Opening(_, idx)) = block_event_source.next() {
-        result.report(idx);
+        Ok(result)
     }
+}
+
+#[cfg(feature = "timing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "timing")))]
+impl MainEngine<'_> {
+    /// Run the engine like [`Engine::run`], adding the time spent on classification
+    /// and on handling of structural events to `stats`.
+    ///
+    /// Head-skipping is disabled for timed runs so that all of the input
+    /// is processed by the classifiers. Reporting the root value is not timed.
+    ///
+    /// # Errors
+    /// Same as [`Engine::run`].
+    #[inline]
+    pub fn run_timed<R: QueryResult>(
+        &self,
+        input: &Input,
+        stats: &mut RunStats,
+    ) -> Result<R, EngineError> {
+        let options = RunOptions {
+            head_skip: false,
+            stats: Some(stats),
+            ..RunOptions::default()
+        };
+        let mut result = R::default();
+        self.run_with_options(input, options, &mut result)?;
+
+        Ok(result)
+    }
+}
+
+/// Report the root value, if the document is not empty, and return the opening event
+/// at which it was reported if it is an object or an array.
+///
+/// Atomic roots, like `42` or `"a"`, are reported at their first byte,
+/// but there is no structural event corresponding to them.
+fn report_root<R: QueryResult>(
+    bytes: &AlignedBytes<alignment::Page>,
+    result: &mut R,
+) -> Option<Structural> {
+    let idx = json::root_start(bytes)?;
+    result.report_in_context(bytes, idx, &MatchContext::ROOT);
+
+    match bytes[idx] {
+        b'{' => Some(Structural::Opening(BracketType::Curly, idx)),
+        b'[' => Some(Structural::Opening(BracketType::Square, idx)),
+        _ => None,
+    }
+}
+
+/// Record an action in the [`Trace`] of the executor, if it is being traced.
+/// Evaluates to an empty statement without the `trace` feature.
+#[cfg(feature = "trace")]
+macro_rules! trace {
+    ($self:ident, $method:ident($($arg:expr),*)) => {
+        if let Some(trace) = $self.trace.as_mut() {
+            trace.$method($($arg),*);
+        }
+    };
+}
+#[cfg(not(feature = "trace"))]
+macro_rules! trace {
+    ($($t:tt)*) => {};
+}
 
-    result
+/// Evaluate a call to the classifier, adding the time it took to the [`RunStats`]
+/// of the executor, if it is being timed. Evaluates to just the call without the `timing` feature.
+#[cfg(feature = "timing")]
+macro_rules! classify {
+    ($self:ident, $call:expr) => {{
+        let start = $self.stats.is_some().then(std::time::Instant::now);
+        let value = $call;
+        if let (Some(stats), Some(start)) = ($self.stats.as_mut(), start) {
+            stats.add_classification(start.elapsed());
+        }
+        value
+    }};
+}
+#[cfg(not(feature = "timing"))]
+macro_rules! classify {
+    ($self:ident, $call:expr) => {
+        $call
+    };
 }
 
 #[cfg(feature = "tail-skip")]
@@ -104,6 +465,22 @@ struct Executor<'q, 'b> {
     bytes: &'b AlignedBytes<alignment::Page>,
     next_event: Option<Structural>,
     is_list: bool,
+    member_buffers: Vec<(u8, MemberBuffer)>,
+    element_counters: Vec<(u8, usize)>,
+    /// Whether the path to every match is tracked in `path`, in which case
+    /// the elements of all lists are counted.
+    tracks_paths: bool,
+    /// Steps of the path leading to the current value, excluding the root.
+    path: Vec<PathStep>,
+    /// Counter of the tokens preceding every match, if their token indices are tracked.
+    tokens: Option<TokenCounter<'b>>,
+    depth_range: Range<usize>,
+    #[cfg(feature = "tail-skip")]
+    depth_profile: Option<&'b DepthProfile>,
+    #[cfg(feature = "trace")]
+    trace: Option<&'b mut Trace>,
+    #[cfg(feature = "timing")]
+    stats: Option<&'b mut RunStats>,
 }
 
 fn query_executor<'q, 'b>(
@@ -118,36 +495,88 @@ fn query_executor<'q, 'b>(
         bytes,
         next_event: None,
         is_list: false,
+        member_buffers: vec![],
+        element_counters: vec![],
+        tracks_paths: false,
+        path: vec![],
+        tokens: None,
+        depth_range: 0..usize::MAX,
+        #[cfg(feature = "tail-skip")]
+        depth_profile: None,
+        #[cfg(feature = "trace")]
+        trace: None,
+        #[cfg(feature = "timing")]
+        stats: None,
     }
 }
 
 impl<'q, 'b> Executor<'q, 'b> {
     #[cfg(feature = "head-skip")]
-    fn run<R: QueryResult>(mut self, result: &mut R) -> Result<(), EngineError> {
-        let mb_head_skip = HeadSkip::new(self.bytes, self.automaton);
+    fn run<Q: QuoteClassifiedIterator<'b>, R: QueryResult>(
+        mut self,
+        quote_classifier: Q,
+        result: &mut R,
+    ) -> Result<(), EngineError> {
+        // Head-skipping reports matches without their context.
+        let mb_head_skip =
+            HeadSkip::new(self.bytes, self.automaton).filter(|_| R::TRACKING.is_none());
 
         match mb_head_skip {
-            Some(head_skip) => head_skip.run_head_skipping(&mut self, result),
-            None => self.run_and_exit(result),
+            Some(head_skip) => head_skip.run_head_skipping(&mut self, quote_classifier, result),
+            None => self.run_and_exit(quote_classifier, result),
         }
     }
 
     #[cfg(not(feature = "head-skip"))]
-    fn run<R: QueryResult>(self, result: &mut R) -> Result<(), EngineError> {
-        self.run_and_exit(result)
+    fn run<Q: QuoteClassifiedIterator<'b>, R: QueryResult>(
+        self,
+        quote_classifier: Q,
+        result: &mut R,
+    ) -> Result<(), EngineError> {
+        self.run_and_exit(quote_classifier, result)
+    }
+
+    fn run_and_exit<Q: QuoteClassifiedIterator<'b>, R: QueryResult>(
+        self,
+        quote_classifier: Q,
+        result: &mut R,
+    ) -> Result<(), EngineError> {
+        self.run_to_end(quote_classifier, result).map(|_| ())
     }
 
-    fn run_and_exit<R: QueryResult>(mut self, result: &mut R) -> Result<(), EngineError> {
-        let quote_classifier = classify_quoted_sequences(self.bytes.relax_alignment());
+    /// Run on the entire document and return whether it was fully consumed,
+    /// i.e. the engine did not stop early with a complete result before the depth returned to zero.
+    fn run_to_end<Q: QuoteClassifiedIterator<'b>, R: QueryResult>(
+        mut self,
+        quote_classifier: Q,
+        result: &mut R,
+    ) -> Result<bool, EngineError> {
         let structural_classifier = classify_structural_characters(quote_classifier);
         #[cfg(feature = "tail-skip")]
-        let mut classifier = TailSkip::new(structural_classifier);
+        let mut classifier =
+            TailSkip::with_depth_profile(structural_classifier, self.depth_profile);
         #[cfg(not(feature = "tail-skip"))]
         let mut classifier = structural_classifier;
+        #[cfg(feature = "timing")]
+        let timer = self
+            .stats
+            .as_ref()
+            .map(|stats| (stats.classification_nanos(), std::time::Instant::now()));
+
+        let outcome = self.run_on_subtree(&mut classifier, result);
+        trace!(self, finish_event());
+        #[cfg(feature = "timing")]
+        if let (Some(stats), Some((classification_before, start))) = (self.stats.as_mut(), timer) {
+            stats.add_handling(start.elapsed(), classification_before);
+        }
+        outcome?;
 
-        self.run_on_subtree(&mut classifier, result)?;
+        if result.is_complete() && self.depth != Depth::ZERO {
+            return Ok(false);
+        }
 
-        self.verify_subtree_closed()
+        self.verify_subtree_closed()?;
+        Ok(true)
     }
 
     fn run_on_subtree<
@@ -159,13 +588,17 @@ impl<'q, 'b> Executor<'q, 'b> {
         classifier: &mut Classifier!(),
         result: &mut R,
     ) -> Result<(), EngineError> {
-        while let Some(event) = self.next_event.or_else(|| classifier.next()) {
+        while let Some(event) = self
+            .next_event
+            .or_else(|| classify!(self, classifier.next()))
+        {
             debug!("====================");
             debug!("Event = {:?}", event);
             debug!("Depth = {:?}", self.depth);
             debug!("Stack = {:?}", self.stack);
             debug!("State = {:?}", self.state);
             debug!("====================");
+            trace!(self, start_event(event, *self.depth, self.state));
 
             self.next_event = None;
             match event {
@@ -173,13 +606,18 @@ impl<'q, 'b> Executor<'q, 'b> {
                 Structural::Comma(idx) => self.handle_comma(classifier, idx, result)?,
                 Structural::Opening(_, idx) => self.handle_opening(classifier, idx, result)?,
                 Structural::Closing(_, idx) => {
-                    self.handle_closing(classifier, idx)?;
+                    self.handle_closing(classifier, idx, result)?;
 
                     if self.depth == Depth::ZERO {
                         break;
                     }
                 }
             }
+
+            if result.is_complete() {
+                debug!("Result is complete, stopping early.");
+                break;
+            }
         }
 
         Ok(())
@@ -202,22 +640,31 @@ impl<'q, 'b> Executor<'q, 'b> {
                 .unwrap_or("[invalid utf8]")
         );
 
-        self.next_event = classifier.next();
+        self.next_event = classify!(self, classifier.next());
         let is_next_opening = self.next_event.map_or(false, |s| s.is_opening());
 
         if !is_next_opening {
             let mut any_matched = false;
 
+            // A matched label takes precedence over patterns and the fallback
+            // even if its target is not accepting, which is the case for excluded labels.
             for &(label, target) in self.automaton[self.state].transitions() {
-                if self.automaton.is_accepting(target) && self.is_match(idx, label)? {
-                    result.report(idx);
+                if self.is_match(idx, label)? {
+                    if self.automaton.is_accepting(target) {
+                        self.report(result, idx);
+                    }
                     any_matched = true;
                     break;
                 }
             }
-            let fallback_state = self.automaton[self.state].fallback_state();
-            if !any_matched && self.automaton.is_accepting(fallback_state) {
-                result.report(idx);
+            if !any_matched {
+                let target = match self.pattern_transition(idx)? {
+                    Some(target) => target,
+                    None => self.automaton[self.state].fallback_state(),
+                };
+                if self.automaton.is_accepting(target) {
+                    self.report(result, idx);
+                }
             }
             #[cfg(feature = "unique-labels")]
             {
@@ -225,7 +672,8 @@ impl<'q, 'b> Executor<'q, 'b> {
                 if any_matched && !is_next_closing && self.automaton.is_unitary(self.state) {
                     let opening = if self.is_list { b'[' } else { b'{' };
                     debug!("Skipping unique state from {}", opening as char);
-                    let stop_at = classifier.skip(opening);
+                    let stop_at = classify!(self, classifier.skip(opening));
+                    trace!(self, record(TraceAction::Skip));
                     let bracket_type = if self.is_list {
                         BracketType::Square
                     } else {
@@ -250,16 +698,19 @@ impl<'q, 'b> Executor<'q, 'b> {
         I: StructuralIterator<'b, Q>,
         R: QueryResult,
     {
-        self.next_event = classifier.next();
+        self.next_event = classify!(self, classifier.next());
         let is_next_opening = self.next_event.map_or(false, |s| s.is_opening());
 
-        if !is_next_opening {
-            let fallback_state = self.automaton[self.state].fallback_state();
-            if self.is_list && self.automaton.is_accepting(fallback_state) {
-                result.report(idx);
+        if let Some((depth, index)) = self.element_counters.last_mut() {
+            if *depth == *self.depth {
+                *index += 1;
             }
         }
 
+        if !is_next_opening && self.is_list && self.automaton.is_accepting(self.element_target()) {
+            self.report(result, idx);
+        }
+
         Ok(())
     }
 
@@ -278,7 +729,7 @@ impl<'q, 'b> Executor<'q, 'b> {
             "Opening {}, increasing depth and pushing stack.",
             self.bytes[idx]
         );
-        let mut any_matched = false;
+        let mut matched = None;
 
         if let Some(colon_idx) = self.find_preceding_colon(idx) {
             debug!(
@@ -290,23 +741,53 @@ impl<'q, 'b> Executor<'q, 'b> {
             );
             for &(label, target) in self.automaton[self.state].transitions() {
                 if self.is_match(colon_idx, label)? {
-                    any_matched = true;
-                    self.transition_to(target, self.bytes[idx]);
-                    if self.automaton.is_accepting(target) {
-                        result.report(colon_idx);
-                    }
+                    matched = Some((colon_idx, target));
                     break;
                 }
             }
+
+            if matched.is_none() {
+                matched = self
+                    .pattern_transition(colon_idx)?
+                    .map(|target| (colon_idx, target));
+            }
+        }
+
+        if let Some((colon_idx, target)) = matched {
+            #[cfg(feature = "tail-skip")]
+            if self.is_nothing_within_reported(target) {
+                // Nothing within the matched value can match, e.g. when only the outermost
+                // matches are reported, so we move on right after the value.
+                if self.automaton.is_accepting(target) {
+                    self.report(result, colon_idx);
+                }
+                classify!(self, classifier.skip(self.bytes[idx]));
+                trace!(self, record(TraceAction::Skip));
+                return Ok(());
+            }
+            self.transition_to(target, self.bytes[idx]);
+            if self.automaton.is_accepting(target) {
+                self.report(result, colon_idx);
+            }
         }
 
-        if !any_matched && self.depth != Depth::ZERO {
-            let fallback = self.automaton[self.state].fallback_state();
+        if matched.is_none() && self.depth != Depth::ZERO {
+            let fallback = self.element_target();
             debug!("Falling back to {fallback}");
 
             #[cfg(feature = "tail-skip")]
             if self.automaton.is_rejecting(fallback) {
-                classifier.skip(self.bytes[idx]);
+                classify!(self, classifier.skip(self.bytes[idx]));
+                trace!(self, record(TraceAction::Skip));
+                return Ok(());
+            } else if self.is_nothing_within_reported(fallback) {
+                // Nothing within the matched value can match, e.g. an array element selected
+                // by a filter, so we move on right after the value.
+                if self.automaton.is_accepting(fallback) {
+                    self.report(result, idx);
+                }
+                classify!(self, classifier.skip(self.bytes[idx]));
+                trace!(self, record(TraceAction::Skip));
                 return Ok(());
             } else {
                 self.transition_to(fallback, self.bytes[idx]);
@@ -315,33 +796,37 @@ impl<'q, 'b> Executor<'q, 'b> {
             self.transition_to(fallback, self.bytes[idx]);
 
             if self.automaton.is_accepting(fallback) {
-                result.report(idx);
+                self.report(result, idx);
             }
         }
 
+        let step = self.path_step(idx);
+        let mut has_index_transitions = false;
+        let mut first_element = None;
         if self.bytes[idx] == b'[' {
             self.is_list = true;
 
-            let fallback = self.automaton[self.state].fallback_state();
-            if self.automaton.is_accepting(fallback) {
+            let table = &self.automaton[self.state];
+            has_index_transitions = table.has_element_transitions();
+            let first_target = table.element_transition(0);
+            if self.needs_commas() {
                 classifier.turn_commas_on(idx);
-                self.next_event = classifier.next();
+            } else {
+                classifier.turn_commas_off();
+            }
+
+            if self.automaton.is_accepting(first_target) {
+                self.next_event = classify!(self, classifier.next());
                 match self.next_event {
                     Some(Structural::Closing(_, close_idx)) => {
-                        for next_idx in (idx + 1)..close_idx {
-                            if !self.bytes[next_idx].is_ascii_whitespace() {
-                                result.report(next_idx);
-                                break;
-                            }
-                        }
+                        first_element = ((idx + 1)..close_idx)
+                            .find(|&next_idx| !self.bytes[next_idx].is_ascii_whitespace());
                     }
                     Some(Structural::Comma(_)) => {
-                        result.report(idx + 1);
+                        first_element = Some(idx + 1);
                     }
                     _ => (),
                 }
-            } else {
-                classifier.turn_commas_off();
             }
         } else {
             self.is_list = false;
@@ -356,20 +841,51 @@ impl<'q, 'b> Executor<'q, 'b> {
             .increment()
             .map_err(|err| EngineError::DepthAboveLimit(idx, err))?;
 
+        if self.tracks_paths {
+            self.path.extend(step);
+        }
+        if has_index_transitions || (self.is_list && self.tracks_paths) {
+            self.element_counters.push((*self.depth, 0));
+        }
+
+        // The first element is within the list, so it is reported at the increased depth.
+        if let Some(first_idx) = first_element {
+            self.report(result, first_idx);
+        }
+
         Ok(())
     }
 
-    fn handle_closing<Q, I>(
+    fn handle_closing<Q, I, R>(
         &mut self,
         classifier: &mut Classifier!(),
         idx: usize,
+        result: &mut R,
     ) -> Result<(), EngineError>
     where
         Q: QuoteClassifiedIterator<'b>,
         I: StructuralIterator<'b, Q>,
+        R: QueryResult,
     {
         debug!("Closing, decreasing depth and popping stack.");
 
+        if matches!(self.element_counters.last(), Some((depth, _)) if *depth == *self.depth) {
+            self.element_counters.pop();
+        }
+
+        if matches!(self.member_buffers.last(), Some((depth, _)) if *depth == *self.depth) {
+            if let Some((_, buffer)) = self.member_buffers.pop() {
+                if let Some(member_idx) = buffer.selected() {
+                    debug!("Member at {member_idx} selected at closing {idx}");
+                    self.accept(result, member_idx);
+                }
+            }
+        }
+
+        if self.tracks_paths {
+            self.path.pop();
+        }
+
         #[cfg(feature = "unique-labels")]
         {
             self.depth
@@ -379,11 +895,13 @@ impl<'q, 'b> Executor<'q, 'b> {
             if let Some(stack_frame) = self.stack.pop_if_at_or_below(*self.depth) {
                 self.state = stack_frame.state;
                 self.is_list = stack_frame.is_list;
+                trace!(self, record(TraceAction::Return(self.state)));
 
                 if self.automaton.is_unitary(self.state) {
                     let opening = if self.is_list { b'[' } else { b'{' };
                     debug!("Skipping unique state from {}", opening as char);
-                    let close_idx = classifier.skip(opening);
+                    let close_idx = classify!(self, classifier.skip(opening));
+                    trace!(self, record(TraceAction::Skip));
                     let bracket_type = if self.is_list {
                         BracketType::Square
                     } else {
@@ -403,14 +921,11 @@ impl<'q, 'b> Executor<'q, 'b> {
             if let Some(stack_frame) = self.stack.pop_if_at_or_below(*self.depth) {
                 self.state = stack_frame.state;
                 self.is_list = stack_frame.is_list;
+                trace!(self, record(TraceAction::Return(self.state)));
             }
         }
 
-        if self.is_list
-            && self
-                .automaton
-                .is_accepting(self.automaton[self.state].fallback_state())
-        {
+        if self.needs_commas() {
             classifier.turn_commas_on(idx);
         } else {
             classifier.turn_commas_off();
@@ -425,6 +940,120 @@ impl<'q, 'b> Executor<'q, 'b> {
         Ok(())
     }
 
+    /// Whether nothing within a value opened at the current depth can be reported
+    /// if it is reached in the `target` state, either because the state is terminal,
+    /// or because the values within are deeper than the depth range.
+    #[cfg(feature = "tail-skip")]
+    fn is_nothing_within_reported(&self, target: State) -> bool {
+        (self.automaton.is_accepting(target) && self.automaton.is_terminal(target))
+            || usize::from(*self.depth) + 1 >= self.depth_range.end
+    }
+
+    fn report<R: QueryResult>(&mut self, result: &mut R, idx: usize) {
+        if !self.depth_range.contains(&usize::from(*self.depth)) {
+            debug!("Value at {idx} outside of the depth range");
+            return;
+        }
+        if let Some(filter) = self.automaton.filter() {
+            if !filter.matches_at(self.bytes, idx, self.automaton.number_syntax()) {
+                debug!("Value at {idx} rejected by the filter");
+                return;
+            }
+        }
+        if let Some(position) = self.automaton.member_from_end() {
+            // Array elements are not members, and members of an object
+            // can only be selected once all of them are known.
+            if !self.is_list {
+                self.buffer_member(position, idx);
+            }
+            return;
+        }
+        self.accept(result, idx);
+    }
+
+    fn accept<R: QueryResult>(&mut self, result: &mut R, idx: usize) {
+        let step = if self.tracks_paths {
+            self.path_step(idx)
+        } else {
+            None
+        };
+        self.path.extend(step);
+        let token_index = self
+            .tokens
+            .as_mut()
+            .map_or(0, |tokens| tokens.token_index_at(idx));
+
+        let context = MatchContext::new(
+            self.bytes,
+            usize::from(*self.depth),
+            &self.path,
+            token_index,
+        );
+        result.report_in_context(self.bytes, idx, &context);
+
+        if step.is_some() {
+            self.path.pop();
+        }
+        trace!(self, record(TraceAction::Report(idx)));
+    }
+
+    fn buffer_member(&mut self, position: NonZeroUsize, idx: usize) {
+        let depth = *self.depth;
+        match self.member_buffers.last_mut() {
+            Some((buffer_depth, buffer)) if *buffer_depth == depth => buffer.push(idx),
+            _ => {
+                let mut buffer = MemberBuffer::new(position);
+                buffer.push(idx);
+                self.member_buffers.push((depth, buffer));
+            }
+        }
+    }
+
+    /// Returns the state to which the current element of the list being processed leads,
+    /// or the fallback state if the current value is not a list.
+    ///
+    /// Elements are counted separately for every list with index transitions,
+    /// independently of the stack, since nested lists can be processed in the same state.
+    fn element_target(&self) -> State {
+        let table = &self.automaton[self.state];
+        match self.element_index() {
+            Some(index) => table.element_transition(index),
+            None => table.fallback_state(),
+        }
+    }
+
+    /// Returns the position of the current element of the list being processed,
+    /// or [`None`] if the current value is not a list or its elements are not counted.
+    fn element_index(&self) -> Option<usize> {
+        match self.element_counters.last() {
+            Some(&(depth, index)) if self.is_list && depth == *self.depth => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Returns the step of the path from the current value to its child reported at `idx`,
+    /// or [`None`] if the reported value is the root.
+    fn path_step(&self, idx: usize) -> Option<PathStep> {
+        if self.depth == Depth::ZERO {
+            None
+        } else if self.is_list {
+            Some(PathStep::Element(self.element_index().unwrap_or(0)))
+        } else {
+            Some(PathStep::Member(idx))
+        }
+    }
+
+    /// Whether the commas in the current list are needed, either to report its elements
+    /// or to count them for index transitions or paths.
+    fn needs_commas(&self) -> bool {
+        let table = &self.automaton[self.state];
+
+        self.is_list
+            && (self.tracks_paths
+                || self.automaton.is_accepting(table.fallback_state())
+                || table.has_element_transitions())
+    }
+
     fn transition_to(&mut self, target: State, opening: u8) {
         let target_is_list = opening == b'[';
         if target != self.state || target_is_list != self.is_list {
@@ -438,6 +1067,7 @@ impl<'q, 'b> Executor<'q, 'b> {
                 is_list: self.is_list,
             });
             self.state = target;
+            trace!(self, record(TraceAction::Transition(target)));
         }
     }
 
@@ -445,15 +1075,17 @@ impl<'q, 'b> Executor<'q, 'b> {
         if self.depth == Depth::ZERO {
             None
         } else {
-            let mut colon_idx = idx - 1;
-            while self.bytes[colon_idx].is_ascii_whitespace() {
-                colon_idx -= 1;
-            }
-            (self.bytes[colon_idx] == b':').then_some(colon_idx)
+            whitespace::last_non_whitespace(&self.bytes[..idx])
+                .filter(|&colon_idx| self.bytes[colon_idx] == b':')
         }
     }
 
     fn is_match(&self, idx: usize, label: &Label) -> Result<bool, EngineError> {
+        if label.has_control_escape() {
+            return json::key_matches_label(self.bytes, idx, label)
+                .ok_or(EngineError::MalformedLabelQuotes(idx));
+        }
+
         let len = label.len() + 2;
 
         let mut closing_quote_idx = idx - 1;
@@ -476,6 +1108,20 @@ impl<'q, 'b> Executor<'q, 'b> {
             && (start_idx == 0 || self.bytes[start_idx - 1] != b'\\'))
     }
 
+    /// Returns the target of the current state's pattern transitions for the key
+    /// of the member whose colon is at `colon_idx`, if it matches any of the patterns.
+    fn pattern_transition(&self, colon_idx: usize) -> Result<Option<State>, EngineError> {
+        let table = &self.automaton[self.state];
+        if !table.has_pattern_transitions() {
+            return Ok(None);
+        }
+
+        let key = json::key_before_colon(self.bytes, colon_idx)
+            .ok_or(EngineError::MalformedLabelQuotes(colon_idx))?;
+
+        Ok(table.pattern_transition(key))
+    }
+
     fn verify_subtree_closed(&self) -> Result<(), EngineError> {
         if self.depth != Depth::ZERO {
             Err(EngineError::MissingClosingCharacter())
@@ -534,13 +1180,34 @@ impl<'q, 'b> CanHeadSkip<'b> for Executor<'q, 'b> {
         structural_classifier: I,
         result: &'r mut R,
     ) -> Result<ResumeClassifierState<'b, Q>, EngineError>
+    where
+        Q: QuoteClassifiedIterator<'b>,
+        R: QueryResult,
+        I: StructuralIterator<'b, Q>,
+    {
+        self.resume_on_subtree(next_event, state, structural_classifier, result)
+    }
+}
+
+impl<'q, 'b> Executor<'q, 'b> {
+    /// Execute the query in the given `state` on the subtree opened by `next_event`,
+    /// which is the last event classified by the `structural_classifier`,
+    /// and return the state of the classifier after the subtree is closed.
+    fn resume_on_subtree<R, Q, I>(
+        &mut self,
+        next_event: Structural,
+        state: State,
+        structural_classifier: I,
+        result: &mut R,
+    ) -> Result<ResumeClassifierState<'b, Q>, EngineError>
     where
         Q: QuoteClassifiedIterator<'b>,
         R: QueryResult,
         I: StructuralIterator<'b, Q>,
     {
         #[cfg(feature = "tail-skip")]
-        let mut classifier = TailSkip::new(structural_classifier);
+        let mut classifier =
+            TailSkip::with_depth_profile(structural_classifier, self.depth_profile);
         #[cfg(not(feature = "tail-skip"))]
         let mut classifier = structural_classifier;
 
@@ -548,7 +1215,10 @@ impl<'q, 'b> CanHeadSkip<'b> for Executor<'q, 'b> {
         self.next_event = Some(next_event);
 
         self.run_on_subtree(&mut classifier, result)?;
-        self.verify_subtree_closed()?;
+
+        if !result.is_complete() {
+            self.verify_subtree_closed()?;
+        }
 
         Ok(classifier.stop())
     }
@@ -0,0 +1,46 @@
+//! Buffering of values matched by a member from end selector (`[last()]`).
+//!
+//! Whether a member is at the given position from the end of its object is only known
+//! when the object is closed, so the engines keep the latest matched members of every
+//! open object and report the selected one at the closing brace.
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+
+/// Upper bound on the memory reserved upfront for a single object.
+const MAX_INITIAL_CAPACITY: usize = 16;
+
+/// Indices of the latest members of a single object that reached an accepting state.
+#[derive(Debug)]
+pub(crate) struct MemberBuffer {
+    position: NonZeroUsize,
+    members: VecDeque<usize>,
+}
+
+impl MemberBuffer {
+    /// Create an empty buffer for selecting the member at `position` from the end.
+    pub(crate) fn new(position: NonZeroUsize) -> Self {
+        Self {
+            position,
+            members: VecDeque::with_capacity(position.get().min(MAX_INITIAL_CAPACITY)),
+        }
+    }
+
+    /// Record a member reported at `idx`, forgetting the ones that are now
+    /// too far from the end to be selected.
+    pub(crate) fn push(&mut self, idx: usize) {
+        if self.members.len() == self.position.get() {
+            self.members.pop_front();
+        }
+        self.members.push_back(idx);
+    }
+
+    /// Index of the selected member, assuming the object is closed,
+    /// or `None` if the object has too few members.
+    pub(crate) fn selected(&self) -> Option<usize> {
+        if self.members.len() == self.position.get() {
+            self.members.front().copied()
+        } else {
+            None
+        }
+    }
+}
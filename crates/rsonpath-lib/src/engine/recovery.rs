@@ -0,0 +1,342 @@
+//! Salvaging the matches of a query from a partially corrupted input.
+//!
+//! Used by [`Engine::run_lenient`](super::Engine::run_lenient), which treats the input as a sequence of records,
+//! the top-level objects and arrays, like in a log of JSON lines. Records with inconsistent
+//! structure are skipped instead of aborting the run, and the query is executed on every
+//! other record.
+//!
+//! # Recovery
+//! The records are found with the quote and structural classifiers in a single pass.
+//! A record starts at an opening character outside of all other records and ends
+//! at the first point at which the depth returns to zero. Its structure is inconsistent
+//! if a closing character of a different kind than the innermost unclosed opening comes first,
+//! e.g. in `{"a": [1}`. Such a closing character closes all openings up to the nearest one
+//! of its kind, or all of them if there is none, so the record ends there.
+//! An inconsistent record is skipped as a whole, together with all values nested in it,
+//! and the search for records resumes right after it. A closing character outside of all records
+//! is skipped as well. All values outside of objects and arrays at the top level are ignored.
+//!
+//! If the input ends before a record is closed, there is no way to tell where the record
+//! was cut off. Then the record is skipped up to its innermost unclosed opening, and the search
+//! resumes right after that opening, so the complete values following it, like the lines
+//! written after a truncated one, are recovered as records of their own.
+//!
+//! Every other record is executed on in place, with its opening character as the root
+//! of the document. A record on which the engine raises an error, e.g. because of
+//! a malformed key, is skipped as a whole, and none of its matches are reported.
+//! Only errors caused by a known limitation of the engine end the run.
+//!
+//! Recovery is not possible after a missing double quote, since all the following
+//! characters are then treated as part of a string.
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+//! use rsonpath_lib::query::JsonPathQuery;
+//! use rsonpath_lib::result::IndexResult;
+//! # use std::error::Error;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let query = JsonPathQuery::parse("$.id")?;
+//! let json = "{\"id\": 1}\n{\"id\": 2, \"tags\": [}\n{\"id\": 3}\n";
+//! let mut contents = json.to_owned();
+//! let input = Input::new(&mut contents);
+//! let engine = RsonpathEngine::compile_query(&query)?;
+//!
+//! let recovered = engine.run_lenient::<IndexResult>(&input)?;
+//!
+//! assert_eq!(recovered.result().get(), &[5, 36]);
+//! assert_eq!(recovered.skipped_records(), 1);
+//! assert_eq!(recovered.skipped_spans(), &[10..30]);
+//! # Ok(())
+//! # }
+//! ```
+use super::error::EngineError;
+use super::Input;
+use crate::classification::classifier_state_at;
+use crate::classification::quotes::QuoteClassifiedIterator;
+use crate::classification::structural::{
+    resume_structural_classification, BracketType, Structural, StructuralIterator,
+};
+use crate::result::{MatchContext, PathStep, QueryResult, Tracking};
+use aligners::{alignment, AlignedBytes};
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+/// Result of a query on a partially corrupted input, returned by [`Engine::run_lenient`](super::Engine::run_lenient).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovered<R> {
+    result: R,
+    skipped: Vec<Range<usize>>,
+}
+
+impl<R> Recovered<R> {
+    /// The matches of the query in all records that were not skipped.
+    #[must_use]
+    #[inline(always)]
+    pub fn result(&self) -> &R {
+        &self.result
+    }
+
+    /// Take the matches of the query, discarding the information on skipped records.
+    #[must_use]
+    #[inline(always)]
+    pub fn into_result(self) -> R {
+        self.result
+    }
+
+    /// Number of corrupted parts of the input that were skipped.
+    ///
+    /// Skipped records and closing characters with no record between them are counted once,
+    /// e.g. an inconsistent record followed by a stray closing character.
+    #[must_use]
+    #[inline(always)]
+    pub fn skipped_records(&self) -> usize {
+        self.skipped.len()
+    }
+
+    /// Spans of the corrupted parts of the input, in the order in which they occur.
+    ///
+    /// Every span starts at the opening character of the first skipped record,
+    /// or at a stray closing character, and ends right after the last character that was skipped.
+    #[must_use]
+    #[inline(always)]
+    pub fn skipped_spans(&self) -> &[Range<usize>] {
+        &self.skipped
+    }
+}
+
+/// Trait that needs to be implemented by an [`Engine`](super::Engine) to run it on the records of an input
+/// with [`run_lenient`].
+pub(super) trait CanRunOnRecord {
+    /// Execute the query on the record opened by the `opening` character,
+    /// treating the record as the root of a document.
+    ///
+    /// The structural `classifier` passed is guaranteed to have classified the
+    /// `opening` and nothing past that, and the record is guaranteed to have consistent structure.
+    /// The engine must report the matches within the record to the `result` at their positions
+    /// in the whole `bytes`, and must not classify anything past the closing character of the record.
+    fn run_on_record<'b, Q, I, R>(
+        &'b self,
+        bytes: &'b AlignedBytes<alignment::Page>,
+        opening: Structural,
+        classifier: I,
+        result: &mut R,
+    ) -> Result<(), EngineError>
+    where
+        Q: QuoteClassifiedIterator<'b>,
+        I: StructuralIterator<'b, Q>,
+        R: QueryResult;
+}
+
+pub(super) fn run_lenient<E, R>(engine: &E, input: &Input) -> Result<Recovered<R>, EngineError>
+where
+    E: CanRunOnRecord + ?Sized,
+    R: QueryResult,
+{
+    let mut recovery = Recovery {
+        engine,
+        bytes: input,
+        result: R::default(),
+        skipped: vec![],
+        is_skipping: false,
+    };
+    let mut resume_at = Some(0);
+
+    while let Some(resume_idx) = resume_at.take() {
+        let mut events = resume_structural_classification(classifier_state_at(input, resume_idx));
+
+        while let Some(event) = events.next() {
+            if recovery.result.is_complete() {
+                break;
+            }
+
+            match event {
+                Structural::Opening(bracket, start) => {
+                    match find_record_end(&mut events, bracket, start) {
+                        RecordEnd::Consistent(end) => recovery.run_on_record(start..end)?,
+                        RecordEnd::Inconsistent(end) => recovery.skip(start..end),
+                        RecordEnd::Truncated(end) => {
+                            recovery.skip(start..end);
+                            resume_at = Some(end);
+                        }
+                    }
+                }
+                Structural::Closing(_, idx) => recovery.skip(idx..idx + 1),
+                Structural::Colon(_) | Structural::Comma(_) => (),
+            }
+        }
+    }
+
+    Ok(Recovered {
+        result: recovery.result,
+        skipped: recovery.skipped,
+    })
+}
+
+/// Position right after the last character of a record, or of its part that was skipped.
+enum RecordEnd {
+    /// The record was closed with consistent structure.
+    Consistent(usize),
+    /// The record was closed with inconsistent structure.
+    Inconsistent(usize),
+    /// The input ended before the record was closed. The position is the one
+    /// right after the innermost unclosed opening.
+    Truncated(usize),
+}
+
+/// Find the end of the record opened by the `opening` character at `start`,
+/// consuming the `events` up to it.
+///
+/// A closing character of a different kind than the innermost unclosed opening
+/// closes all openings up to the nearest one of its kind, or all of them if there is none,
+/// so that the record ends at the first point at which the depth returns to zero.
+fn find_record_end<I>(events: &mut I, opening: BracketType, start: usize) -> RecordEnd
+where
+    I: Iterator<Item = Structural>,
+{
+    let mut stack = vec![(opening, start)];
+    let mut is_consistent = true;
+
+    for event in events {
+        match event {
+            Structural::Opening(bracket, idx) => stack.push((bracket, idx)),
+            Structural::Closing(bracket, idx) => {
+                match stack.iter().rposition(|&(opening, _)| opening == bracket) {
+                    Some(depth) => {
+                        is_consistent &= depth + 1 == stack.len();
+                        stack.truncate(depth);
+                    }
+                    None => {
+                        is_consistent = false;
+                        stack.clear();
+                    }
+                }
+
+                if stack.is_empty() {
+                    return if is_consistent {
+                        RecordEnd::Consistent(idx + 1)
+                    } else {
+                        RecordEnd::Inconsistent(idx + 1)
+                    };
+                }
+            }
+            Structural::Colon(_) | Structural::Comma(_) => (),
+        }
+    }
+
+    let innermost = stack.last().map_or(start, |&(_, idx)| idx);
+    RecordEnd::Truncated(innermost + 1)
+}
+
+struct Recovery<'a, E: ?Sized, R> {
+    engine: &'a E,
+    bytes: &'a AlignedBytes<alignment::Page>,
+    result: R,
+    skipped: Vec<Range<usize>>,
+    /// Whether no record was found since the latest skipped span.
+    is_skipping: bool,
+}
+
+impl<'a, E: CanRunOnRecord + ?Sized, R: QueryResult> Recovery<'a, E, R> {
+    /// Execute the query on the record at `span` and report its matches,
+    /// or skip the record if the engine fails on it.
+    fn run_on_record(&mut self, span: Range<usize>) -> Result<(), EngineError> {
+        let mut classifier =
+            resume_structural_classification(classifier_state_at(self.bytes, span.start));
+        let mut matches = RecordMatches::<R>::default();
+        let outcome = match classifier.next() {
+            Some(opening) => {
+                self.engine
+                    .run_on_record(self.bytes, opening, classifier, &mut matches)
+            }
+            None => Ok(()),
+        };
+
+        match outcome {
+            Ok(()) => (),
+            Err(err @ EngineError::NotSupported(_)) => return Err(err),
+            Err(_) => {
+                self.skip(span);
+                return Ok(());
+            }
+        }
+
+        self.is_skipping = false;
+        for recorded in &matches.matches {
+            let context = MatchContext::new(
+                self.bytes,
+                recorded.depth,
+                &recorded.path,
+                recorded.token_index,
+            );
+            self.result
+                .report_in_context(self.bytes, recorded.index, &context);
+            if self.result.is_complete() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record the `span` as skipped, extending the latest skipped span
+    /// if no record was found since.
+    fn skip(&mut self, span: Range<usize>) {
+        match self.skipped.last_mut() {
+            Some(last) if self.is_skipping => last.end = span.end,
+            _ => self.skipped.push(span),
+        }
+        self.is_skipping = true;
+    }
+}
+
+/// Matches of the query in a single record, kept until the engine finishes the record
+/// without an error, together with the same context as tracked for `R`.
+#[derive(Debug, PartialEq)]
+struct RecordMatches<R> {
+    matches: Vec<RecordedMatch>,
+    phantom: PhantomData<R>,
+}
+
+/// Index and context of a match kept in [`RecordMatches`].
+#[derive(Debug, PartialEq)]
+struct RecordedMatch {
+    index: usize,
+    depth: usize,
+    path: Vec<PathStep>,
+    token_index: usize,
+}
+
+impl<R> Default for RecordMatches<R> {
+    fn default() -> Self {
+        Self {
+            matches: vec![],
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<R> Display for RecordMatches<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.matches)
+    }
+}
+
+impl<R: QueryResult> QueryResult for RecordMatches<R> {
+    const TRACKING: Tracking = R::TRACKING;
+
+    fn report(&mut self, bytes: &[u8], index: usize) {
+        self.report_in_context(bytes, index, &MatchContext::ROOT);
+    }
+
+    fn report_in_context(&mut self, _bytes: &[u8], index: usize, context: &MatchContext<'_>) {
+        self.matches.push(RecordedMatch {
+            index,
+            depth: context.depth(),
+            path: context.steps().to_vec(),
+            token_index: context.token_index(),
+        });
+    }
+}
@@ -1,23 +1,27 @@
 //! Reference implementation of a JSONPath query engine with recursive descent.
 #[cfg(feature = "head-skip")]
 use super::head_skipping::{CanHeadSkip, HeadSkip};
+use super::recovery::{self, CanRunOnRecord, Recovered};
 use crate::classification::quotes::{classify_quoted_sequences, QuoteClassifiedIterator};
 #[cfg(feature = "unique-labels")]
 use crate::classification::structural::BracketType;
 use crate::classification::structural::{
     classify_structural_characters, Structural, StructuralIterator,
 };
-#[cfg(feature = "head-skip")]
+use crate::classification::whitespace;
 use crate::classification::ResumeClassifierState;
 use crate::debug;
 use crate::engine::error::EngineError;
+use crate::engine::member_buffer::MemberBuffer;
 #[cfg(feature = "tail-skip")]
 use crate::engine::tail_skipping::TailSkip;
+use crate::engine::token_counter::TokenCounter;
 use crate::engine::{Compiler, Engine, Input};
+use crate::json;
 use crate::query::automaton::{Automaton, State};
 use crate::query::error::CompilerError;
 use crate::query::{JsonPathQuery, Label};
-use crate::result::QueryResult;
+use crate::result::{MatchContext, PathStep, QueryResult, Tracking};
 use aligners::{alignment, AlignedBytes, AlignedSlice};
 
 /// Recursive implementation of the JSONPath query engine.
@@ -44,9 +48,15 @@ impl Compiler for RecursiveEngine<'_> {
 
 impl Engine for RecursiveEngine<'_> {
     #[inline]
-    fn run<R: QueryResult>(&self, input: &Input) -> Result<R, EngineError> {
-        if self.automaton.is_empty_query() {
-            return empty_query(input);
+    fn run_into<R: QueryResult>(&self, input: &Input, result: &mut R) -> Result<(), EngineError> {
+        if result.is_complete() {
+            return Ok(());
+        }
+        if self.automaton.is_accepting(self.automaton.initial_state()) {
+            report_root(input, result);
+        }
+        if self.automaton.is_empty_query() || result.is_complete() {
+            return Ok(());
         }
 
         let aligned_bytes: &AlignedSlice<alignment::Page> = input;
@@ -59,36 +69,73 @@ impl Engine for RecursiveEngine<'_> {
 
         match classifier.next() {
             Some(Structural::Opening(_, idx)) => {
-                let mut result = R::default();
-                let mut execution_ctx = ExecutionContext::new(&self.automaton, input);
-                execution_ctx.run(
-                    &mut classifier,
-                    self.automaton.initial_state(),
-                    idx,
-                    &mut result,
-                )?;
-                Ok(result)
+                let mut execution_ctx =
+                    ExecutionContext::new(&self.automaton, input, R::TRACKING, 0);
+                execution_ctx.run(&mut classifier, self.automaton.initial_state(), idx, result)?;
+                Ok(())
             }
-            _ => Ok(R::default()),
+            _ => Ok(()),
         }
     }
+
+    #[inline]
+    fn run_lenient<R: QueryResult>(&self, input: &Input) -> Result<Recovered<R>, EngineError> {
+        recovery::run_lenient(self, input)
+    }
 }
 
-fn empty_query<R: QueryResult>(bytes: &AlignedBytes<alignment::Page>) -> Result<R, EngineError> {
-    let quote_classifier = classify_quoted_sequences(bytes.relax_alignment());
-    let mut block_event_source = classify_structural_characters(quote_classifier);
-    let mut result = R::default();
+impl CanRunOnRecord for RecursiveEngine<'_> {
+    fn run_on_record<'b, Q, I, R>(
+        &'b self,
+        bytes: &'b AlignedBytes<alignment::Page>,
+        opening: Structural,
+        classifier: I,
+        result: &mut R,
+    ) -> Result<(), EngineError>
+    where
+        Q: QuoteClassifiedIterator<'b>,
+        I: StructuralIterator<'b, Q>,
+        R: QueryResult,
+    {
+        let initial_state = self.automaton.initial_state();
 
-    if let Some(Structural::Opening(_, idx)) = block_event_source.next() {
-        result.report(idx);
+        if self.automaton.is_accepting(initial_state) {
+            result.report_in_context(bytes, opening.idx(), &MatchContext::ROOT);
+        }
+        if self.automaton.is_empty_query() || result.is_complete() {
+            return Ok(());
+        }
+
+        let mut execution_ctx =
+            ExecutionContext::new(&self.automaton, bytes, R::TRACKING, opening.idx());
+        execution_ctx.resume_on_subtree(opening, initial_state, classifier, result)?;
+
+        Ok(())
     }
+}
 
-    Ok(result)
+/// Report the root value, if the document is not empty.
+fn report_root<R: QueryResult>(bytes: &AlignedBytes<alignment::Page>, result: &mut R) {
+    if let Some(idx) = json::root_start(bytes) {
+        result.report_in_context(bytes, idx, &MatchContext::ROOT);
+    }
 }
 
 struct ExecutionContext<'q, 'b> {
     automaton: &'b Automaton<'q>,
     bytes: &'b AlignedBytes<alignment::Page>,
+    /// Depth of the subtree being run, the root being at depth 0.
+    depth: usize,
+    /// Whether the path to every match is tracked in `path`, in which case
+    /// the elements of all lists are counted.
+    tracks_paths: bool,
+    /// Steps of the path leading to the subtree being run.
+    path: Vec<PathStep>,
+    /// Counter of the tokens preceding every match, if their token indices are tracked.
+    tokens: Option<TokenCounter<'b>>,
+    /// Buffers of the subtrees being run, `None` for arrays, used only
+    /// if the query ends with a member from end selector.
+    member_buffers: Vec<Option<MemberBuffer>>,
 }
 
 #[cfg(feature = "tail-skip")]
@@ -105,11 +152,25 @@ macro_rules! Classifier {
 }
 
 impl<'q, 'b> ExecutionContext<'q, 'b> {
+    /// Create a context for running on the document starting at `start` in `bytes`,
+    /// tracking the parts of the matches' context requested by `tracking`.
     pub(crate) fn new(
         automaton: &'b Automaton<'q>,
         bytes: &'b AlignedBytes<alignment::Page>,
+        tracking: Tracking,
+        start: usize,
     ) -> Self {
-        Self { automaton, bytes }
+        Self {
+            automaton,
+            bytes,
+            depth: 0,
+            tracks_paths: tracking.is_path_tracked(),
+            path: vec![],
+            tokens: tracking
+                .is_token_index_tracked()
+                .then(|| TokenCounter::new(bytes, start)),
+            member_buffers: vec![],
+        }
     }
 
     #[cfg(feature = "head-skip")]
@@ -125,10 +186,16 @@ impl<'q, 'b> ExecutionContext<'q, 'b> {
         I: StructuralIterator<'b, Q>,
         R: QueryResult,
     {
-        let mb_head_skip = HeadSkip::new(self.bytes, self.automaton);
+        // Head-skipping reports matches without their context.
+        let mb_head_skip =
+            HeadSkip::new(self.bytes, self.automaton).filter(|_| R::TRACKING.is_none());
 
         match mb_head_skip {
-            Some(head_skip) => head_skip.run_head_skipping(self, result),
+            Some(head_skip) => head_skip.run_head_skipping(
+                self,
+                classify_quoted_sequences(self.bytes.relax_alignment()),
+                result,
+            ),
             None => self
                 .run_on_subtree(classifier, state, open_idx, result)
                 .map(|_| ()),
@@ -170,7 +237,10 @@ impl<'q, 'b> ExecutionContext<'q, 'b> {
         let fallback_state = self.automaton[state].fallback_state();
         let is_fallback_accepting = self.automaton.is_accepting(fallback_state);
         let is_list = self.bytes[open_idx] == b'[';
-        let needs_commas = is_list && is_fallback_accepting;
+        let has_index_transitions = self.automaton[state].has_element_transitions();
+        let needs_commas =
+            is_list && (self.tracks_paths || is_fallback_accepting || has_index_transitions);
+        let mut element_index = 0;
         let needs_colons = !is_list && self.automaton.has_transition_to_accepting(state);
 
         let config_characters = |classifier: &mut Classifier!(), idx: usize| {
@@ -189,26 +259,41 @@ impl<'q, 'b> ExecutionContext<'q, 'b> {
 
         config_characters(classifier, open_idx);
 
+        if let Some(position) = self.automaton.member_from_end() {
+            self.member_buffers
+                .push((!is_list).then(|| MemberBuffer::new(position)));
+        }
+
         if needs_commas {
             next_event = classifier.next();
+            let is_first_accepting = self
+                .automaton
+                .is_accepting(self.automaton[state].element_transition(0));
             if let Some(Structural::Closing(_, close_idx)) = next_event {
-                for idx in (open_idx + 1)..close_idx {
-                    if !self.bytes[idx].is_ascii_whitespace() {
-                        debug!("Accepting only item in the list.");
-                        result.report(idx);
-                        break;
+                if is_first_accepting {
+                    for idx in (open_idx + 1)..close_idx {
+                        if !self.bytes[idx].is_ascii_whitespace() {
+                            debug!("Accepting only item in the list.");
+                            self.report(result, idx, PathStep::Element(0));
+                            break;
+                        }
                     }
                 }
+                self.close_member_buffer(result);
                 return Ok(close_idx);
             }
 
-            if matches!(next_event, Some(Structural::Comma(_))) {
+            if is_first_accepting && matches!(next_event, Some(Structural::Comma(_))) {
                 debug!("Accepting first item in the list.");
-                result.report(open_idx + 1);
+                self.report(result, open_idx + 1, PathStep::Element(0));
             }
         }
 
         loop {
+            if result.is_complete() {
+                debug!("Result is complete, stopping early.");
+                break;
+            }
             if next_event.is_none() {
                 next_event = classifier.next();
             }
@@ -218,10 +303,12 @@ impl<'q, 'b> ExecutionContext<'q, 'b> {
                     latest_idx = idx;
                     next_event = classifier.next();
                     let is_next_opening = next_event.map_or(false, |s| s.is_opening());
+                    element_index += 1;
+                    let target = self.automaton[state].element_transition(element_index);
 
-                    if !is_next_opening && is_list && is_fallback_accepting {
+                    if !is_next_opening && is_list && self.automaton.is_accepting(target) {
                         debug!("Accepting on comma.");
-                        result.report(idx);
+                        self.report(result, idx, PathStep::Element(element_index));
                     }
                 }
                 Some(Structural::Colon(idx)) => {
@@ -238,18 +325,28 @@ impl<'q, 'b> ExecutionContext<'q, 'b> {
                     if !is_next_opening {
                         let mut any_matched = false;
 
+                        // A matched label takes precedence over patterns and the fallback
+                        // even if its target is not accepting, which is the case for excluded labels.
                         for &(label, target) in self.automaton[state].transitions() {
-                            if self.automaton.is_accepting(target) && self.is_match(idx, label)? {
-                                debug!("Accept {idx}");
-                                result.report(idx);
+                            if self.is_match(idx, label)? {
+                                if self.automaton.is_accepting(target) {
+                                    debug!("Accept {idx}");
+                                    self.report(result, idx, PathStep::Member(idx));
+                                }
                                 any_matched = true;
                                 break;
                             }
                         }
-                        let fallback_state = self.automaton[state].fallback_state();
-                        if !any_matched && self.automaton.is_accepting(fallback_state) {
-                            debug!("Value accepted by fallback.");
-                            result.report(idx);
+                        if !any_matched {
+                            if let Some(target) = self.pattern_transition(state, idx)? {
+                                if self.automaton.is_accepting(target) {
+                                    debug!("Value accepted by pattern.");
+                                    self.report(result, idx, PathStep::Member(idx));
+                                }
+                            } else if self.automaton.is_accepting(fallback_state) {
+                                debug!("Value accepted by fallback.");
+                                self.report(result, idx, PathStep::Member(idx));
+                            }
                         }
                         #[cfg(feature = "unique-labels")]
                         {
@@ -264,20 +361,20 @@ impl<'q, 'b> ExecutionContext<'q, 'b> {
                                 } else {
                                     BracketType::Curly
                                 };
-                                next_event = Some(Structural::Opening(bracket_type, stop_at));
+                                next_event = Some(Structural::Closing(bracket_type, stop_at));
                             }
                         }
                     }
                 }
                 Some(Structural::Opening(_, idx)) => {
                     let mut matched = None;
-                    let colon_idx = {
-                        let mut colon_idx = idx - 1;
-                        while colon_idx > 0 && self.bytes[colon_idx].is_ascii_whitespace() {
-                            colon_idx -= 1;
-                        }
-                        (self.bytes[colon_idx] == b':').then_some(colon_idx)
+                    let step = if is_list {
+                        PathStep::Element(element_index)
+                    } else {
+                        PathStep::Member(idx)
                     };
+                    let colon_idx = whitespace::last_non_whitespace(&self.bytes[..idx])
+                        .filter(|&colon_idx| self.bytes[colon_idx] == b':');
 
                     if let Some(colon_idx) = colon_idx {
                         debug!(
@@ -293,33 +390,59 @@ impl<'q, 'b> ExecutionContext<'q, 'b> {
                                 matched = Some(target);
                                 if self.automaton.is_accepting(target) {
                                     debug!("Accept {idx}");
-                                    result.report(colon_idx);
+                                    self.report(result, colon_idx, PathStep::Member(colon_idx));
                                 }
                                 break;
                             }
                         }
+
+                        if matched.is_none() {
+                            matched = self.pattern_transition(state, colon_idx)?;
+                            if let Some(target) = matched {
+                                if self.automaton.is_accepting(target) {
+                                    debug!("Accept {idx}");
+                                    self.report(result, colon_idx, PathStep::Member(colon_idx));
+                                }
+                            }
+                        }
                     }
 
                     let end_idx = match matched {
-                        Some(target) => self.run_on_subtree(classifier, target, idx, result)?,
+                        // Nothing within a matched value reached in a terminal state can match,
+                        // e.g. when only the outermost matches are reported.
+                        #[cfg(feature = "tail-skip")]
+                        Some(target) if self.automaton.is_terminal(target) => {
+                            classifier.skip(self.bytes[idx])
+                        }
+                        Some(target) => {
+                            self.run_on_nested_subtree(classifier, target, idx, step, result)?
+                        }
                         None => {
-                            let fallback = self.automaton[state].fallback_state();
+                            let fallback = if is_list {
+                                self.automaton[state].element_transition(element_index)
+                            } else {
+                                fallback_state
+                            };
                             debug!("Falling back to {fallback}");
 
                             if self.automaton.is_accepting(fallback) {
                                 debug!("Accept {idx}");
-                                result.report(idx);
+                                self.report(result, idx, step);
                             }
 
+                            // Nothing within a value reached in a terminal state can match,
+                            // e.g. an array element selected by a filter.
                             #[cfg(feature = "tail-skip")]
-                            if self.automaton.is_rejecting(fallback_state) {
+                            if self.automaton.is_rejecting(fallback)
+                                || self.automaton.is_terminal(fallback)
+                            {
                                 classifier.skip(self.bytes[idx])
                             } else {
-                                self.run_on_subtree(classifier, fallback_state, idx, result)?
+                                self.run_on_nested_subtree(classifier, fallback, idx, step, result)?
                             }
                             #[cfg(not(feature = "tail-skip"))]
                             {
-                                self.run_on_subtree(classifier, fallback_state, idx, result)?
+                                self.run_on_nested_subtree(classifier, fallback, idx, step, result)?
                             }
                         }
                     };
@@ -349,10 +472,88 @@ impl<'q, 'b> ExecutionContext<'q, 'b> {
             }
         }
 
+        self.close_member_buffer(result);
+
         Ok(latest_idx)
     }
 
+    /// Run on the subtree opened at `open_idx`, which is a value within the current subtree
+    /// at the given path `step`.
+    fn run_on_nested_subtree<'r, Q, I, R>(
+        &mut self,
+        classifier: &mut Classifier!(),
+        state: State,
+        open_idx: usize,
+        step: PathStep,
+        result: &'r mut R,
+    ) -> Result<usize, EngineError>
+    where
+        Q: QuoteClassifiedIterator<'b>,
+        I: StructuralIterator<'b, Q>,
+        R: QueryResult,
+    {
+        self.depth += 1;
+        if self.tracks_paths {
+            self.path.push(step);
+        }
+        let outcome = self.run_on_subtree(classifier, state, open_idx, result);
+        if self.tracks_paths {
+            self.path.pop();
+        }
+        self.depth -= 1;
+
+        outcome
+    }
+
+    fn report<R: QueryResult>(&mut self, result: &mut R, idx: usize, step: PathStep) {
+        if let Some(filter) = self.automaton.filter() {
+            if !filter.matches_at(self.bytes, idx, self.automaton.number_syntax()) {
+                debug!("Value at {idx} rejected by the filter");
+                return;
+            }
+        }
+        if self.automaton.member_from_end().is_some() {
+            if let Some(Some(buffer)) = self.member_buffers.last_mut() {
+                buffer.push(idx);
+            }
+            return;
+        }
+        self.accept(result, idx, step);
+    }
+
+    /// Report the value at `idx` within the current subtree at the given path `step`.
+    fn accept<R: QueryResult>(&mut self, result: &mut R, idx: usize, step: PathStep) {
+        if self.tracks_paths {
+            self.path.push(step);
+        }
+        let token_index = self
+            .tokens
+            .as_mut()
+            .map_or(0, |tokens| tokens.token_index_at(idx));
+
+        let context = MatchContext::new(self.bytes, self.depth + 1, &self.path, token_index);
+        result.report_in_context(self.bytes, idx, &context);
+
+        if self.tracks_paths {
+            self.path.pop();
+        }
+    }
+
+    fn close_member_buffer<R: QueryResult>(&mut self, result: &mut R) {
+        if let Some(Some(buffer)) = self.member_buffers.pop() {
+            if let Some(idx) = buffer.selected() {
+                debug!("Member at {idx} selected");
+                self.accept(result, idx, PathStep::Member(idx));
+            }
+        }
+    }
+
     fn is_match(&self, idx: usize, label: &Label) -> Result<bool, EngineError> {
+        if label.has_control_escape() {
+            return json::key_matches_label(self.bytes, idx, label)
+                .ok_or(EngineError::MalformedLabelQuotes(idx));
+        }
+
         let len = label.len() + 2;
 
         let mut closing_quote_idx = idx - 1;
@@ -374,6 +575,22 @@ impl<'q, 'b> ExecutionContext<'q, 'b> {
         Ok(label.bytes_with_quotes() == slice
             && (start_idx == 0 || self.bytes[start_idx - 1] != b'\\'))
     }
+
+    fn pattern_transition(
+        &self,
+        state: State,
+        colon_idx: usize,
+    ) -> Result<Option<State>, EngineError> {
+        let table = &self.automaton[state];
+        if !table.has_pattern_transitions() {
+            return Ok(None);
+        }
+
+        let key = json::key_before_colon(self.bytes, colon_idx)
+            .ok_or(EngineError::MalformedLabelQuotes(colon_idx))?;
+
+        Ok(table.pattern_transition(key))
+    }
 }
 
 #[cfg(feature = "head-skip")]
@@ -385,6 +602,26 @@ impl<'q, 'b> CanHeadSkip<'b> for ExecutionContext<'q, 'b> {
         structural_classifier: I,
         result: &'r mut R,
     ) -> Result<ResumeClassifierState<'b, Q>, EngineError>
+    where
+        Q: QuoteClassifiedIterator<'b>,
+        R: QueryResult,
+        I: StructuralIterator<'b, Q>,
+    {
+        self.resume_on_subtree(next_event, state, structural_classifier, result)
+    }
+}
+
+impl<'q, 'b> ExecutionContext<'q, 'b> {
+    /// Execute the query in the given `state` on the subtree opened by `next_event`,
+    /// which is the last event classified by the `structural_classifier`,
+    /// and return the state of the classifier after the subtree is closed.
+    fn resume_on_subtree<R, Q, I>(
+        &mut self,
+        next_event: Structural,
+        state: State,
+        structural_classifier: I,
+        result: &mut R,
+    ) -> Result<ResumeClassifierState<'b, Q>, EngineError>
     where
         Q: QuoteClassifiedIterator<'b>,
         R: QueryResult,
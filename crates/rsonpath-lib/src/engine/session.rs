@@ -0,0 +1,152 @@
+//! Running many queries on the same document.
+//!
+//! The [`Session`] is bound to a single [`Input`] and classifies its quoted sequences only once,
+//! when it is created. All queries run in the session reuse that classification instead
+//! of classifying the document again, like an [`Engine`](super::Engine) does on every run.
+//! The classification is stored as one bit per byte of the input, so the session takes up
+//! an eighth of the size of the input on top of it.
+//!
+//! A session created with [`Session::new_with_depth_profile`] also precomputes where every
+//! object and array of the input ends. Queries then skip irrelevant subtrees with a binary
+//! search instead of classifying their depth. The profile takes up 16 bytes for every bracket
+//! in the input on 64-bit targets, which can be more than the size of the input itself
+//! for documents with many small objects and arrays, so it pays off only when many queries
+//! are run on the same document.
+//! It is only used if the `tail-skip` feature is enabled.
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::engine::session::Session;
+//! use rsonpath_lib::engine::Input;
+//! use rsonpath_lib::query::{automaton::Automaton, JsonPathQuery};
+//! use rsonpath_lib::result::CountResult;
+//! # use std::error::Error;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let mut contents = r#"{"a": [1, {"b": "]"}], "b": {"a": 2}}"#.to_owned();
+//! let input = Input::new(&mut contents);
+//! let session = Session::new(&input);
+//!
+//! for (query, count) in [("$..a", 2), ("$..b", 2), ("$.a.*", 2), ("$.b.b", 0)] {
+//!     let query = JsonPathQuery::parse(query)?;
+//!     let automaton = Automaton::new(&query)?;
+//!     assert_eq!(session.run::<CountResult>(&automaton)?.get(), count);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use super::error::EngineError;
+use super::main;
+use super::Input;
+use crate::classification::depth::profile::DepthProfile;
+use crate::classification::quotes::{quote_masks, CachedQuoteClassifier};
+use crate::query::automaton::Automaton;
+use crate::result::QueryResult;
+
+/// Input with its quote classification, on which any number of queries can be run.
+///
+/// The results of all queries are the same as those of the [`MainEngine`](super::main::MainEngine).
+pub struct Session<'i> {
+    input: &'i Input,
+    quote_masks: Vec<u64>,
+    depth_profile: Option<DepthProfile>,
+}
+
+impl<'i> Session<'i> {
+    /// Classify the quoted sequences of the `input` and bind it to a new session.
+    #[must_use]
+    #[inline]
+    pub fn new(input: &'i Input) -> Self {
+        Self {
+            input,
+            quote_masks: quote_masks(input.relax_alignment()),
+            depth_profile: None,
+        }
+    }
+
+    /// Classify the quoted sequences of the `input`, compute its depth profile,
+    /// and bind it to a new session.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use rsonpath_lib::engine::session::Session;
+    /// use rsonpath_lib::engine::Input;
+    /// use rsonpath_lib::query::{automaton::Automaton, JsonPathQuery};
+    /// use rsonpath_lib::result::CountResult;
+    /// # use std::error::Error;
+    ///
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let mut contents = r#"{"a": [{"b": 1}, {"c": [2]}], "b": {"a": 3}}"#.to_owned();
+    /// let input = Input::new(&mut contents);
+    /// let session = Session::new_with_depth_profile(&input);
+    ///
+    /// let query = JsonPathQuery::parse("$.a[0].b")?;
+    /// let automaton = Automaton::new(&query)?;
+    /// assert_eq!(session.run::<CountResult>(&automaton)?.get(), 1);
+    /// assert!(session.approx_memory_bytes() > Session::new(&input).approx_memory_bytes());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn new_with_depth_profile(input: &'i Input) -> Self {
+        let bytes = input.relax_alignment();
+        let quote_masks = quote_masks(bytes);
+        let depth_profile =
+            DepthProfile::new(CachedQuoteClassifier::new(bytes, &quote_masks), bytes.len());
+
+        Self {
+            input,
+            quote_masks,
+            depth_profile: Some(depth_profile),
+        }
+    }
+
+    /// The input to which the session is bound.
+    #[must_use]
+    #[inline(always)]
+    pub fn input(&self) -> &'i Input {
+        self.input
+    }
+
+    /// Compute the [`QueryResult`] of the query represented by the `automaton` on the input
+    /// of the session.
+    ///
+    /// # Errors
+    /// Same as [`Engine::run`](super::Engine::run).
+    #[inline]
+    pub fn run<R: QueryResult>(&self, automaton: &Automaton) -> Result<R, EngineError> {
+        let mut result = R::default();
+        let quote_classifier =
+            CachedQuoteClassifier::new(self.input.relax_alignment(), &self.quote_masks);
+        let options = main::RunOptions {
+            depth_profile: self.depth_profile.as_ref(),
+            ..main::RunOptions::default()
+        };
+        main::run_with(
+            automaton,
+            self.input,
+            quote_classifier,
+            options,
+            &mut result,
+        )?;
+
+        Ok(result)
+    }
+
+    /// Estimate the number of bytes of heap memory taken up by the cached classification,
+    /// not including the input itself.
+    ///
+    /// The estimate is exact up to the unused capacity of the allocations,
+    /// one bit for every byte of the padded input, and two [`usize`] values
+    /// for every bracket if the session has a depth profile.
+    #[must_use]
+    #[inline]
+    pub fn approx_memory_bytes(&self) -> usize {
+        self.quote_masks.len() * std::mem::size_of::<u64>()
+            + self
+                .depth_profile
+                .as_ref()
+                .map_or(0, DepthProfile::approx_memory_bytes)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::classification::depth::profile::DepthProfile;
 use crate::classification::depth::{
     resume_depth_classification, DepthBlock, DepthIterator, DepthIteratorResumeOutcome,
 };
@@ -15,6 +16,7 @@ where
     I: StructuralIterator<'b, Q>,
 {
     classifier: I,
+    depth_profile: Option<&'b DepthProfile>,
     phantom: PhantomData<&'b Q>,
 }
 
@@ -24,13 +26,27 @@ where
     I: StructuralIterator<'b, Q>,
 {
     pub(crate) fn new(classifier: I) -> Self {
+        Self::with_depth_profile(classifier, None)
+    }
+
+    /// Skip with the precomputed `depth_profile` of the document instead of
+    /// classifying depth, if it is given.
+    pub(crate) fn with_depth_profile(
+        classifier: I,
+        depth_profile: Option<&'b DepthProfile>,
+    ) -> Self {
         Self {
             classifier,
+            depth_profile,
             phantom: PhantomData,
         }
     }
 
     pub(crate) fn skip(&mut self, opening: u8) -> usize {
+        if let Some(depth_profile) = self.depth_profile {
+            return self.skip_with_profile(depth_profile);
+        }
+
         debug!("Skipping");
         let mut idx = 0;
 
@@ -71,6 +87,28 @@ where
         idx
     }
 
+    fn skip_with_profile(&mut self, depth_profile: &DepthProfile) -> usize {
+        debug!("Skipping with the depth profile");
+        let mut idx = 0;
+
+        replace_with_or_abort(&mut self.classifier, |classifier| {
+            let mut resume_state = classifier.stop();
+            // Without a block the classifier is at the start of the next one.
+            let from = match resume_state.block {
+                Some(_) => resume_state.get_idx(),
+                None => resume_state.get_idx() + Q::block_size(),
+            };
+            let closing = depth_profile.enclosing_closing(from);
+
+            resume_state.offset_bytes((closing + 1 - from) as isize);
+            debug!("Finished at {}", resume_state.get_idx());
+            idx = resume_state.get_idx();
+            I::resume(resume_state)
+        });
+
+        idx
+    }
+
     #[cfg(feature = "head-skip")]
     pub(crate) fn stop(self) -> ResumeClassifierState<'b, Q> {
         self.classifier.stop()
@@ -0,0 +1,109 @@
+//! Breakdown of the time spent by the [`MainEngine`](super::main::MainEngine) on its work.
+//!
+//! The execution time is split between classification of the input, i.e. advancing
+//! the quote and structural classifiers to the next structural event or skipping over subtrees,
+//! and handling of the events, i.e. stepping the automaton and reporting matches.
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::engine::{Compiler, Input, RsonpathEngine};
+//! use rsonpath_lib::engine::timing::RunStats;
+//! use rsonpath_lib::query::JsonPathQuery;
+//! use rsonpath_lib::result::CountResult;
+//! # use std::error::Error;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let query = JsonPathQuery::parse("$..b")?;
+//! let mut contents = r#"{"a": {"b": 42}, "b": [1, 2]}"#.to_owned();
+//! let input = Input::new(&mut contents);
+//! let engine = RsonpathEngine::compile_query(&query)?;
+//! let mut stats = RunStats::new();
+//!
+//! let count = engine.run_timed::<CountResult>(&input, &mut stats)?.get();
+//!
+//! assert_eq!(count, 2);
+//! assert_eq!(
+//!     stats.total_nanos(),
+//!     stats.classification_nanos() + stats.handling_nanos()
+//! );
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+/// Cumulative time spent on classification and event handling during query execution.
+///
+/// Times are added up over all runs using the same stats, so a single [`RunStats`]
+/// can be used to measure a series of runs. The time needed to read the clock
+/// is included in the measurements, so the breakdown is only accurate for inputs
+/// taking considerably longer to process than a single structural event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RunStats {
+    classification_nanos: u64,
+    handling_nanos: u64,
+}
+
+impl RunStats {
+    /// Create stats with no time recorded.
+    #[must_use]
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Nanoseconds spent in the classifiers, finding the next structural event
+    /// or skipping over a subtree.
+    #[must_use]
+    #[inline(always)]
+    pub fn classification_nanos(&self) -> u64 {
+        self.classification_nanos
+    }
+
+    /// Nanoseconds spent handling the structural events, i.e. in the engine itself.
+    #[must_use]
+    #[inline(always)]
+    pub fn handling_nanos(&self) -> u64 {
+        self.handling_nanos
+    }
+
+    /// Sum of the classification and handling times.
+    #[must_use]
+    #[inline(always)]
+    pub fn total_nanos(&self) -> u64 {
+        self.classification_nanos + self.handling_nanos
+    }
+
+    /// Set all recorded times back to zero.
+    #[inline(always)]
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Add `elapsed` to the classification time.
+    pub(crate) fn add_classification(&mut self, elapsed: Duration) {
+        self.classification_nanos += as_nanos(elapsed);
+    }
+
+    /// Add the part of `elapsed` not spent on classification to the handling time,
+    /// given the classification time recorded before the measured period started.
+    pub(crate) fn add_handling(&mut self, elapsed: Duration, classification_before: u64) {
+        let classification = self.classification_nanos - classification_before;
+        self.handling_nanos += as_nanos(elapsed).saturating_sub(classification);
+    }
+}
+
+fn as_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+impl Display for RunStats {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "classification: {}ns, handling: {}ns",
+            self.classification_nanos, self.handling_nanos
+        )
+    }
+}
@@ -0,0 +1,109 @@
+//! Counting of the tokens preceding the values matched by the engines.
+//!
+//! Tokens are the structural characters outside of strings, i.e. `{`, `}`, `[`, `]`, `:`, and `,`.
+//! The engines classify commas and colons only when the query needs them and skip
+//! whole subtrees, so the tokens are counted by a separate structural classifier
+//! with all of them turned on, advanced up to every match.
+use crate::classification::classifier_state_at;
+use crate::classification::structural::{resume_structural_classification, Structural};
+use crate::json;
+use aligners::{alignment, AlignedBytes};
+use std::iter::Peekable;
+
+type Tokens<'b> = Peekable<Box<dyn Iterator<Item = Structural> + 'b>>;
+
+/// Token indices of the values of a single document, counted in one pass
+/// when the values are queried in the order of their positions.
+pub(crate) struct TokenCounter<'b> {
+    bytes: &'b AlignedBytes<alignment::Page>,
+    tokens: Tokens<'b>,
+    /// Number of tokens consumed from `tokens`.
+    count: usize,
+    /// Start of the value queried last.
+    position: usize,
+    /// Positions of tokens consumed by previous queries, with the number of tokens
+    /// preceding them, in increasing order. The first one is the start of the document.
+    checkpoints: Vec<(usize, usize)>,
+}
+
+impl<'b> TokenCounter<'b> {
+    /// Create a counter for the document starting at `start` in `bytes`,
+    /// which must not be within a string.
+    pub(crate) fn new(bytes: &'b AlignedBytes<alignment::Page>, start: usize) -> Self {
+        Self {
+            bytes,
+            tokens: classify_tokens(bytes, start),
+            count: 0,
+            position: start,
+            checkpoints: vec![(start, 0)],
+        }
+    }
+
+    /// Token index of the value reported at `idx`, as in [`QueryResult::report`](crate::result::QueryResult::report).
+    ///
+    /// Values reported out of order, like buffered members, restart the counting
+    /// from the nearest token preceding them that was already counted.
+    pub(crate) fn token_index_at(&mut self, idx: usize) -> usize {
+        let value_start = json::value_start(self.bytes, idx);
+
+        if value_start < self.position {
+            let kept = self
+                .checkpoints
+                .partition_point(|&(checkpoint, _)| checkpoint < value_start);
+            self.checkpoints.truncate(kept.max(1));
+            let (checkpoint, count) = self.checkpoints[self.checkpoints.len() - 1];
+            self.tokens = classify_tokens(self.bytes, checkpoint);
+            self.count = count;
+        }
+
+        let mut last = None;
+        while let Some(token) = self.tokens.next_if(|token| token.idx() < value_start) {
+            self.count += 1;
+            last = Some(token.idx());
+        }
+        if let Some(last) = last {
+            self.checkpoints.push((last, self.count - 1));
+        }
+        self.position = value_start;
+
+        self.count
+    }
+}
+
+/// Classify all tokens of `bytes` starting at `start`.
+fn classify_tokens(bytes: &AlignedBytes<alignment::Page>, start: usize) -> Tokens<'_> {
+    let mut state = classifier_state_at(bytes, start);
+    state.are_commas_on = true;
+    state.are_colons_on = true;
+    let tokens: Box<dyn Iterator<Item = Structural> + '_> =
+        Box::new(resume_structural_classification(state));
+
+    tokens.peekable()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(&[0, 4, 8, 14, 28] => vec![0, 2, 4, 6, 10]; "in order")]
+    #[test_case(&[8, 4, 28, 14] => vec![4, 2, 10, 6]; "out of order")]
+    fn token_index_at_test(indices: &[usize]) -> Vec<usize> {
+        let bytes = AlignedBytes::new_padded(r#"{"a": [1, {"b": "}:,"}], "c": 2}"#.as_bytes());
+        let mut counter = TokenCounter::new(&bytes, 0);
+
+        indices
+            .iter()
+            .map(|&idx| counter.token_index_at(idx))
+            .collect()
+    }
+
+    #[test]
+    fn token_index_at_within_document_test() {
+        let bytes = AlignedBytes::new_padded(r#"{"a": "{"} {"b": [1]}"#.as_bytes());
+        let mut counter = TokenCounter::new(&bytes, 11);
+
+        assert_eq!(counter.token_index_at(15), 2);
+        assert_eq!(counter.token_index_at(18), 3);
+    }
+}
@@ -0,0 +1,163 @@
+//! Structured trace of the execution of a query by the [`MainEngine`](super::main::MainEngine).
+//!
+//! The trace records every structural event processed by the engine, together with
+//! the depth and automaton state at which it was processed, and the actions it caused.
+//! Unlike the debug logging of the engines, it is available in release builds.
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::engine::{Compiler, Input, RsonpathEngine};
+//! use rsonpath_lib::engine::trace::{Trace, TraceAction};
+//! use rsonpath_lib::query::JsonPathQuery;
+//! use rsonpath_lib::result::CountResult;
+//! # use std::error::Error;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let query = JsonPathQuery::parse("$.a.b")?;
+//! let mut contents = r#"{"a": {"b": 42}}"#.to_owned();
+//! let input = Input::new(&mut contents);
+//! let engine = RsonpathEngine::compile_query(&query)?;
+//! let mut trace = Trace::new();
+//!
+//! let count = engine.run_traced::<CountResult>(&input, &mut trace)?.get();
+//!
+//! assert_eq!(count, 1);
+//! assert!(trace
+//!     .entries()
+//!     .iter()
+//!     .any(|entry| entry.action == TraceAction::Report(10)));
+//! # Ok(())
+//! # }
+//! ```
+use crate::classification::structural::Structural;
+use crate::query::automaton::State;
+use std::fmt::{self, Display};
+
+/// Action taken by the engine in response to a structural event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TraceAction {
+    /// The event did not cause any action.
+    None,
+    /// A match was reported at the given index.
+    Report(usize),
+    /// The engine entered a subtree and transitioned to the given state.
+    Transition(State),
+    /// The engine left a subtree and returned to the given state.
+    Return(State),
+    /// The engine skipped the rest of the current subtree without processing it.
+    Skip,
+}
+
+/// A single entry in a [`Trace`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceEntry {
+    /// The structural event being processed.
+    pub event: Structural,
+    /// Depth in the document before processing the event.
+    pub depth: u8,
+    /// Automaton state before processing the event.
+    pub state: State,
+    /// Action taken.
+    pub action: TraceAction,
+}
+
+/// Buffer collecting [`TraceEntries`](TraceEntry) during query execution.
+///
+/// An event causing multiple actions is recorded as multiple entries, one for each action,
+/// in the order they were taken. An event causing no actions is recorded once
+/// with [`TraceAction::None`].
+///
+/// Subtrees skipped by the engine are not traced, apart from the [`TraceAction::Skip`]
+/// entry for the event that caused the skip.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    entries: Vec<TraceEntry>,
+    current: Option<(Structural, u8, State)>,
+    current_recorded: bool,
+}
+
+impl Trace {
+    /// Create an empty trace.
+    #[must_use]
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All entries recorded thus far, in order.
+    #[must_use]
+    #[inline(always)]
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// Remove all recorded entries.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.finish_event();
+        self.entries.clear();
+    }
+
+    /// Start recording actions for a new `event`.
+    pub(crate) fn start_event(&mut self, event: Structural, depth: u8, state: State) {
+        self.finish_event();
+        self.current = Some((event, depth, state));
+        self.current_recorded = false;
+    }
+
+    /// Record an `action` taken in response to the current event.
+    pub(crate) fn record(&mut self, action: TraceAction) {
+        if let Some((event, depth, state)) = self.current {
+            self.entries.push(TraceEntry {
+                event,
+                depth,
+                state,
+                action,
+            });
+            self.current_recorded = true;
+        }
+    }
+
+    /// Finish recording actions for the current event, if any.
+    pub(crate) fn finish_event(&mut self) {
+        if !self.current_recorded {
+            self.record(TraceAction::None);
+        }
+        self.current = None;
+    }
+}
+
+impl Display for TraceAction {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Report(idx) => write!(f, "report {idx}"),
+            Self::Transition(state) => write!(f, "goto {state}"),
+            Self::Return(state) => write!(f, "return to {state}"),
+            Self::Skip => write!(f, "skip"),
+        }
+    }
+}
+
+impl Display for TraceEntry {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} depth={} state={}: {}",
+            self.event, self.depth, self.state, self.action
+        )
+    }
+}
+
+impl Display for Trace {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{entry}")?;
+        }
+        Ok(())
+    }
+}
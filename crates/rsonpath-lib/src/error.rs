@@ -1,4 +1,6 @@
 //! Common errors shared across the library.
+use crate::engine::error::EngineError;
+use crate::query::error::{CompilerError, ParserError};
 use std::fmt::Display;
 use thiserror::Error;
 
@@ -52,6 +54,62 @@ impl UnsupportedFeatureError {
         Self::untracked("Large Automaton Queries")
     }
 
+    /// Non-terminal Filters feature &ndash; filter selectors followed by other selectors
+    /// in a query. Unsupported and not planned.
+    #[must_use]
+    #[inline(always)]
+    pub fn non_terminal_filters() -> Self {
+        Self::untracked("Non-terminal Filters")
+    }
+
+    /// Filters in Alternations feature &ndash; filter selectors in an alternation
+    /// of more than one query. Unsupported and not planned.
+    #[must_use]
+    #[inline(always)]
+    pub fn filters_in_alternations() -> Self {
+        Self::untracked("Filters in Alternations")
+    }
+
+    /// Complement of Non-label Selectors feature &ndash; complement automata of queries
+    /// ending with a wildcard, pattern, or filter selector. Unsupported and not planned.
+    #[must_use]
+    #[inline(always)]
+    pub fn complement_of_non_label_selectors() -> Self {
+        Self::untracked("Complement of Non-label Selectors")
+    }
+
+    /// Non-terminal Member From End Selectors feature &ndash; `[last()]` selectors followed
+    /// by other selectors in a query. Unsupported and not planned.
+    #[must_use]
+    #[inline(always)]
+    pub fn non_terminal_member_from_end() -> Self {
+        Self::untracked("Non-terminal Member From End Selectors")
+    }
+
+    /// Member From End Selectors in Alternations feature &ndash; `[last()]` selectors
+    /// in an alternation of more than one query. Unsupported and not planned.
+    #[must_use]
+    #[inline(always)]
+    pub fn member_from_end_in_alternations() -> Self {
+        Self::untracked("Member From End Selectors in Alternations")
+    }
+
+    /// Outermost Matches of Filters feature &ndash; automata reporting only outermost matches
+    /// of queries ending with a filter or `[last()]` selector. Unsupported and not planned.
+    #[must_use]
+    #[inline(always)]
+    pub fn outermost_matches_of_filters() -> Self {
+        Self::untracked("Outermost Matches of Filters")
+    }
+
+    /// Lenient Runs feature &ndash; [`Engine::run_lenient`](crate::engine::Engine::run_lenient)
+    /// on an engine that does not implement recovery. Depends on the engine.
+    #[must_use]
+    #[inline(always)]
+    pub fn lenient_runs() -> Self {
+        Self::untracked("Lenient Runs")
+    }
+
     /// Returns the issue number on GitHub corresponding to the unsupported feature.
     /// Is [`None`] if the feature is not planned.
     #[must_use]
@@ -99,3 +157,20 @@ impl Display for UnsupportedFeatureError {
         }
     }
 }
+
+/// Error raised by the functions running a query in a single call, like [`count`](crate::count),
+/// at any stage of the execution.
+///
+/// Each variant holds the original error of the stage that failed.
+#[derive(Debug, Error)]
+pub enum QueryError {
+    /// The query could not be parsed.
+    #[error(transparent)]
+    Parser(#[from] ParserError),
+    /// The query could not be compiled.
+    #[error(transparent)]
+    Compiler(#[from] CompilerError),
+    /// The query could not be executed on the document.
+    #[error(transparent)]
+    Engine(#[from] EngineError),
+}
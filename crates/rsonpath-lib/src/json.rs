@@ -0,0 +1,549 @@
+//! Utilities for inspecting raw JSON directly in the input bytes.
+//!
+//! These are slow, scalar helpers meant for the rare cases where the engines
+//! need to look at the actual contents of the document, for example to extract
+//! a key that cannot be matched by simple byte comparison with a [`Label`](crate::query::Label).
+use crate::classification::whitespace;
+use crate::query::Label;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Find the key of the object member whose colon is at `colon_idx`.
+///
+/// Returns the raw contents of the key between its quotes, with all escape
+/// sequences intact, or [`None`] if the bytes before the colon do not form a quoted key.
+pub(crate) fn key_before_colon(bytes: &[u8], colon_idx: usize) -> Option<&[u8]> {
+    let closing_quote_idx = whitespace::last_non_whitespace(&bytes[..colon_idx])?;
+
+    if bytes[closing_quote_idx] != b'"' {
+        return None;
+    }
+
+    let mut opening_quote_idx = closing_quote_idx;
+    loop {
+        opening_quote_idx = opening_quote_idx.checked_sub(1)?;
+
+        if bytes[opening_quote_idx] == b'"' && !is_escaped(bytes, opening_quote_idx) {
+            return Some(&bytes[opening_quote_idx + 1..closing_quote_idx]);
+        }
+    }
+}
+
+/// Find the key of the object member whose value is reported by an engine at `idx`,
+/// as in [`value_at`].
+///
+/// Returns the raw contents of the key between its quotes, with all escape sequences intact,
+/// or [`None`] if the value is not a member of an object, e.g. it is an array element.
+pub(crate) fn key_of_value(bytes: &[u8], idx: usize) -> Option<&[u8]> {
+    let start = value_start(bytes, idx);
+    let colon_idx = whitespace::last_non_whitespace(&bytes[..start.min(bytes.len())])?;
+
+    if bytes[colon_idx] == b':' {
+        key_before_colon(bytes, colon_idx)
+    } else {
+        None
+    }
+}
+
+/// Find the value reported by an engine at `idx`.
+///
+/// The `idx` is either the index of the colon or comma preceding the value,
+/// or an index from which the value can be found by skipping whitespace,
+/// as described in [`QueryResult::report`](crate::result::QueryResult::report).
+/// Returns the bytes of the entire value, i.e. including quotes of strings
+/// and brackets of objects and arrays.
+pub(crate) fn value_at(bytes: &[u8], idx: usize) -> &[u8] {
+    &bytes[value_span(bytes, idx)]
+}
+
+/// Find the range of indices of the value reported at `idx`, as in [`value_at`].
+pub(crate) fn value_span(bytes: &[u8], idx: usize) -> Range<usize> {
+    let start = value_start(bytes, idx);
+
+    let end = match bytes.get(start) {
+        Some(b'"') => string_end(bytes, start),
+        Some(b'{' | b'[') => complex_end(bytes, start),
+        Some(_) => atom_end(bytes, start),
+        None => start,
+    };
+
+    start..end
+}
+
+/// Find the index of the first byte of the value reported at `idx`, as in [`value_at`].
+pub(crate) fn value_start(bytes: &[u8], idx: usize) -> usize {
+    let mut start = idx;
+    if matches!(bytes.get(start), Some(b':' | b',')) {
+        start += 1;
+    }
+    skip_whitespace(bytes, start)
+}
+
+/// Find the index of the first byte of the root value of the document in `bytes`.
+///
+/// Returns [`None`] if the document is empty, i.e. if the first byte after leading whitespace
+/// cannot start a JSON value. This is also the case when only padding of the input remains.
+pub(crate) fn root_start(bytes: &[u8]) -> Option<usize> {
+    let start = skip_whitespace(bytes, 0);
+
+    match bytes.get(start) {
+        Some(b'{' | b'[' | b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n') => Some(start),
+        _ => None,
+    }
+}
+
+/// Find the index of the first byte of the value of the member with the unescaped `key`
+/// in the object starting at the beginning of `bytes`.
+///
+/// Only the values of the members preceding the found one are scanned, so `bytes`
+/// can extend past the end of the object, or even past the end of the found value.
+/// Returns [`None`] if `bytes` do not start with an object or if it has no such member.
+/// If there are many members with the same key, the first one is returned.
+pub(crate) fn member_value_start(bytes: &[u8], key: &[u8]) -> Option<usize> {
+    if bytes.first() != Some(&b'{') {
+        return None;
+    }
+    let mut idx = 1;
+
+    loop {
+        idx = skip_whitespace(bytes, idx);
+        if bytes.get(idx) != Some(&b'"') {
+            return None;
+        }
+        let key_end = string_end(bytes, idx);
+        if key_end < idx + 2 {
+            return None;
+        }
+        let member_key = &bytes[idx + 1..key_end - 1];
+
+        idx = skip_whitespace(bytes, key_end);
+        if bytes.get(idx) != Some(&b':') {
+            return None;
+        }
+
+        if *unescape(member_key) == *key {
+            return Some(value_start(bytes, idx));
+        }
+
+        idx = skip_whitespace(bytes, value_span(bytes, idx).end);
+        if bytes.get(idx) != Some(&b',') {
+            return None;
+        }
+        idx += 1;
+    }
+}
+
+/// Count the members of the object starting at the beginning of `bytes`.
+///
+/// Only colons directly within the object are counted, so members of nested objects are not.
+/// The scan stops at the end of the object, so `bytes` can extend past it.
+/// Returns [`None`] if `bytes` do not start with an object.
+pub(crate) fn object_size(bytes: &[u8]) -> Option<usize> {
+    if bytes.first() != Some(&b'{') {
+        return None;
+    }
+    let mut depth = 0_usize;
+    let mut size = 0;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'"' => {
+                idx = string_end(bytes, idx);
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            b':' if depth == 1 => size += 1,
+            _ => (),
+        }
+        idx += 1;
+    }
+
+    Some(size)
+}
+
+/// Iterate over the bytes of a JSON value, skipping all whitespace outside of strings.
+pub(crate) fn significant_bytes(bytes: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    let mut is_in_string = false;
+    let mut is_escaped = false;
+
+    bytes.iter().copied().filter(move |&b| {
+        if is_escaped {
+            is_escaped = false;
+        } else if is_in_string && b == b'\\' {
+            is_escaped = true;
+        } else if b == b'"' {
+            is_in_string = !is_in_string;
+        } else if !is_in_string {
+            return !b.is_ascii_whitespace();
+        }
+        true
+    })
+}
+
+/// Parse a JSON number as `T`. The value must start like a JSON number, with a digit
+/// after the optional minus sign, which excludes things like `inf`, `-nan` or `+1`
+/// accepted by [`std::str::FromStr`].
+pub(crate) fn parse_number<T: std::str::FromStr>(value: &[u8]) -> Option<T> {
+    match value {
+        [b'0'..=b'9', ..] | [b'-', b'0'..=b'9', ..] => {
+            std::str::from_utf8(value).ok()?.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+/// Returns whether the `value` is a number valid in JSON, e.g. without leading zeros,
+/// without a leading plus sign, and with digits on both sides of the decimal point.
+pub(crate) fn is_json_number(value: &[u8]) -> bool {
+    let digits = |idx: usize| {
+        value[idx..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let mut idx = usize::from(value.first() == Some(&b'-'));
+
+    match value.get(idx) {
+        Some(b'0') => idx += 1,
+        Some(b'1'..=b'9') => idx += digits(idx),
+        _ => return false,
+    }
+    if value.get(idx) == Some(&b'.') {
+        let fraction = digits(idx + 1);
+        if fraction == 0 {
+            return false;
+        }
+        idx += 1 + fraction;
+    }
+    if matches!(value.get(idx), Some(b'e' | b'E')) {
+        idx += 1;
+        if matches!(value.get(idx), Some(b'+' | b'-')) {
+            idx += 1;
+        }
+        let exponent = digits(idx);
+        if exponent == 0 {
+            return false;
+        }
+        idx += exponent;
+    }
+
+    idx == value.len()
+}
+
+fn skip_whitespace(bytes: &[u8], mut idx: usize) -> usize {
+    while bytes.get(idx).map_or(false, u8::is_ascii_whitespace) {
+        idx += 1;
+    }
+    idx
+}
+
+/// Find the end of a string starting with a quote at `start`. Returns the index
+/// right after the closing quote, or the length of `bytes` if the string is not closed.
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut idx = start + 1;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'\\' => idx += 2,
+            b'"' => return idx + 1,
+            _ => idx += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+/// Find the end of an object or array starting at `start`. Returns the index
+/// right after the matching closing character, or the length of `bytes` if it is not closed.
+fn complex_end(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0_usize;
+    let mut idx = start;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'"' => {
+                idx = string_end(bytes, idx);
+                continue;
+            }
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return idx + 1;
+                }
+            }
+            _ => (),
+        }
+        idx += 1;
+    }
+
+    bytes.len()
+}
+
+/// Find the end of a number or a literal starting at `start`.
+fn atom_end(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&b| b.is_ascii_whitespace() || matches!(b, b',' | b'}' | b']' | 0))
+        .map_or(bytes.len(), |len| start + len)
+}
+
+/// Returns whether the character at `idx` is escaped, i.e. it is preceded
+/// by an odd number of backslashes.
+pub(crate) fn is_escaped(bytes: &[u8], idx: usize) -> bool {
+    let backslashes = bytes[..idx]
+        .iter()
+        .rev()
+        .take_while(|&&b| b == b'\\')
+        .count();
+
+    backslashes % 2 == 1
+}
+
+/// Returns whether the contents of a string contain an escape sequence of a control character,
+/// i.e. one of `\b`, `\f`, `\n`, `\r`, `\t`, or `\u0000` to `\u001F`.
+///
+/// Such characters have more than one valid escaped form, so strings containing them
+/// cannot be compared byte by byte.
+pub(crate) fn has_control_escape(bytes: &[u8]) -> bool {
+    let mut idx = 0;
+
+    while idx + 1 < bytes.len() {
+        if bytes[idx] != b'\\' {
+            idx += 1;
+            continue;
+        }
+
+        match bytes[idx + 1] {
+            b'b' | b'f' | b'n' | b'r' | b't' => return true,
+            b'u' if bytes
+                .get(idx + 2..idx + 6)
+                .and_then(parse_hex4)
+                .map_or(false, |c| c < 0x20) =>
+            {
+                return true
+            }
+            _ => idx += 2,
+        }
+    }
+
+    false
+}
+
+/// Compare the key of the object member whose colon is at `colon_idx` with a `label`
+/// containing escapes of control characters.
+///
+/// Control characters have more than one valid escaped form, e.g. `\n` and `\u000A`,
+/// so both the key and the label are unescaped before comparison.
+/// Returns [`None`] if the bytes before the colon do not form a quoted key.
+pub(crate) fn key_matches_label(bytes: &[u8], colon_idx: usize, label: &Label) -> Option<bool> {
+    let key = key_before_colon(bytes, colon_idx)?;
+    Some(unescape(key) == unescape(label.bytes()))
+}
+
+/// Decode all JSON escape sequences in the contents of a string.
+///
+/// Invalid escape sequences, including unpaired UTF-16 surrogates, are left verbatim.
+/// If there are no escape sequences, the `bytes` are returned without copying.
+pub(crate) fn unescape(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if !bytes.contains(&b'\\') {
+        return Cow::Borrowed(bytes);
+    }
+
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        if bytes[idx] != b'\\' || idx + 1 == bytes.len() {
+            result.push(bytes[idx]);
+            idx += 1;
+            continue;
+        }
+
+        let decoded = match bytes[idx + 1] {
+            b'"' => b'"',
+            b'\\' => b'\\',
+            b'/' => b'/',
+            b'b' => 0x08,
+            b'f' => 0x0C,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'u' => {
+                if let Some((character, len)) = decode_unicode_escape(&bytes[idx..]) {
+                    let mut buf = [0; 4];
+                    result.extend_from_slice(character.encode_utf8(&mut buf).as_bytes());
+                    idx += len;
+                } else {
+                    result.push(b'\\');
+                    idx += 1;
+                }
+                continue;
+            }
+            _ => {
+                result.push(b'\\');
+                idx += 1;
+                continue;
+            }
+        };
+
+        result.push(decoded);
+        idx += 2;
+    }
+
+    Cow::Owned(result)
+}
+
+/// Decode a `\uXXXX` escape sequence, or a surrogate pair of two such sequences,
+/// at the start of `bytes`. Returns the character and the length of the sequence.
+fn decode_unicode_escape(bytes: &[u8]) -> Option<(char, usize)> {
+    let high = parse_hex4(bytes.get(2..6)?)?;
+
+    if (0xD800..0xDC00).contains(&high) {
+        if bytes.get(6..8) != Some(b"\\u") {
+            return None;
+        }
+        let low = parse_hex4(bytes.get(8..12)?)?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return None;
+        }
+        let code_point = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+
+        char::from_u32(code_point).map(|c| (c, 12))
+    } else {
+        char::from_u32(high).map(|c| (c, 6))
+    }
+}
+
+fn parse_hex4(bytes: &[u8]) -> Option<u32> {
+    bytes.iter().try_fold(0, |acc, &b| {
+        char::from(b).to_digit(16).map(|digit| (acc << 4) | digit)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(r#"{"key": 42}"#, 6 => Some(&b"key"[..]); "simple")]
+    #[test_case(r#"{"key"   : 42}"#, 9 => Some(&b"key"[..]); "whitespace before colon")]
+    #[test_case(r#"{"k\"ey": 42}"#, 8 => Some(&br#"k\"ey"#[..]); "escaped quote")]
+    #[test_case(r#"{"key\\": 42}"#, 8 => Some(&br#"key\\"#[..]); "escaped backslash")]
+    #[test_case(r#"{"": 42}"#, 3 => Some(&b""[..]); "empty")]
+    #[test_case(r#"{42: 42}"#, 3 => None; "unquoted")]
+    fn key_before_colon_test(json: &str, colon_idx: usize) -> Option<&[u8]> {
+        key_before_colon(json.as_bytes(), colon_idx)
+    }
+
+    #[test_case(r#"{"a": 42}"#, 4 => Some(&b"a"[..]); "colon")]
+    #[test_case(r#"{"a" :  42}"#, 7 => Some(&b"a"[..]); "value start")]
+    #[test_case(r#"{"a": 1, "b\"": [2]}"#, 14 => Some(&br#"b\""#[..]); "escaped key")]
+    #[test_case(r#"[1, 2]"#, 3 => None; "comma")]
+    #[test_case(r#"[1, 2]"#, 1 => None; "first element")]
+    #[test_case(r#"{"a": 42}"#, 0 => None; "root")]
+    fn key_of_value_test(json: &str, idx: usize) -> Option<&[u8]> {
+        key_of_value(json.as_bytes(), idx)
+    }
+
+    #[test_case(r#"{"a": 42, "b": 1}"#, 4 => "42"; "number after colon")]
+    #[test_case(r#"[1, -2.5e3]"#, 2 => "-2.5e3"; "number after comma")]
+    #[test_case(r#"[true]"#, 1 => "true"; "first list item")]
+    #[test_case(r#"{"a": "x\"}y"}"#, 4 => r#""x\"}y""#; "string with escapes")]
+    #[test_case(r#"{"a": {"b": ["]", {}]}}"#, 4 => r#"{"b": ["]", {}]}"#; "object")]
+    #[test_case(r#"{"a": null}"#, 4 => "null"; "null at end of object")]
+    fn value_at_test(json: &str, idx: usize) -> &str {
+        std::str::from_utf8(value_at(json.as_bytes(), idx)).unwrap()
+    }
+
+    #[test_case(r#"{"a": 1, "b": [2]}"#, "b" => Some("[2]}"); "second member")]
+    #[test_case(r#"{ "a" : { "b": 1 } }"#, "a" => Some(r#"{ "b": 1 } }"#); "whitespace")]
+    #[test_case(r#"{"\u0061": 1}"#, "a" => Some("1}"); "escaped key")]
+    #[test_case(r#"{"a": 1, "a": 2}"#, "a" => Some(r#"1, "a": 2}"#); "duplicate key")]
+    #[test_case(r#"{"a": {"b": 1}}"#, "b" => None; "nested key")]
+    #[test_case(r#"{}"#, "a" => None; "empty object")]
+    #[test_case(r#"["a"]"#, "a" => None; "array")]
+    #[test_case(r#"{"a": [1, "b": "#, "a" => Some(r#"[1, "b": "#); "truncated value")]
+    fn member_value_start_test<'a>(json: &'a str, key: &str) -> Option<&'a str> {
+        member_value_start(json.as_bytes(), key.as_bytes()).map(|start| &json[start..])
+    }
+
+    #[test_case(r#"{"a": 1, "b": [2]}"# => Some(2); "simple")]
+    #[test_case(r#"{}"# => Some(0); "empty")]
+    #[test_case(r#"{"a": {"b": 1, "c": 2}, "d": [{"e": 3}]}"# => Some(2); "nested members")]
+    #[test_case(r#"{"a:": "}:"}, "b": 1"# => Some(1); "colons in strings and trailing bytes")]
+    #[test_case(r#"[{"a": 1}]"# => None; "array")]
+    #[test_case(r#" {"a": 1}"# => None; "leading whitespace")]
+    fn object_size_test(json: &str) -> Option<usize> {
+        object_size(json.as_bytes())
+    }
+
+    #[test_case("0" => true; "zero")]
+    #[test_case("-12.5e+3" => true; "all parts")]
+    #[test_case("1E9" => true; "capital exponent")]
+    #[test_case("01" => false; "leading zero")]
+    #[test_case("-00.5" => false; "negative leading zero")]
+    #[test_case("+5" => false; "plus sign")]
+    #[test_case("1." => false; "empty fraction")]
+    #[test_case(".5" => false; "no integer part")]
+    #[test_case("1e" => false; "empty exponent")]
+    #[test_case("-" => false; "only minus")]
+    #[test_case("1x" => false; "trailing characters")]
+    fn is_json_number_test(value: &str) -> bool {
+        is_json_number(value.as_bytes())
+    }
+
+    #[test_case("-2.5" => Some(-2.5); "negative")]
+    #[test_case("05" => Some(5.0); "leading zero")]
+    #[test_case("inf" => None; "infinity")]
+    #[test_case("-inf" => None; "minus infinity")]
+    #[test_case("-infinity" => None; "minus infinity spelled out")]
+    #[test_case("-nan" => None; "minus nan")]
+    #[test_case("+5" => None; "plus sign")]
+    #[test_case("-" => None; "only minus")]
+    fn parse_number_test(value: &str) -> Option<f64> {
+        parse_number(value.as_bytes())
+    }
+
+    #[test_case(r#" { "a" : [ 1 , 2 ] } "# => r#"{"a":[1,2]}"#; "whitespace outside strings")]
+    #[test_case(r#"[" a b ", "\" c"]"# => r#"[" a b ","\" c"]"#; "whitespace in strings")]
+    #[test_case(r#"["\\", " "]"# => r#"["\\"," "]"#; "escaped backslash")]
+    fn significant_bytes_test(json: &str) -> String {
+        String::from_utf8(significant_bytes(json.as_bytes()).collect()).unwrap()
+    }
+
+    #[test_case(r#"plain"# => false; "no escapes")]
+    #[test_case(r#"a\"b\/"# => false; "non-control escapes")]
+    #[test_case(r#"a\nb"# => true; "short escape")]
+    #[test_case(r#"\u001F"# => true; "unicode escape")]
+    #[test_case(r#"\u0020"# => false; "unicode escape of space")]
+    #[test_case(r#"\\n"# => false; "escaped backslash")]
+    #[test_case(r#"\\\t"# => true; "escaped backslash and tab")]
+    fn has_control_escape_test(input: &str) -> bool {
+        has_control_escape(input.as_bytes())
+    }
+
+    #[test_case(r#"plain"# => "plain"; "no escapes")]
+    #[test_case(r#"a\"b\\c\/d"# => r#"a"b\c/d"#; "simple escapes")]
+    #[test_case(r#"\b\f\n\r\t"# => "\u{8}\u{c}\n\r\t"; "control escapes")]
+    #[test_case(r#"\u0041\u00e9"# => "Aé"; "unicode escapes")]
+    #[test_case(r#"\ud83d\ude00"# => "😀"; "surrogate pair")]
+    #[test_case(r#"\ud83d!"# => r#"\ud83d!"#; "unpaired surrogate")]
+    #[test_case(r#"\x"# => r#"\x"#; "invalid escape")]
+    fn unescape_test(input: &str) -> String {
+        String::from_utf8(unescape(input.as_bytes()).into_owned()).unwrap()
+    }
+
+    #[test_case(r#"{"a\nb": 1}"#, 7, r#"a\nb"# => Some(true); "same escape")]
+    #[test_case(r#"{"a\u000Ab": 1}"#, 11, r#"a\nb"# => Some(true); "different escapes")]
+    #[test_case(r#"{"a\tb": 1}"#, 7, r#"a\nb"# => Some(false); "different characters")]
+    #[test_case(r#"{a: 1}"#, 2, r#"a\nb"# => None; "unquoted key")]
+    fn key_matches_label_test(json: &str, colon_idx: usize, label: &str) -> Option<bool> {
+        key_matches_label(json.as_bytes(), colon_idx, &Label::new(label))
+    }
+}
@@ -45,6 +45,10 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! For one-off queries the [`count`], [`indices`], and [`values`] functions do all of the above in a single call.
+//! They parse and compile the query and copy the document on every call, so an engine compiled once
+//! should be used to run the same query many times.
 //! # Input JSON assumptions
 //!
 //! The JSON must be a syntactically valid JSON encoded in UTF-8 as defined by
@@ -70,14 +74,37 @@
 //! ```ebnf
 //! query = [root] , { selector }
 //! root = "$"
-//! selector = wildcard child | child | descendant
+//! selector = wildcard child | child | descendant | descendant or self | pattern child | pattern descendant
+//!            | prefix child | prefix descendant
+//!            | filter | descendant filter | member from end | array index | slice | descendant slice
 //! wildcard child = dot wildcard | index wildcard
 //! child = dot | index
 //! dot = "." , label
 //! dot wildcard = ".*"
 //! descendant = ".." , ( label | index )
+//! descendant or self = "..." , ( label | index )
 //! index = "[" , quoted label , "]"
 //! index wildcard = "[*]"
+//! pattern child = "[~" , quoted pattern , "]"
+//! pattern descendant = ".." , pattern child
+//! prefix child = "." , label , "*" | prefix index
+//! prefix descendant = ".." , ( label , "*" | prefix index )
+//! prefix index = "[~prefix(" , quoted label , ")]"
+//! filter = "[?(" , filter expression , ")]"
+//! descendant filter = ".." , filter
+//! filter expression = "@" , { child } , ( "==" , json value | WHITESPACE , "is" , WHITESPACE , "scalar"
+//!                     | WHITESPACE , "in" , WHITESPACE , ( numeric range | literal set ) )
+//!                     | "size(" , "@" , { child } , ")" , ( "==" | "!=" | "<" | "<=" | ">" | ">=" ) , NONNEGATIVE
+//! numeric range = json number , ( ".." | "..=" ) , json number
+//! literal set = "[" , [ set literal , { "," , set literal } ] , "]"
+//! set literal = json value | "'" , { ? any character ? - "'" | "\'" } , "'"
+//! member from end = "[last(" , [ POSITIVE ] , ")]"
+//! array index = "[" , NONNEGATIVE , "]"
+//! slice = "[" , [ NONNEGATIVE ] , ":" , [ NONNEGATIVE ] , [ ":" , [ POSITIVE ] ] , "]"
+//! descendant slice = ".." , slice
+//! json value = ? any JSON value ?
+//! json number = ? any JSON number ?
+//! quoted pattern = ("'" , { ? any character ? - "'" | "\'" } , "'") | ('"' , { ? any character ? - '"' | '\"' } , '"')
 //! label = label first , { label character }
 //! label first = ALPHA | "_" | NONASCII
 //! label character = ALPHANUMERIC | "_" | NONASCII
@@ -89,7 +116,10 @@
 //! ALPHANUMERIC = ? [A-Za-z0-9] ?
 //! NONASCII = ? [\u0080-\u10FFFF] ?
 //! UNESCAPED = ? [^'"\u0000-\u001F] ?
-//! ESCAPED = ? \\[btnfr/\\] ?
+//! ESCAPED = ? \\[btnfr/\\] | \\u00[01][0-9A-Fa-f] ?
+//! WHITESPACE = ? [ \t\r\n]+ ?
+//! POSITIVE = ? [0-9]* [1-9] [0-9]* ?
+//! NONNEGATIVE = ? 0 | [1-9] [0-9]* ?
 //! ```
 //!
 //! ## Semantics
@@ -106,6 +136,10 @@
 //! ### Child selector (`.<label>`, `[<label>]`)
 //! Matches any value under a specified key in the current object
 //! and then executes the rest of the query on that value.
+//! Keys are compared with the label byte by byte. With the `unicode-normalization` feature,
+//! `JsonPathQuery::into_normalization_insensitive` makes the comparison insensitive
+//! to the Unicode normalization form of keys and labels. With the `accent-folding` feature,
+//! `JsonPathQuery::into_accent_insensitive` makes the comparison insensitive to diacritics.
 //!
 //! ### Child wildcard selector (`.*`, `[*]`)
 //! Matches any value regardless of key in the current object, or any value within the current array,
@@ -116,6 +150,93 @@
 //! Looks for the specified key in every value nested in the current object or array,
 //! recursively.
 //!
+//! ### Descendant-or-self selector (`...<label>`, `...[<label>]`)
+//! Like the descendant selector, but also considers the current value itself, i.e. the value
+//! selected by the previous selector, which matches if its key is the specified label.
+//! For example, on `{"a": {"a": 1}}` the query `$.a..a` matches only the inner value,
+//! while `$.a...a` matches both the outer and the inner one. Every value is matched at most once.
+//! The root value has no key, so `$...a` is equivalent to `$..a`.
+//!
+//! ### Pattern selectors (`[~'<regex>']`, `..[~'<regex>']`)
+//! Available only with the `regex` feature.
+//! Behave like the child and descendant selectors, respectively, but match any key
+//! in which the regular expression finds a match. The key is unescaped before matching.
+//! The contents of the pattern are taken verbatim, the only escape sequence is an escaped quote.
+//!
+//! ### Prefix selectors (`.<label>*`, `[~prefix(<label>)]`, `..<label>*`, `..[~prefix(<label>)]`)
+//! Behave like the child and descendant selectors, respectively, but match any key
+//! starting with the label, including the label itself. For example, `$..field_*`
+//! selects every value in the document under a key starting with `field_`.
+//! The `[~prefix(<label>)]` form takes a quoted label, like the index form of the child selector.
+//!
+//! ### Filter selector (`[?(@<path> == <value>)]`)
+//! Matches any value in the current object or array for which the relative path of child selectors
+//! starting at that value (`@`) leads to a value equal to the given JSON value, and then executes
+//! the rest of the query on it. Whitespace is allowed around the `==` operator.
+//!
+//! Values are compared after removing whitespace outside of strings, but are not normalized otherwise.
+//! In particular, objects with members in different order are not equal, and neither are numbers or strings
+//! written differently, like `1.0` and `1`, or `"\u0041"` and `"A"`.
+//!
+//! The `@<path> is scalar` expression instead tests whether the value at the end of the path
+//! is a string, number, `true`, `false`, or `null`, i.e. not an object or an array.
+//!
+//! The `@<path> in <start>..<end>` expression tests whether the value at the end of the path
+//! is a number `x` such that `start <= x < end`, while `@<path> in <start>..=<end>` also includes the end.
+//! Numbers are compared as 64-bit floating point values, and values that are not numbers,
+//! including strings containing numbers, are never in a range. Values have to be valid JSON numbers,
+//! so e.g. `01` or `+5` are not numbers, unless the query is compiled with
+//! [`Automaton::with_number_syntax`](query::automaton::Automaton::with_number_syntax).
+//!
+//! The `@<path> in [<value>, <value>, ...]` expression tests whether the value at the end of the path
+//! is equal to any of the listed JSON values, e.g. `$.users[?(@.status in ['active', 'pending'])]`.
+//! Strings in the list can also be single-quoted, and whitespace is allowed around the values.
+//! Unlike `==`, strings are compared after decoding escape sequences, so `"\u0041"` is equal to `"A"`,
+//! and numbers are compared as 64-bit floating point values, so `1.0` is equal to `1`.
+//! Other values are compared as with `==`. The membership is tested with a hash lookup,
+//! so long lists are as fast as short ones.
+//!
+//! The `size(@<path>) <operator> <n>` expression tests whether the value at the end of the path
+//! is an object whose number of members compares with `n` as given by the operator, which is one of
+//! `==`, `!=`, `<`, `<=`, `>`, or `>=`. For example, `$.configs[?(size(@) == 0)]` selects empty objects.
+//! Only the members of the object itself are counted, not the members of objects nested in it.
+//! Values that are not objects, including arrays, never match, even with `!=`.
+//! Whitespace is allowed around the operator and inside the parentheses.
+//!
+//! The descendant filter selector (`..[?(<expression>)]`) matches any value nested in the current
+//! object or array, recursively, that satisfies the filter expression. For example,
+//! `$..[?(@ is scalar)]` selects every scalar value in the document.
+//! Currently, a filter selector can only be the last selector of a query.
+//!
+//! ### Member from end selector (`[last()]`, `[last(<n>)]`)
+//! Matches the last member of the current object, or the member at position `n` counting
+//! from the end, so `[last(1)]` is the same as `[last()]` and `[last(2)]` is the second to last member.
+//! Members are ordered as they appear in the document, keys are not sorted.
+//! Arrays, empty objects, and objects with fewer than `n` members give no matches.
+//! Since the position is only known when the object is closed, matches are reported
+//! at the end of their object, so a match nested in an earlier member is reported first.
+//! Currently, a member from end selector can only be the last selector of a query.
+//!
+//! ### Array index selector (`[<n>]`)
+//! Matches the element at index `n` of the current array, counting from 0 for the first element,
+//! and then executes the rest of the query on that value. Objects give no matches, even if they
+//! have a key like `"0"`. Index selectors can be chained to walk into nested arrays,
+//! so `$[0][1]` selects `2` in `[[1, 2]]`.
+//!
+//! Conversely, a label selector like `$['0']` or `$["0"]` never matches array elements, unless the query
+//! is compiled with [`Automaton::new_with_numeric_key_coercion`](query::automaton::Automaton::new_with_numeric_key_coercion).
+//! Then labels that are non-negative integers are treated as indices in arrays and as keys in objects.
+//!
+//! ### Array slice selector (`[<start>:<end>:<step>]`, `..[<start>:<end>:<step>]`)
+//! Matches every element of the current array whose index is at least `start` and less than `end`,
+//! counting every `step` elements from `start`. The `start` defaults to 0, the `end` to the end of the array,
+//! and the `step` to 1, so `$[1:3]` selects `2` and `3` in `[1, 2, 3, 4]`, and `$[::2]` selects `1` and `3`.
+//! Negative values are not supported.
+//!
+//! The descendant slice selector (`..[<start>:<end>:<step>]`) applies the slice to every array nested
+//! in the current value, with each array counting its elements from 0, so `$..[::2]` selects
+//! `[1, 2]`, `1`, and `4` in `[[1, 2], 3, 4]`.
+//!
 //! ## Active development
 //!
 //! Only the aforementioned selectors are supported at this moment.
@@ -198,12 +319,23 @@
 // Unsafe code allowed only for SIMD.
 #![cfg_attr(not(feature = "simd"), forbid(unsafe_code))]
 
+#[cfg(feature = "bench")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bench")))]
+pub mod bench;
 pub mod classification;
 pub mod engine;
 pub mod error;
+mod json;
 pub mod query;
 pub mod result;
+pub mod scan;
+pub mod validation;
 use cfg_if::cfg_if;
+use engine::{Compiler, Engine, Input, RsonpathEngine};
+use error::QueryError;
+use query::JsonPathQuery;
+use result::{CountResult, IndexResult, QueryResult, SpanResult};
+use std::sync::Once;
 
 cfg_if! {
     if #[cfg(simd = "avx2")] {
@@ -218,6 +350,104 @@ cfg_if! {
     }
 }
 
+/// Eagerly perform the one-time setup of the SIMD routines used by the engines.
+///
+/// The classifiers of this crate are selected at compile time, but the [`memchr`](https://docs.rs/memchr)
+/// searcher used for head-skipping detects the CPU features at runtime and caches its choice
+/// on first use. Calling this function during startup moves that cost out of the first query,
+/// which can matter for latency-sensitive services.
+///
+/// Calling it is optional &ndash; otherwise the detection happens lazily when first needed.
+/// The function is idempotent and thread-safe, only the first call does any work.
+///
+/// # Examples
+/// ```rust
+/// std::thread::scope(|s| {
+///     s.spawn(rsonpath_lib::warm_up);
+///     s.spawn(rsonpath_lib::warm_up);
+/// });
+/// ```
+#[inline]
+pub fn warm_up() {
+    static WARM_UP: Once = Once::new();
+
+    WARM_UP.call_once(|| {
+        debug!("Warming up SIMD routines.");
+        #[cfg(feature = "head-skip")]
+        {
+            // The searcher implementation is chosen based on the CPU features on first search.
+            let finder = memchr::memmem::Finder::new(br#""warm_up""#);
+            finder.find(br#"{"warm_up": true}"#);
+        }
+    });
+}
+
+/// Count the matches of the `query` in the `json` document.
+///
+/// # Errors
+/// [`QueryError`] holding the error of parsing or compiling the query, or of running it on the document.
+///
+/// # Examples
+/// ```rust
+/// let json = r#"{"a": [1, {"a": 2}], "b": {"a": 3}}"#;
+///
+/// assert_eq!(rsonpath_lib::count("$..a", json)?, 3);
+/// # Ok::<(), rsonpath_lib::error::QueryError>(())
+/// ```
+#[inline]
+pub fn count(query: &str, json: &str) -> Result<usize, QueryError> {
+    run_once::<CountResult>(query, json).map(|result| result.get())
+}
+
+/// Find the indices of the matches of the `query` in the `json` document,
+/// as reported by an [`IndexResult`].
+///
+/// # Errors
+/// [`QueryError`] holding the error of parsing or compiling the query, or of running it on the document.
+///
+/// # Examples
+/// ```rust
+/// let json = r#"{"a": 1, "b": {"a": 2}}"#;
+///
+/// assert_eq!(rsonpath_lib::indices("$.*", json)?, vec![4, 14]);
+/// # Ok::<(), rsonpath_lib::error::QueryError>(())
+/// ```
+#[inline]
+pub fn indices(query: &str, json: &str) -> Result<Vec<usize>, QueryError> {
+    run_once::<IndexResult>(query, json).map(Vec::from)
+}
+
+/// Find the raw JSON of the values matched by the `query` in the `json` document.
+///
+/// # Errors
+/// [`QueryError`] holding the error of parsing or compiling the query, or of running it on the document.
+///
+/// # Examples
+/// ```rust
+/// use rsonpath_lib::error::QueryError;
+///
+/// let json = r#"{"a": [1, {"a": "2"}], "b": {"a": null}}"#;
+///
+/// assert_eq!(rsonpath_lib::values("$..a", json)?, vec![r#"[1, {"a": "2"}]"#, r#""2""#, "null"]);
+/// assert!(matches!(rsonpath_lib::values("$..", json), Err(QueryError::Parser(_))));
+/// # Ok::<(), QueryError>(())
+/// ```
+#[inline]
+pub fn values<'j>(query: &str, json: &'j str) -> Result<Vec<&'j str>, QueryError> {
+    let spans = run_once::<SpanResult>(query, json)?;
+
+    Ok(spans.get().iter().map(|span| &json[span.clone()]).collect())
+}
+
+fn run_once<R: QueryResult>(query: &str, json: &str) -> Result<R, QueryError> {
+    let query = JsonPathQuery::parse(query)?;
+    let engine = RsonpathEngine::compile_query(&query)?;
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+
+    Ok(engine.run(&input)?)
+}
+
 /// Macro for debug logging. Evaluates to [`log::debug`], if debug assertions are enabled.
 /// Otherwise it's an empty statement.
 ///
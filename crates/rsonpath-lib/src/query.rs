@@ -30,12 +30,30 @@
 pub mod automaton;
 pub mod builder;
 pub mod error;
+mod filter;
 mod parser;
+mod pattern;
+mod selector;
+mod slice;
 
+use crate::json;
 use aligners::{alignment, AlignedBytes, AlignedSlice};
 use cfg_if::cfg_if;
 use log::*;
 use std::fmt::{self, Display};
+use std::num::NonZeroUsize;
+
+pub use filter::{
+    Comparison, ComparisonOperator, FilterExpression, JsonLiteral, LiteralSet, NumberSyntax,
+    NumericRange,
+};
+#[cfg(feature = "accent-folding")]
+pub use pattern::FoldedLabel;
+#[cfg(feature = "unicode-normalization")]
+pub use pattern::NormalizedLabel;
+pub use pattern::{KeyComparator, KeyPattern};
+pub use selector::Selector;
+pub use slice::ArraySlice;
 
 cfg_if! {
     if #[cfg(feature = "simd")] {
@@ -66,6 +84,7 @@ cfg_if! {
 pub struct Label {
     label: AlignedBytes<LabelAlignment>,
     label_with_quotes: AlignedBytes<LabelAlignment>,
+    has_control_escape: bool,
 }
 
 impl std::fmt::Debug for Label {
@@ -87,6 +106,7 @@ impl Clone for Label {
         Self {
             label: label_clone,
             label_with_quotes: quoted_clone,
+            has_control_escape: self.has_control_escape,
         }
     }
 }
@@ -107,6 +127,7 @@ impl Label {
         Self {
             label: without_quotes,
             label_with_quotes: with_quotes,
+            has_control_escape: json::has_control_escape(bytes),
         }
     }
 
@@ -125,6 +146,16 @@ impl Label {
         &self.label_with_quotes
     }
 
+    /// Whether the label contains an escaped control character, like `\n` or `\u001F`.
+    ///
+    /// Control characters can be escaped in more than one way, e.g. a newline both as `\n`
+    /// and `\u000A`, so such labels must be compared with keys after unescaping both,
+    /// and not byte by byte.
+    #[inline(always)]
+    pub(crate) fn has_control_escape(&self) -> bool {
+        self.has_control_escape
+    }
+
     /// Return a display object with a UTF8 representation of this label.
     ///
     /// If the label contains invalid UTF8, the value will always be `"[invalid utf8]"`.
@@ -200,6 +231,30 @@ pub enum JsonPathQueryNode {
     AnyChild(Option<Box<JsonPathQueryNode>>),
     /// Represents recursive descent ('`..`' token).
     Descendant(Label, Option<Box<JsonPathQueryNode>>),
+    /// Represents recursive descent that also considers the current value itself
+    /// ('`...`' token).
+    DescendantOrSelf(Label, Option<Box<JsonPathQueryNode>>),
+    /// Represents direct descendant with a key matching a [`KeyPattern`] ('`[~'`' token).
+    PatternChild(KeyPattern, Option<Box<JsonPathQueryNode>>),
+    /// Represents recursive descent looking for keys matching a [`KeyPattern`] ('`..[~'`' tokens).
+    PatternDescendant(KeyPattern, Option<Box<JsonPathQueryNode>>),
+    /// Represents direct descendants satisfying a [`FilterExpression`] ('`[?(`' token).
+    Filter(FilterExpression, Option<Box<JsonPathQueryNode>>),
+    /// Represents recursive descent looking for values satisfying a [`FilterExpression`]
+    /// ('`..[?(`' tokens).
+    DescendantFilter(FilterExpression, Option<Box<JsonPathQueryNode>>),
+    /// Represents the member of an object at the given position counting from the end
+    /// in document order, starting from 1 for the last member ('`[last(`' token).
+    MemberFromEnd(NonZeroUsize, Option<Box<JsonPathQueryNode>>),
+    /// Represents the element of an array at the given index, starting from 0
+    /// for the first element ('`[`' token followed by a number).
+    ArrayIndex(usize, Option<Box<JsonPathQueryNode>>),
+    /// Represents the elements of an array selected by an [`ArraySlice`]
+    /// ('`[`' token followed by a slice, like `[1:5:2]`).
+    Slice(ArraySlice, Option<Box<JsonPathQueryNode>>),
+    /// Represents recursive descent looking for elements of arrays selected by an [`ArraySlice`]
+    /// ('`..[`' tokens followed by a slice).
+    DescendantSlice(ArraySlice, Option<Box<JsonPathQueryNode>>),
 }
 
 use JsonPathQueryNode::*;
@@ -213,10 +268,44 @@ impl JsonPathQueryNode {
     #[inline(always)]
     pub fn child(&self) -> Option<&Self> {
         match self {
-            Root(node) | Child(_, node) | AnyChild(node) | Descendant(_, node) => node.as_deref(),
+            Root(node)
+            | Child(_, node)
+            | AnyChild(node)
+            | Descendant(_, node)
+            | DescendantOrSelf(_, node)
+            | PatternChild(_, node)
+            | PatternDescendant(_, node)
+            | Filter(_, node)
+            | DescendantFilter(_, node)
+            | MemberFromEnd(_, node)
+            | ArrayIndex(_, node)
+            | Slice(_, node)
+            | DescendantSlice(_, node) => node.as_deref(),
         }
     }
 
+    /// Return a display object with the representation of only this selector,
+    /// without the selectors following it in the query.
+    #[inline(always)]
+    pub(crate) fn selector_display(&self) -> impl Display + '_ {
+        SelectorDisplay(self)
+    }
+
+    /// Returns whether the node is a descendant selector of any kind,
+    /// i.e. one that can match values at any depth below the current value.
+    #[must_use]
+    #[inline(always)]
+    pub fn is_any_descendant(&self) -> bool {
+        matches!(
+            self,
+            Descendant(_, _)
+                | DescendantOrSelf(_, _)
+                | PatternDescendant(_, _)
+                | DescendantFilter(_, _)
+                | DescendantSlice(_, _)
+        )
+    }
+
     /// Create an iterator over nodes of the query in sequence,
     /// starting from the root.
     #[must_use]
@@ -292,6 +381,87 @@ impl JsonPathQuery {
 
         Self { root }
     }
+
+    /// Turn the query into one matching keys regardless of their Unicode normalization form.
+    ///
+    /// Every label selector is replaced with a pattern selector with the
+    /// [`KeyPattern::normalized`] pattern of the same label, so both the labels and the keys
+    /// of the document are normalized to NFC before comparison. Labels of descendant-or-self
+    /// selectors and in paths of filter expressions are still compared byte by byte.
+    ///
+    /// Pattern selectors are significantly slower than label selectors,
+    /// so this should only be used when the input is known to mix normalization forms.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::JsonPathQuery;
+    /// let query = JsonPathQuery::parse("$.a..b.*").unwrap().into_normalization_insensitive();
+    ///
+    /// assert_eq!(query.to_string(), "$[~nfc('a')]..[~nfc('b')][*]");
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    #[must_use]
+    #[inline]
+    pub fn into_normalization_insensitive(self) -> Self {
+        self.map_labels(KeyPattern::normalized)
+    }
+
+    /// Turn the query into one matching keys regardless of diacritics.
+    ///
+    /// Every label selector is replaced with a pattern selector with the
+    /// [`KeyPattern::accent_folded`] pattern of the same label, so diacritics are removed from
+    /// both the labels and the keys of the document before comparison. Case is preserved,
+    /// and labels of descendant-or-self selectors and in paths of filter expressions
+    /// are still compared byte by byte.
+    ///
+    /// Pattern selectors are significantly slower than label selectors,
+    /// so this should only be used when the input is known to spell keys inconsistently.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::JsonPathQuery;
+    /// let query = JsonPathQuery::parse("$.caf\u{e9}..b.*").unwrap().into_accent_insensitive();
+    ///
+    /// assert_eq!(query.to_string(), "$[~fold('caf\u{e9}')]..[~fold('b')][*]");
+    /// ```
+    #[cfg(feature = "accent-folding")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "accent-folding")))]
+    #[must_use]
+    #[inline]
+    pub fn into_accent_insensitive(self) -> Self {
+        self.map_labels(KeyPattern::accent_folded)
+    }
+
+    /// Replace every label selector with a pattern selector built from its label by `f`.
+    ///
+    /// Labels of descendant-or-self selectors and in paths of filter expressions are kept.
+    #[cfg(any(feature = "unicode-normalization", feature = "accent-folding"))]
+    fn map_labels<F: Fn(Label) -> KeyPattern>(self, f: F) -> Self {
+        fn map(node: JsonPathQueryNode, f: &impl Fn(Label) -> KeyPattern) -> JsonPathQueryNode {
+            let map_child =
+                |child: Option<Box<JsonPathQueryNode>>| child.map(|child| Box::new(map(*child, f)));
+            match node {
+                Root(child) => Root(map_child(child)),
+                Child(label, child) => PatternChild(f(label), map_child(child)),
+                AnyChild(child) => AnyChild(map_child(child)),
+                Descendant(label, child) => PatternDescendant(f(label), map_child(child)),
+                DescendantOrSelf(label, child) => DescendantOrSelf(label, map_child(child)),
+                PatternChild(pattern, child) => PatternChild(pattern, map_child(child)),
+                PatternDescendant(pattern, child) => PatternDescendant(pattern, map_child(child)),
+                Filter(filter, child) => Filter(filter, map_child(child)),
+                DescendantFilter(filter, child) => DescendantFilter(filter, map_child(child)),
+                MemberFromEnd(position, child) => MemberFromEnd(position, map_child(child)),
+                ArrayIndex(index, child) => ArrayIndex(index, map_child(child)),
+                Slice(slice, child) => Slice(slice, map_child(child)),
+                DescendantSlice(slice, child) => DescendantSlice(slice, map_child(child)),
+            }
+        }
+
+        Self {
+            root: Box::new(map(*self.root, &f)),
+        }
+    }
 }
 
 impl Display for JsonPathQuery {
@@ -304,12 +474,7 @@ impl Display for JsonPathQuery {
 impl Display for JsonPathQueryNode {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Root(_) => write!(f, "$"),
-            Child(label, _) => write!(f, "['{}']", label.display()),
-            AnyChild(_) => write!(f, "[*]"),
-            Descendant(label, _) => write!(f, "..['{}']", label.display()),
-        }?;
+        write!(f, "{}", self.selector_display())?;
 
         if let Some(child) = self.child() {
             write!(f, "{child}")
@@ -319,6 +484,31 @@ impl Display for JsonPathQueryNode {
     }
 }
 
+/// Display of a single [`JsonPathQueryNode`], without the nodes following it.
+struct SelectorDisplay<'a>(&'a JsonPathQueryNode);
+
+impl Display for SelectorDisplay<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Root(_) => write!(f, "$"),
+            Child(label, _) => write!(f, "['{}']", label.display()),
+            AnyChild(_) => write!(f, "[*]"),
+            Descendant(label, _) => write!(f, "..['{}']", label.display()),
+            DescendantOrSelf(label, _) => write!(f, "...['{}']", label.display()),
+            PatternChild(pattern, _) => write!(f, "[{pattern}]"),
+            PatternDescendant(pattern, _) => write!(f, "..[{pattern}]"),
+            Filter(filter, _) => write!(f, "[?({filter})]"),
+            DescendantFilter(filter, _) => write!(f, "..[?({filter})]"),
+            MemberFromEnd(position, _) if position.get() == 1 => write!(f, "[last()]"),
+            MemberFromEnd(position, _) => write!(f, "[last({position})]"),
+            ArrayIndex(index, _) => write!(f, "[{index}]"),
+            Slice(slice, _) => write!(f, "[{slice}]"),
+            DescendantSlice(slice, _) => write!(f, "..[{slice}]"),
+        }
+    }
+}
+
 /// Equips a struct with information on the type of [`JsonPathQueryNode`] it represents
 /// and methods to extract query elements from it.
 pub trait JsonPathQueryNodeType {
@@ -331,9 +521,13 @@ pub trait JsonPathQueryNodeType {
     /// Returns `true` iff the type is [`JsonPathQueryNode::Child`].
     fn is_child(&self) -> bool;
 
-    /// If the type is [`JsonPathQueryNode::Descendant`] or [`JsonPathQueryNode::Child`]
-    /// returns the label it represents; otherwise, `None`.
+    /// If the type is [`JsonPathQueryNode::Descendant`], [`JsonPathQueryNode::DescendantOrSelf`],
+    /// or [`JsonPathQueryNode::Child`] returns the label it represents; otherwise, `None`.
     fn label(&self) -> Option<&Label>;
+
+    /// If the type is [`JsonPathQueryNode::PatternDescendant`] or [`JsonPathQueryNode::PatternChild`]
+    /// returns the pattern it represents; otherwise, `None`.
+    fn pattern(&self) -> Option<&KeyPattern>;
 }
 
 impl JsonPathQueryNodeType for JsonPathQueryNode {
@@ -355,8 +549,35 @@ impl JsonPathQueryNodeType for JsonPathQueryNode {
     #[inline(always)]
     fn label(&self) -> Option<&Label> {
         match self {
-            Child(label, _) | Descendant(label, _) => Some(label),
-            Root(_) | AnyChild(_) => None,
+            Child(label, _) | Descendant(label, _) | DescendantOrSelf(label, _) => Some(label),
+            Root(_)
+            | AnyChild(_)
+            | PatternChild(_, _)
+            | PatternDescendant(_, _)
+            | Filter(_, _)
+            | DescendantFilter(_, _)
+            | MemberFromEnd(_, _)
+            | ArrayIndex(_, _)
+            | Slice(_, _)
+            | DescendantSlice(_, _) => None,
+        }
+    }
+
+    #[inline(always)]
+    fn pattern(&self) -> Option<&KeyPattern> {
+        match self {
+            PatternChild(pattern, _) | PatternDescendant(pattern, _) => Some(pattern),
+            Root(_)
+            | AnyChild(_)
+            | Child(_, _)
+            | Descendant(_, _)
+            | DescendantOrSelf(_, _)
+            | Filter(_, _)
+            | DescendantFilter(_, _)
+            | MemberFromEnd(_, _)
+            | ArrayIndex(_, _)
+            | Slice(_, _)
+            | DescendantSlice(_, _) => None,
         }
     }
 }
@@ -385,6 +606,11 @@ impl<T: std::ops::Deref<Target = JsonPathQueryNode>> JsonPathQueryNodeType for O
     fn label(&self) -> Option<&Label> {
         self.as_ref().and_then(|x| x.label())
     }
+
+    #[inline(always)]
+    fn pattern(&self) -> Option<&KeyPattern> {
+        self.as_ref().and_then(|x| x.pattern())
+    }
 }
 
 #[cfg(test)]
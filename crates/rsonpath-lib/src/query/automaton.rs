@@ -1,21 +1,31 @@
 //! Automaton representations of a JSONPath query.
 mod minimizer;
 mod nfa;
+mod selectivity;
 mod small_set;
 mod state;
 
+pub use selectivity::Selectivity;
 pub use state::{State, StateAttributes};
 
-use super::{error::CompilerError, JsonPathQuery, Label};
+use super::{
+    error::CompilerError, ArraySlice, Comparison, FilterExpression, JsonPathQuery, KeyPattern,
+    Label, NumberSyntax, Selector,
+};
 use crate::debug;
+use crate::error::UnsupportedFeatureError;
+use crate::json;
 use nfa::NondeterministicAutomaton;
 use smallvec::SmallVec;
-use std::{fmt::Display, ops::Index};
+use std::{fmt::Display, num::NonZeroUsize, ops::Index};
 
 /// A minimal, deterministic automaton representing a JSONPath query.
 #[derive(Debug, PartialEq, Eq)]
 pub struct Automaton<'q> {
     states: Vec<StateTable<'q>>,
+    filter: Option<&'q FilterExpression>,
+    member_from_end: Option<NonZeroUsize>,
+    number_syntax: NumberSyntax,
 }
 
 /// A single transition of an [`Automaton`].
@@ -23,13 +33,40 @@ type Transition<'q> = (&'q Label, State);
 
 /// A transition table of a single [`State`] of an [`Automaton`].
 ///
-/// Contains transitions triggered by matching labels, and a fallback transition
-/// triggered when none of the label transitions match.
+/// Contains transitions triggered by matching labels, transitions triggered
+/// by keys matching [`KeyPattern`]s, transitions triggered by array elements
+/// at specific indices or within [`ArraySlice`]s, and a fallback transition triggered
+/// when none of the other transitions match.
 #[derive(Debug)]
 pub struct StateTable<'q> {
     attributes: StateAttributes,
     transitions: SmallVec<[Transition<'q>; 2]>,
     fallback_state: State,
+    pattern_transitions: PatternTransitions<'q>,
+    index_transitions: SmallVec<[IndexTransition; 1]>,
+    slice_transitions: SliceTransitions,
+}
+
+/// A transition of an [`Automaton`] triggered by the array element at the given index.
+type IndexTransition = (usize, State);
+
+/// Transitions of a [`StateTable`] triggered by keys matching [`KeyPattern`]s.
+///
+/// A key can match any subset of the patterns, and the target depends on the entire subset.
+/// The targets are stored for every nonempty subset, indexed by its bitmask minus one.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PatternTransitions<'q> {
+    patterns: SmallVec<[&'q KeyPattern; 1]>,
+    targets: SmallVec<[State; 1]>,
+}
+
+/// Transitions of a [`StateTable`] triggered by array elements within [`ArraySlice`]s.
+///
+/// Stored like [`PatternTransitions`], with a target for every nonempty subset of the slices.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct SliceTransitions {
+    slices: SmallVec<[ArraySlice; 1]>,
+    targets: SmallVec<[State; 1]>,
 }
 
 impl<'q> Default for StateTable<'q> {
@@ -39,6 +76,9 @@ impl<'q> Default for StateTable<'q> {
             attributes: StateAttributes::default(),
             transitions: Default::default(),
             fallback_state: State(0),
+            pattern_transitions: PatternTransitions::default(),
+            index_transitions: SmallVec::default(),
+            slice_transitions: SliceTransitions::default(),
         }
     }
 }
@@ -47,6 +87,13 @@ impl<'q> PartialEq for StateTable<'q> {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
         self.fallback_state == other.fallback_state
+            && self.pattern_transitions == other.pattern_transitions
+            && self.slice_transitions == other.slice_transitions
+            && self.index_transitions.len() == other.index_transitions.len()
+            && self
+                .index_transitions
+                .iter()
+                .all(|x| other.index_transitions.contains(x))
             && self.transitions.len() == other.transitions.len()
             && self
                 .transitions
@@ -85,6 +132,243 @@ impl<'q> Automaton<'q> {
         Automaton::minimize(nfa)
     }
 
+    /// Convert a sequence of [`Selectors`](Selector) following the root into a minimal
+    /// deterministic automaton, without writing them as a query string and parsing it.
+    ///
+    /// The result is the same as that of [`Automaton::new`] for the query consisting of
+    /// the same selectors, and so are the errors for unsupported sequences of selectors,
+    /// e.g. a filter that is not the last selector. An empty sequence is the query `$`.
+    ///
+    /// # Errors
+    /// Same as [`Automaton::new`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::{JsonPathQuery, Label, Selector};
+    /// # use rsonpath_lib::query::automaton::Automaton;
+    /// let selectors = [
+    ///     Selector::Child(Label::new("a")),
+    ///     Selector::Descendant(Label::new("b")),
+    ///     Selector::ArrayIndex(0),
+    /// ];
+    /// let query = JsonPathQuery::parse("$.a..b[0]").unwrap();
+    ///
+    /// let automaton = Automaton::from_selectors(&selectors).unwrap();
+    ///
+    /// assert_eq!(automaton, Automaton::new(&query).unwrap());
+    /// ```
+    #[inline]
+    pub fn from_selectors(selectors: &'q [Selector]) -> Result<Self, CompilerError> {
+        let nfa = NondeterministicAutomaton::new_from_selectors(selectors)?;
+        debug!("NFA: {}", nfa);
+        Automaton::minimize(nfa)
+    }
+
+    /// Convert an alternation of [`JsonPathQueries`](JsonPathQuery) into a single
+    /// minimal deterministic automaton.
+    ///
+    /// The resulting automaton matches a node if and only if it is matched by at least
+    /// one of the `queries`, so the whole alternation can be evaluated in a single pass
+    /// over the document.
+    ///
+    /// # Errors
+    /// - [`CompilerError::QueryTooComplex`] raised if the queries are too complex
+    /// and the automaton size was exceeded.
+    /// - [`CompilerError::NotSupported`] raised if any of the queries contains elements
+    /// not yet supported by the compiler.
+    /// - [`CompilerError::EmptyAlternation`] raised if `queries` is empty.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// let queries = [
+    ///     JsonPathQuery::parse("$.a").unwrap(),
+    ///     JsonPathQuery::parse("$..b").unwrap(),
+    /// ];
+    /// let automaton = Automaton::new_alternation(&queries).unwrap();
+    ///
+    /// assert!(!automaton.is_empty_query());
+    /// ```
+    #[inline]
+    pub fn new_alternation(queries: &'q [JsonPathQuery]) -> Result<Self, CompilerError> {
+        let nfa = NondeterministicAutomaton::new_alternation(queries)?;
+        debug!("NFA: {}", nfa);
+        Automaton::minimize(nfa)
+    }
+
+    /// Convert a [`JsonPathQuery`] into a minimal deterministic automaton
+    /// coercing numeric labels to array indices.
+    ///
+    /// Every label that is a non-negative integer in canonical form, i.e. consisting only
+    /// of digits without leading zeroes, matches both the key equal to it in objects and the element
+    /// at the index it represents in arrays. The ambiguity is resolved by the type of the container
+    /// at runtime, so `$['0']` selects the member with key `"0"` of an object, but the first element
+    /// of an array. Other labels, like `'00'` or `'-1'`, are only ever compared with keys.
+    ///
+    /// This applies to all label selectors in the query, both child and descendant,
+    /// regardless of whether they were written in the bracket or dot notation.
+    /// [`Automaton::new`] never coerces labels, so that `$['0']` selects only the member
+    /// with key `"0"`, and `$[0]` only the first element of an array.
+    ///
+    /// # Errors
+    /// Same as [`Automaton::new`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+    /// # use rsonpath_lib::query::JsonPathQuery;
+    /// # use rsonpath_lib::query::automaton::Automaton;
+    /// # use rsonpath_lib::result::CountResult;
+    /// let query = JsonPathQuery::parse("$.*['1']").unwrap();
+    /// let automaton = Automaton::new_with_numeric_key_coercion(&query).unwrap();
+    /// let engine = RsonpathEngine::from_compiled_query(automaton);
+    /// let mut contents = r#"[{"1": "key"}, ["first", "second"], {"01": "other key"}]"#.to_owned();
+    /// let input = Input::new(&mut contents);
+    ///
+    /// let count = engine.run::<CountResult>(&input).unwrap().get();
+    ///
+    /// assert_eq!(count, 2);
+    /// ```
+    #[inline]
+    pub fn new_with_numeric_key_coercion(query: &'q JsonPathQuery) -> Result<Self, CompilerError> {
+        let nfa = NondeterministicAutomaton::new_with_numeric_key_coercion(query)?;
+        debug!("NFA: {}", nfa);
+        Automaton::minimize(nfa)
+    }
+
+    /// Convert a [`JsonPathQuery`] into a minimal deterministic automaton,
+    /// rejecting the query if it contains any descendant selector.
+    ///
+    /// Queries without descendant selectors only ever inspect values up to a fixed depth,
+    /// so this can be used to accept only queries that are cheap to execute.
+    /// All kinds of descendant selectors are rejected, i.e. `..`, `...`,
+    /// as well as descendant pattern and filter selectors.
+    ///
+    /// # Errors
+    /// - [`CompilerError::DescendantDisallowed`] raised for the first descendant
+    /// selector in the query.
+    /// - Otherwise, same as [`Automaton::new`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// # use rsonpath_lib::query::error::CompilerError;
+    /// let query = JsonPathQuery::parse("$.a.*..b").unwrap();
+    /// let error = Automaton::new_without_descendants(&query).unwrap_err();
+    ///
+    /// assert!(matches!(
+    ///     &error,
+    ///     CompilerError::DescendantDisallowed { selector, position: 3 } if selector == "..['b']"
+    /// ));
+    ///
+    /// let query = JsonPathQuery::parse("$.a.*.b").unwrap();
+    /// assert!(Automaton::new_without_descendants(&query).is_ok());
+    /// ```
+    #[inline]
+    pub fn new_without_descendants(query: &'q JsonPathQuery) -> Result<Self, CompilerError> {
+        let descendant = query
+            .root()
+            .iter()
+            .enumerate()
+            .find(|(_, node)| node.is_any_descendant());
+
+        if let Some((position, node)) = descendant {
+            return Err(CompilerError::DescendantDisallowed {
+                selector: node.selector_display().to_string(),
+                position,
+            });
+        }
+
+        Automaton::new(query)
+    }
+
+    /// Convert a [`JsonPathQuery`] into a minimal deterministic automaton
+    /// matching only the outermost matches of the query.
+    ///
+    /// Values nested within a value matched by the query are never matched, even if they
+    /// would be matched by the query on their own. For example, `$..a` matches only the outer
+    /// `a` in `{"a": {"a": 1}}`, but both values in `{"b": {"a": 1}, "c": {"a": 2}}`.
+    /// All accepting states are terminal, so engines skip the entire subtree of every match.
+    ///
+    /// # Errors
+    /// - [`CompilerError::NotSupported`] raised if the query ends with a filter
+    /// or a member from end selector, since values reaching the accepting states are not
+    /// all matches then.
+    /// - Otherwise, same as [`Automaton::new`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// let query = JsonPathQuery::parse("$..a").unwrap();
+    /// let automaton = Automaton::new_outermost(&query).unwrap();
+    /// let initial = &automaton[automaton.initial_state()];
+    /// let accepting = initial.transitions()[0].1;
+    ///
+    /// assert!(automaton.is_accepting(accepting));
+    /// assert!(automaton.is_terminal(accepting));
+    /// ```
+    #[inline]
+    pub fn new_outermost(query: &'q JsonPathQuery) -> Result<Self, CompilerError> {
+        let mut automaton = Automaton::new(query)?;
+
+        if automaton.filter.is_some() || automaton.member_from_end.is_some() {
+            return Err(UnsupportedFeatureError::outermost_matches_of_filters().into());
+        }
+
+        for table in &mut automaton.states {
+            if table.attributes.is_accepting() {
+                *table = StateTable {
+                    attributes: StateAttributes::ACCEPTING,
+                    ..StateTable::default()
+                };
+            }
+        }
+
+        Ok(automaton)
+    }
+
+    /// Convert a [`JsonPathQuery`] into a minimal deterministic automaton
+    /// matching the complement of the query's last selector.
+    ///
+    /// The resulting automaton matches all children of the nodes matched by the query
+    /// without its last selector, apart from those matched by the whole query.
+    /// For example, the complement of `$.config.secret` matches all children
+    /// of `$.config` with keys other than `secret`, and all elements of `$.config`
+    /// if it is an array.
+    ///
+    /// # Errors
+    /// - [`CompilerError::QueryTooComplex`] raised if the query is too complex
+    /// and the automaton size was exceeded.
+    /// - [`CompilerError::InvalidComplement`] raised if the query does not end
+    /// with a child selector, e.g. is `$` or ends with a descendant selector.
+    /// - [`CompilerError::NotSupported`] raised if the query ends with a child selector
+    /// other than a label, or contains elements not yet supported by the compiler.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// # use rsonpath_lib::query::error::CompilerError;
+    /// let query = JsonPathQuery::parse("$.config.secret").unwrap();
+    /// let automaton = Automaton::new_complement(&query).unwrap();
+    ///
+    /// assert!(!automaton.is_empty_query());
+    ///
+    /// let query = JsonPathQuery::parse("$..secret").unwrap();
+    /// let error = Automaton::new_complement(&query).unwrap_err();
+    ///
+    /// assert!(matches!(error, CompilerError::InvalidComplement));
+    /// ```
+    #[inline]
+    pub fn new_complement(query: &'q JsonPathQuery) -> Result<Self, CompilerError> {
+        let nfa = NondeterministicAutomaton::new_complement(query)?;
+        debug!("NFA: {}", nfa);
+        Automaton::minimize(nfa)
+    }
+
     /// Returns whether this automaton represents an empty JSONPath query ('$').
     ///
     /// # Examples
@@ -111,6 +395,116 @@ impl<'q> Automaton<'q> {
         self.states.len() == 2
     }
 
+    /// Returns the filter that every value has to satisfy to be accepted,
+    /// if the query ends with a filter selector.
+    ///
+    /// The filter is not represented in the states of the automaton &ndash; it is
+    /// compiled as a wildcard, and engines have to apply it to every value
+    /// that reaches an accepting state.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// let query = JsonPathQuery::parse("$.items[?(@.id == 42)]").unwrap();
+    /// let automaton = Automaton::new(&query).unwrap();
+    /// let filter = automaton.filter().unwrap();
+    ///
+    /// assert!(filter.matches(br#"{"id": 42}"#));
+    /// ```
+    #[must_use]
+    #[inline(always)]
+    pub fn filter(&self) -> Option<&'q FilterExpression> {
+        self.filter
+    }
+
+    /// Returns the position from the end of its object that a member has to be at
+    /// to be accepted, if the query ends with a member from end selector.
+    ///
+    /// Like the [`filter`](Automaton::filter), the selector is compiled as a wildcard.
+    /// Engines have to buffer the members of every object that reach an accepting state,
+    /// and report the one at the given position when the object is closed.
+    /// Values reached in arrays are never accepted.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// let query = JsonPathQuery::parse("$.items[last(2)]").unwrap();
+    /// let automaton = Automaton::new(&query).unwrap();
+    ///
+    /// assert_eq!(automaton.member_from_end().map(|n| n.get()), Some(2));
+    /// ```
+    #[must_use]
+    #[inline(always)]
+    pub fn member_from_end(&self) -> Option<NonZeroUsize> {
+        self.member_from_end
+    }
+
+    /// Set the [`NumberSyntax`] of values compared with numbers by the [`filter`](Automaton::filter).
+    ///
+    /// By default, the syntax is [`Strict`](NumberSyntax::Strict), so the engines follow the JSON
+    /// grammar and values like `01` or `+5` are not numbers. Setting it to
+    /// [`Lenient`](NumberSyntax::Lenient) makes such values compare as the numbers they represent.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+    /// # use rsonpath_lib::query::{JsonPathQuery, NumberSyntax};
+    /// # use rsonpath_lib::query::automaton::Automaton;
+    /// # use rsonpath_lib::result::CountResult;
+    /// let query = JsonPathQuery::parse("$.*[?(@ in 1..10)]").unwrap();
+    /// let mut contents = r#"[[1, 01, +5, 007.5]]"#.to_owned();
+    /// let input = Input::new(&mut contents);
+    ///
+    /// let strict = RsonpathEngine::from_compiled_query(Automaton::new(&query).unwrap());
+    /// let automaton = Automaton::new(&query).unwrap().with_number_syntax(NumberSyntax::Lenient);
+    /// let lenient = RsonpathEngine::from_compiled_query(automaton);
+    ///
+    /// assert_eq!(strict.run::<CountResult>(&input).unwrap().get(), 1);
+    /// assert_eq!(lenient.run::<CountResult>(&input).unwrap().get(), 4);
+    /// ```
+    #[must_use]
+    #[inline(always)]
+    pub fn with_number_syntax(mut self, number_syntax: NumberSyntax) -> Self {
+        self.number_syntax = number_syntax;
+        self
+    }
+
+    /// Returns the [`NumberSyntax`] of values compared with numbers by the [`filter`](Automaton::filter).
+    #[must_use]
+    #[inline(always)]
+    pub fn number_syntax(&self) -> NumberSyntax {
+        self.number_syntax
+    }
+
+    /// Returns whether every match of the query can be counted the moment it is found,
+    /// without inspecting the matched value or keeping track of its position.
+    ///
+    /// This holds for queries built only from label, wildcard, descendant, and pattern selectors.
+    /// Queries ending with a [`filter`](Automaton::filter) need to read every value reaching
+    /// an accepting state, and those ending with a [member from end](Automaton::member_from_end)
+    /// selector need to buffer the positions of members until their object is closed.
+    /// Callers can use this to route simple counting queries to a specialized path.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// let simple = JsonPathQuery::parse("$..a.*..b").unwrap();
+    /// let filtered = JsonPathQuery::parse("$..a[?(@ is scalar)]").unwrap();
+    /// let last = JsonPathQuery::parse("$..a[last()]").unwrap();
+    ///
+    /// assert!(Automaton::new(&simple).unwrap().is_count_optimizable());
+    /// assert!(!Automaton::new(&filtered).unwrap().is_count_optimizable());
+    /// assert!(!Automaton::new(&last).unwrap().is_count_optimizable());
+    /// ```
+    #[must_use]
+    #[inline(always)]
+    pub fn is_count_optimizable(&self) -> bool {
+        self.filter.is_none() && self.member_from_end.is_none()
+    }
+
     /// Returns the rejecting state of the automaton.
     ///
     /// The state is defined as the unique state from which there
@@ -192,8 +586,8 @@ impl<'q> Automaton<'q> {
     }
 
     /// Returns whether the given state is unitary.
-    /// A unitary state is one that has exactly one labelled transition
-    /// and its fallback targets the rejecting state.
+    /// A unitary state is one that has exactly one labelled transition,
+    /// no pattern transitions, and its fallback targets the rejecting state.
     ///
     /// Intuitively, there exists only one label that progresses towards
     /// acceptance from this state.
@@ -213,6 +607,122 @@ impl<'q> Automaton<'q> {
         self[state].attributes.is_unitary()
     }
 
+    /// Returns whether the given state is terminal.
+    /// A terminal state is one whose labelled and index transitions and fallback all target
+    /// the rejecting state, and which has no pattern transitions.
+    ///
+    /// Intuitively, no values nested within a value reached in this state can be matched,
+    /// so the engines can skip them after the value itself is handled.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// let query = JsonPathQuery::parse("$.a").unwrap();
+    /// let automaton = Automaton::new(&query).unwrap();
+    /// let state_2 = automaton[automaton.initial_state()].transitions()[0].1;
+    ///
+    /// assert!(automaton.is_terminal(state_2));
+    /// assert!(!automaton.is_terminal(automaton.initial_state()));
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn is_terminal(&self, state: State) -> bool {
+        let table = &self[state];
+
+        self.is_rejecting(table.fallback_state())
+            && !table.has_pattern_transitions()
+            && !table.has_slice_transitions()
+            && table
+                .transitions()
+                .iter()
+                .all(|&(_, target)| self.is_rejecting(target))
+            && table
+                .index_transitions()
+                .iter()
+                .all(|&(_, target)| self.is_rejecting(target))
+    }
+
+    /// Returns the only labelled transition of the initial state if the engines
+    /// can search for its label with head-skipping.
+    ///
+    /// This is the case if the initial state loops back to itself on every other key
+    /// and array element, and the label does not contain escaped control characters,
+    /// since keys can escape them differently than the label.
+    pub(crate) fn head_skip_transition(&self) -> Option<Transition<'q>> {
+        let initial_state = self.initial_state();
+        let table = &self[initial_state];
+
+        match table.transitions() {
+            [transition]
+                if table.fallback_state() == initial_state
+                    && !table.has_pattern_transitions()
+                    && !table.has_element_transitions()
+                    && !transition.0.has_control_escape() =>
+            {
+                Some(*transition)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the approximate number of bytes allocated on the heap for this automaton.
+    ///
+    /// This includes the tables of all states and the storage of every distinct [`Label`]
+    /// used in labelled transitions or in the filter, even though the labels are owned
+    /// by the query rather than the automaton. The size of the automaton is dominated by labels
+    /// for queries with many different labels, e.g. large alternations. The internal storage
+    /// of [`KeyPatterns`](KeyPattern), like compiled regular expressions, is not included.
+    ///
+    /// The result is meant for budgeting, e.g. rejecting queries whose automata are too large,
+    /// and does not account for allocator overhead.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// let short = JsonPathQuery::parse("$.a").unwrap();
+    /// let long = JsonPathQuery::parse("$.aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa").unwrap();
+    ///
+    /// let short_bytes = Automaton::new(&short).unwrap().approx_memory_bytes();
+    /// let long_bytes = Automaton::new(&long).unwrap().approx_memory_bytes();
+    ///
+    /// assert_eq!(long_bytes - short_bytes, 2 * 31);
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn approx_memory_bytes(&self) -> usize {
+        fn label_bytes(label: &Label) -> usize {
+            label.bytes().len() + label.bytes_with_quotes().len()
+        }
+
+        let mut total = self.states.capacity() * std::mem::size_of::<StateTable>();
+        let mut labels: Vec<&Label> = vec![];
+
+        for state in &self.states {
+            total += state.approx_heap_bytes();
+
+            for &(label, _) in &state.transitions {
+                if !labels.iter().any(|&other| std::ptr::eq(other, label)) {
+                    labels.push(label);
+                }
+            }
+        }
+        total += labels.into_iter().map(label_bytes).sum::<usize>();
+
+        if let Some(filter) = self.filter {
+            total += std::mem::size_of_val(filter.path());
+            total += filter.path().iter().map(label_bytes).sum::<usize>();
+            total += match filter.comparison() {
+                Comparison::Equal(literal) => literal.bytes().len(),
+                Comparison::InSet(set) => set.approx_heap_bytes(),
+                Comparison::IsScalar | Comparison::InRange(_) | Comparison::Size(..) => 0,
+            };
+        }
+
+        total
+    }
+
     fn minimize(nfa: NondeterministicAutomaton<'q>) -> Result<Self, CompilerError> {
         minimizer::minimize(nfa)
     }
@@ -238,6 +748,157 @@ impl<'q> StateTable<'q> {
     pub fn transitions(&self) -> &[Transition<'q>] {
         &self.transitions
     }
+
+    /// Returns whether this state has any transitions triggered
+    /// by keys matching a [`KeyPattern`].
+    #[must_use]
+    #[inline(always)]
+    pub fn has_pattern_transitions(&self) -> bool {
+        !self.pattern_transitions.patterns.is_empty()
+    }
+
+    /// Returns the collection of transitions from this state triggered
+    /// by the array elements at specific indices.
+    ///
+    /// A transition is triggered by the element at the contained index, counting from 0,
+    /// and leads to the contained [`State`]. Index transitions are never triggered by keys
+    /// of objects, and elements at other indices take the fallback transition.
+    #[must_use]
+    #[inline(always)]
+    pub fn index_transitions(&self) -> &[IndexTransition] {
+        &self.index_transitions
+    }
+
+    /// Returns whether this state has any transitions triggered
+    /// by array elements within an [`ArraySlice`].
+    #[must_use]
+    #[inline(always)]
+    pub fn has_slice_transitions(&self) -> bool {
+        !self.slice_transitions.slices.is_empty()
+    }
+
+    /// Returns whether the transition taken by an array element can depend on its index,
+    /// i.e. this state has any [`index_transitions`](StateTable::index_transitions)
+    /// or [slice transitions](StateTable::has_slice_transitions).
+    #[must_use]
+    #[inline(always)]
+    pub fn has_element_transitions(&self) -> bool {
+        !self.index_transitions.is_empty() || self.has_slice_transitions()
+    }
+
+    /// Returns the state to which the array element at the given `index` leads,
+    /// taking the fallback transition if there is no transition for the index.
+    ///
+    /// Explicit index transitions take precedence, as they already account for the slices
+    /// containing their index. Otherwise the target depends on all slices containing the index.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// let query = JsonPathQuery::parse("$[1]").unwrap();
+    /// let automaton = Automaton::new(&query).unwrap();
+    /// let initial = &automaton[automaton.initial_state()];
+    ///
+    /// assert!(automaton.is_accepting(initial.element_transition(1)));
+    /// assert!(automaton.is_rejecting(initial.element_transition(0)));
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn element_transition(&self, index: usize) -> State {
+        if let Some(&(_, target)) = self
+            .index_transitions
+            .iter()
+            .find(|&&(other, _)| other == index)
+        {
+            return target;
+        }
+
+        let mask = self
+            .slice_transitions
+            .slices
+            .iter()
+            .enumerate()
+            .filter(|(_, slice)| slice.contains(index))
+            .fold(0, |mask, (i, _)| mask | (1 << i));
+
+        if mask == 0 {
+            self.fallback_state
+        } else {
+            self.slice_transitions.targets[mask - 1]
+        }
+    }
+
+    /// Returns the state to which a key leads by matching [`KeyPattern`]s,
+    /// or [`None`] if it does not match any pattern of this state.
+    ///
+    /// The `key` are the raw contents of the key between its quotes,
+    /// as they appear in the JSON document. It is unescaped before matching.
+    ///
+    /// This should be used only if the key does not match any of the labelled
+    /// [`transitions`](StateTable::transitions), since those already
+    /// account for the patterns matching their labels.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// # #[cfg(feature = "regex")]
+    /// # {
+    /// let query = JsonPathQuery::parse("$[~'^a+$']").unwrap();
+    /// let automaton = Automaton::new(&query).unwrap();
+    /// let initial = &automaton[automaton.initial_state()];
+    /// let target = initial.pattern_transition(b"aaa").unwrap();
+    ///
+    /// assert!(automaton.is_accepting(target));
+    /// assert_eq!(initial.pattern_transition(b"b"), None);
+    /// # }
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn pattern_transition(&self, key: &[u8]) -> Option<State> {
+        if !self.has_pattern_transitions() {
+            return None;
+        }
+
+        let key = json::unescape(key);
+        let mask = self
+            .pattern_transitions
+            .patterns
+            .iter()
+            .enumerate()
+            .filter(|(_, pattern)| pattern.is_match(&key))
+            .fold(0, |mask, (i, _)| mask | (1 << i));
+
+        (mask != 0).then(|| self.pattern_transitions.targets[mask - 1])
+    }
+
+    /// Heap storage of the transitions, excluding the labels and patterns themselves.
+    fn approx_heap_bytes(&self) -> usize {
+        let mut total = 0;
+
+        if self.transitions.spilled() {
+            total += self.transitions.capacity() * std::mem::size_of::<Transition>();
+        }
+        if self.pattern_transitions.patterns.spilled() {
+            total +=
+                self.pattern_transitions.patterns.capacity() * std::mem::size_of::<&KeyPattern>();
+        }
+        if self.pattern_transitions.targets.spilled() {
+            total += self.pattern_transitions.targets.capacity() * std::mem::size_of::<State>();
+        }
+        if self.index_transitions.spilled() {
+            total += self.index_transitions.capacity() * std::mem::size_of::<IndexTransition>();
+        }
+        if self.slice_transitions.slices.spilled() {
+            total += self.slice_transitions.slices.capacity() * std::mem::size_of::<ArraySlice>();
+        }
+        if self.slice_transitions.targets.spilled() {
+            total += self.slice_transitions.targets.capacity() * std::mem::size_of::<State>();
+        }
+
+        total
+    }
 }
 
 impl<'q> Display for Automaton<'q> {
@@ -280,6 +941,17 @@ impl<'q> Display for Automaton<'q> {
             for (label, state) in transitions.transitions.iter() {
                 writeln!(f, "  {i} -> {} [label=\"{}\"]", state.0, label.display(),)?
             }
+            for (j, pattern) in transitions.pattern_transitions.patterns.iter().enumerate() {
+                let state = transitions.pattern_transitions.targets[(1 << j) - 1];
+                writeln!(f, "  {i} -> {} [label=\"{pattern}\"]", state.0)?
+            }
+            for (index, state) in &transitions.index_transitions {
+                writeln!(f, "  {i} -> {} [label=\"[{index}]\"]", state.0)?
+            }
+            for (j, slice) in transitions.slice_transitions.slices.iter().enumerate() {
+                let state = transitions.slice_transitions.targets[(1 << j) - 1];
+                writeln!(f, "  {i} -> {} [label=\"[{slice}]\"]", state.0)?
+            }
             writeln!(f, "  {i} -> {} [label=\"*\"]", transitions.fallback_state.0)?;
         }
         write!(f, "}}")?;
@@ -5,9 +5,13 @@
 use super::nfa::{self, NfaState, NfaStateId};
 use super::small_set::{SmallSet, SmallSet256};
 use super::state::StateAttributesBuilder;
-use super::{Automaton, NondeterministicAutomaton, State as DfaStateId, StateTable};
-use super::{Label, StateAttributes};
+use super::{ArraySlice, KeyPattern, Label, NumberSyntax, StateAttributes};
+use super::{
+    Automaton, NondeterministicAutomaton, PatternTransitions, SliceTransitions,
+    State as DfaStateId, StateTable,
+};
 use crate::debug;
+use crate::json;
 use crate::query::error::CompilerError;
 use smallvec::{smallvec, SmallVec};
 use vector_map::VecMap;
@@ -19,7 +23,6 @@ pub(super) fn minimize(nfa: NondeterministicAutomaton) -> Result<Automaton, Comp
     let minimizer = Minimizer {
         nfa,
         superstates: VecMap::new(),
-        checkpoints: VecMap::new(),
         active_superstates: smallvec![],
         dfa_states: vec![],
         accepting: SmallSet256::default(),
@@ -33,8 +36,6 @@ pub(super) struct Minimizer<'q> {
     nfa: NondeterministicAutomaton<'q>,
     /// All superstates created thus far mapping to their index in the DFA being constructed.
     superstates: VecMap<SmallSet256, DfaStateId>,
-    /// Map from superstates to the furthest reachable checkpoint on a path leading to that superstate.
-    checkpoints: VecMap<SmallSet256, NfaStateId>,
     /// Superstates that have not been processed and expanded yet.
     active_superstates: SmallVec<[SmallSet256; 2]>,
     /// All superstates created thus far, in order matching the `superstates` map.
@@ -43,10 +44,25 @@ pub(super) struct Minimizer<'q> {
     accepting: SmallSet256,
 }
 
+/// Maximum number of distinct [`KeyPattern`]s in a single superstate.
+/// A target is computed for every subset of the patterns, so this has to be kept low.
+const MAX_PATTERNS_PER_STATE: usize = 8;
+
+/// Maximum number of distinct [`ArraySlice`]s in a single superstate,
+/// limited for the same reason as [`MAX_PATTERNS_PER_STATE`].
+const MAX_SLICES_PER_STATE: usize = 8;
+
 #[derive(Debug)]
 struct SuperstateTransitionTable<'q> {
     labelled: VecMap<&'q Label, SmallSet256>,
+    indexed: VecMap<usize, SmallSet256>,
     wildcard: SmallSet256,
+    patterns: SmallVec<[&'q KeyPattern; 1]>,
+    /// Targets for every nonempty subset of `patterns`, indexed by the subset's bitmask minus one.
+    pattern_targets: SmallVec<[SmallSet256; 1]>,
+    slices: SmallVec<[ArraySlice; 1]>,
+    /// Targets for every nonempty subset of `slices`, indexed by the subset's bitmask minus one.
+    slice_targets: SmallVec<[SmallSet256; 1]>,
 }
 
 /**
@@ -62,8 +78,14 @@ struct SuperstateTransitionTable<'q> {
  * This allows on-the-fly minimization with the `normalize` function, vastly reducing
  * the number of superstates to consider.
  *
- * Identifying checkpoints is easy - these are exactly the singleton sets of Recursive
- * NFA states.
+ * Identifying checkpoints is easy - these are exactly the Recursive NFA states.
+ * After normalization, a superstate contains at most one of them for each alternative.
+ *
+ * An NFA of an alternation of queries consists of independent paths, one for each
+ * alternative. The properties above hold for each of the paths separately, so all
+ * of the reasoning is applied per alternative - the initial superstate contains
+ * the initial states of all alternatives, and normalization only ever removes states
+ * on the path of the alternative of the checkpoint.
  *
  * We expand each superstate by examining all transitions originating from NFA states
  * in the superstate. The targets of those transitions are consolidated into superstates.
@@ -82,12 +104,15 @@ impl<'q> Minimizer<'q> {
             transitions: smallvec![],
             fallback_state: Self::rejecting_state(),
             attributes: StateAttributesBuilder::new().rejecting().into(),
+            pattern_transitions: PatternTransitions::default(),
+            index_transitions: smallvec![],
+            slice_transitions: SliceTransitions::default(),
         });
         self.superstates
             .insert(SmallSet256::default(), Self::rejecting_state());
 
-        // Initial superstate is {0}.
-        let initial_superstate = [0].into();
+        // Initial superstate contains the initial states of all alternatives, {0} for a single query.
+        let initial_superstate = self.nfa.initial_states();
         self.activate_if_new(initial_superstate)?;
 
         while let Some(superstate) = self.active_superstates.pop() {
@@ -96,6 +121,9 @@ impl<'q> Minimizer<'q> {
 
         Ok(Automaton {
             states: self.dfa_states,
+            filter: self.nfa.filter,
+            member_from_end: self.nfa.member_from_end,
+            number_syntax: NumberSyntax::Strict,
         })
     }
 
@@ -117,7 +145,10 @@ impl<'q> Minimizer<'q> {
             self.active_superstates.push(superstate);
             self.dfa_states.push(StateTable::default());
             debug!("New superstate created: {superstate:?} {identifier}");
-            if superstate.contains(self.nfa.accepting_state().0) {
+            if superstate
+                .iter()
+                .any(|nfa_state| self.nfa.is_accepting(NfaStateId(nfa_state)))
+            {
                 self.accepting.insert(identifier.0);
             }
         }
@@ -128,16 +159,16 @@ impl<'q> Minimizer<'q> {
     /// Create the superstate's [`TransitionTable`] by processing all transitions
     /// of NFA states within the superstate.
     fn process_superstate(&mut self, current_superstate: SmallSet256) -> Result<(), CompilerError> {
-        let current_checkpoint = self.determine_checkpoint(current_superstate);
+        let current_checkpoints = self.determine_checkpoints(current_superstate);
         debug!(
-            "Expanding superstate: {current_superstate:?}, last checkpoint is {current_checkpoint:?}"
+            "Expanding superstate: {current_superstate:?}, last checkpoints are {current_checkpoints:?}"
         );
 
         let mut transitions =
-            self.process_nfa_transitions(current_superstate, current_checkpoint)?;
+            self.process_nfa_transitions(current_superstate, current_checkpoints)?;
         debug!("Raw transitions: {:?}", transitions);
 
-        self.normalize_superstate_transitions(&mut transitions, current_checkpoint)?;
+        self.normalize_superstate_transitions(&mut transitions)?;
         debug!("Normalized transitions: {:?}", transitions);
 
         // Translate the transitions to the data model expected by TransitionTable.
@@ -147,16 +178,47 @@ impl<'q> Minimizer<'q> {
             .map(|(label, state)| (label, self.superstates[&state]))
             .collect();
         debug!("Translated transitions: {translated_transitions:?}");
+        let index_transitions: SmallVec<_> = transitions
+            .indexed
+            .into_iter()
+            .map(|(index, state)| (index, self.superstates[&state]))
+            .collect();
+        let pattern_transitions = PatternTransitions {
+            patterns: transitions.patterns,
+            targets: transitions
+                .pattern_targets
+                .iter()
+                .map(|state| self.superstates[state])
+                .collect(),
+        };
+        let slice_transitions = SliceTransitions {
+            slices: transitions.slices,
+            targets: transitions
+                .slice_targets
+                .iter()
+                .map(|state| self.superstates[state])
+                .collect(),
+        };
 
-        // If a checkpoint was reached, its singleton superstate is this DFA state's fallback state.
+        // If any checkpoints were reached, the superstate containing them is this DFA state's fallback state.
         // Otherwise, we set the fallback to the rejecting state.
         let id = self.superstates[&current_superstate];
         let fallback_state = self.superstates[&transitions.wildcard];
-        let attributes = self.build_attributes(id, &translated_transitions, fallback_state);
+        let attributes = self.build_attributes(
+            id,
+            &translated_transitions,
+            &pattern_transitions,
+            &index_transitions,
+            &slice_transitions,
+            fallback_state,
+        );
         let mut table = &mut self.dfa_states[id.0 as usize];
         table.transitions = translated_transitions;
         table.fallback_state = fallback_state;
         table.attributes = attributes;
+        table.pattern_transitions = pattern_transitions;
+        table.index_transitions = index_transitions;
+        table.slice_transitions = slice_transitions;
 
         Ok(())
     }
@@ -167,6 +229,9 @@ impl<'q> Minimizer<'q> {
         &self,
         id: DfaStateId,
         transitions: &[(&Label, DfaStateId)],
+        pattern_transitions: &PatternTransitions,
+        index_transitions: &[(usize, DfaStateId)],
+        slice_transitions: &SliceTransitions,
         fallback: DfaStateId,
     ) -> StateAttributes {
         let mut attrs = StateAttributesBuilder::new();
@@ -179,7 +244,12 @@ impl<'q> Minimizer<'q> {
             debug!("{id} is rejecting");
             attrs = attrs.rejecting();
         }
-        if transitions.len() == 1 && fallback == Self::rejecting_state() {
+        if transitions.len() == 1
+            && pattern_transitions.patterns.is_empty()
+            && index_transitions.is_empty()
+            && slice_transitions.slices.is_empty()
+            && fallback == Self::rejecting_state()
+        {
             debug!("{id} is unitary");
             attrs = attrs.unitary();
         }
@@ -187,6 +257,17 @@ impl<'q> Minimizer<'q> {
             || transitions
                 .iter()
                 .any(|(_, s)| self.accepting.contains(s.0))
+            || pattern_transitions
+                .targets
+                .iter()
+                .any(|s| self.accepting.contains(s.0))
+            || index_transitions
+                .iter()
+                .any(|(_, s)| self.accepting.contains(s.0))
+            || slice_transitions
+                .targets
+                .iter()
+                .any(|s| self.accepting.contains(s.0))
         {
             debug!("{id} has transitions to accepting");
             attrs = attrs.transitions_to_accepting();
@@ -195,28 +276,15 @@ impl<'q> Minimizer<'q> {
         attrs.into()
     }
 
-    /// Determine what is the furthest reachable checkpoint on the path to this
-    /// superstate. This is either the superstate itself, if it is a checkpoint,
-    /// or the one flowed into from a previous superstate via the `checkpoints` map.
-    fn determine_checkpoint(&mut self, superstate: SmallSet256) -> Option<NfaStateId> {
-        if let Some(nfa_state) = self.as_checkpoint(superstate) {
-            self.checkpoints.insert(superstate, nfa_state);
-            Some(nfa_state)
-        } else {
-            self.checkpoints.get(&superstate).copied()
-        }
-    }
-
-    /// Determine whether the `superstate` is a checkpoint, and if yes
-    /// return the Recursive NFA state it represents. Otherwise, return `None`.
-    fn as_checkpoint(&self, superstate: SmallSet256) -> Option<NfaStateId> {
-        if let Some(single_state) = superstate.singleton().map(NfaStateId) {
-            if matches!(self.nfa[single_state], NfaState::Recursive(_)) {
-                return Some(single_state);
-            }
-        }
-
-        None
+    /// Determine what are the furthest reachable checkpoints on the path to this
+    /// superstate. Since every transition leads back to the current checkpoints
+    /// and superstates are normalized, these are exactly the Recursive NFA states
+    /// in the superstate, at most one for each alternative.
+    fn determine_checkpoints(&self, superstate: SmallSet256) -> SmallSet256 {
+        superstate
+            .iter()
+            .filter(|&x| matches!(self.nfa[NfaStateId(x)], NfaState::Recursive(_)))
+            .collect()
     }
 
     /// Create the transition table for a superstate by traversing all NFA transitions
@@ -224,27 +292,37 @@ impl<'q> Minimizer<'q> {
     fn process_nfa_transitions(
         &self,
         current_superstate: SmallSet256,
-        current_checkpoint: Option<NfaStateId>,
+        current_checkpoints: SmallSet256,
     ) -> Result<SuperstateTransitionTable<'q>, CompilerError> {
         let mut wildcard_targets = current_superstate
             .iter()
             .map(NfaStateId)
             .filter_map(|id| match self.nfa[id] {
-                NfaState::Recursive(nfa::Transition::Wildcard)
-                | NfaState::Direct(nfa::Transition::Wildcard) => Some(id.next().map(|x| x.0)),
+                NfaState::Recursive(nfa::Transition::Wildcard | nfa::Transition::AllExcept(_))
+                | NfaState::Direct(nfa::Transition::Wildcard | nfa::Transition::AllExcept(_)) => {
+                    Some(id.next().map(|x| x.0))
+                }
                 _ => None,
             })
             .collect::<Result<SmallSet256, _>>()?;
-        if let Some(checkpoint) = current_checkpoint {
-            wildcard_targets.insert(checkpoint.0);
+        for checkpoint in current_checkpoints.iter() {
+            wildcard_targets.insert(checkpoint);
         }
 
         debug!("Wildcard target: {wildcard_targets:?}");
 
         let mut transitions = SuperstateTransitionTable {
             labelled: VecMap::new(),
+            indexed: VecMap::new(),
             wildcard: wildcard_targets,
+            patterns: smallvec![],
+            pattern_targets: smallvec![],
+            slices: smallvec![],
+            slice_targets: smallvec![],
         };
+        let mut pattern_nexts: SmallVec<[u8; 1]> = smallvec![];
+        let mut slice_nexts: SmallVec<[u8; 1]> = smallvec![];
+        let mut exclusions: SmallVec<[(&'q Label, u8); 1]> = smallvec![];
 
         for nfa_state in current_superstate.iter().map(NfaStateId) {
             match self.nfa[nfa_state] {
@@ -258,15 +336,73 @@ impl<'q> Minimizer<'q> {
                         label.display(),
                         nfa_state.next()?,
                     );
-                    // Add the target NFA state to the target superstate, or create a singleton
-                    // set if this is the first transition via this label encountered in the loop.
-                    if let Some(target) = transitions.labelled.get_mut(&label) {
-                        target.insert(nfa_state.next()?.0);
-                    } else {
-                        let mut new_set = transitions.wildcard;
-                        new_set.insert(nfa_state.next()?.0);
-                        transitions.labelled.insert(label, new_set);
+                    insert_target(
+                        &mut transitions.labelled,
+                        label,
+                        transitions.wildcard,
+                        nfa_state.next()?.0,
+                    );
+                }
+                NfaState::Direct(nfa::Transition::Pattern(pattern))
+                | NfaState::Recursive(nfa::Transition::Pattern(pattern)) => {
+                    debug!(
+                        "Considering transition {nfa_state} --{pattern}-> {}",
+                        nfa_state.next()?,
+                    );
+                    transitions.patterns.push(pattern);
+                    pattern_nexts.push(nfa_state.next()?.0);
+                }
+                // The forward transition is already in the wildcard target,
+                // the excluded label gets a target without it below.
+                NfaState::Direct(nfa::Transition::AllExcept(label))
+                | NfaState::Recursive(nfa::Transition::AllExcept(label)) => {
+                    debug!(
+                        "Considering transition {nfa_state} --!{}-> {}",
+                        label.display(),
+                        nfa_state.next()?,
+                    );
+                    if !transitions.labelled.contains_key(&label) {
+                        transitions.labelled.insert(label, transitions.wildcard);
                     }
+                    exclusions.push((label, nfa_state.next()?.0));
+                }
+                // Array indices never match keys, so neither labels nor patterns
+                // have to be taken into account for them.
+                NfaState::Direct(nfa::Transition::Index(index))
+                | NfaState::Recursive(nfa::Transition::Index(index)) => {
+                    debug!(
+                        "Considering transition {nfa_state} --[{index}]-> {}",
+                        nfa_state.next()?,
+                    );
+                    insert_target(
+                        &mut transitions.indexed,
+                        index,
+                        transitions.wildcard,
+                        nfa_state.next()?.0,
+                    );
+                }
+                // Keys equal to the label and elements at the index take the same transition,
+                // as if there were a labelled and an index transition.
+                NfaState::Direct(nfa::Transition::LabelOrIndex(label, index))
+                | NfaState::Recursive(nfa::Transition::LabelOrIndex(label, index)) => {
+                    debug!(
+                        "Considering transition {nfa_state} --{}|[{index}]-> {}",
+                        label.display(),
+                        nfa_state.next()?,
+                    );
+                    let next = nfa_state.next()?.0;
+                    insert_target(&mut transitions.labelled, label, transitions.wildcard, next);
+                    insert_target(&mut transitions.indexed, index, transitions.wildcard, next);
+                }
+                // Slices are resolved into targets for every subset after all indices are known.
+                NfaState::Direct(nfa::Transition::Slice(slice))
+                | NfaState::Recursive(nfa::Transition::Slice(slice)) => {
+                    debug!(
+                        "Considering transition {nfa_state} --[{slice}]-> {}",
+                        nfa_state.next()?,
+                    );
+                    transitions.slices.push(slice);
+                    slice_nexts.push(nfa_state.next()?.0);
                 }
                 NfaState::Direct(nfa::Transition::Wildcard)
                 | NfaState::Recursive(nfa::Transition::Wildcard)
@@ -274,6 +410,64 @@ impl<'q> Minimizer<'q> {
             }
         }
 
+        if transitions.patterns.len() > MAX_PATTERNS_PER_STATE
+            || transitions.slices.len() > MAX_SLICES_PER_STATE
+        {
+            return Err(CompilerError::QueryTooComplex(None));
+        }
+
+        // An element at an explicit index can also be within some of the slices,
+        // so the index's target has to include their targets.
+        for (&index, target) in transitions.indexed.iter_mut() {
+            for (slice, &next) in transitions.slices.iter().zip(&slice_nexts) {
+                if slice.contains(index) {
+                    target.insert(next);
+                }
+            }
+        }
+
+        // A key matching a label can also match some of the patterns,
+        // so the label's target has to include their targets.
+        for (label, target) in transitions.labelled.iter_mut() {
+            let key = json::unescape(label.bytes());
+            for (pattern, &next) in transitions.patterns.iter().zip(&pattern_nexts) {
+                if pattern.is_match(&key) {
+                    target.insert(next);
+                }
+            }
+        }
+
+        // Keys matching an excluded label do not take the excluding transition.
+        for (label, next) in exclusions {
+            if let Some(target) = transitions.labelled.get_mut(&label) {
+                *target = target.iter().filter(|&x| x != next).collect();
+            }
+        }
+
+        // Keys not matching any label go to the wildcard target extended with
+        // targets of all patterns they match.
+        for mask in 1..(1_usize << transitions.patterns.len()) {
+            let mut target = transitions.wildcard;
+            for (i, &next) in pattern_nexts.iter().enumerate() {
+                if mask & (1 << i) != 0 {
+                    target.insert(next);
+                }
+            }
+            transitions.pattern_targets.push(target);
+        }
+
+        // Elements at other indices go to the wildcard target extended with
+        // targets of all slices containing them.
+        for mask in 1..(1_usize << transitions.slices.len()) {
+            let mut target = transitions.wildcard;
+            for (i, &next) in slice_nexts.iter().enumerate() {
+                if mask & (1 << i) != 0 {
+                    target.insert(next);
+                }
+            }
+            transitions.slice_targets.push(target);
+        }
+
         Ok(transitions)
     }
 
@@ -282,51 +476,68 @@ impl<'q> Minimizer<'q> {
     fn normalize_superstate_transitions(
         &mut self,
         transitions: &mut SuperstateTransitionTable,
-        current_checkpoint: Option<NfaStateId>,
     ) -> Result<(), CompilerError> {
-        fn normalize_one(
-            this: &mut Minimizer,
-            state: &mut SmallSet256,
-            current_checkpoint: Option<NfaStateId>,
-        ) -> Result<(), CompilerError> {
-            if let Some(checkpoint) = current_checkpoint {
-                state.insert(checkpoint.0);
-            }
+        // The checkpoints are already included in all targets, since labelled targets
+        // are built on top of the wildcard target.
+        self.normalize(&mut transitions.wildcard);
+        self.activate_if_new(transitions.wildcard)?;
 
-            this.normalize(state);
-            this.activate_if_new(*state)?;
+        for (_, state) in transitions.labelled.iter_mut() {
+            self.normalize(state);
+            self.activate_if_new(*state)?;
+        }
 
-            if let Some(checkpoint) = current_checkpoint {
-                this.checkpoints.insert(*state, checkpoint);
-            }
+        for state in &mut transitions.pattern_targets {
+            self.normalize(state);
+            self.activate_if_new(*state)?;
+        }
 
-            Ok(())
+        for (_, state) in transitions.indexed.iter_mut() {
+            self.normalize(state);
+            self.activate_if_new(*state)?;
         }
 
-        normalize_one(self, &mut transitions.wildcard, current_checkpoint)?;
-        for (_, state) in transitions.labelled.iter_mut() {
-            normalize_one(self, state, current_checkpoint)?;
+        for state in &mut transitions.slice_targets {
+            self.normalize(state);
+            self.activate_if_new(*state)?;
         }
 
         Ok(())
     }
 
     /// If a superstate contains a Recursive NFA state, then all the NFA states
-    /// prior to that Recursive state can be removed, equalizing many possible
-    /// combinations.
+    /// of the same alternative prior to that Recursive state can be removed,
+    /// equalizing many possible combinations.
     fn normalize(&self, superstate: &mut SmallSet256) {
-        let furthest_checkpoint = superstate
-            .iter()
-            .map(NfaStateId)
-            .filter(|&x| matches!(self.nfa[x], NfaState::Recursive(_)))
-            .max();
+        let checkpoints = self.determine_checkpoints(*superstate);
 
-        if let Some(cutoff) = furthest_checkpoint {
-            superstate.remove_all_before(cutoff.0);
+        for cutoff in checkpoints.iter().map(NfaStateId) {
+            let start = self.nfa.alternative_start(cutoff);
+            *superstate = superstate
+                .iter()
+                .filter(|&x| x < start.0 || x >= cutoff.0)
+                .collect();
         }
     }
 }
 
+/// Add the `next` NFA state to the target superstate of the `key`, or create one from
+/// the `wildcard` target if this is the first transition via the `key` encountered.
+fn insert_target<K: PartialEq>(
+    targets: &mut VecMap<K, SmallSet256>,
+    key: K,
+    wildcard: SmallSet256,
+    next: u8,
+) {
+    if let Some(target) = targets.get_mut(&key) {
+        target.insert(next);
+    } else {
+        let mut new_set = wildcard;
+        new_set.insert(next);
+        targets.insert(key, new_set);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::*;
@@ -340,6 +551,8 @@ mod tests {
         // Query = $
         let nfa = NondeterministicAutomaton {
             ordered_states: vec![NfaState::Accepting],
+            filter: None,
+            member_from_end: None,
         };
 
         let result = minimize(nfa).unwrap();
@@ -349,13 +562,22 @@ mod tests {
                     transitions: smallvec![],
                     fallback_state: State(0),
                     attributes: StateAttributes::REJECTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![],
                     fallback_state: State(0),
                     attributes: StateAttributes::ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
             ],
+            filter: None,
+            member_from_end: None,
+            number_syntax: NumberSyntax::Strict,
         };
 
         assert_eq!(result, expected);
@@ -369,6 +591,8 @@ mod tests {
                 NfaState::Direct(nfa::Transition::Wildcard),
                 NfaState::Accepting,
             ],
+            filter: None,
+            member_from_end: None,
         };
 
         let result = minimize(nfa).unwrap();
@@ -378,18 +602,30 @@ mod tests {
                     transitions: smallvec![],
                     fallback_state: State(0),
                     attributes: StateAttributes::REJECTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![],
                     fallback_state: State(2),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![],
                     fallback_state: State(0),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
             ],
+            filter: None,
+            member_from_end: None,
+            number_syntax: NumberSyntax::Strict,
         };
 
         assert_eq!(result, expected);
@@ -405,6 +641,8 @@ mod tests {
                 NfaState::Direct(nfa::Transition::Wildcard),
                 NfaState::Accepting,
             ],
+            filter: None,
+            member_from_end: None,
         };
 
         let result = minimize(nfa).unwrap();
@@ -414,29 +652,47 @@ mod tests {
                     transitions: smallvec![],
                     fallback_state: State(0),
                     attributes: StateAttributes::REJECTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(2)),],
                     fallback_state: State(1),
                     attributes: StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(4))],
                     fallback_state: State(3),
                     attributes: StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(2))],
                     fallback_state: State(1),
                     attributes: StateAttributes::ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(4))],
                     fallback_state: State(3),
                     attributes: StateAttributes::ACCEPTING
                         | StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
             ],
+            filter: None,
+            member_from_end: None,
+            number_syntax: NumberSyntax::Strict,
         };
 
         assert_eq!(result, expected);
@@ -454,6 +710,8 @@ mod tests {
                 NfaState::Direct(nfa::Transition::Wildcard),
                 NfaState::Accepting,
             ],
+            filter: None,
+            member_from_end: None,
         };
 
         let result = minimize(nfa).unwrap();
@@ -463,33 +721,54 @@ mod tests {
                     transitions: smallvec![],
                     fallback_state: State(0),
                     attributes: StateAttributes::REJECTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(2))],
                     fallback_state: State(0),
                     attributes: StateAttributes::UNITARY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![],
                     fallback_state: State(3),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![],
                     fallback_state: State(4),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![],
                     fallback_state: State(5),
                     attributes: StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![],
                     fallback_state: State(0),
                     attributes: StateAttributes::ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
             ],
+            filter: None,
+            member_from_end: None,
+            number_syntax: NumberSyntax::Strict,
         };
 
         assert_eq!(result, expected);
@@ -506,6 +785,8 @@ mod tests {
                 NfaState::Direct(nfa::Transition::Wildcard),
                 NfaState::Accepting,
             ],
+            filter: None,
+            member_from_end: None,
         };
 
         let result = minimize(nfa).unwrap();
@@ -515,49 +796,79 @@ mod tests {
                     transitions: smallvec![],
                     fallback_state: State(0),
                     attributes: StateAttributes::REJECTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(2))],
                     fallback_state: State(1),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(4))],
                     fallback_state: State(3),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(8))],
                     fallback_state: State(7),
                     attributes: StateAttributes::EMPTY | StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(6))],
                     fallback_state: State(5),
                     attributes: StateAttributes::EMPTY | StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(8))],
                     fallback_state: State(7),
                     attributes: StateAttributes::ACCEPTING
                         | StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(6))],
                     fallback_state: State(5),
                     attributes: StateAttributes::EMPTY | StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(2))],
                     fallback_state: State(1),
                     attributes: StateAttributes::ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label, State(4))],
                     fallback_state: State(3),
                     attributes: StateAttributes::ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
             ],
+            filter: None,
+            member_from_end: None,
+            number_syntax: NumberSyntax::Strict,
         };
 
         assert_eq!(result, expected);
@@ -583,6 +894,8 @@ mod tests {
                 NfaState::Recursive(nfa::Transition::Labelled(&label_d)),
                 NfaState::Accepting,
             ],
+            filter: None,
+            member_from_end: None,
         };
 
         let result = minimize(nfa).unwrap();
@@ -592,48 +905,78 @@ mod tests {
                     transitions: smallvec![],
                     fallback_state: State(0),
                     attributes: StateAttributes::REJECTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_x, State(2))],
                     fallback_state: State(0),
                     attributes: StateAttributes::UNITARY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(3))],
                     fallback_state: State(2),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(3)), (&label_b, State(4))],
                     fallback_state: State(2),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(5))],
                     fallback_state: State(2),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(3)), (&label_b, State(6))],
                     fallback_state: State(2),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(5)), (&label_c, State(7))],
                     fallback_state: State(2),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_d, State(8))],
                     fallback_state: State(7),
                     attributes: StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_d, State(8))],
                     fallback_state: State(7),
                     attributes: StateAttributes::ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
             ],
+            filter: None,
+            member_from_end: None,
+            number_syntax: NumberSyntax::Strict,
         };
 
         assert_eq!(result, expected);
@@ -655,6 +998,8 @@ mod tests {
                 NfaState::Direct(nfa::Transition::Labelled(&label_b)),
                 NfaState::Accepting,
             ],
+            filter: None,
+            member_from_end: None,
         };
 
         let result = minimize(nfa).unwrap();
@@ -664,49 +1009,263 @@ mod tests {
                     transitions: smallvec![],
                     fallback_state: State(0),
                     attributes: StateAttributes::REJECTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_x, State(2))],
                     fallback_state: State(0),
                     attributes: StateAttributes::UNITARY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![],
                     fallback_state: State(3),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(4))],
                     fallback_state: State(3),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(6))],
                     fallback_state: State(5),
                     attributes: StateAttributes::EMPTY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(4)), (&label_b, State(8))],
                     fallback_state: State(3),
                     attributes: StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(6)), (&label_b, State(7))],
                     fallback_state: State(5),
                     attributes: StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(4)), (&label_b, State(8))],
                     fallback_state: State(3),
                     attributes: StateAttributes::ACCEPTING
                         | StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
                 StateTable {
                     transitions: smallvec![(&label_a, State(4))],
                     fallback_state: State(3),
                     attributes: StateAttributes::ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+            ],
+            filter: None,
+            member_from_end: None,
+            number_syntax: NumberSyntax::Strict,
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn complement_of_child_test() {
+        // Query = complement of $.a.b
+        let label_a = Label::new("a");
+        let label_b = Label::new("b");
+
+        let nfa = NondeterministicAutomaton {
+            ordered_states: vec![
+                NfaState::Direct(nfa::Transition::Labelled(&label_a)),
+                NfaState::Direct(nfa::Transition::AllExcept(&label_b)),
+                NfaState::Accepting,
+            ],
+            filter: None,
+            member_from_end: None,
+        };
+
+        let result = minimize(nfa).unwrap();
+        let expected = Automaton {
+            states: vec![
+                StateTable {
+                    transitions: smallvec![],
+                    fallback_state: State(0),
+                    attributes: StateAttributes::REJECTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+                StateTable {
+                    transitions: smallvec![(&label_a, State(2))],
+                    fallback_state: State(0),
+                    attributes: StateAttributes::UNITARY,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+                StateTable {
+                    transitions: smallvec![(&label_b, State(0))],
+                    fallback_state: State(3),
+                    attributes: StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+                StateTable {
+                    transitions: smallvec![],
+                    fallback_state: State(0),
+                    attributes: StateAttributes::ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+            ],
+            filter: None,
+            member_from_end: None,
+            number_syntax: NumberSyntax::Strict,
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn alternation_of_children_test() {
+        // Query = $.a | $.b
+        let label_a = Label::new("a");
+        let label_b = Label::new("b");
+
+        let nfa = NondeterministicAutomaton {
+            ordered_states: vec![
+                NfaState::Direct(nfa::Transition::Labelled(&label_a)),
+                NfaState::Accepting,
+                NfaState::Direct(nfa::Transition::Labelled(&label_b)),
+                NfaState::Accepting,
+            ],
+            filter: None,
+            member_from_end: None,
+        };
+
+        let result = minimize(nfa).unwrap();
+        let expected = Automaton {
+            states: vec![
+                StateTable {
+                    transitions: smallvec![],
+                    fallback_state: State(0),
+                    attributes: StateAttributes::REJECTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+                StateTable {
+                    transitions: smallvec![(&label_a, State(2)), (&label_b, State(3))],
+                    fallback_state: State(0),
+                    attributes: StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+                StateTable {
+                    transitions: smallvec![],
+                    fallback_state: State(0),
+                    attributes: StateAttributes::ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+                StateTable {
+                    transitions: smallvec![],
+                    fallback_state: State(0),
+                    attributes: StateAttributes::ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+            ],
+            filter: None,
+            member_from_end: None,
+            number_syntax: NumberSyntax::Strict,
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn alternation_of_descendants_test() {
+        // Query = $..a | $..b
+        let label_a = Label::new("a");
+        let label_b = Label::new("b");
+
+        let nfa = NondeterministicAutomaton {
+            ordered_states: vec![
+                NfaState::Recursive(nfa::Transition::Labelled(&label_a)),
+                NfaState::Accepting,
+                NfaState::Recursive(nfa::Transition::Labelled(&label_b)),
+                NfaState::Accepting,
+            ],
+            filter: None,
+            member_from_end: None,
+        };
+
+        let result = minimize(nfa).unwrap();
+        let expected = Automaton {
+            states: vec![
+                StateTable {
+                    transitions: smallvec![],
+                    fallback_state: State(0),
+                    attributes: StateAttributes::REJECTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+                StateTable {
+                    transitions: smallvec![(&label_a, State(2)), (&label_b, State(3))],
+                    fallback_state: State(1),
+                    attributes: StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+                StateTable {
+                    transitions: smallvec![(&label_a, State(2)), (&label_b, State(3))],
+                    fallback_state: State(1),
+                    attributes: StateAttributes::ACCEPTING
+                        | StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
+                },
+                StateTable {
+                    transitions: smallvec![(&label_a, State(2)), (&label_b, State(3))],
+                    fallback_state: State(1),
+                    attributes: StateAttributes::ACCEPTING
+                        | StateAttributes::TRANSITIONS_TO_ACCEPTING,
+                    pattern_transitions: PatternTransitions::default(),
+                    index_transitions: smallvec![],
+                    slice_transitions: SliceTransitions::default(),
                 },
             ],
+            filter: None,
+            member_from_end: None,
+            number_syntax: NumberSyntax::Strict,
         };
 
         assert_eq!(result, expected);
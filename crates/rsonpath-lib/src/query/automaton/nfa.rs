@@ -1,18 +1,39 @@
 //! Definition of a nondeterministic automaton that can be directly
 //! obtained from a JsonPath query. This is then turned into
 //! a DFA with the minimizer.
+use super::small_set::{SmallSet, SmallSet256};
+use crate::error::UnsupportedFeatureError;
+use crate::json;
 use crate::query::{
-    error::CompilerError, JsonPathQuery, JsonPathQueryNode, JsonPathQueryNodeType, Label,
+    error::CompilerError, ArraySlice, FilterExpression, JsonPathQuery, JsonPathQueryNode,
+    JsonPathQueryNodeType, KeyPattern, Label, Selector,
 };
-use std::{fmt::Display, ops::Index};
+use std::{fmt::Display, num::NonZeroUsize, ops::Index};
 
 /// An NFA representing a query. It is always a directed path
 /// from an initial state to the unique accepting state at the end,
 /// where transitions are either self-loops or go forward to the immediate
 /// successor in the path.
+///
+/// An NFA representing an alternation of queries is a sequence of such paths,
+/// one for each alternative, each with its own initial and accepting state.
+///
+/// A query with descendant-or-self selectors is represented as an alternation of all
+/// queries obtained by replacing each of them either with a descendant selector, or with
+/// the previous selector restricted to the same label.
+///
+/// A query ending with a filter selector is represented as if the filter was a wildcard,
+/// or a descendant wildcard for descendant filters, and the filter has to be applied
+/// to every value reaching the accepting state.
+///
+/// Similarly, a query ending with a member from end selector is represented as if it was
+/// a wildcard, and only the values at the given position from the end of their object
+/// are reported.
 #[derive(Debug, PartialEq, Eq)]
 pub(super) struct NondeterministicAutomaton<'q> {
     pub(super) ordered_states: Vec<NfaState<'q>>,
+    pub(super) filter: Option<&'q FilterExpression>,
+    pub(super) member_from_end: Option<NonZeroUsize>,
 }
 
 /// Types of states allowed in an NFA directly mapped from a [`JsonPathQuery`].
@@ -32,8 +53,19 @@ use NfaState::*;
 pub(super) enum Transition<'q> {
     /// A transition matching a specific [`Label`] only.
     Labelled(&'q Label),
+    /// A transition matching keys satisfying a [`KeyPattern`].
+    Pattern(&'q KeyPattern),
     /// A transition matching anything.
     Wildcard,
+    /// A transition matching anything apart from a specific [`Label`].
+    AllExcept(&'q Label),
+    /// A transition matching only the array element at a specific index.
+    Index(usize),
+    /// A transition matching a specific [`Label`] in objects,
+    /// and the array element at the index it represents in arrays.
+    LabelOrIndex(&'q Label, usize),
+    /// A transition matching only the array elements selected by an [`ArraySlice`].
+    Slice(ArraySlice),
 }
 
 /// State of an [`NondeterministicAutomaton`]. Thin wrapper over a state's
@@ -62,40 +94,338 @@ impl<'q> NondeterministicAutomaton<'q> {
     /// Returns a [`CompilerError::QueryTooComplex`] if the internal limit
     /// on the state number is exceeded.
     pub(super) fn new(query: &'q JsonPathQuery) -> Result<Self, CompilerError> {
-        debug_assert!(query.root().is_root());
+        Self::new_alternation(std::slice::from_ref(query))
+    }
+
+    /// Translate a sequence of alternative [`JsonPathQueries`](JsonPathQuery) into a single NFA.
+    ///
+    /// Every alternative is translated into its own directed path ending with an [`Accepting`]
+    /// state, and the paths are laid out in `ordered_states` one after another.
+    ///
+    /// # Errors
+    /// Returns a [`CompilerError::QueryTooComplex`] if the internal limit
+    /// on the state number is exceeded, and [`CompilerError::EmptyAlternation`]
+    /// if there are no `queries`. Returns [`CompilerError::NotSupported`]
+    /// if a filter or member from end selector is not the last selector of its query,
+    /// or if there are such selectors in an alternation of more than one query.
+    pub(super) fn new_alternation(queries: &'q [JsonPathQuery]) -> Result<Self, CompilerError> {
+        if queries.is_empty() {
+            return Err(CompilerError::EmptyAlternation);
+        }
 
-        let states_result: Result<Vec<NfaState>, CompilerError> = query
-            .root()
+        let alternatives: Vec<Vec<_>> = queries
             .iter()
-            .filter_map(|node| match node {
-                JsonPathQueryNode::Root(_) => None,
-                JsonPathQueryNode::Descendant(label, _) => {
-                    Some(Ok(Recursive(Transition::Labelled(label))))
-                }
-                JsonPathQueryNode::Child(label, _) => Some(Ok(Direct(Transition::Labelled(label)))),
-                JsonPathQueryNode::AnyChild(_) => Some(Ok(Direct(Transition::Wildcard))),
+            .map(|query| {
+                debug_assert!(query.root().is_root());
+                query
+                    .root()
+                    .iter()
+                    .filter_map(SelectorRef::of_node)
+                    .collect()
             })
             .collect();
-        let mut states = states_result?;
 
-        states.push(Accepting);
+        Self::from_alternatives(&alternatives)
+    }
+
+    /// Translate a sequence of [`Selectors`](Selector) following the root into an NFA,
+    /// exactly like the [`JsonPathQuery`] with the same selectors.
+    ///
+    /// # Errors
+    /// Same as [`NondeterministicAutomaton::new_alternation`] for a single query.
+    pub(super) fn new_from_selectors(selectors: &'q [Selector]) -> Result<Self, CompilerError> {
+        Self::from_alternatives(&[selectors.iter().map(SelectorRef::from).collect()])
+    }
+
+    fn from_alternatives(alternatives: &[Vec<SelectorRef<'q>>]) -> Result<Self, CompilerError> {
+        let mut states = vec![];
+        let mut filter = None;
+        let mut member_from_end = None;
 
-        let accepting_state: Result<u8, _> = (states.len() - 1).try_into();
-        if let Err(err) = accepting_state {
+        for selectors in alternatives {
+            for (i, selector) in selectors.iter().enumerate() {
+                let is_last = i + 1 == selectors.len();
+                if let SelectorRef::Filter(expression) | SelectorRef::DescendantFilter(expression) =
+                    *selector
+                {
+                    if !is_last {
+                        return Err(UnsupportedFeatureError::non_terminal_filters().into());
+                    }
+                    if alternatives.len() > 1 {
+                        return Err(UnsupportedFeatureError::filters_in_alternations().into());
+                    }
+                    filter = Some(expression);
+                }
+                if let SelectorRef::MemberFromEnd(position) = *selector {
+                    if !is_last {
+                        return Err(UnsupportedFeatureError::non_terminal_member_from_end().into());
+                    }
+                    if alternatives.len() > 1 {
+                        return Err(
+                            UnsupportedFeatureError::member_from_end_in_alternations().into()
+                        );
+                    }
+                    member_from_end = Some(position);
+                }
+            }
+
+            // Every descendant-or-self selector doubles the number of paths for the query:
+            // one where it is an ordinary descendant selector, and one where it is merged
+            // with the previous selector to match the value selected by it.
+            let mut paths = vec![vec![]];
+            for selector in selectors {
+                let state = match *selector {
+                    SelectorRef::Descendant(label) => Recursive(Transition::Labelled(label)),
+                    SelectorRef::DescendantOrSelf(label) => {
+                        paths = paths
+                            .into_iter()
+                            .flat_map(|path| {
+                                let merged = merge_self(&path, label);
+                                let mut descendant = path;
+                                descendant.push(Recursive(Transition::Labelled(label)));
+                                std::iter::once(descendant).chain(merged)
+                            })
+                            .collect();
+                        if paths.len() > MAX_DESCENDANT_OR_SELF_PATHS {
+                            return Err(CompilerError::QueryTooComplex(None));
+                        }
+                        continue;
+                    }
+                    SelectorRef::Child(label) => Direct(Transition::Labelled(label)),
+                    SelectorRef::AnyChild
+                    | SelectorRef::Filter(_)
+                    | SelectorRef::MemberFromEnd(_) => Direct(Transition::Wildcard),
+                    SelectorRef::PatternChild(pattern) => Direct(Transition::Pattern(pattern)),
+                    SelectorRef::PatternDescendant(pattern) => {
+                        Recursive(Transition::Pattern(pattern))
+                    }
+                    SelectorRef::DescendantFilter(_) => Recursive(Transition::Wildcard),
+                    SelectorRef::ArrayIndex(index) => Direct(Transition::Index(index)),
+                    SelectorRef::Slice(slice) => Direct(Transition::Slice(slice)),
+                    SelectorRef::DescendantSlice(slice) => Recursive(Transition::Slice(slice)),
+                };
+                for path in &mut paths {
+                    path.push(state);
+                }
+            }
+
+            for path in paths {
+                states.extend(path);
+                states.push(Accepting);
+            }
+        }
+
+        let last_state: Result<u8, _> = (states.len() - 1).try_into();
+        if let Err(err) = last_state {
             Err(CompilerError::QueryTooComplex(Some(err)))
         } else {
             Ok(NondeterministicAutomaton {
                 ordered_states: states,
+                filter,
+                member_from_end,
             })
         }
     }
 
-    pub(super) fn accepting_state(&self) -> NfaStateId {
-        // CAST: safe because of the check in `new`.
-        NfaStateId((self.ordered_states.len() - 1) as u8)
+    /// Translate a [`JsonPathQuery`] into an NFA where labels that are canonical
+    /// non-negative integers also match array elements at the index they represent.
+    ///
+    /// # Errors
+    /// Same as [`NondeterministicAutomaton::new`].
+    pub(super) fn new_with_numeric_key_coercion(
+        query: &'q JsonPathQuery,
+    ) -> Result<Self, CompilerError> {
+        let mut nfa = Self::new(query)?;
+
+        for state in &mut nfa.ordered_states {
+            if let Direct(transition) | Recursive(transition) = state {
+                if let Transition::Labelled(label) = *transition {
+                    if let Some(index) = numeric_label_index(label) {
+                        *transition = Transition::LabelOrIndex(label, index);
+                    }
+                }
+            }
+        }
+
+        Ok(nfa)
+    }
+
+    /// Translate a [`JsonPathQuery`] into an NFA matching the complement of its last selector.
+    ///
+    /// The resulting NFA matches all children of the nodes matched by the query's parent path
+    /// (the query without its last selector), apart from those matched by the query itself.
+    ///
+    /// # Errors
+    /// Returns a [`CompilerError::QueryTooComplex`] if the internal limit
+    /// on the state number is exceeded, and [`CompilerError::InvalidComplement`]
+    /// if the query does not end with a child selector. Returns [`CompilerError::NotSupported`]
+    /// if the query ends with a child selector other than a label.
+    pub(super) fn new_complement(query: &'q JsonPathQuery) -> Result<Self, CompilerError> {
+        let excluded = match query.root().iter().last() {
+            Some(JsonPathQueryNode::Child(label, _)) => label,
+            Some(
+                JsonPathQueryNode::AnyChild(_)
+                | JsonPathQueryNode::PatternChild(_, _)
+                | JsonPathQueryNode::Filter(_, _)
+                | JsonPathQueryNode::MemberFromEnd(_, _)
+                | JsonPathQueryNode::ArrayIndex(_, _)
+                | JsonPathQueryNode::Slice(_, _),
+            ) => return Err(UnsupportedFeatureError::complement_of_non_label_selectors().into()),
+            Some(
+                JsonPathQueryNode::Root(_)
+                | JsonPathQueryNode::Descendant(_, _)
+                | JsonPathQueryNode::DescendantOrSelf(_, _)
+                | JsonPathQueryNode::PatternDescendant(_, _)
+                | JsonPathQueryNode::DescendantFilter(_, _)
+                | JsonPathQueryNode::DescendantSlice(_, _),
+            )
+            | None => return Err(CompilerError::InvalidComplement),
+        };
+
+        let mut nfa = Self::new(query)?;
+        // There are many paths if the query contains descendant-or-self selectors,
+        // and all of them end with the excluded label.
+        for i in 0..nfa.ordered_states.len() - 1 {
+            if nfa.ordered_states[i + 1] == Accepting {
+                nfa.ordered_states[i] = Direct(Transition::AllExcept(excluded));
+            }
+        }
+
+        Ok(nfa)
+    }
+
+    /// Returns the set of initial states of all alternatives in the NFA.
+    pub(super) fn initial_states(&self) -> SmallSet256 {
+        let mut result = SmallSet256::default();
+        result.insert(0);
+
+        for (i, state) in self.ordered_states.iter().enumerate() {
+            if *state == Accepting && i + 1 < self.ordered_states.len() {
+                // CAST: safe because of the check in `new_alternation`.
+                result.insert((i + 1) as u8);
+            }
+        }
+
+        result
+    }
+
+    /// Returns the first state of the alternative containing the given `state`.
+    pub(super) fn alternative_start(&self, state: NfaStateId) -> NfaStateId {
+        let mut start = state.0;
+
+        while start > 0 && self.ordered_states[start as usize - 1] != Accepting {
+            start -= 1;
+        }
+
+        NfaStateId(start)
+    }
+
+    /// Returns whether the given `state` is one of the accepting states.
+    pub(super) fn is_accepting(&self, state: NfaStateId) -> bool {
+        self[state] == Accepting
     }
 }
 
+/// A selector of a query borrowed either from a [`JsonPathQueryNode`] or from a [`Selector`].
+#[derive(Clone, Copy)]
+enum SelectorRef<'q> {
+    Child(&'q Label),
+    AnyChild,
+    Descendant(&'q Label),
+    DescendantOrSelf(&'q Label),
+    PatternChild(&'q KeyPattern),
+    PatternDescendant(&'q KeyPattern),
+    Filter(&'q FilterExpression),
+    DescendantFilter(&'q FilterExpression),
+    MemberFromEnd(NonZeroUsize),
+    ArrayIndex(usize),
+    Slice(ArraySlice),
+    DescendantSlice(ArraySlice),
+}
+
+impl<'q> SelectorRef<'q> {
+    /// The selector of the `node`, or [`None`] for the root.
+    fn of_node(node: &'q JsonPathQueryNode) -> Option<Self> {
+        Some(match node {
+            JsonPathQueryNode::Root(_) => return None,
+            JsonPathQueryNode::Child(label, _) => Self::Child(label),
+            JsonPathQueryNode::AnyChild(_) => Self::AnyChild,
+            JsonPathQueryNode::Descendant(label, _) => Self::Descendant(label),
+            JsonPathQueryNode::DescendantOrSelf(label, _) => Self::DescendantOrSelf(label),
+            JsonPathQueryNode::PatternChild(pattern, _) => Self::PatternChild(pattern),
+            JsonPathQueryNode::PatternDescendant(pattern, _) => Self::PatternDescendant(pattern),
+            JsonPathQueryNode::Filter(expression, _) => Self::Filter(expression),
+            JsonPathQueryNode::DescendantFilter(expression, _) => {
+                Self::DescendantFilter(expression)
+            }
+            JsonPathQueryNode::MemberFromEnd(position, _) => Self::MemberFromEnd(*position),
+            JsonPathQueryNode::ArrayIndex(index, _) => Self::ArrayIndex(*index),
+            JsonPathQueryNode::Slice(slice, _) => Self::Slice(*slice),
+            JsonPathQueryNode::DescendantSlice(slice, _) => Self::DescendantSlice(*slice),
+        })
+    }
+}
+
+impl<'q> From<&'q Selector> for SelectorRef<'q> {
+    fn from(selector: &'q Selector) -> Self {
+        match selector {
+            Selector::Child(label) => Self::Child(label),
+            Selector::AnyChild => Self::AnyChild,
+            Selector::Descendant(label) => Self::Descendant(label),
+            Selector::DescendantOrSelf(label) => Self::DescendantOrSelf(label),
+            Selector::PatternChild(pattern) => Self::PatternChild(pattern),
+            Selector::PatternDescendant(pattern) => Self::PatternDescendant(pattern),
+            Selector::Filter(expression) => Self::Filter(expression),
+            Selector::DescendantFilter(expression) => Self::DescendantFilter(expression),
+            Selector::MemberFromEnd(position) => Self::MemberFromEnd(*position),
+            Selector::ArrayIndex(index) => Self::ArrayIndex(*index),
+            Selector::Slice(slice) => Self::Slice(*slice),
+            Selector::DescendantSlice(slice) => Self::DescendantSlice(*slice),
+        }
+    }
+}
+
+/// Returns the index represented by the `label` if it is a non-negative integer
+/// in canonical form, i.e. consisting only of digits without leading zeroes.
+fn numeric_label_index(label: &Label) -> Option<usize> {
+    let digits = std::str::from_utf8(label.bytes()).ok()?;
+    let index: usize = digits.parse().ok()?;
+
+    (index.to_string() == digits).then_some(index)
+}
+
+/// Upper bound on the number of paths a single query can be expanded into
+/// because of descendant-or-self selectors. Each path takes at least one state,
+/// so more paths could never fit in the NFA anyway.
+const MAX_DESCENDANT_OR_SELF_PATHS: usize = 256;
+
+/// Merge a descendant-or-self selector with `label` into the last selector of the `path`,
+/// so that it matches the value selected by the last selector if its key is `label`.
+///
+/// Returns [`None`] if no value can be matched by both, in particular if the `path`
+/// is empty, since the root has no key.
+fn merge_self<'q>(path: &[NfaState<'q>], label: &'q Label) -> Option<Vec<NfaState<'q>>> {
+    let (last, prefix) = path.split_last()?;
+    let (transition, make_state): (_, fn(Transition<'q>) -> NfaState<'q>) = match *last {
+        Direct(transition) => (transition, Direct),
+        Recursive(transition) => (transition, Recursive),
+        Accepting => return None,
+    };
+    let is_match = match transition {
+        Transition::Labelled(other) | Transition::LabelOrIndex(other, _) => other == label,
+        Transition::Pattern(pattern) => pattern.is_match(&json::unescape(label.bytes())),
+        Transition::Wildcard => true,
+        Transition::AllExcept(other) => other != label,
+        Transition::Index(_) | Transition::Slice(_) => false,
+    };
+
+    is_match.then(|| {
+        let mut merged = prefix.to_vec();
+        merged.push(make_state(Transition::Labelled(label)));
+        merged
+    })
+}
+
 impl<'q> Index<NfaStateId> for NondeterministicAutomaton<'q> {
     type Output = NfaState<'q>;
 
@@ -114,27 +444,54 @@ impl<'q> Display for NondeterministicAutomaton<'q> {
     // This is the format for https://paperman.name/semigroup/
     // for easy debugging of minimization.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let all_labels: Vec<_> =
-            self.ordered_states
-                .iter()
-                .filter_map(|s| match s {
-                    Direct(Transition::Labelled(label))
-                    | Recursive(Transition::Labelled(label)) => Some(*label),
-                    _ => None,
-                })
-                .collect();
+        let all_labels: Vec<_> = self
+            .ordered_states
+            .iter()
+            .filter_map(|s| match s {
+                Direct(
+                    Transition::Labelled(label)
+                    | Transition::AllExcept(label)
+                    | Transition::LabelOrIndex(label, _),
+                )
+                | Recursive(
+                    Transition::Labelled(label)
+                    | Transition::AllExcept(label)
+                    | Transition::LabelOrIndex(label, _),
+                ) => Some(*label),
+                _ => None,
+            })
+            .collect();
 
         for (i, state) in self.ordered_states.iter().enumerate() {
             match state {
                 Direct(Transition::Labelled(label)) => {
                     writeln!(f, "s{i}.{} -> s{};", label.display(), i + 1)?;
                 }
+                Direct(Transition::Pattern(pattern)) => {
+                    writeln!(f, "s{i}.{pattern} -> s{};", i + 1)?;
+                }
+                Direct(Transition::Index(index)) => {
+                    writeln!(f, "s{i}.[{index}] -> s{};", i + 1)?;
+                }
+                Direct(Transition::Slice(slice)) => {
+                    writeln!(f, "s{i}.[{slice}] -> s{};", i + 1)?;
+                }
+                Direct(Transition::LabelOrIndex(label, index)) => {
+                    writeln!(f, "s{i}.{} -> s{};", label.display(), i + 1)?;
+                    writeln!(f, "s{i}.[{index}] -> s{};", i + 1)?;
+                }
                 Direct(Transition::Wildcard) => {
                     for label in &all_labels {
                         writeln!(f, "s{i}.{} -> s{};", label.display(), i + 1)?;
                     }
                     writeln!(f, "s{i}.X -> s{};", i + 1)?;
                 }
+                Direct(Transition::AllExcept(label)) => {
+                    for label in all_labels.iter().filter(|&l| l != label) {
+                        writeln!(f, "s{i}.{} -> s{};", label.display(), i + 1)?;
+                    }
+                    writeln!(f, "s{i}.X -> s{};", i + 1)?;
+                }
                 Recursive(Transition::Labelled(label)) => {
                     writeln!(f, "s{i}.{} -> s{i}, s{};", label.display(), i + 1)?;
                     for label in all_labels.iter().filter(|&l| l != label) {
@@ -142,12 +499,39 @@ impl<'q> Display for NondeterministicAutomaton<'q> {
                     }
                     writeln!(f, "s{i}.X -> s{i};")?;
                 }
+                Recursive(Transition::Pattern(pattern)) => {
+                    writeln!(f, "s{i}.{pattern} -> s{i}, s{};", i + 1)?;
+                    writeln!(f, "s{i}.X -> s{i};")?;
+                }
+                Recursive(Transition::Index(index)) => {
+                    writeln!(f, "s{i}.[{index}] -> s{i}, s{};", i + 1)?;
+                    writeln!(f, "s{i}.X -> s{i};")?;
+                }
+                Recursive(Transition::Slice(slice)) => {
+                    writeln!(f, "s{i}.[{slice}] -> s{i}, s{};", i + 1)?;
+                    writeln!(f, "s{i}.X -> s{i};")?;
+                }
+                Recursive(Transition::LabelOrIndex(label, index)) => {
+                    writeln!(f, "s{i}.{} -> s{i}, s{};", label.display(), i + 1)?;
+                    writeln!(f, "s{i}.[{index}] -> s{i}, s{};", i + 1)?;
+                    for label in all_labels.iter().filter(|&l| l != label) {
+                        writeln!(f, "s{i}.{} -> s{i};", label.display())?;
+                    }
+                    writeln!(f, "s{i}.X -> s{i};")?;
+                }
                 Recursive(Transition::Wildcard) => {
                     for label in &all_labels {
                         writeln!(f, "s{i}.{} -> s{i}, s{};", label.display(), i + 1)?;
                     }
                     writeln!(f, "s{i}.X -> s{i}, s{};", i + 1)?;
                 }
+                Recursive(Transition::AllExcept(label)) => {
+                    writeln!(f, "s{i}.{} -> s{i};", label.display())?;
+                    for label in all_labels.iter().filter(|&l| l != label) {
+                        writeln!(f, "s{i}.{} -> s{i}, s{};", label.display(), i + 1)?;
+                    }
+                    writeln!(f, "s{i}.X -> s{i}, s{};", i + 1)?;
+                }
                 Accepting => (),
             }
         }
@@ -0,0 +1,133 @@
+//! Compile-time estimate of how much of a document the engines need to process
+//! to execute a query, based on the structure of its [`Automaton`].
+//!
+//! Compiling the [`Automaton`] is all the work done before running a query, so
+//! [`Automaton::new`] followed by [`Automaton::selectivity`] serves as a dry run,
+//! without a separate report type. The `--compile` option of the `rsonpath` CLI
+//! prints the estimate this way, along with the automaton itself.
+//! The estimate is computed on demand, so engines compiling the automaton do not pay for it.
+use super::{Automaton, State};
+use std::fmt::Display;
+
+/// Advisory classification of a query by the portion of the document
+/// that the engines may have to inspect to execute it.
+///
+/// The variants are ordered from the most to the least selective,
+/// so they can be compared to apply e.g. different timeouts to different query shapes.
+/// The estimate is based only on the shape of the query, not on the contents of any document
+/// &ndash; a label search for a key occurring in every object is as expensive as a full scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Selectivity {
+    /// The query has no descendant selectors. The engines only descend into values
+    /// selected by the query and skip all other subtrees, e.g. `$.a.*.b`.
+    Anchored,
+    /// The query starts with a descendant search for a single label, which the engines
+    /// perform with a fast substring search over the entire document, e.g. `$..a.b`
+    /// or `$..a..b`. Subtrees of the found values may still need to be scanned fully.
+    LabelSearch,
+    /// The query has a descendant selector after a prefix of other selectors, e.g. `$.a..b`.
+    /// The engines inspect every value within the subtrees selected by the prefix.
+    SubtreeScan,
+    /// The query has a descendant selector that the engines reach without matching any specific label,
+    /// e.g. `$..[?(@ is scalar)]`, `$.*..a`, or an alternation of `$..a` and `$..b`.
+    /// The engines inspect every value in the document.
+    FullScan,
+}
+
+impl Display for Selectivity {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Anchored => write!(f, "anchored"),
+            Self::LabelSearch => write!(f, "label search"),
+            Self::SubtreeScan => write!(f, "subtree scan"),
+            Self::FullScan => write!(f, "full scan"),
+        }
+    }
+}
+
+impl<'q> Automaton<'q> {
+    /// Estimate the [`Selectivity`] of the query represented by this automaton.
+    ///
+    /// Descendant selectors are represented by states that can be reached again after leaving them.
+    /// If such a state is reached from the initial state by following only fallback transitions,
+    /// the query is a [`FullScan`](Selectivity::FullScan), unless it is the initial state itself
+    /// and the engines can use head-skipping for it, which requires the `head-skip` feature.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::*;
+    /// # use rsonpath_lib::query::automaton::*;
+    /// let selectivity = |query| Automaton::new(&JsonPathQuery::parse(query).unwrap()).unwrap().selectivity();
+    ///
+    /// assert_eq!(selectivity("$.a.*.b"), Selectivity::Anchored);
+    /// assert_eq!(selectivity("$.a..b"), Selectivity::SubtreeScan);
+    /// assert_eq!(selectivity("$..[?(@ is scalar)]"), Selectivity::FullScan);
+    /// # #[cfg(feature = "head-skip")]
+    /// assert_eq!(selectivity("$..a.b"), Selectivity::LabelSearch);
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn selectivity(&self) -> Selectivity {
+        let initial_state = self.initial_state();
+
+        if cfg!(feature = "head-skip")
+            && self.is_recursive(initial_state)
+            && self.head_skip_transition().is_some()
+        {
+            return Selectivity::LabelSearch;
+        }
+
+        let mut state = initial_state;
+        for _ in 0..self.states.len() {
+            if self.is_rejecting(state) {
+                break;
+            }
+            if self.is_recursive(state) {
+                return Selectivity::FullScan;
+            }
+            state = self[state].fallback_state();
+        }
+
+        if (0..self.states.len()).any(|i| self.is_recursive(State(i as u8))) {
+            Selectivity::SubtreeScan
+        } else {
+            Selectivity::Anchored
+        }
+    }
+
+    /// Returns whether the `state` can be reached again after a transition out of it,
+    /// i.e. it lies on a cycle not going through the rejecting state.
+    fn is_recursive(&self, state: State) -> bool {
+        let mut visited = vec![false; self.states.len()];
+        let mut stack = self.successors(state);
+
+        while let Some(next) = stack.pop() {
+            if next == state {
+                return true;
+            }
+            if !visited[next.0 as usize] {
+                visited[next.0 as usize] = true;
+                stack.extend(self.successors(next));
+            }
+        }
+
+        false
+    }
+
+    /// All non-rejecting targets of transitions of the `state`.
+    fn successors(&self, state: State) -> Vec<State> {
+        let table = &self[state];
+
+        table
+            .transitions()
+            .iter()
+            .map(|&(_, target)| target)
+            .chain(table.pattern_transitions.targets.iter().copied())
+            .chain(table.index_transitions().iter().map(|&(_, target)| target))
+            .chain(table.slice_transitions.targets.iter().copied())
+            .chain(std::iter::once(table.fallback_state()))
+            .filter(|&target| !self.is_rejecting(target))
+            .collect()
+    }
+}
@@ -5,6 +5,7 @@ use std::{collections::BTreeSet, fmt::Debug};
 
 /// Traits for highly optimised sets of elements of type `T`,
 /// which are assumed to be relatively small elements that can be ordered.
+#[allow(dead_code)] // Not all operations are needed by the minimizer, but they are all tested.
 pub(crate) trait SmallSet<T: Copy + PartialOrd + Ord>: IntoIterator<Item = T> {
     /// Returns the number of elements in the set.
     fn len(&self) -> usize;
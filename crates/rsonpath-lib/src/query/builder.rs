@@ -1,6 +1,9 @@
 //! Utility for building a [`JsonPathQuery`](`crate::query::JsonPathQuery`)
 //! programatically.
-use super::{JsonPathQuery, JsonPathQueryNode, Label};
+use super::{
+    ArraySlice, FilterExpression, JsonPathQuery, JsonPathQueryNode, KeyPattern, Label, Selector,
+};
+use std::num::NonZeroUsize;
 
 /// Builder for [`JsonPathQuery`] instances.
 ///
@@ -19,7 +22,7 @@ use super::{JsonPathQuery, JsonPathQueryNode, Label};
 /// assert_eq!(format!("{query}"), "$['a']..['b'][*]['c']");
 /// ```
 pub struct JsonPathQueryBuilder {
-    nodes: Vec<NodeTemplate>,
+    nodes: Vec<Selector>,
 }
 
 impl JsonPathQueryBuilder {
@@ -43,7 +46,7 @@ impl JsonPathQueryBuilder {
     #[must_use]
     #[inline(always)]
     pub fn child(mut self, label: Label) -> Self {
-        self.nodes.push(NodeTemplate::Child(label));
+        self.nodes.push(Selector::Child(label));
         self
     }
 
@@ -51,7 +54,7 @@ impl JsonPathQueryBuilder {
     #[must_use]
     #[inline(always)]
     pub fn any_child(mut self) -> Self {
-        self.nodes.push(NodeTemplate::AnyChild);
+        self.nodes.push(Selector::AnyChild);
         self
     }
 
@@ -59,7 +62,84 @@ impl JsonPathQueryBuilder {
     #[must_use]
     #[inline(always)]
     pub fn descendant(mut self, label: Label) -> Self {
-        self.nodes.push(NodeTemplate::Descendant(label));
+        self.nodes.push(Selector::Descendant(label));
+        self
+    }
+
+    /// Add a descendant-or-self selector with a given label.
+    ///
+    /// Unlike [`descendant`](JsonPathQueryBuilder::descendant), the selector also matches
+    /// the value selected by the previous selector, if its key is equal to the `label`.
+    #[must_use]
+    #[inline(always)]
+    pub fn descendant_or_self(mut self, label: Label) -> Self {
+        self.nodes.push(Selector::DescendantOrSelf(label));
+        self
+    }
+
+    /// Add a child selector with a given key pattern.
+    #[must_use]
+    #[inline(always)]
+    pub fn pattern_child(mut self, pattern: KeyPattern) -> Self {
+        self.nodes.push(Selector::PatternChild(pattern));
+        self
+    }
+
+    /// Add a descendant selector with a given key pattern.
+    #[must_use]
+    #[inline(always)]
+    pub fn pattern_descendant(mut self, pattern: KeyPattern) -> Self {
+        self.nodes.push(Selector::PatternDescendant(pattern));
+        self
+    }
+
+    /// Add a filter selector with a given filter expression.
+    #[must_use]
+    #[inline(always)]
+    pub fn filter(mut self, filter: FilterExpression) -> Self {
+        self.nodes.push(Selector::Filter(filter));
+        self
+    }
+
+    /// Add a descendant filter selector with a given filter expression.
+    #[must_use]
+    #[inline(always)]
+    pub fn descendant_filter(mut self, filter: FilterExpression) -> Self {
+        self.nodes.push(Selector::DescendantFilter(filter));
+        self
+    }
+
+    /// Add a selector of the member at the given `position` counting from the end
+    /// of an object, starting from 1 for the last member.
+    #[must_use]
+    #[inline(always)]
+    pub fn member_from_end(mut self, position: NonZeroUsize) -> Self {
+        self.nodes.push(Selector::MemberFromEnd(position));
+        self
+    }
+
+    /// Add a selector of the array element at the given `index`,
+    /// starting from 0 for the first element.
+    #[must_use]
+    #[inline(always)]
+    pub fn array_index(mut self, index: usize) -> Self {
+        self.nodes.push(Selector::ArrayIndex(index));
+        self
+    }
+
+    /// Add a selector of the array elements selected by the given `slice`.
+    #[must_use]
+    #[inline(always)]
+    pub fn slice(mut self, slice: ArraySlice) -> Self {
+        self.nodes.push(Selector::Slice(slice));
+        self
+    }
+
+    /// Add a descendant selector of the array elements selected by the given `slice`.
+    #[must_use]
+    #[inline(always)]
+    pub fn descendant_slice(mut self, slice: ArraySlice) -> Self {
+        self.nodes.push(Selector::DescendantSlice(slice));
         self
     }
 
@@ -71,11 +151,34 @@ impl JsonPathQueryBuilder {
 
         for node in self.nodes.into_iter().rev() {
             last = match node {
-                NodeTemplate::Child(label) => Some(Box::new(JsonPathQueryNode::Child(label, last))),
-                NodeTemplate::AnyChild => Some(Box::new(JsonPathQueryNode::AnyChild(last))),
-                NodeTemplate::Descendant(label) => {
+                Selector::Child(label) => Some(Box::new(JsonPathQueryNode::Child(label, last))),
+                Selector::AnyChild => Some(Box::new(JsonPathQueryNode::AnyChild(last))),
+                Selector::Descendant(label) => {
                     Some(Box::new(JsonPathQueryNode::Descendant(label, last)))
                 }
+                Selector::DescendantOrSelf(label) => {
+                    Some(Box::new(JsonPathQueryNode::DescendantOrSelf(label, last)))
+                }
+                Selector::PatternChild(pattern) => {
+                    Some(Box::new(JsonPathQueryNode::PatternChild(pattern, last)))
+                }
+                Selector::PatternDescendant(pattern) => Some(Box::new(
+                    JsonPathQueryNode::PatternDescendant(pattern, last),
+                )),
+                Selector::Filter(filter) => Some(Box::new(JsonPathQueryNode::Filter(filter, last))),
+                Selector::DescendantFilter(filter) => {
+                    Some(Box::new(JsonPathQueryNode::DescendantFilter(filter, last)))
+                }
+                Selector::MemberFromEnd(position) => {
+                    Some(Box::new(JsonPathQueryNode::MemberFromEnd(position, last)))
+                }
+                Selector::ArrayIndex(index) => {
+                    Some(Box::new(JsonPathQueryNode::ArrayIndex(index, last)))
+                }
+                Selector::Slice(slice) => Some(Box::new(JsonPathQueryNode::Slice(slice, last))),
+                Selector::DescendantSlice(slice) => {
+                    Some(Box::new(JsonPathQueryNode::DescendantSlice(slice, last)))
+                }
             };
         }
 
@@ -98,9 +201,3 @@ impl From<JsonPathQueryBuilder> for JsonPathQuery {
         value.build()
     }
 }
-
-enum NodeTemplate {
-    Child(Label),
-    AnyChild,
-    Descendant(Label),
-}
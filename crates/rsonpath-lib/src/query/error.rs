@@ -35,12 +35,16 @@
 //! ```
 use std::{
     fmt::{self, Display},
+    io,
     num::TryFromIntError,
 };
 use thiserror::Error;
 
 /// Errors raised by the query parser.
+///
+/// The set of errors depends on the enabled features, so the enum is non-exhaustive.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ParserError {
     /// Parsing error that occurred due to invalid input.
     #[error("one or more parsing errors occurred:\n{}", .report)]
@@ -60,6 +64,11 @@ pub enum ParserError {
         #[source]
         source: nom::error::Error<String>,
     },
+    /// A pattern selector contained an invalid regular expression.
+    #[cfg(feature = "regex")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "regex")))]
+    #[error("invalid regular expression in a pattern selector")]
+    InvalidRegex(#[from] regex::Error),
 }
 
 /// Error report created during the parser's run over a single input string.
@@ -134,12 +143,56 @@ impl ParseErrorReport {
 }
 
 /// Errors raised by the query compiler.
+///
+/// New kinds of queries bring new errors, so the enum is non-exhaustive.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum CompilerError {
     /// Max automaton size was exceeded during compilation of the query.
     #[error("Max automaton size was exceeded. Query is too complex.")]
     QueryTooComplex(#[source] Option<TryFromIntError>),
+    /// An alternation of queries was requested, but no queries were given.
+    #[error("Cannot compile an alternation of zero queries.")]
+    EmptyAlternation,
+    /// A complement of a query was requested, but the query does not end with
+    /// a child selector, so its complement is not well-defined.
+    #[error("Cannot compile a complement of a query not ending with a child selector.")]
+    InvalidComplement,
+    /// Descendant selectors were disallowed for the compilation,
+    /// but the query contains one.
+    #[error(
+        "Descendant selector `{selector}` at position {position} of the query is not allowed."
+    )]
+    DescendantDisallowed {
+        /// The first descendant selector in the query.
+        selector: String,
+        /// Position of the selector in the query, counting selectors from 1
+        /// for the first one after the root `$`.
+        position: usize,
+    },
     /// Compiler error that occurred due to a known limitation.
     #[error(transparent)]
     NotSupported(#[from] crate::error::UnsupportedFeatureError),
 }
+
+/// Convert into an [`io::Error`] to use `?` uniformly in code performing IO.
+///
+/// Errors caused by a known limitation are [`Unsupported`](io::ErrorKind::Unsupported),
+/// all other errors are caused by the compiled query and are
+/// [`InvalidInput`](io::ErrorKind::InvalidInput). The original error is kept as the
+/// inner error of the [`io::Error`], available with [`io::Error::into_inner`],
+/// and its message is used as the message of the [`io::Error`].
+impl From<CompilerError> for io::Error {
+    #[inline]
+    fn from(error: CompilerError) -> Self {
+        let kind = match error {
+            CompilerError::NotSupported(_) => io::ErrorKind::Unsupported,
+            CompilerError::QueryTooComplex(_)
+            | CompilerError::EmptyAlternation
+            | CompilerError::InvalidComplement
+            | CompilerError::DescendantDisallowed { .. } => io::ErrorKind::InvalidInput,
+        };
+
+        Self::new(kind, error)
+    }
+}
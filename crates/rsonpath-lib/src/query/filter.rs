@@ -0,0 +1,561 @@
+//! Filter expressions selecting values by comparing their contents with literals.
+use super::error::ParserError;
+use super::Label;
+use crate::json;
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+
+/// Expression of a filter selector, e.g. `@.meta == {"k": 1}`.
+///
+/// The expression is evaluated separately for every candidate value, by following
+/// the relative path of labels starting at the value (`@`) and comparing the value
+/// found at its end with the expected one. If the path does not lead to any value,
+/// the candidate is not selected.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::query::{Comparison, FilterExpression, JsonLiteral, Label};
+/// let literal = JsonLiteral::parse(r#"{"k": 1}"#).unwrap();
+/// let filter = FilterExpression::new(vec![Label::new("meta")], Comparison::Equal(literal));
+///
+/// assert!(filter.matches(br#"{"meta": { "k" : 1 }}"#));
+/// assert!(!filter.matches(br#"{"meta": {"k": 2}}"#));
+/// assert!(!filter.matches(br#"{"k": 1}"#));
+///
+/// let is_true = FilterExpression::new(vec![], Comparison::Equal(JsonLiteral::parse("true").unwrap()));
+///
+/// assert!(is_true.matches(b"true"));
+/// assert!(!is_true.matches(b"truent"));
+/// assert!(!is_true.matches(br#""true""#));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterExpression {
+    path: Vec<Label>,
+    comparison: Comparison,
+}
+
+/// Comparison performed by a [`FilterExpression`] on the value at the end of its path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Comparison {
+    /// Matches values equal to the [`JsonLiteral`].
+    ///
+    /// Two values are equal if they are identical after removing all whitespace
+    /// outside of strings. The values are not canonicalized otherwise, in particular:
+    /// - objects with the same members in different order are *not* equal;
+    /// - numbers have to be written the same way, so `1.0` is *not* equal to `1`;
+    /// - strings have to use the same escape sequences, so `"\u0041"` is *not* equal to `"A"`.
+    Equal(JsonLiteral),
+    /// Matches scalar values, i.e. strings, numbers, `true`, `false`, and `null`,
+    /// but not objects or arrays.
+    IsScalar,
+    /// Matches numbers within the [`NumericRange`].
+    ///
+    /// Values that are not numbers are never in the range.
+    InRange(NumericRange),
+    /// Matches values equal to any of the literals of the [`LiteralSet`].
+    InSet(LiteralSet),
+    /// Matches objects whose number of members compares with the given number
+    /// as described by the [`ComparisonOperator`], e.g. `size(@) > 10`.
+    ///
+    /// Only members of the object itself are counted, not members of nested objects.
+    /// Values that are not objects, including arrays, never match, whatever the operator.
+    Size(ComparisonOperator, usize),
+}
+
+/// Operator comparing a property of a value with a number, as in [`Comparison::Size`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComparisonOperator {
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `<`
+    Less,
+    /// `<=`
+    LessOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+}
+
+/// Syntax of numbers in the document read by a [`FilterExpression`] to compare them with numbers,
+/// e.g. with a [`NumericRange`].
+///
+/// Values that are not numbers in the syntax are never equal to numbers nor in ranges.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::query::{Comparison, FilterExpression, NumberSyntax, NumericRange};
+/// let range = NumericRange::new(1.0, 10.0).unwrap();
+/// let filter = FilterExpression::new(vec![], Comparison::InRange(range));
+///
+/// assert!(filter.matches_with(b"5", NumberSyntax::Strict));
+/// assert!(!filter.matches_with(b"05", NumberSyntax::Strict));
+/// assert!(!filter.matches_with(b"+5", NumberSyntax::Strict));
+/// assert!(filter.matches_with(b"05", NumberSyntax::Lenient));
+/// assert!(filter.matches_with(b"+5", NumberSyntax::Lenient));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumberSyntax {
+    /// Only numbers valid in JSON, so e.g. `01`, `+5`, or `1.` are not numbers.
+    #[default]
+    Strict,
+    /// Also numbers with leading zeros, e.g. `01` or `-007.5`, with a plus sign, e.g. `+5`,
+    /// or with nothing after the decimal point, e.g. `1.`, as written by some non-conforming producers.
+    Lenient,
+}
+
+/// Range of numbers of a [`Comparison::InRange`], e.g. `1..10` or `1..=10`.
+///
+/// The start is always included in the range, while the end is only included
+/// if the range was created with [`NumericRange::new_inclusive`]. The bounds are always finite.
+/// Both the bounds and the compared values are read as [`f64`], so integers
+/// differing only beyond 53 significant bits are indistinguishable.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::query::{Comparison, FilterExpression, Label, NumericRange};
+/// let exclusive = NumericRange::new(1.0, 10.0).unwrap();
+/// let inclusive = NumericRange::new_inclusive(1.0, 10.0).unwrap();
+/// let exclusive = FilterExpression::new(vec![], Comparison::InRange(exclusive));
+/// let inclusive = FilterExpression::new(vec![], Comparison::InRange(inclusive));
+///
+/// assert!(exclusive.matches(b"1"));
+/// assert!(exclusive.matches(b"9.99e0"));
+/// assert!(!exclusive.matches(b"10"));
+/// assert!(inclusive.matches(b"10"));
+/// assert!(!inclusive.matches(br#""5""#));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumericRange {
+    start: f64,
+    end: f64,
+    is_end_inclusive: bool,
+}
+
+// The constructors only create ranges with finite bounds, so equality is reflexive.
+impl Eq for NumericRange {}
+
+/// Set of literals of a [`Comparison::InSet`], e.g. `["active", "pending"]`.
+///
+/// Unlike [`Comparison::Equal`], values are compared with the literals by their meaning:
+/// - strings are equal if they are the same after decoding escape sequences,
+///   so `"\u0041"` *is* equal to `"A"`;
+/// - numbers are compared as [`f64`], same as in a [`NumericRange`], so `1.0` *is* equal to `1`;
+/// - `true`, `false`, `null`, objects, and arrays are compared as in [`Comparison::Equal`].
+///
+/// Membership is checked with a hash lookup, so the cost of a comparison does not depend on
+/// the number of literals in the set.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::query::{Comparison, FilterExpression, JsonLiteral, LiteralSet};
+/// let literals = ["\"active\"", "\"pending\"", "1", "null"]
+///     .into_iter()
+///     .map(|text| JsonLiteral::parse(text).unwrap())
+///     .collect();
+/// let filter = FilterExpression::new(vec![], Comparison::InSet(LiteralSet::new(literals)));
+///
+/// assert!(filter.matches(br#""active""#));
+/// assert!(filter.matches(br#""\u0070ending""#));
+/// assert!(filter.matches(b"1.0"));
+/// assert!(filter.matches(b"null"));
+/// assert!(!filter.matches(b"true"));
+/// assert!(!filter.matches(br#""1""#));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiteralSet {
+    literals: Vec<JsonLiteral>,
+    strings: HashSet<Vec<u8>>,
+    numbers: HashSet<u64>,
+    others: HashSet<Vec<u8>>,
+}
+
+/// A JSON value given as an operand of a [`Comparison`].
+///
+/// The literal is stored with all whitespace outside of strings removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonLiteral {
+    normalized: Vec<u8>,
+}
+
+impl FilterExpression {
+    /// Create a filter applying the `comparison` to the value found
+    /// by following the `path` of labels from the filtered value.
+    #[must_use]
+    #[inline(always)]
+    pub fn new(path: Vec<Label>, comparison: Comparison) -> Self {
+        Self { path, comparison }
+    }
+
+    /// Labels leading from the filtered value to the compared value.
+    #[must_use]
+    #[inline(always)]
+    pub fn path(&self) -> &[Label] {
+        &self.path
+    }
+
+    /// Comparison performed on the value at the end of the [`path`](FilterExpression::path).
+    #[must_use]
+    #[inline(always)]
+    pub fn comparison(&self) -> &Comparison {
+        &self.comparison
+    }
+
+    /// Returns whether the filter selects the `value`, given as its raw JSON bytes.
+    ///
+    /// Values are compared with numbers only if they are valid JSON numbers,
+    /// as with [`NumberSyntax::Strict`].
+    #[must_use]
+    #[inline(always)]
+    pub fn matches(&self, value: &[u8]) -> bool {
+        self.matches_with(value, NumberSyntax::Strict)
+    }
+
+    /// Returns whether the filter selects the `value`, given as its raw JSON bytes,
+    /// reading numbers in the `number_syntax`.
+    #[must_use]
+    #[inline]
+    pub fn matches_with(&self, value: &[u8], number_syntax: NumberSyntax) -> bool {
+        self.matches_from(value, json::value_start(value, 0), number_syntax)
+    }
+
+    /// Returns whether the filter selects the value reported at `idx` in the document `bytes`,
+    /// as in [`QueryResult::report`](crate::result::QueryResult::report).
+    pub(crate) fn matches_at(&self, bytes: &[u8], idx: usize, number_syntax: NumberSyntax) -> bool {
+        self.matches_from(bytes, json::value_start(bytes, idx), number_syntax)
+    }
+
+    /// Returns whether the filter selects the value starting at `start` in `bytes`.
+    ///
+    /// The scan is bounded by the path and the comparison, not by the size of the filtered value.
+    /// The members of every object on the path are scanned only up to the one with the label,
+    /// and the lookup stops as soon as a label is missing. The value at the end of the path
+    /// is then read only as far as the comparison needs: scalar tests look at its first byte,
+    /// comparisons with scalars, sets of scalars, and ranges reject objects and arrays by their
+    /// first byte, comparisons with objects and arrays stop after the length of the literal,
+    /// and size tests stop at the end of the object. In particular, `bytes` can extend past
+    /// the end of the value.
+    fn matches_from(&self, bytes: &[u8], start: usize, number_syntax: NumberSyntax) -> bool {
+        let mut current = &bytes[start.min(bytes.len())..];
+
+        for label in &self.path {
+            match json::member_value_start(current, &json::unescape(label.bytes())) {
+                Some(start) => current = &current[start..],
+                None => return false,
+            }
+        }
+        let first = current.first();
+
+        match self.comparison {
+            Comparison::Equal(ref literal) if literal.is_scalar() => {
+                is_scalar_start(first) && json::value_at(current, 0) == literal.bytes()
+            }
+            // The literal is a complete object or array, so a value starting
+            // with all of its significant bytes ends right after them.
+            Comparison::Equal(ref literal) => json::significant_bytes(current)
+                .take(literal.bytes().len())
+                .eq(literal.bytes().iter().copied()),
+            Comparison::IsScalar => is_scalar_start(first),
+            Comparison::InRange(ref range) => {
+                matches!(first, Some(b'-' | b'+' | b'0'..=b'9'))
+                    && number_syntax
+                        .parse(json::value_at(current, 0))
+                        .map_or(false, |x| range.contains(x))
+            }
+            Comparison::InSet(ref set) => {
+                (is_scalar_start(first) || !set.is_scalar())
+                    && set.contains_with(json::value_at(current, 0), number_syntax)
+            }
+            Comparison::Size(operator, size) => {
+                json::object_size(current).map_or(false, |actual| operator.compare(actual, size))
+            }
+        }
+    }
+}
+
+fn is_scalar_start(first: Option<&u8>) -> bool {
+    !matches!(first, None | Some(b'{' | b'['))
+}
+
+impl NumberSyntax {
+    /// Read the `value` as a number, if it is a number in this syntax.
+    fn parse(self, value: &[u8]) -> Option<f64> {
+        match self {
+            Self::Strict if json::is_json_number(value) => json::parse_number(value),
+            Self::Strict => None,
+            Self::Lenient => match value {
+                [b'+', rest @ ..] if rest.first().map_or(false, u8::is_ascii_digit) => {
+                    json::parse_number(rest)
+                }
+                _ => json::parse_number(value),
+            },
+        }
+    }
+}
+
+impl ComparisonOperator {
+    /// Returns whether `left` and `right` are in the relation described by the operator,
+    /// e.g. `left < right` for [`Less`](ComparisonOperator::Less).
+    #[must_use]
+    #[inline]
+    pub fn compare(self, left: usize, right: usize) -> bool {
+        match self {
+            Self::Equal => left == right,
+            Self::NotEqual => left != right,
+            Self::Less => left < right,
+            Self::LessOrEqual => left <= right,
+            Self::Greater => left > right,
+            Self::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+impl Display for ComparisonOperator {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self {
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+        };
+        write!(f, "{operator}")
+    }
+}
+
+impl NumericRange {
+    /// Create a range of numbers from `start` inclusive to `end` exclusive, i.e. `start..end`.
+    ///
+    /// Returns `None` if either bound is not finite.
+    #[must_use]
+    #[inline(always)]
+    pub fn new(start: f64, end: f64) -> Option<Self> {
+        Self::with_bounds(start, end, false)
+    }
+
+    /// Create a range of numbers from `start` to `end`, both inclusive, i.e. `start..=end`.
+    ///
+    /// Returns `None` if either bound is not finite.
+    #[must_use]
+    #[inline(always)]
+    pub fn new_inclusive(start: f64, end: f64) -> Option<Self> {
+        Self::with_bounds(start, end, true)
+    }
+
+    fn with_bounds(start: f64, end: f64, is_end_inclusive: bool) -> Option<Self> {
+        (start.is_finite() && end.is_finite()).then_some(Self {
+            start,
+            end,
+            is_end_inclusive,
+        })
+    }
+
+    /// The smallest number in the range.
+    #[must_use]
+    #[inline(always)]
+    pub fn start(&self) -> f64 {
+        self.start
+    }
+
+    /// The end of the range, included in it only if [`is_end_inclusive`](NumericRange::is_end_inclusive).
+    #[must_use]
+    #[inline(always)]
+    pub fn end(&self) -> f64 {
+        self.end
+    }
+
+    /// Whether the [`end`](NumericRange::end) is included in the range.
+    #[must_use]
+    #[inline(always)]
+    pub fn is_end_inclusive(&self) -> bool {
+        self.is_end_inclusive
+    }
+
+    /// Returns whether the number `x` is in the range.
+    #[must_use]
+    #[inline]
+    pub fn contains(&self, x: f64) -> bool {
+        self.start <= x && (x < self.end || self.is_end_inclusive && x <= self.end)
+    }
+}
+
+impl Display for NumericRange {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = if self.is_end_inclusive { "..=" } else { ".." };
+        write!(f, "{}{operator}{}", self.start, self.end)
+    }
+}
+
+impl LiteralSet {
+    /// Create a set of the `literals`. Duplicates are allowed and do not change the set.
+    #[must_use]
+    #[inline]
+    pub fn new(literals: Vec<JsonLiteral>) -> Self {
+        let mut strings = HashSet::new();
+        let mut numbers = HashSet::new();
+        let mut others = HashSet::new();
+
+        for literal in &literals {
+            let value = literal.bytes();
+            if let Some(contents) = string_contents(value) {
+                strings.insert(json::unescape(contents).into_owned());
+            } else if let Some(x) = json::parse_number(value) {
+                numbers.insert(number_key(x));
+            } else {
+                others.insert(value.to_vec());
+            }
+        }
+
+        Self {
+            literals,
+            strings,
+            numbers,
+            others,
+        }
+    }
+
+    /// The literals of the set, in the order they were given.
+    #[must_use]
+    #[inline(always)]
+    pub fn literals(&self) -> &[JsonLiteral] {
+        &self.literals
+    }
+
+    /// Returns whether the `value`, given as its raw JSON bytes, is equal to any literal of the set.
+    ///
+    /// The value is compared with numbers only if it is a valid JSON number,
+    /// as with [`NumberSyntax::Strict`].
+    #[must_use]
+    #[inline(always)]
+    pub fn contains(&self, value: &[u8]) -> bool {
+        self.contains_with(value, NumberSyntax::Strict)
+    }
+
+    /// Returns whether the `value`, given as its raw JSON bytes, is equal to any literal of the set,
+    /// reading numbers in the `number_syntax`.
+    #[must_use]
+    #[inline]
+    pub fn contains_with(&self, value: &[u8], number_syntax: NumberSyntax) -> bool {
+        if let Some(contents) = string_contents(value) {
+            self.strings.contains(json::unescape(contents).as_ref())
+        } else if let Some(x) = number_syntax.parse(value) {
+            self.numbers.contains(&number_key(x))
+        } else {
+            !self.others.is_empty()
+                && self
+                    .others
+                    .contains(&json::significant_bytes(value).collect::<Vec<_>>())
+        }
+    }
+
+    /// Returns whether all literals of the set are scalars.
+    pub(crate) fn is_scalar(&self) -> bool {
+        self.literals.iter().all(JsonLiteral::is_scalar)
+    }
+
+    /// Approximate number of bytes the set occupies on the heap.
+    pub(crate) fn approx_heap_bytes(&self) -> usize {
+        let literal_bytes: usize = self.literals.iter().map(|x| x.bytes().len()).sum();
+        2 * literal_bytes + self.numbers.capacity() * std::mem::size_of::<u64>()
+    }
+}
+
+/// Contents of the string between its quotes, if the `value` is a string.
+fn string_contents(value: &[u8]) -> Option<&[u8]> {
+    match value {
+        [b'"', contents @ .., b'"'] => Some(contents),
+        _ => None,
+    }
+}
+
+/// Key of the number `x` in a [`LiteralSet`], the same for all representations
+/// of the same number, including `0` and `-0`.
+fn number_key(x: f64) -> u64 {
+    (x + 0.0).to_bits()
+}
+
+impl JsonLiteral {
+    /// Parse a JSON value into a literal.
+    ///
+    /// # Errors
+    /// Returns [`ParserError::SyntaxError`] if the `text` is not a single valid JSON value.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::JsonLiteral;
+    /// let literal = JsonLiteral::parse(r#" [1, "a b", {"c": null}] "#).unwrap();
+    ///
+    /// assert_eq!(literal.to_string(), r#"[1,"a b",{"c":null}]"#);
+    /// assert!(JsonLiteral::parse("[1, 2").is_err());
+    /// ```
+    #[inline]
+    pub fn parse(text: &str) -> Result<Self, ParserError> {
+        super::parser::parse_json_literal(text)
+    }
+
+    /// Create a literal from `text` that is known to be a valid JSON value.
+    pub(crate) fn from_valid(text: &str) -> Self {
+        Self {
+            normalized: json::significant_bytes(text.as_bytes()).collect(),
+        }
+    }
+
+    /// Returns whether the literal is a string, a number, `true`, `false`, or `null`.
+    pub(crate) fn is_scalar(&self) -> bool {
+        is_scalar_start(self.normalized.first())
+    }
+
+    /// Bytes of the literal, with all whitespace outside of strings removed.
+    #[must_use]
+    #[inline(always)]
+    pub fn bytes(&self) -> &[u8] {
+        &self.normalized
+    }
+}
+
+impl Display for FilterExpression {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Comparison::Size(..) = self.comparison {
+            write!(f, "size(")?;
+        }
+        write!(f, "@")?;
+        for label in &self.path {
+            write!(f, "['{}']", label.display())?;
+        }
+        match self.comparison {
+            Comparison::Equal(ref literal) => write!(f, " == {literal}"),
+            Comparison::IsScalar => write!(f, " is scalar"),
+            Comparison::InRange(ref range) => write!(f, " in {range}"),
+            Comparison::InSet(ref set) => write!(f, " in {set}"),
+            Comparison::Size(operator, size) => write!(f, ") {operator} {size}"),
+        }
+    }
+}
+
+impl Display for LiteralSet {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, literal) in self.literals.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{literal}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl Display for JsonLiteral {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.normalized))
+    }
+}
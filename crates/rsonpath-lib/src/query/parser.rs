@@ -1,11 +1,15 @@
 use super::error::{ParseErrorReport, ParserError};
 use crate::debug;
-use crate::query::{JsonPathQuery, JsonPathQueryNode, JsonPathQueryNodeType, Label};
+use crate::query::{
+    ArraySlice, Comparison, ComparisonOperator, FilterExpression, JsonLiteral, JsonPathQuery,
+    JsonPathQueryNode, JsonPathQueryNodeType, KeyPattern, Label, LiteralSet, NumericRange,
+};
 use nom::{
     branch::*, bytes::complete::*, character::complete::*, combinator::*, multi::*, sequence::*, *,
 };
 use std::borrow::Borrow;
 use std::fmt::{self, Display};
+use std::num::NonZeroUsize;
 
 #[derive(Debug, Clone)]
 enum Token<'a> {
@@ -13,6 +17,19 @@ enum Token<'a> {
     Child(LabelString<'a>),
     WildcardChild(),
     Descendant(LabelString<'a>),
+    DescendantOrSelf(LabelString<'a>),
+    PrefixChild(LabelString<'a>),
+    PrefixDescendant(LabelString<'a>),
+    #[cfg(feature = "regex")]
+    PatternChild(String),
+    #[cfg(feature = "regex")]
+    PatternDescendant(String),
+    Filter(FilterExpression),
+    DescendantFilter(FilterExpression),
+    MemberFromEnd(NonZeroUsize),
+    ArrayIndex(usize),
+    Slice(ArraySlice),
+    DescendantSlice(ArraySlice),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -28,6 +45,19 @@ impl Display for Token<'_> {
             Token::Child(label) => write!(f, "['{label}']"),
             Token::WildcardChild() => write!(f, "[*]"),
             Token::Descendant(label) => write!(f, "..['{label}']"),
+            Token::DescendantOrSelf(label) => write!(f, "...['{label}']"),
+            Token::PrefixChild(label) => write!(f, "[~prefix('{label}')]"),
+            Token::PrefixDescendant(label) => write!(f, "..[~prefix('{label}')]"),
+            #[cfg(feature = "regex")]
+            Token::PatternChild(pattern) => write!(f, "[~'{pattern}']"),
+            #[cfg(feature = "regex")]
+            Token::PatternDescendant(pattern) => write!(f, "..[~'{pattern}']"),
+            Token::Filter(filter) => write!(f, "[?({filter})]"),
+            Token::DescendantFilter(filter) => write!(f, "..[?({filter})]"),
+            Token::MemberFromEnd(position) => write!(f, "[last({position})]"),
+            Token::ArrayIndex(index) => write!(f, "[{index}]"),
+            Token::Slice(slice) => write!(f, "[{slice}]"),
+            Token::DescendantSlice(slice) => write!(f, "..[{slice}]"),
         }
     }
 }
@@ -124,6 +154,41 @@ fn tokens_to_node<'a, I: Iterator<Item = Token<'a>>>(
                     Label::new(label.borrow()),
                     child_node,
                 ))),
+                Token::DescendantOrSelf(label) => Ok(Some(JsonPathQueryNode::DescendantOrSelf(
+                    Label::new(label.borrow()),
+                    child_node,
+                ))),
+                Token::PrefixChild(label) => Ok(Some(JsonPathQueryNode::PatternChild(
+                    KeyPattern::prefix(Label::new(label.borrow())),
+                    child_node,
+                ))),
+                Token::PrefixDescendant(label) => Ok(Some(JsonPathQueryNode::PatternDescendant(
+                    KeyPattern::prefix(Label::new(label.borrow())),
+                    child_node,
+                ))),
+                #[cfg(feature = "regex")]
+                Token::PatternChild(pattern) => Ok(Some(JsonPathQueryNode::PatternChild(
+                    KeyPattern::regex(&pattern)?,
+                    child_node,
+                ))),
+                #[cfg(feature = "regex")]
+                Token::PatternDescendant(pattern) => Ok(Some(
+                    JsonPathQueryNode::PatternDescendant(KeyPattern::regex(&pattern)?, child_node),
+                )),
+                Token::Filter(filter) => Ok(Some(JsonPathQueryNode::Filter(filter, child_node))),
+                Token::DescendantFilter(filter) => Ok(Some(JsonPathQueryNode::DescendantFilter(
+                    filter, child_node,
+                ))),
+                Token::MemberFromEnd(position) => {
+                    Ok(Some(JsonPathQueryNode::MemberFromEnd(position, child_node)))
+                }
+                Token::ArrayIndex(index) => {
+                    Ok(Some(JsonPathQueryNode::ArrayIndex(index, child_node)))
+                }
+                Token::Slice(slice) => Ok(Some(JsonPathQueryNode::Slice(slice, child_node))),
+                Token::DescendantSlice(slice) => {
+                    Ok(Some(JsonPathQueryNode::DescendantSlice(slice, child_node)))
+                }
             }
         }
         _ => Ok(None),
@@ -155,8 +220,15 @@ fn jsonpath<'a>() -> impl Parser<'a, (Option<Token<'a>>, Vec<Token<'a>>)> {
 fn non_root<'a>() -> impl Parser<'a, Vec<Token<'a>>> {
     many0(alt((
         wildcard_child_selector(),
+        prefix_selector(),
         child_selector(),
+        descendant_or_self_selector(),
         descendant_selector(),
+        pattern_selector(),
+        filter_selector(),
+        member_from_end_selector(),
+        array_index_selector(),
+        slice_selector(),
     )))
 }
 
@@ -186,6 +258,355 @@ fn descendant_selector<'a>() -> impl Parser<'a, Token<'a>> {
     )
 }
 
+fn descendant_or_self_selector<'a>() -> impl Parser<'a, Token<'a>> {
+    map(
+        preceded(tag("..."), alt((label(), index_selector()))),
+        Token::DescendantOrSelf,
+    )
+}
+
+fn prefix_selector<'a>() -> impl Parser<'a, Token<'a>> {
+    alt((
+        map(
+            preceded(
+                tag(".."),
+                alt((terminated(label(), char('*')), prefix_index())),
+            ),
+            Token::PrefixDescendant,
+        ),
+        map(
+            alt((
+                preceded(char('.'), terminated(label(), char('*'))),
+                prefix_index(),
+            )),
+            Token::PrefixChild,
+        ),
+    ))
+}
+
+fn prefix_index<'a>() -> impl Parser<'a, LabelString<'a>> {
+    delimited(tag("[~prefix("), quoted_label(), tag(")]"))
+}
+
+#[cfg(feature = "regex")]
+fn pattern_selector<'a>() -> impl Parser<'a, Token<'a>> {
+    alt((
+        map(preceded(tag(".."), pattern()), Token::PatternDescendant),
+        map(pattern(), Token::PatternChild),
+    ))
+}
+
+#[cfg(not(feature = "regex"))]
+fn pattern_selector<'a>() -> impl Parser<'a, Token<'a>> {
+    fail
+}
+
+#[cfg(feature = "regex")]
+fn pattern<'a>() -> impl Parser<'a, String> {
+    delimited(
+        tag("[~"),
+        alt((
+            delimited(char('\''), raw_string('\''), char('\'')),
+            delimited(char('"'), raw_string('"'), char('"')),
+        )),
+        char(']'),
+    )
+}
+
+/// Parser for the contents of a quoted pattern up to the closing `quote`.
+///
+/// Unlike labels, patterns are taken verbatim, so that the backslashes
+/// in a regex like `\d+` have their usual meaning. The only exception is
+/// an escaped `quote`, which is unescaped.
+#[cfg(feature = "regex")]
+fn raw_string<'a>(quote: char) -> impl Parser<'a, String> {
+    move |input: &'a str| {
+        let mut result = String::new();
+        let mut chars = input.char_indices();
+
+        while let Some((idx, c)) = chars.next() {
+            if c == quote {
+                return Ok((&input[idx..], result));
+            }
+            if c == '\\' {
+                match chars.next() {
+                    Some((_, next)) if next == quote => result.push(quote),
+                    Some((_, next)) => {
+                        result.push('\\');
+                        result.push(next);
+                    }
+                    None => break,
+                }
+            } else {
+                result.push(c);
+            }
+        }
+
+        Err(nom::Err::Error(error::Error::new(
+            input,
+            error::ErrorKind::Char,
+        )))
+    }
+}
+
+fn member_from_end_selector<'a>() -> impl Parser<'a, Token<'a>> {
+    map_opt(
+        delimited(tag("[last("), opt(digit1), tag(")]")),
+        |digits: Option<&str>| {
+            digits
+                .unwrap_or("1")
+                .parse()
+                .ok()
+                .and_then(NonZeroUsize::new)
+                .map(Token::MemberFromEnd)
+        },
+    )
+}
+
+fn array_index_selector<'a>() -> impl Parser<'a, Token<'a>> {
+    map(
+        delimited(char('['), nonnegative_integer(), char(']')),
+        Token::ArrayIndex,
+    )
+}
+
+fn slice_selector<'a>() -> impl Parser<'a, Token<'a>> {
+    alt((
+        map(preceded(tag(".."), slice()), Token::DescendantSlice),
+        map(slice(), Token::Slice),
+    ))
+}
+
+fn slice<'a>() -> impl Parser<'a, ArraySlice> {
+    map_opt(
+        delimited(
+            char('['),
+            tuple((
+                opt(nonnegative_integer()),
+                preceded(char(':'), opt(nonnegative_integer())),
+                opt(preceded(char(':'), opt(nonnegative_integer()))),
+            )),
+            char(']'),
+        ),
+        |(start, end, step)| {
+            // A missing step is 1, but an explicit step of 0 would select nothing.
+            let step = NonZeroUsize::new(step.flatten().unwrap_or(1))?;
+            Some(ArraySlice::new(start.unwrap_or(0), end, step))
+        },
+    )
+}
+
+fn nonnegative_integer<'a>() -> impl Parser<'a, usize> {
+    map_opt(digit1, |digits: &str| {
+        // Leading zeros are not allowed, same as in JSON numbers.
+        if digits.len() > 1 && digits.starts_with('0') {
+            None
+        } else {
+            digits.parse().ok()
+        }
+    })
+}
+
+fn filter_selector<'a>() -> impl Parser<'a, Token<'a>> {
+    alt((
+        map(preceded(tag(".."), filter()), Token::DescendantFilter),
+        map(filter(), Token::Filter),
+    ))
+}
+
+fn filter<'a>() -> impl Parser<'a, FilterExpression> {
+    delimited(
+        pair(tag("[?("), multispace0),
+        filter_expression(),
+        pair(multispace0, tag(")]")),
+    )
+}
+
+fn filter_expression<'a>() -> impl Parser<'a, FilterExpression> {
+    alt((size_expression(), comparison_expression()))
+}
+
+fn relative_path<'a>() -> impl Parser<'a, Vec<Label>> {
+    map(
+        preceded(char('@'), many0(alt((dot_selector(), index_selector())))),
+        |path| {
+            path.iter()
+                .map(|label| Label::new(label.borrow()))
+                .collect()
+        },
+    )
+}
+
+fn size_expression<'a>() -> impl Parser<'a, FilterExpression> {
+    map(
+        tuple((
+            delimited(
+                pair(tag("size("), multispace0),
+                relative_path(),
+                pair(multispace0, char(')')),
+            ),
+            delimited(multispace0, comparison_operator, multispace0),
+            nonnegative_integer(),
+        )),
+        |(path, operator, size)| FilterExpression::new(path, Comparison::Size(operator, size)),
+    )
+}
+
+fn comparison_operator(input: &str) -> IResult<&str, ComparisonOperator> {
+    alt((
+        value(ComparisonOperator::Equal, tag("==")),
+        value(ComparisonOperator::NotEqual, tag("!=")),
+        value(ComparisonOperator::LessOrEqual, tag("<=")),
+        value(ComparisonOperator::GreaterOrEqual, tag(">=")),
+        value(ComparisonOperator::Less, char('<')),
+        value(ComparisonOperator::Greater, char('>')),
+    ))(input)
+}
+
+fn comparison_expression<'a>() -> impl Parser<'a, FilterExpression> {
+    map(
+        pair(
+            relative_path(),
+            alt((
+                map(
+                    preceded(tuple((multispace0, tag("=="), multispace0)), json_literal),
+                    Comparison::Equal,
+                ),
+                map(
+                    tuple((multispace1, tag("is"), multispace1, tag("scalar"))),
+                    |_| Comparison::IsScalar,
+                ),
+                map(
+                    preceded(tuple((multispace1, tag("in"), multispace1)), numeric_range),
+                    Comparison::InRange,
+                ),
+                map(
+                    preceded(tuple((multispace1, tag("in"), multispace1)), literal_set),
+                    Comparison::InSet,
+                ),
+            )),
+        ),
+        |(path, comparison)| FilterExpression::new(path, comparison),
+    )
+}
+
+/// Parse a complete JSON value, allowing surrounding whitespace.
+pub(crate) fn parse_json_literal(text: &str) -> Result<JsonLiteral, ParserError> {
+    match all_consuming(delimited(multispace0, json_literal, multispace0))(text).finish() {
+        Ok((_, literal)) => Ok(literal),
+        Err(err) => {
+            let mut report = ParseErrorReport::new();
+            report.record_at(text.len() - err.input.len());
+            Err(ParserError::SyntaxError { report })
+        }
+    }
+}
+
+fn json_literal(input: &str) -> IResult<&str, JsonLiteral> {
+    map(json_value, JsonLiteral::from_valid)(input)
+}
+
+// The JSON grammar is recursive, so the parsers below are functions
+// instead of the usual parser factories.
+fn json_value(input: &str) -> IResult<&str, &str> {
+    alt((
+        json_object,
+        json_array,
+        json_string,
+        json_number,
+        tag("true"),
+        tag("false"),
+        tag("null"),
+    ))(input)
+}
+
+fn json_object(input: &str) -> IResult<&str, &str> {
+    let member = separated_pair(
+        delimited(multispace0, json_string, multispace0),
+        char(':'),
+        delimited(multispace0, json_value, multispace0),
+    );
+    recognize(delimited(
+        char('{'),
+        alt((recognize(separated_list1(char(','), member)), multispace0)),
+        char('}'),
+    ))(input)
+}
+
+fn json_array(input: &str) -> IResult<&str, &str> {
+    recognize(delimited(
+        char('['),
+        alt((
+            recognize(separated_list1(
+                char(','),
+                delimited(multispace0, json_value, multispace0),
+            )),
+            multispace0,
+        )),
+        char(']'),
+    ))(input)
+}
+
+fn json_string(input: &str) -> IResult<&str, &str> {
+    let escape = preceded(
+        char('\\'),
+        alt((
+            recognize(one_of(r#""\/bfnrt"#)),
+            recognize(pair(
+                char('u'),
+                count(satisfy(|c| c.is_ascii_hexdigit()), 4),
+            )),
+        )),
+    );
+    let unescaped = recognize(verify(none_of(r#""\"#), |&c| u32::from(c) >= 0x20));
+    recognize(delimited(
+        char('"'),
+        many0(alt((unescaped, escape))),
+        char('"'),
+    ))(input)
+}
+
+fn json_number(input: &str) -> IResult<&str, &str> {
+    recognize(tuple((
+        opt(char('-')),
+        alt((tag("0"), recognize(pair(one_of("123456789"), digit0)))),
+        opt(pair(char('.'), digit1)),
+        opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+    )))(input)
+}
+
+fn numeric_range(input: &str) -> IResult<&str, NumericRange> {
+    let number = |input| map_opt(json_number, |number: &str| number.parse::<f64>().ok())(input);
+    alt((
+        map_opt(
+            separated_pair(number, tag("..="), number),
+            |(start, end)| NumericRange::new_inclusive(start, end),
+        ),
+        map_opt(separated_pair(number, tag(".."), number), |(start, end)| {
+            NumericRange::new(start, end)
+        }),
+    ))(input)
+}
+
+fn literal_set(input: &str) -> IResult<&str, LiteralSet> {
+    // Strings in a set can also be single-quoted, like labels, for convenience.
+    let single_quoted_string = map(
+        delimited(char('\''), opt(single_quoted_label()), char('\'')),
+        |contents| JsonLiteral::from_valid(&format!("\"{}\"", contents.unwrap_or_default())),
+    );
+    map(
+        delimited(
+            pair(char('['), multispace0),
+            separated_list0(
+                tuple((multispace0, char(','), multispace0)),
+                alt((json_literal, single_quoted_string)),
+            ),
+            pair(multispace0, char(']')),
+        ),
+        LiteralSet::new,
+    )(input)
+}
+
 fn index_selector<'a>() -> impl Parser<'a, LabelString<'a>> {
     delimited(char('['), quoted_label(), char(']'))
 }
@@ -260,9 +681,32 @@ fn escaped<'a>() -> impl Parser<'a, &'a str> {
         value("\\t", tag("t")),
         value("\\\\", tag("\\")),
         value("/", tag("/")),
+        escaped_control_character(),
     ))
 }
 
+/// Escaped forms of the control characters from `U+0000` to `U+001F` in labels.
+/// Characters with a short escape sequence use it, others use a lowercase `\u00XX` sequence.
+const CONTROL_CHARACTER_ESCAPES: [&str; 0x20] = [
+    "\\u0000", "\\u0001", "\\u0002", "\\u0003", "\\u0004", "\\u0005", "\\u0006", "\\u0007", "\\b",
+    "\\t", "\\n", "\\u000b", "\\f", "\\r", "\\u000e", "\\u000f", "\\u0010", "\\u0011", "\\u0012",
+    "\\u0013", "\\u0014", "\\u0015", "\\u0016", "\\u0017", "\\u0018", "\\u0019", "\\u001a",
+    "\\u001b", "\\u001c", "\\u001d", "\\u001e", "\\u001f",
+];
+
+fn escaped_control_character<'a>() -> impl Parser<'a, &'a str> {
+    preceded(
+        char('u'),
+        map_opt(
+            take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()),
+            |hex: &str| {
+                let code = usize::from_str_radix(hex, 16).ok()?;
+                CONTROL_CHARACTER_ESCAPES.get(code).copied()
+            },
+        ),
+    )
+}
+
 fn unescaped<'a>() -> impl Parser<'a, char> {
     verify(none_of(r#"'"\"#), |&c| u32::from(c) >= 0x20)
 }
@@ -352,6 +796,33 @@ mod tests {
         assert_eq!(result, Ok(("", r#"\""#.to_owned())));
     }
 
+    #[test]
+    fn unicode_escaped_control_character_in_label() {
+        let input = r#"a\u001Fb\u0000"#;
+
+        let result = super::single_quoted_label()(input);
+
+        assert_eq!(result, Ok(("", r#"a\u001fb\u0000"#.to_owned())));
+    }
+
+    #[test]
+    fn unicode_escaped_control_character_with_short_form() {
+        let input = r#"\u000A"#;
+
+        let result = super::double_quoted_label()(input);
+
+        assert_eq!(result, Ok(("", r#"\n"#.to_owned())));
+    }
+
+    #[test]
+    fn unicode_escape_of_non_control_character_is_invalid() {
+        let input = "$['\\u0041']";
+
+        let result = parse_json_path_query(input);
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn quoted_label() {
         let input = "'a'";
@@ -0,0 +1,399 @@
+//! Patterns matching keys by criteria other than byte equality with a [`Label`](super::Label).
+use super::Label;
+use crate::json;
+#[cfg(feature = "regex")]
+use regex::bytes::Regex;
+use std::fmt::{self, Debug, Display};
+use std::sync::Arc;
+#[cfg(feature = "unicode-normalization")]
+use unicode_normalization::UnicodeNormalization;
+
+/// User-supplied function comparing the key of a member with a label.
+///
+/// The first argument is the key from the document, the second is the label from the query.
+/// Both are unescaped.
+pub type KeyComparator = Arc<dyn Fn(&[u8], &[u8]) -> bool + Send + Sync>;
+
+/// Pattern that a key has to match to be selected by a pattern selector, e.g.
+/// [`JsonPathQueryNode::PatternChild`](super::JsonPathQueryNode::PatternChild).
+///
+/// Patterns are always evaluated against the unescaped contents of a key,
+/// i.e. after decoding all JSON escape sequences.
+///
+/// Pattern selectors cannot be executed as efficiently as label selectors,
+/// since the key has to be extracted from the document and tested separately.
+/// This cost is only paid for keys that do not match any label in the current
+/// state of the query automaton.
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum KeyPattern {
+    /// Matches keys for which the `comparator` returns `true` when called with
+    /// the key and the `label`.
+    ///
+    /// This is an escape hatch for matching that cannot be expressed otherwise,
+    /// e.g. accent-insensitive or locale-specific comparisons.
+    Custom {
+        /// Label passed to the `comparator` for every key.
+        label: Label,
+        /// Function deciding whether a key matches the `label`.
+        comparator: KeyComparator,
+    },
+    /// Matches keys starting with a label, including the label itself.
+    Prefix(Label),
+    /// Matches keys in which the regular expression finds a match.
+    ///
+    /// The expression is not anchored by default &ndash; use `^` and `$`
+    /// to require the entire key to match.
+    #[cfg(feature = "regex")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "regex")))]
+    Regex(Regex),
+    /// Matches keys equal to a label after normalizing both to the
+    /// Unicode Normalization Form C (NFC).
+    #[cfg(feature = "unicode-normalization")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    Normalized(NormalizedLabel),
+    /// Matches keys equal to a label after removing diacritics from both,
+    /// as described in [`FoldedLabel`].
+    #[cfg(feature = "accent-folding")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "accent-folding")))]
+    AccentFolded(FoldedLabel),
+}
+
+/// A [`Label`] together with its unescaped contents in the Unicode Normalization Form C (NFC).
+///
+/// Used by [`KeyPattern::Normalized`] to match keys regardless of their normalization form,
+/// e.g. the precomposed `"caf\u{e9}"` and the decomposed `"cafe\u{301}"`.
+#[cfg(feature = "unicode-normalization")]
+#[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedLabel {
+    label: Label,
+    nfc: String,
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl NormalizedLabel {
+    /// Normalize the `label`.
+    #[must_use]
+    #[inline]
+    pub fn new(label: Label) -> Self {
+        let nfc = String::from_utf8_lossy(&json::unescape(label.bytes()))
+            .nfc()
+            .collect();
+
+        Self { label, nfc }
+    }
+
+    /// The original label.
+    #[must_use]
+    #[inline(always)]
+    pub fn label(&self) -> &Label {
+        &self.label
+    }
+
+    /// Unescaped contents of the label in NFC.
+    #[must_use]
+    #[inline(always)]
+    pub fn nfc(&self) -> &str {
+        &self.nfc
+    }
+
+    /// Returns whether the unescaped `key` is equal to the label in NFC.
+    ///
+    /// ASCII keys are compared directly, since they are already normalized.
+    /// Keys that are not valid UTF-8 cannot be normalized and are also compared directly.
+    #[must_use]
+    #[inline]
+    pub fn is_match(&self, key: &[u8]) -> bool {
+        if key.is_ascii() {
+            return key == self.nfc.as_bytes();
+        }
+        match std::str::from_utf8(key) {
+            Ok(key) => key.nfc().eq(self.nfc.chars()),
+            Err(_) => key == self.nfc.as_bytes(),
+        }
+    }
+}
+
+/// A [`Label`] together with its unescaped contents with diacritics removed.
+///
+/// Used by [`KeyPattern::AccentFolded`] to match keys regardless of accents,
+/// e.g. `"caf\u{e9}"` and `"cafe"`. Folding removes combining diacritical marks
+/// (`U+0300` to `U+036F`) and replaces precomposed Latin letters with diacritics
+/// (`U+00C0` to `U+017F`) with their base letters, preserving case. Letters that are
+/// not a base letter with diacritics, like `æ`, `ø`, or `ß`, are left unchanged,
+/// and so are all other characters.
+#[cfg(feature = "accent-folding")]
+#[cfg_attr(docsrs, doc(cfg(feature = "accent-folding")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldedLabel {
+    label: Label,
+    folded: String,
+}
+
+/// Base letters of the characters from `U+00C0` to `U+017F`, with `.` for characters
+/// that are not a base letter with diacritics.
+#[cfg(feature = "accent-folding")]
+const ACCENT_FOLD_TABLE: &[u8] = concat!(
+    "AAAAAA.CEEEEIIII.NOOOOO..UUUUY..",
+    "aaaaaa.ceeeeiiii.nooooo..uuuuy.y",
+    "AaAaAaCcCcCcCcDd..EeEeEeEeEeGgGg",
+    "GgGgHh..IiIiIiIiI...JjKk.LlLlLl.",
+    "...NnNnNn...OoOoOo..RrRrRrSsSsSs",
+    "SsTtTt..UuUuUuUuUuUuWwYyYZzZzZz.",
+)
+.as_bytes();
+
+/// Remove diacritics from all characters in `text`, as described in [`FoldedLabel`].
+#[cfg(feature = "accent-folding")]
+fn fold_accents(text: &str) -> impl Iterator<Item = char> + '_ {
+    text.chars()
+        .filter(|c| !('\u{300}'..='\u{36f}').contains(c))
+        .map(|c| {
+            let folded = (u32::from(c))
+                .checked_sub(0xC0)
+                .and_then(|offset| ACCENT_FOLD_TABLE.get(usize::try_from(offset).ok()?));
+            match folded {
+                Some(&base) if base != b'.' => char::from(base),
+                _ => c,
+            }
+        })
+}
+
+#[cfg(feature = "accent-folding")]
+impl FoldedLabel {
+    /// Fold the `label`.
+    #[must_use]
+    #[inline]
+    pub fn new(label: Label) -> Self {
+        let folded =
+            fold_accents(&String::from_utf8_lossy(&json::unescape(label.bytes()))).collect();
+
+        Self { label, folded }
+    }
+
+    /// The original label.
+    #[must_use]
+    #[inline(always)]
+    pub fn label(&self) -> &Label {
+        &self.label
+    }
+
+    /// Unescaped contents of the label with diacritics removed.
+    #[must_use]
+    #[inline(always)]
+    pub fn folded(&self) -> &str {
+        &self.folded
+    }
+
+    /// Returns whether the unescaped `key` is equal to the label after removing diacritics.
+    ///
+    /// ASCII keys are compared directly, since they have no diacritics.
+    /// Keys that are not valid UTF-8 cannot be folded and are also compared directly.
+    #[must_use]
+    #[inline]
+    pub fn is_match(&self, key: &[u8]) -> bool {
+        if key.is_ascii() {
+            return key == self.folded.as_bytes();
+        }
+        match std::str::from_utf8(key) {
+            Ok(key) => fold_accents(key).eq(self.folded.chars()),
+            Err(_) => key == self.folded.as_bytes(),
+        }
+    }
+}
+
+impl KeyPattern {
+    /// Create a pattern matching keys against a regular expression.
+    ///
+    /// # Errors
+    /// Returns an error if the `pattern` is not a valid regular expression
+    /// as accepted by [`regex::bytes::Regex::new`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::KeyPattern;
+    /// let pattern = KeyPattern::regex(r"^item_\d+$").unwrap();
+    ///
+    /// assert!(pattern.is_match(b"item_42"));
+    /// assert!(!pattern.is_match(b"item_"));
+    /// ```
+    #[cfg(feature = "regex")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "regex")))]
+    #[inline]
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Self::Regex)
+    }
+
+    /// Create a pattern matching keys with a custom `comparator` function.
+    ///
+    /// The `comparator` is called with the unescaped key and the unescaped `label`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::{KeyPattern, Label};
+    /// let pattern = KeyPattern::custom(Label::new("name"), |key, label| {
+    ///     key.eq_ignore_ascii_case(label)
+    /// });
+    ///
+    /// assert!(pattern.is_match(b"NaMe"));
+    /// assert!(!pattern.is_match(b"names"));
+    /// ```
+    #[inline]
+    pub fn custom<F>(label: Label, comparator: F) -> Self
+    where
+        F: Fn(&[u8], &[u8]) -> bool + Send + Sync + 'static,
+    {
+        Self::Custom {
+            label,
+            comparator: Arc::new(comparator),
+        }
+    }
+
+    /// Create a pattern matching keys starting with the `label`.
+    ///
+    /// This is the pattern of prefix selectors, e.g. `$..field_*`.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::{KeyPattern, Label};
+    /// let pattern = KeyPattern::prefix(Label::new("field_"));
+    ///
+    /// assert!(pattern.is_match(b"field_1"));
+    /// assert!(pattern.is_match(b"field_"));
+    /// assert!(!pattern.is_match(b"field"));
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn prefix(label: Label) -> Self {
+        Self::Prefix(label)
+    }
+
+    /// Create a pattern matching keys equal to the `label` regardless of their
+    /// Unicode normalization form.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::{KeyPattern, Label};
+    /// let pattern = KeyPattern::normalized(Label::new("caf\u{e9}"));
+    ///
+    /// assert!(pattern.is_match("caf\u{e9}".as_bytes()));
+    /// assert!(pattern.is_match("cafe\u{301}".as_bytes()));
+    /// assert!(!pattern.is_match(b"cafe"));
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unicode-normalization")))]
+    #[must_use]
+    #[inline]
+    pub fn normalized(label: Label) -> Self {
+        Self::Normalized(NormalizedLabel::new(label))
+    }
+
+    /// Create a pattern matching keys equal to the `label` regardless of diacritics.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use rsonpath_lib::query::{KeyPattern, Label};
+    /// let pattern = KeyPattern::accent_folded(Label::new("caf\u{e9}"));
+    ///
+    /// assert!(pattern.is_match(b"cafe"));
+    /// assert!(pattern.is_match("cafe\u{301}".as_bytes()));
+    /// assert!(pattern.is_match("caf\u{e8}".as_bytes()));
+    /// assert!(!pattern.is_match(b"Cafe"));
+    /// ```
+    #[cfg(feature = "accent-folding")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "accent-folding")))]
+    #[must_use]
+    #[inline]
+    pub fn accent_folded(label: Label) -> Self {
+        Self::AccentFolded(FoldedLabel::new(label))
+    }
+
+    /// Returns whether the unescaped `key` matches the pattern.
+    #[must_use]
+    #[inline]
+    pub fn is_match(&self, key: &[u8]) -> bool {
+        match *self {
+            Self::Custom {
+                ref label,
+                ref comparator,
+            } => comparator(key, &json::unescape(label.bytes())),
+            Self::Prefix(ref label) => key.starts_with(&json::unescape(label.bytes())),
+            #[cfg(feature = "regex")]
+            Self::Regex(ref regex) => regex.is_match(key),
+            #[cfg(feature = "unicode-normalization")]
+            Self::Normalized(ref label) => label.is_match(key),
+            #[cfg(feature = "accent-folding")]
+            Self::AccentFolded(ref label) => label.is_match(key),
+        }
+    }
+}
+
+impl Debug for KeyPattern {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Custom { ref label, .. } => f
+                .debug_struct("Custom")
+                .field("label", label)
+                .finish_non_exhaustive(),
+            Self::Prefix(ref label) => f.debug_tuple("Prefix").field(label).finish(),
+            #[cfg(feature = "regex")]
+            Self::Regex(ref regex) => f.debug_tuple("Regex").field(regex).finish(),
+            #[cfg(feature = "unicode-normalization")]
+            Self::Normalized(ref label) => f.debug_tuple("Normalized").field(label).finish(),
+            #[cfg(feature = "accent-folding")]
+            Self::AccentFolded(ref label) => f.debug_tuple("AccentFolded").field(label).finish(),
+        }
+    }
+}
+
+/// Custom patterns are equal only if they share the same comparator instance.
+impl PartialEq for KeyPattern {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match *self {
+            Self::Custom {
+                ref label,
+                ref comparator,
+            } => matches!(
+                other,
+                Self::Custom { label: other_label, comparator: other_comparator }
+                    if label == other_label && Arc::ptr_eq(comparator, other_comparator)
+            ),
+            Self::Prefix(ref label) => {
+                matches!(other, Self::Prefix(other_label) if label == other_label)
+            }
+            #[cfg(feature = "regex")]
+            Self::Regex(ref regex) => {
+                matches!(other, Self::Regex(other_regex) if regex.as_str() == other_regex.as_str())
+            }
+            #[cfg(feature = "unicode-normalization")]
+            Self::Normalized(ref label) => {
+                matches!(other, Self::Normalized(other_label) if label == other_label)
+            }
+            #[cfg(feature = "accent-folding")]
+            Self::AccentFolded(ref label) => {
+                matches!(other, Self::AccentFolded(other_label) if label == other_label)
+            }
+        }
+    }
+}
+
+impl Eq for KeyPattern {}
+
+impl Display for KeyPattern {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Custom { ref label, .. } => write!(f, "~custom('{}')", label.display()),
+            Self::Prefix(ref label) => write!(f, "~prefix('{}')", label.display()),
+            #[cfg(feature = "regex")]
+            Self::Regex(ref regex) => write!(f, "~'{}'", regex.as_str().replace('\'', "\\'")),
+            #[cfg(feature = "unicode-normalization")]
+            Self::Normalized(ref label) => write!(f, "~nfc('{}')", label.label().display()),
+            #[cfg(feature = "accent-folding")]
+            Self::AccentFolded(ref label) => write!(f, "~fold('{}')", label.label().display()),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Single selectors of a query, for building queries from structured input with [`Selector`].
+use super::{ArraySlice, FilterExpression, KeyPattern, Label};
+use std::num::NonZeroUsize;
+
+/// A single selector of a JSONPath query, without the selectors following it.
+///
+/// A sequence of selectors can be compiled directly with
+/// [`Automaton::from_selectors`](super::automaton::Automaton::from_selectors),
+/// without writing it as a query string and parsing it. The root selector `$` is implicit
+/// at the start of every sequence, so it has no variant.
+///
+/// Every variant corresponds to the [`JsonPathQueryNode`](super::JsonPathQueryNode) of the same name.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::query::{Label, Selector};
+/// let selectors = vec![
+///     Selector::Child(Label::new("a")),
+///     Selector::Descendant(Label::new("b")),
+///     Selector::AnyChild,
+///     Selector::ArrayIndex(0),
+/// ];
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selector {
+    /// Child selector with a label, e.g. `.a` or `['a']`.
+    Child(Label),
+    /// Child wildcard selector, `.*` or `[*]`.
+    AnyChild,
+    /// Descendant selector with a label, e.g. `..a` or `..['a']`.
+    Descendant(Label),
+    /// Descendant-or-self selector with a label, e.g. `...a` or `...['a']`.
+    DescendantOrSelf(Label),
+    /// Child selector with a key pattern, e.g. `[~'^a']`.
+    PatternChild(KeyPattern),
+    /// Descendant selector with a key pattern, e.g. `..[~'^a']`.
+    PatternDescendant(KeyPattern),
+    /// Filter selector, e.g. `[?(@.a == 1)]`.
+    Filter(FilterExpression),
+    /// Descendant filter selector, e.g. `..[?(@.a == 1)]`.
+    DescendantFilter(FilterExpression),
+    /// Member from end selector with a position starting from 1 for the last member, e.g. `[last()]`.
+    MemberFromEnd(NonZeroUsize),
+    /// Array index selector, e.g. `[0]`.
+    ArrayIndex(usize),
+    /// Array slice selector, e.g. `[1:5:2]`.
+    Slice(ArraySlice),
+    /// Descendant array slice selector, e.g. `..[1:5:2]`.
+    DescendantSlice(ArraySlice),
+}
@@ -0,0 +1,81 @@
+//! Selection of array elements by their indices with [`ArraySlice`].
+use std::fmt::{self, Display};
+use std::num::NonZeroUsize;
+
+/// Slice of the elements of an array, selected by a slice selector, e.g.
+/// [`JsonPathQueryNode::Slice`](super::JsonPathQueryNode::Slice).
+///
+/// Selects every element whose index is at least `start` and less than `end`,
+/// and whose distance from `start` is a multiple of `step`. All indices count from 0
+/// for the first element of the array, and the `end` can be omitted to select elements
+/// up to the end of the array. Negative indices and steps are not supported,
+/// since the length of an array is not known until it is closed.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::query::ArraySlice;
+/// # use std::num::NonZeroUsize;
+/// let slice = ArraySlice::new(1, Some(6), NonZeroUsize::new(2).unwrap());
+/// let selected: Vec<_> = (0..10).filter(|&i| slice.contains(i)).collect();
+///
+/// assert_eq!(selected, vec![1, 3, 5]);
+/// assert_eq!(slice.to_string(), "1:6:2");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArraySlice {
+    start: usize,
+    end: Option<usize>,
+    step: NonZeroUsize,
+}
+
+impl ArraySlice {
+    /// Create a slice selecting elements from `start` up to, but not including, `end`,
+    /// or to the end of the array if `end` is [`None`], every `step` elements.
+    #[must_use]
+    #[inline(always)]
+    pub fn new(start: usize, end: Option<usize>, step: NonZeroUsize) -> Self {
+        Self { start, end, step }
+    }
+
+    /// Index of the first selected element.
+    #[must_use]
+    #[inline(always)]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Index right after the last element that can be selected,
+    /// or [`None`] if the slice extends to the end of the array.
+    #[must_use]
+    #[inline(always)]
+    pub fn end(&self) -> Option<usize> {
+        self.end
+    }
+
+    /// Distance between consecutive selected elements.
+    #[must_use]
+    #[inline(always)]
+    pub fn step(&self) -> NonZeroUsize {
+        self.step
+    }
+
+    /// Returns whether the element at `index` is selected by the slice.
+    #[must_use]
+    #[inline]
+    pub fn contains(&self, index: usize) -> bool {
+        index >= self.start
+            && self.end.map_or(true, |end| index < end)
+            && (index - self.start) % self.step.get() == 0
+    }
+}
+
+impl Display for ArraySlice {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.start)?;
+        if let Some(end) = self.end {
+            write!(f, "{end}")?;
+        }
+        write!(f, ":{}", self.step)
+    }
+}
@@ -1,11 +1,225 @@
 //! Result types that can be returned by a JSONPath query engine.
 use crate::debug;
+use crate::json;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
 use std::fmt::{self, Display};
+use std::hash::Hasher;
+use std::ops::Range;
+
+#[cfg(feature = "arrow")]
+#[cfg_attr(docsrs, doc(cfg(feature = "arrow")))]
+pub mod arrow;
+#[cfg(feature = "csv")]
+#[cfg_attr(docsrs, doc(cfg(feature = "csv")))]
+pub mod csv;
+pub mod scalar;
+pub mod wire;
 
 /// Result that can be reported during query execution.
 pub trait QueryResult: Default + Display + PartialEq {
-    /// Report a match of the query.
-    fn report(&mut self, index: usize);
+    /// Report a match of the query at `index` in the input `bytes`.
+    ///
+    /// The `index` is either the index of the colon or comma preceding the matched value,
+    /// or an index from which the value can be found by skipping whitespace.
+    fn report(&mut self, bytes: &[u8], index: usize);
+
+    /// Parts of the [`MatchContext`] of every match that the engine has to track
+    /// and pass to [`report_in_context`](QueryResult::report_in_context).
+    ///
+    /// Nothing is tracked by default.
+    const TRACKING: Tracking = Tracking::NONE;
+
+    /// Report a match of the query at `index` in the input `bytes`, like [`report`](QueryResult::report),
+    /// together with the `context` of the match tracked by the engine.
+    ///
+    /// Engines report every match with this function, unless [nothing is tracked](QueryResult::TRACKING).
+    /// The default implementation ignores the `context` and forwards to [`report`](QueryResult::report).
+    #[inline(always)]
+    fn report_in_context(&mut self, bytes: &[u8], index: usize, _context: &MatchContext<'_>) {
+        self.report(bytes, index);
+    }
+
+    /// Returns whether the result is complete, i.e. reporting further
+    /// matches could not change it anymore.
+    ///
+    /// Engines check this after every reported match and stop
+    /// the execution as soon as it returns `true`. The default implementation
+    /// always returns `false`, so the entire document is processed.
+    #[must_use]
+    #[inline(always)]
+    fn is_complete(&self) -> bool {
+        false
+    }
+
+    /// Clear all matches reported thus far, returning the result to its initial state.
+    ///
+    /// This allows reusing a single result for many runs of
+    /// [`Engine::run_into`](crate::engine::Engine::run_into).
+    #[inline]
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Parts of the [`MatchContext`] of a match that can be tracked by the engine,
+/// requested by a [`QueryResult`] with [`QueryResult::TRACKING`].
+///
+/// Tracking is not free, e.g. head-skipping cannot be used when anything is tracked,
+/// so engines track only the requested parts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tracking {
+    depth: bool,
+    path: bool,
+    token_index: bool,
+}
+
+impl Tracking {
+    /// Nothing is tracked.
+    pub const NONE: Self = Self {
+        depth: false,
+        path: false,
+        token_index: false,
+    };
+
+    /// The [depth](MatchContext::depth) of every match is tracked.
+    pub const DEPTH: Self = Self {
+        depth: true,
+        path: false,
+        token_index: false,
+    };
+
+    /// The [path](MatchContext::path) to every match is tracked.
+    pub const PATH: Self = Self {
+        depth: false,
+        path: true,
+        token_index: false,
+    };
+
+    /// The [token index](MatchContext::token_index) of every match is tracked.
+    pub const TOKEN_INDEX: Self = Self {
+        depth: false,
+        path: false,
+        token_index: true,
+    };
+
+    /// Track all parts tracked by `self` or by `other`.
+    #[must_use]
+    #[inline(always)]
+    pub const fn union(self, other: Self) -> Self {
+        Self {
+            depth: self.depth || other.depth,
+            path: self.path || other.path,
+            token_index: self.token_index || other.token_index,
+        }
+    }
+
+    /// Whether nothing is tracked.
+    #[must_use]
+    #[inline(always)]
+    pub(crate) const fn is_none(self) -> bool {
+        !self.depth && !self.path && !self.token_index
+    }
+
+    /// Whether the path to every match is tracked.
+    #[must_use]
+    #[inline(always)]
+    pub(crate) const fn is_path_tracked(self) -> bool {
+        self.path
+    }
+
+    /// Whether the token index of every match is tracked.
+    #[must_use]
+    #[inline(always)]
+    pub(crate) const fn is_token_index_tracked(self) -> bool {
+        self.token_index
+    }
+}
+
+/// Step of a path tracked by the engine, leading from a value to one of its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PathStep {
+    /// Member of an object, reported by an engine at the given index, as in [`QueryResult::report`].
+    Member(usize),
+    /// Element of an array at the given position, starting from zero.
+    Element(usize),
+}
+
+/// Context of a match tracked by the engine during query execution,
+/// passed to [`QueryResult::report_in_context`].
+///
+/// Only the parts requested by [`QueryResult::TRACKING`] are guaranteed to be tracked,
+/// all other parts have unspecified values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchContext<'a> {
+    bytes: &'a [u8],
+    depth: usize,
+    path: &'a [PathStep],
+    token_index: usize,
+}
+
+impl<'a> MatchContext<'a> {
+    /// Context of the root value of a document.
+    pub(crate) const ROOT: MatchContext<'static> = MatchContext {
+        bytes: &[],
+        depth: 0,
+        path: &[],
+        token_index: 0,
+    };
+
+    pub(crate) fn new(
+        bytes: &'a [u8],
+        depth: usize,
+        path: &'a [PathStep],
+        token_index: usize,
+    ) -> Self {
+        Self {
+            bytes,
+            depth,
+            path,
+            token_index,
+        }
+    }
+
+    /// Depth of the matched value, i.e. the number of objects and arrays enclosing it,
+    /// so the root value is at depth 0, its direct children at depth 1, etc.
+    #[must_use]
+    #[inline(always)]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Path leading from the root of the document to the matched value.
+    ///
+    /// The array indices are counted by the engine, and the keys are read
+    /// from the document and decoded only when this function is called.
+    #[must_use]
+    #[inline]
+    pub fn path(&self) -> Vec<PathSegment> {
+        self.path
+            .iter()
+            .map(|step| match *step {
+                PathStep::Member(idx) => {
+                    let key = json::key_of_value(self.bytes, idx).unwrap_or_default();
+                    PathSegment::Key(String::from_utf8_lossy(&json::unescape(key)).into_owned())
+                }
+                PathStep::Element(index) => PathSegment::Index(index),
+            })
+            .collect()
+    }
+
+    /// Number of tokens preceding the matched value, i.e. of the structural characters
+    /// `{`, `}`, `[`, `]`, `:`, and `,` outside of strings, so the root value has index 0.
+    #[must_use]
+    #[inline(always)]
+    pub fn token_index(&self) -> usize {
+        self.token_index
+    }
+
+    /// Steps of the path leading from the root of the document to the matched value.
+    pub(crate) fn steps(&self) -> &'a [PathStep] {
+        self.path
+    }
 }
 
 /// Result informing on the number of values matching the executed query.
@@ -32,12 +246,373 @@ impl Display for CountResult {
 
 impl QueryResult for CountResult {
     #[inline(always)]
-    fn report(&mut self, _item: usize) {
+    fn report(&mut self, _bytes: &[u8], _item: usize) {
         debug!("Reporting result: {_item}");
         self.count += 1;
     }
 }
 
+/// Result summarizing the values matching the executed query,
+/// with their number and the range of indices at which they occur.
+///
+/// This is computed in constant memory, without collecting all indices
+/// like [`IndexResult`] does.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SummaryResult {
+    count: usize,
+    bounds: Option<(usize, usize)>,
+}
+
+impl SummaryResult {
+    /// Number of values matched by the executed query.
+    #[must_use]
+    #[inline(always)]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Smallest index of a match of the executed query,
+    /// or [`None`] if there were no matches.
+    #[must_use]
+    #[inline(always)]
+    pub fn first(&self) -> Option<usize> {
+        self.bounds.map(|(first, _)| first)
+    }
+
+    /// Largest index of a match of the executed query,
+    /// or [`None`] if there were no matches.
+    #[must_use]
+    #[inline(always)]
+    pub fn last(&self) -> Option<usize> {
+        self.bounds.map(|(_, last)| last)
+    }
+}
+
+impl Display for SummaryResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.bounds {
+            Some((first, last)) => write!(f, "{} [{first}..={last}]", self.count),
+            None => write!(f, "{}", self.count),
+        }
+    }
+}
+
+impl QueryResult for SummaryResult {
+    #[inline(always)]
+    fn report(&mut self, _bytes: &[u8], item: usize) {
+        debug!("Reporting result: {item}");
+        self.count += 1;
+        self.bounds = Some(match self.bounds {
+            Some((first, last)) => (first.min(item), last.max(item)),
+            None => (item, item),
+        });
+    }
+}
+
+/// Result computing aggregate statistics of the numbers matching the executed query.
+///
+/// Every match is parsed as a JSON number and folded into the minimum, maximum, and sum
+/// of all matched numbers, without collecting them. Matches that are not numbers,
+/// e.g. strings or objects, are only counted as skipped.
+///
+/// Integers fitting in an [`i64`] are summed exactly, all other numbers are summed
+/// as [`f64`] with compensated (Kahan-Babuška) summation, so that the rounding error
+/// of the sum does not grow with the number of matches. The minimum and maximum
+/// are kept as [`f64`], so they can be imprecise for integers larger than 2<sup>53</sup>.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+/// # use rsonpath_lib::query::JsonPathQuery;
+/// # use rsonpath_lib::result::NumericStatsResult;
+/// let query = JsonPathQuery::parse("$..price").unwrap();
+/// let mut contents = r#"[{"price": 10}, {"price": 2.5}, {"price": "n/a"}]"#.to_owned();
+/// let input = Input::new(&mut contents);
+/// let engine = RsonpathEngine::compile_query(&query).unwrap();
+///
+/// let result = engine.run::<NumericStatsResult>(&input).unwrap();
+///
+/// assert_eq!(result.count(), 2);
+/// assert_eq!(result.skipped(), 1);
+/// assert_eq!(result.min(), Some(2.5));
+/// assert_eq!(result.max(), Some(10.0));
+/// assert_eq!(result.sum(), 12.5);
+/// assert_eq!(result.mean(), Some(6.25));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NumericStatsResult {
+    count: usize,
+    skipped: usize,
+    bounds: Option<(f64, f64)>,
+    integer_sum: i128,
+    float_sum: f64,
+    float_compensation: f64,
+    has_floats: bool,
+}
+
+impl NumericStatsResult {
+    /// Number of matched numbers.
+    #[must_use]
+    #[inline(always)]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Number of matched values that were not numbers.
+    #[must_use]
+    #[inline(always)]
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Smallest matched number, or [`None`] if no numbers were matched.
+    #[must_use]
+    #[inline(always)]
+    pub fn min(&self) -> Option<f64> {
+        self.bounds.map(|(min, _)| min)
+    }
+
+    /// Largest matched number, or [`None`] if no numbers were matched.
+    #[must_use]
+    #[inline(always)]
+    pub fn max(&self) -> Option<f64> {
+        self.bounds.map(|(_, max)| max)
+    }
+
+    /// Sum of all matched numbers, zero if no numbers were matched.
+    #[must_use]
+    #[inline]
+    pub fn sum(&self) -> f64 {
+        self.integer_sum as f64 + (self.float_sum + self.float_compensation)
+    }
+
+    /// Exact sum of all matched numbers, if all of them were integers fitting in an [`i64`].
+    /// Returns [`None`] if any other number was matched.
+    #[must_use]
+    #[inline(always)]
+    pub fn integer_sum(&self) -> Option<i128> {
+        (!self.has_floats).then_some(self.integer_sum)
+    }
+
+    /// Arithmetic mean of the matched numbers, or [`None`] if no numbers were matched.
+    #[must_use]
+    #[inline]
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum() / self.count as f64)
+    }
+
+    fn add_float(&mut self, value: f64) {
+        // Kahan-Babuška (Neumaier) summation, correct also when `value` is larger than the sum.
+        let sum = self.float_sum + value;
+        if self.float_sum.abs() >= value.abs() {
+            self.float_compensation += (self.float_sum - sum) + value;
+        } else {
+            self.float_compensation += (value - sum) + self.float_sum;
+        }
+        self.float_sum = sum;
+        self.has_floats = true;
+    }
+}
+
+impl Display for NumericStatsResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} numbers, {} skipped", self.count, self.skipped)?;
+        if let (Some((min, max)), Some(mean)) = (self.bounds, self.mean()) {
+            write!(f, ", min {min}, max {max}, sum {}, mean {mean}", self.sum())?;
+        }
+        Ok(())
+    }
+}
+
+impl QueryResult for NumericStatsResult {
+    #[inline]
+    fn report(&mut self, bytes: &[u8], item: usize) {
+        debug!("Reporting result: {item}");
+        let value = json::value_at(bytes, item);
+        let number = if let Some(integer) = json::parse_number::<i64>(value) {
+            self.integer_sum += i128::from(integer);
+            integer as f64
+        } else if let Some(float) = json::parse_number::<f64>(value) {
+            self.add_float(float);
+            float
+        } else {
+            self.skipped += 1;
+            return;
+        };
+
+        self.count += 1;
+        self.bounds = Some(match self.bounds {
+            Some((min, max)) => (min.min(number), max.max(number)),
+            None => (number, number),
+        });
+    }
+}
+
+/// Result informing whether any value matches the executed query.
+///
+/// The result is complete after the first match is reported,
+/// so the engines stop execution at the earliest possible moment.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ExistsResult {
+    exists: bool,
+}
+
+impl ExistsResult {
+    /// Whether any value was matched by the executed query.
+    #[must_use]
+    #[inline(always)]
+    pub fn get(&self) -> bool {
+        self.exists
+    }
+}
+
+impl Display for ExistsResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.exists)
+    }
+}
+
+impl QueryResult for ExistsResult {
+    #[inline(always)]
+    fn report(&mut self, _bytes: &[u8], _item: usize) {
+        debug!("Reporting result: {_item}");
+        self.exists = true;
+    }
+
+    #[inline(always)]
+    fn is_complete(&self) -> bool {
+        self.exists
+    }
+}
+
+/// Result keeping only the span of the match at a given position,
+/// complete as soon as that match is reported.
+///
+/// Backs [`Engine::run_nth`](crate::engine::Engine::run_nth).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct NthResult {
+    remaining: usize,
+    span: Option<Range<usize>>,
+}
+
+impl NthResult {
+    /// Create a result for the match at position `n`, counting from zero.
+    pub(crate) fn new(n: usize) -> Self {
+        Self {
+            remaining: n,
+            span: None,
+        }
+    }
+
+    /// Span of the selected match, or [`None`] if fewer matches were reported.
+    pub(crate) fn into_span(self) -> Option<Range<usize>> {
+        self.span
+    }
+}
+
+impl Display for NthResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.span)
+    }
+}
+
+impl QueryResult for NthResult {
+    #[inline]
+    fn report(&mut self, bytes: &[u8], item: usize) {
+        debug!("Reporting result: {item}");
+        if self.span.is_some() {
+            return;
+        }
+        if self.remaining == 0 {
+            self.span = Some(json::value_span(bytes, item));
+        } else {
+            self.remaining -= 1;
+        }
+    }
+
+    #[inline(always)]
+    fn is_complete(&self) -> bool {
+        self.span.is_some()
+    }
+}
+
+/// Result counting the values matching the executed query at every depth
+/// of the document.
+///
+/// The depth of a value is the number of objects and arrays enclosing it,
+/// so the root value is at depth 0, its direct children at depth 1, etc.
+///
+/// The depth is [tracked](Tracking::DEPTH) by the engine and passed in the [`MatchContext`]
+/// of every match, so the document is not inspected again. A match reported with
+/// [`report`](QueryResult::report), without its context, is counted at depth 0.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+/// # use rsonpath_lib::query::JsonPathQuery;
+/// # use rsonpath_lib::result::DepthHistogramResult;
+/// let query = JsonPathQuery::parse("$..x").unwrap();
+/// let mut contents = r#"{"x": 1, "a": [{"x": 2}, {"x": 3}]}"#.to_owned();
+/// let input = Input::new(&mut contents);
+/// let engine = RsonpathEngine::compile_query(&query).unwrap();
+///
+/// let result = engine.run::<DepthHistogramResult>(&input).unwrap();
+///
+/// assert_eq!(result.get(), &[0, 1, 0, 2]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DepthHistogramResult {
+    counts: Vec<usize>,
+}
+
+impl DepthHistogramResult {
+    /// Number of matched values at every depth, indexed by the depth.
+    ///
+    /// The slice ends at the largest depth with any matches.
+    #[must_use]
+    #[inline(always)]
+    pub fn get(&self) -> &[usize] {
+        &self.counts
+    }
+}
+
+impl From<DepthHistogramResult> for Vec<usize> {
+    #[inline(always)]
+    fn from(result: DepthHistogramResult) -> Self {
+        result.counts
+    }
+}
+
+impl Display for DepthHistogramResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.counts)
+    }
+}
+
+impl QueryResult for DepthHistogramResult {
+    const TRACKING: Tracking = Tracking::DEPTH;
+
+    #[inline]
+    fn report(&mut self, bytes: &[u8], item: usize) {
+        self.report_in_context(bytes, item, &MatchContext::ROOT);
+    }
+
+    #[inline]
+    fn report_in_context(&mut self, _bytes: &[u8], item: usize, context: &MatchContext<'_>) {
+        debug!("Reporting result: {item}");
+        let depth = context.depth();
+        if self.counts.len() <= depth {
+            self.counts.resize(depth + 1, 0);
+        }
+        self.counts[depth] += 1;
+    }
+}
+
 /// Query result containing all indices of colons that constitute a
 /// match.
 #[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -70,8 +645,781 @@ impl Display for IndexResult {
 
 impl QueryResult for IndexResult {
     #[inline(always)]
-    fn report(&mut self, item: usize) {
+    fn report(&mut self, _bytes: &[u8], item: usize) {
         debug!("Reporting result: {item}");
         self.indices.push(item);
     }
 }
+
+/// Query result containing the spans of all values matching the executed query,
+/// i.e. the ranges of their indices in the input.
+///
+/// A span covers exactly the bytes of the value, without the key, colon, comma,
+/// or whitespace around it:
+/// - for strings, the span includes both the opening and the closing quote;
+/// - for objects and arrays, the span includes the opening and closing bracket;
+/// - for numbers, `true`, `false`, and `null`, the span covers the literal itself.
+///
+/// To redact the contents of a string while keeping the document valid JSON, overwrite
+/// only the bytes between the quotes, i.e. `span.start + 1..span.end - 1`. Overwriting
+/// them with characters that need no escaping, like `*`, never produces invalid JSON,
+/// even if the original string contained escape sequences. Other values have to be replaced
+/// with valid JSON values, e.g. with `null` padded with whitespace to the length of the span.
+///
+/// Spans are in the order in which the matches were reported. Spans of matches nested within
+/// other matches, e.g. for `$..a` on `{"a": {"a": 1}}`, lie within the spans of the outer matches.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+/// # use rsonpath_lib::query::JsonPathQuery;
+/// # use rsonpath_lib::result::SpanResult;
+/// let query = JsonPathQuery::parse("$..password").unwrap();
+/// let json = r#"{"user": {"password": "s3cr\"t", "id": 7}}"#;
+/// let mut contents = json.to_owned();
+/// let input = Input::new(&mut contents);
+/// let engine = RsonpathEngine::compile_query(&query).unwrap();
+///
+/// let result = engine.run::<SpanResult>(&input).unwrap();
+///
+/// let mut redacted = json.as_bytes().to_vec();
+/// for span in result.get() {
+///     redacted[span.start + 1..span.end - 1].fill(b'*');
+/// }
+/// assert_eq!(
+///     String::from_utf8(redacted).unwrap(),
+///     r#"{"user": {"password": "*******", "id": 7}}"#
+/// );
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct SpanResult {
+    spans: Vec<Range<usize>>,
+}
+
+impl SpanResult {
+    /// Spans of the matched values, in the order in which they were reported.
+    #[must_use]
+    #[inline(always)]
+    pub fn get(&self) -> &[Range<usize>] {
+        &self.spans
+    }
+}
+
+impl From<SpanResult> for Vec<Range<usize>> {
+    #[inline(always)]
+    fn from(result: SpanResult) -> Self {
+        result.spans
+    }
+}
+
+impl Display for SpanResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.spans)
+    }
+}
+
+impl QueryResult for SpanResult {
+    #[inline]
+    fn report(&mut self, bytes: &[u8], item: usize) {
+        debug!("Reporting result: {item}");
+        self.spans.push(json::value_span(bytes, item));
+    }
+}
+
+/// What a [`CappedSpanResult`] does with values longer than its limit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OversizedValues {
+    /// Leave the value out of the result entirely.
+    Skip,
+    /// Report the first bytes of the value up to the limit,
+    /// with the span flagged as [truncated](CappedSpan::is_truncated).
+    #[default]
+    Truncate,
+}
+
+/// Span of a value reported by a [`CappedSpanResult`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CappedSpan {
+    /// Range of indices of the value in the input, cut to the limit if the value is truncated.
+    pub span: Range<usize>,
+    /// Whether the value is longer than the limit and [`span`](CappedSpan::span)
+    /// covers only its prefix. A truncated span is in general not valid JSON.
+    pub is_truncated: bool,
+}
+
+/// Query result containing the spans of all values matching the executed query,
+/// like [`SpanResult`], but with a limit on the length of a single value.
+///
+/// Values longer than the limit are either skipped or truncated to the limit,
+/// as chosen with [`OversizedValues`]. Truncated spans are flagged, so that they cannot
+/// be mistaken for complete values. The limit only bounds the extracted slices &ndash;
+/// the end of every matched value still has to be found to compare its length to the limit.
+///
+/// The default result has no limit, so it reports the same spans as [`SpanResult`].
+/// To use a limit, create the result with [`CappedSpanResult::new`]
+/// and run the engine with [`Engine::run_into`](crate::engine::Engine::run_into).
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+/// # use rsonpath_lib::query::JsonPathQuery;
+/// # use rsonpath_lib::result::{CappedSpanResult, OversizedValues};
+/// let query = JsonPathQuery::parse("$..data").unwrap();
+/// let json = r#"[{"data": "short"}, {"data": "a very long blob"}]"#;
+/// let mut contents = json.to_owned();
+/// let input = Input::new(&mut contents);
+/// let engine = RsonpathEngine::compile_query(&query).unwrap();
+///
+/// let mut result = CappedSpanResult::new(8, OversizedValues::Truncate);
+/// engine.run_into(&input, &mut result).unwrap();
+///
+/// let (short, long) = (&result.get()[0], &result.get()[1]);
+/// assert_eq!(&json[short.span.clone()], r#""short""#);
+/// assert!(!short.is_truncated);
+/// assert_eq!(&json[long.span.clone()], r#""a very "#);
+/// assert!(long.is_truncated);
+///
+/// let mut result = CappedSpanResult::new(8, OversizedValues::Skip);
+/// engine.run_into(&input, &mut result).unwrap();
+///
+/// assert_eq!(result.get().len(), 1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CappedSpanResult {
+    spans: Vec<CappedSpan>,
+    max_value_bytes: usize,
+    oversized: OversizedValues,
+}
+
+impl CappedSpanResult {
+    /// Create an empty result reporting values of at most `max_value_bytes` bytes,
+    /// handling longer values as specified by `oversized`.
+    #[must_use]
+    #[inline(always)]
+    pub fn new(max_value_bytes: usize, oversized: OversizedValues) -> Self {
+        Self {
+            spans: vec![],
+            max_value_bytes,
+            oversized,
+        }
+    }
+
+    /// Spans of the matched values, in the order in which they were reported.
+    #[must_use]
+    #[inline(always)]
+    pub fn get(&self) -> &[CappedSpan] {
+        &self.spans
+    }
+}
+
+impl Default for CappedSpanResult {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new(usize::MAX, OversizedValues::default())
+    }
+}
+
+impl From<CappedSpanResult> for Vec<CappedSpan> {
+    #[inline(always)]
+    fn from(result: CappedSpanResult) -> Self {
+        result.spans
+    }
+}
+
+impl Display for CappedSpanResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        for (i, capped) in self.spans.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{:?}", capped.span)?;
+            if capped.is_truncated {
+                f.write_str(" (truncated)")?;
+            }
+        }
+        f.write_str("]")
+    }
+}
+
+impl QueryResult for CappedSpanResult {
+    #[inline]
+    fn report(&mut self, bytes: &[u8], item: usize) {
+        debug!("Reporting result: {item}");
+        let span = json::value_span(bytes, item);
+
+        if span.len() <= self.max_value_bytes {
+            self.spans.push(CappedSpan {
+                span,
+                is_truncated: false,
+            });
+        } else if self.oversized == OversizedValues::Truncate {
+            self.spans.push(CappedSpan {
+                span: span.start..span.start + self.max_value_bytes,
+                is_truncated: true,
+            });
+        }
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        self.spans.clear();
+    }
+}
+
+/// Segment of the path leading from the root of the document to a value.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PathSegment {
+    /// Key of an object member, with all JSON escape sequences decoded.
+    Key(String),
+    /// Position of an element in an array, starting from zero.
+    Index(usize),
+}
+
+impl Display for PathSegment {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(key) => {
+                f.write_str("['")?;
+                for c in key.chars() {
+                    match c {
+                        '\'' => f.write_str("\\'")?,
+                        '\\' => f.write_str("\\\\")?,
+                        _ => write!(f, "{c}")?,
+                    }
+                }
+                f.write_str("']")
+            }
+            Self::Index(index) => write!(f, "[{index}]"),
+        }
+    }
+}
+
+/// Query result containing the paths to all values matching the executed query,
+/// as sequences of [`PathSegment`]s.
+///
+/// The path of the root value is empty. Array indices are the actual positions
+/// of the elements in their arrays. The path is [tracked](Tracking::PATH) by the engine
+/// and passed in the [`MatchContext`] of every match, with the array indices counted
+/// by the engine, so only the keys on the path are read from the document.
+/// A match reported with [`report`](QueryResult::report), without its context,
+/// gets the empty path of the root.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+/// # use rsonpath_lib::query::JsonPathQuery;
+/// # use rsonpath_lib::result::{PathResult, PathSegment};
+/// let query = JsonPathQuery::parse("$..x").unwrap();
+/// let mut contents = r#"{"a": [{"x": 1}, {"y": 2, "x": 3}]}"#.to_owned();
+/// let input = Input::new(&mut contents);
+/// let engine = RsonpathEngine::compile_query(&query).unwrap();
+///
+/// let result = engine.run::<PathResult>(&input).unwrap();
+///
+/// assert_eq!(
+///     result.get()[1],
+///     vec![
+///         PathSegment::Key("a".to_owned()),
+///         PathSegment::Index(1),
+///         PathSegment::Key("x".to_owned())
+///     ]
+/// );
+/// assert_eq!(result.to_string(), "$['a'][0]['x']\n$['a'][1]['x']\n");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PathResult {
+    paths: Vec<Vec<PathSegment>>,
+}
+
+impl PathResult {
+    /// Paths to the matched values, in the order in which they were reported.
+    #[must_use]
+    #[inline(always)]
+    pub fn get(&self) -> &[Vec<PathSegment>] {
+        &self.paths
+    }
+}
+
+impl From<PathResult> for Vec<Vec<PathSegment>> {
+    #[inline(always)]
+    fn from(result: PathResult) -> Self {
+        result.paths
+    }
+}
+
+impl Display for PathResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for path in &self.paths {
+            f.write_str("$")?;
+            for segment in path {
+                write!(f, "{segment}")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl QueryResult for PathResult {
+    const TRACKING: Tracking = Tracking::PATH;
+
+    #[inline]
+    fn report(&mut self, bytes: &[u8], item: usize) {
+        self.report_in_context(bytes, item, &MatchContext::ROOT);
+    }
+
+    #[inline]
+    fn report_in_context(&mut self, _bytes: &[u8], item: usize, context: &MatchContext<'_>) {
+        debug!("Reporting result: {item}");
+        self.paths.push(context.path());
+    }
+}
+
+/// Query result containing the token indices of all values matching the executed query.
+///
+/// Tokens are the structural characters of the document outside of strings,
+/// i.e. `{`, `}`, `[`, `]`, `:`, and `,`. The token index of a value is the number
+/// of tokens preceding it, so the root value has index 0. Unlike byte offsets,
+/// token indices do not depend on whitespace, so the same match in a pretty-printed
+/// and a minified version of a document gets the same index. The token index is
+/// [tracked](Tracking::TOKEN_INDEX) by the engine and passed in the [`MatchContext`].
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+/// # use rsonpath_lib::query::JsonPathQuery;
+/// # use rsonpath_lib::result::TokenIndexResult;
+/// let query = JsonPathQuery::parse("$..x").unwrap();
+/// let engine = RsonpathEngine::compile_query(&query).unwrap();
+/// let mut minified = r#"{"a":[{"x":1},{"x":2}]}"#.to_owned();
+/// let mut pretty = "{\n  \"a\": [\n    { \"x\": 1 },\n    { \"x\": 2 }\n  ]\n}".to_owned();
+///
+/// let minified_result = engine.run::<TokenIndexResult>(&Input::new(&mut minified)).unwrap();
+/// let pretty_result = engine.run::<TokenIndexResult>(&Input::new(&mut pretty)).unwrap();
+///
+/// assert_eq!(minified_result.get(), &[5, 9]);
+/// assert_eq!(minified_result, pretty_result);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TokenIndexResult {
+    indices: Vec<usize>,
+}
+
+impl TokenIndexResult {
+    /// Token indices of the matched values, in the order in which they were reported.
+    #[must_use]
+    #[inline(always)]
+    pub fn get(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl From<TokenIndexResult> for Vec<usize> {
+    #[inline(always)]
+    fn from(result: TokenIndexResult) -> Self {
+        result.indices
+    }
+}
+
+impl Display for TokenIndexResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.indices)
+    }
+}
+
+impl QueryResult for TokenIndexResult {
+    const TRACKING: Tracking = Tracking::TOKEN_INDEX;
+
+    #[inline]
+    fn report(&mut self, bytes: &[u8], item: usize) {
+        self.report_in_context(bytes, item, &MatchContext::ROOT);
+    }
+
+    #[inline]
+    fn report_in_context(&mut self, _bytes: &[u8], item: usize, context: &MatchContext<'_>) {
+        debug!("Reporting result: {item}");
+        self.indices.push(context.token_index());
+    }
+}
+
+/// Query result containing the keys of all values matching the executed query.
+///
+/// The key of a match is the key of the object member that is the matched value.
+/// Array elements and the root of the document have no key. The key is found by going back
+/// from the reported index of the match, so nothing has to be [tracked](Tracking) by the engine.
+///
+/// Every key is available in two forms: [`keys_unescaped`](KeysResult::keys_unescaped)
+/// with all JSON escape sequences decoded, and [`keys_raw`](KeysResult::keys_raw)
+/// with the exact bytes of the document between the quotes of the key.
+/// Keys are in the order in which the matches were reported.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+/// # use rsonpath_lib::query::JsonPathQuery;
+/// # use rsonpath_lib::result::KeysResult;
+/// let query = JsonPathQuery::parse("$.person.*").unwrap();
+/// let mut contents = r#"{"person": {"name": "Ann", "\u0061ge": 42}}"#.to_owned();
+/// let input = Input::new(&mut contents);
+/// let engine = RsonpathEngine::compile_query(&query).unwrap();
+///
+/// let result = engine.run::<KeysResult>(&input).unwrap();
+///
+/// assert_eq!(result.keys_unescaped(), &[Some("name".to_owned()), Some("age".to_owned())]);
+/// assert_eq!(result.keys_raw(), &[Some(b"name".to_vec()), Some(br"\u0061ge".to_vec())]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct KeysResult {
+    keys: Vec<Option<String>>,
+    raw_keys: Vec<Option<Vec<u8>>>,
+}
+
+impl KeysResult {
+    /// Keys of the matched values with escape sequences decoded,
+    /// [`None`] for values that are not object members.
+    ///
+    /// Same as [`keys_unescaped`](KeysResult::keys_unescaped).
+    #[must_use]
+    #[inline(always)]
+    pub fn get(&self) -> &[Option<String>] {
+        &self.keys
+    }
+
+    /// Keys of the matched values with all JSON escape sequences decoded,
+    /// [`None`] for values that are not object members.
+    ///
+    /// Invalid escape sequences, like unpaired UTF-16 surrogates, are kept as written.
+    #[must_use]
+    #[inline(always)]
+    pub fn keys_unescaped(&self) -> &[Option<String>] {
+        &self.keys
+    }
+
+    /// Keys of the matched values exactly as they are written in the document,
+    /// without the enclosing quotes, [`None`] for values that are not object members.
+    ///
+    /// The bytes are not decoded in any way, so escape sequences like `\u0061`
+    /// are kept as written, and the key can be written back to reproduce the document.
+    #[must_use]
+    #[inline(always)]
+    pub fn keys_raw(&self) -> &[Option<Vec<u8>>] {
+        &self.raw_keys
+    }
+}
+
+impl From<KeysResult> for Vec<Option<String>> {
+    #[inline(always)]
+    fn from(result: KeysResult) -> Self {
+        result.keys
+    }
+}
+
+impl Display for KeysResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.keys)
+    }
+}
+
+impl QueryResult for KeysResult {
+    #[inline]
+    fn report(&mut self, bytes: &[u8], item: usize) {
+        debug!("Reporting result: {item}");
+        let raw_key = json::key_of_value(bytes, item);
+        let key = raw_key.map(|key| String::from_utf8_lossy(&json::unescape(key)).into_owned());
+        self.keys.push(key);
+        self.raw_keys.push(raw_key.map(<[u8]>::to_vec));
+    }
+}
+
+/// Result forwarding every match to all of the results in a tuple,
+/// allowing a single run of the engine to produce many results at once.
+///
+/// Tuples of two to four results are supported, and nesting them allows any number of results.
+/// The combined result is complete only when all of its components are complete,
+/// and it is displayed as its components, one per line.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+/// # use rsonpath_lib::query::JsonPathQuery;
+/// # use rsonpath_lib::result::{CountResult, FanOutResult, IndexResult};
+/// let query = JsonPathQuery::parse("$..x").unwrap();
+/// let mut contents = r#"{"a": {"x": 1}, "b": [{"x": 2}]}"#.to_owned();
+/// let input = Input::new(&mut contents);
+/// let engine = RsonpathEngine::compile_query(&query).unwrap();
+///
+/// let result = engine
+///     .run::<FanOutResult<(CountResult, IndexResult)>>(&input)
+///     .unwrap();
+/// let (count, indices) = result.get();
+///
+/// assert_eq!(count.get(), 2);
+/// assert_eq!(indices.get(), &[10, 26]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FanOutResult<T> {
+    results: T,
+}
+
+impl<T> FanOutResult<T> {
+    /// Get the tuple of all component results.
+    #[must_use]
+    #[inline(always)]
+    pub fn get(&self) -> &T {
+        &self.results
+    }
+
+    /// Consume the result and return the tuple of all component results.
+    #[must_use]
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        self.results
+    }
+}
+
+macro_rules! fan_out_result {
+    ($first:ident: $first_idx:tt $(, $rest:ident: $idx:tt)+) => {
+        impl<$first: Display $(, $rest: Display)+> Display for FanOutResult<($first $(, $rest)+)> {
+            #[inline]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.results.$first_idx)?;
+                $(write!(f, "\n{}", self.results.$idx)?;)+
+                Ok(())
+            }
+        }
+
+        impl<$first: QueryResult $(, $rest: QueryResult)+> QueryResult
+            for FanOutResult<($first $(, $rest)+)>
+        {
+            const TRACKING: Tracking = $first::TRACKING $(.union($rest::TRACKING))+;
+
+            #[inline(always)]
+            fn report(&mut self, bytes: &[u8], index: usize) {
+                self.results.$first_idx.report(bytes, index);
+                $(self.results.$idx.report(bytes, index);)+
+            }
+
+            #[inline(always)]
+            fn report_in_context(&mut self, bytes: &[u8], index: usize, context: &MatchContext<'_>) {
+                self.results.$first_idx.report_in_context(bytes, index, context);
+                $(self.results.$idx.report_in_context(bytes, index, context);)+
+            }
+
+            #[inline(always)]
+            fn is_complete(&self) -> bool {
+                self.results.$first_idx.is_complete() $(&& self.results.$idx.is_complete())+
+            }
+
+            #[inline]
+            fn reset(&mut self) {
+                self.results.$first_idx.reset();
+                $(self.results.$idx.reset();)+
+            }
+        }
+    };
+}
+
+fan_out_result!(A: 0, B: 1);
+fan_out_result!(A: 0, B: 1, C: 2);
+fan_out_result!(A: 0, B: 1, C: 2, D: 3);
+
+/// Result wrapping another result and passing to it only the first match of every distinct value.
+///
+/// Two matches have the same value if the bytes of their values are identical, i.e. the bytes
+/// of their spans as in [`SpanResult`]. No normalization is done, so e.g. `{"a":1}` and `{"a": 1}`,
+/// or `1` and `1.0`, are distinct values. Unlike counting distinct values, the wrapped result
+/// still gets the positions of the matches, only repeats of an earlier value are suppressed.
+///
+/// Only a 64-bit hash of every distinct value is stored, so the memory used is proportional
+/// to the number of distinct values, not to their size or to the number of matches.
+/// Two distinct values with the same hash are treated as the same value, which is
+/// extremely unlikely unless there are billions of distinct values. The value of every match
+/// is hashed in full, so the end of every matched value has to be found, even if the wrapped
+/// result does not inspect the values.
+///
+/// # Examples
+/// ```rust
+/// # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+/// # use rsonpath_lib::query::JsonPathQuery;
+/// # use rsonpath_lib::result::{DedupResult, IndexResult};
+/// let query = JsonPathQuery::parse("$..x").unwrap();
+/// let mut contents = r#"[{"x": [1]}, {"x": 2}, {"x": [1]}, {"x": [ 1 ]}]"#.to_owned();
+/// let input = Input::new(&mut contents);
+/// let engine = RsonpathEngine::compile_query(&query).unwrap();
+///
+/// let result = engine.run::<DedupResult<IndexResult>>(&input).unwrap();
+///
+/// assert_eq!(result.get().get(), &[5, 17, 39]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DedupResult<R> {
+    result: R,
+    seen: HashSet<u64>,
+}
+
+impl<R> DedupResult<R> {
+    /// Get the wrapped result.
+    #[must_use]
+    #[inline(always)]
+    pub fn get(&self) -> &R {
+        &self.result
+    }
+
+    /// Consume the result and return the wrapped result.
+    #[must_use]
+    #[inline(always)]
+    pub fn into_inner(self) -> R {
+        self.result
+    }
+
+    /// Number of distinct values matched so far.
+    #[must_use]
+    #[inline(always)]
+    pub fn distinct_values(&self) -> usize {
+        self.seen.len()
+    }
+}
+
+impl<R: Display> Display for DedupResult<R> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.result)
+    }
+}
+
+impl<R> DedupResult<R> {
+    /// Whether the value at `index` was not matched before, remembering it as matched.
+    fn is_first_occurrence(&mut self, bytes: &[u8], index: usize) -> bool {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(json::value_at(bytes, index));
+
+        let is_first = self.seen.insert(hasher.finish());
+        if !is_first {
+            debug!("Suppressing repeated value at {index}");
+        }
+
+        is_first
+    }
+}
+
+impl<R: QueryResult> QueryResult for DedupResult<R> {
+    const TRACKING: Tracking = R::TRACKING;
+
+    #[inline]
+    fn report(&mut self, bytes: &[u8], index: usize) {
+        if self.is_first_occurrence(bytes, index) {
+            self.result.report(bytes, index);
+        }
+    }
+
+    #[inline]
+    fn report_in_context(&mut self, bytes: &[u8], index: usize, context: &MatchContext<'_>) {
+        if self.is_first_occurrence(bytes, index) {
+            self.result.report_in_context(bytes, index, context);
+        }
+    }
+
+    #[inline(always)]
+    fn is_complete(&self) -> bool {
+        self.result.is_complete()
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        self.result.reset();
+        self.seen.clear();
+    }
+}
+
+/// Value returned together with the tag given to
+/// [`Engine::run_tagged`](crate::engine::Engine::run_tagged).
+///
+/// The tag is opaque to the engines and returned unchanged, so it can carry anything
+/// needed to correlate the value back to its input, like an identifier or a timestamp,
+/// also when runs are reordered or executed on many threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tagged<T, V> {
+    tag: T,
+    value: V,
+}
+
+impl<T, V> Tagged<T, V> {
+    /// Attach the `tag` to the `value`.
+    #[must_use]
+    #[inline(always)]
+    pub fn new(tag: T, value: V) -> Self {
+        Self { tag, value }
+    }
+
+    /// The tag attached to the value.
+    #[must_use]
+    #[inline(always)]
+    pub fn tag(&self) -> &T {
+        &self.tag
+    }
+
+    /// The tagged value.
+    #[must_use]
+    #[inline(always)]
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    /// Consume the tagged value and return the tag and the value.
+    #[must_use]
+    #[inline(always)]
+    pub fn into_parts(self) -> (T, V) {
+        (self.tag, self.value)
+    }
+}
+
+/// Result of [`MainEngine::run_with_consumption`](crate::engine::main::MainEngine::run_with_consumption)
+/// together with the information whether the whole document was processed.
+///
+/// A result with no matches is conclusive only if the document was fully consumed,
+/// since otherwise the engine could have stopped before reaching a match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Consumed<R> {
+    result: R,
+    is_fully_consumed: bool,
+}
+
+impl<R> Consumed<R> {
+    pub(crate) fn new(result: R, is_fully_consumed: bool) -> Self {
+        Self {
+            result,
+            is_fully_consumed,
+        }
+    }
+
+    /// The matches of the query.
+    #[must_use]
+    #[inline(always)]
+    pub fn result(&self) -> &R {
+        &self.result
+    }
+
+    /// Take the matches of the query, discarding the information on consumption.
+    #[must_use]
+    #[inline(always)]
+    pub fn into_result(self) -> R {
+        self.result
+    }
+
+    /// Whether the engine reached the end of the document with all of its objects
+    /// and arrays closed, as opposed to stopping early because the result was
+    /// [complete](QueryResult::is_complete).
+    #[must_use]
+    #[inline(always)]
+    pub fn is_fully_consumed(&self) -> bool {
+        self.is_fully_consumed
+    }
+}
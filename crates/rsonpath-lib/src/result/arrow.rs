@@ -0,0 +1,212 @@
+//! Results collecting matched values directly into [Apache Arrow](https://arrow.apache.org/) arrays.
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+//! use rsonpath_lib::query::JsonPathQuery;
+//! use rsonpath_lib::result::arrow::ArrowResult;
+//! use arrow_array::{builder::Int64Builder, Array, Int64Array};
+//! # use std::error::Error;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let query = JsonPathQuery::parse("$..id")?;
+//! let mut contents = r#"[{"id": 1}, {"id": null}, {"id": 3}]"#.to_owned();
+//! let input = Input::new(&mut contents);
+//! let engine = RsonpathEngine::compile_query(&query)?;
+//!
+//! let array = engine.run::<ArrowResult<Int64Builder>>(&input)?.finish()?;
+//! let array = array.as_any().downcast_ref::<Int64Array>().unwrap();
+//!
+//! assert_eq!(array, &Int64Array::from(vec![Some(1), None, Some(3)]));
+//! # Ok(())
+//! # }
+//! ```
+use super::QueryResult;
+use crate::{debug, json};
+use arrow_array::builder::{
+    ArrayBuilder, BooleanBuilder, Float64Builder, Int32Builder, Int64Builder, StringBuilder,
+    UInt64Builder,
+};
+use arrow_array::ArrayRef;
+use std::fmt::{self, Display};
+use thiserror::Error;
+
+/// Arrow array builder that can be filled with matched JSON values.
+///
+/// Implemented for builders of primitive types that have a natural JSON representation.
+/// JSON `null` is always appended as an Arrow null.
+pub trait JsonArrayBuilder: ArrayBuilder + Default {
+    /// Name of the Arrow type built, used in error messages.
+    const TYPE_NAME: &'static str;
+
+    /// Append the raw bytes of a non-null JSON `value`.
+    ///
+    /// Returns `false` if the value cannot be represented in the built Arrow type,
+    /// in which case nothing is appended.
+    fn append_json(&mut self, value: &[u8]) -> bool;
+
+    /// Append an Arrow null.
+    fn append_json_null(&mut self);
+}
+
+/// Error raised when a matched value does not fit the declared Arrow type.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("matched value `{value}` at position {idx} cannot be converted to Arrow type {expected}")]
+pub struct ArrowTypeMismatchError {
+    /// Position at which the value was reported by the engine.
+    pub idx: usize,
+    /// Raw JSON of the value, lossily converted to UTF-8.
+    pub value: String,
+    /// Name of the Arrow type that was expected.
+    pub expected: &'static str,
+}
+
+/// Result collecting all matched values into an Arrow array builder.
+///
+/// Each match is appended to the builder `B`, in the order of the matches
+/// in the document. Conversion stops at the first value that does not fit
+/// the Arrow type, and the error is returned from [`ArrowResult::finish`].
+/// Since all later values would be discarded, the engines stop at that point as well.
+#[derive(Debug, Default)]
+pub struct ArrowResult<B> {
+    builder: B,
+    error: Option<ArrowTypeMismatchError>,
+}
+
+impl<B: JsonArrayBuilder> ArrowResult<B> {
+    /// Number of values appended thus far.
+    #[must_use]
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.builder.len()
+    }
+
+    /// Returns whether there were no values appended.
+    #[must_use]
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.builder.is_empty()
+    }
+
+    /// Finish building and return the Arrow array with all matched values.
+    ///
+    /// # Errors
+    /// Returns [`ArrowTypeMismatchError`] describing the first matched value
+    /// that could not be converted to the Arrow type of the builder.
+    #[inline]
+    pub fn finish(mut self) -> Result<ArrayRef, ArrowTypeMismatchError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.builder.finish()),
+        }
+    }
+}
+
+impl<B: JsonArrayBuilder> PartialEq for ArrowResult<B> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.error == other.error
+            && self.builder.finish_cloned().as_ref() == other.builder.finish_cloned().as_ref()
+    }
+}
+
+impl<B: JsonArrayBuilder> Display for ArrowResult<B> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            Some(error) => write!(f, "{error}"),
+            None => write!(f, "{:?}", self.builder.finish_cloned()),
+        }
+    }
+}
+
+impl<B: JsonArrayBuilder> QueryResult for ArrowResult<B> {
+    #[inline]
+    fn report(&mut self, bytes: &[u8], item: usize) {
+        debug!("Reporting result: {item}");
+        if self.error.is_some() {
+            return;
+        }
+
+        let value = json::value_at(bytes, item);
+        if value == b"null" {
+            self.builder.append_json_null();
+        } else if !self.builder.append_json(value) {
+            self.error = Some(ArrowTypeMismatchError {
+                idx: item,
+                value: String::from_utf8_lossy(value).into_owned(),
+                expected: B::TYPE_NAME,
+            });
+        }
+    }
+
+    #[inline(always)]
+    fn is_complete(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+macro_rules! numeric_builder {
+    ($builder:ty, $name:literal) => {
+        impl JsonArrayBuilder for $builder {
+            const TYPE_NAME: &'static str = $name;
+
+            #[inline]
+            fn append_json(&mut self, value: &[u8]) -> bool {
+                json::parse_number(value)
+                    .map(|x| self.append_value(x))
+                    .is_some()
+            }
+
+            #[inline(always)]
+            fn append_json_null(&mut self) {
+                self.append_null()
+            }
+        }
+    };
+}
+
+numeric_builder!(Int32Builder, "Int32");
+numeric_builder!(Int64Builder, "Int64");
+numeric_builder!(UInt64Builder, "UInt64");
+numeric_builder!(Float64Builder, "Float64");
+
+impl JsonArrayBuilder for BooleanBuilder {
+    const TYPE_NAME: &'static str = "Boolean";
+
+    #[inline]
+    fn append_json(&mut self, value: &[u8]) -> bool {
+        match value {
+            b"true" => self.append_value(true),
+            b"false" => self.append_value(false),
+            _ => return false,
+        }
+        true
+    }
+
+    #[inline(always)]
+    fn append_json_null(&mut self) {
+        self.append_null()
+    }
+}
+
+impl JsonArrayBuilder for StringBuilder {
+    const TYPE_NAME: &'static str = "Utf8";
+
+    #[inline]
+    fn append_json(&mut self, value: &[u8]) -> bool {
+        let contents = match value {
+            [b'"', contents @ .., b'"'] => json::unescape(contents),
+            _ => return false,
+        };
+
+        std::str::from_utf8(&contents)
+            .map(|s| self.append_value(s))
+            .is_ok()
+    }
+
+    #[inline(always)]
+    fn append_json_null(&mut self) {
+        self.append_null()
+    }
+}
@@ -0,0 +1,162 @@
+//! Extraction of tabular data from matches of queries, written as CSV rows.
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::engine::{Compiler, Input, RsonpathEngine};
+//! use rsonpath_lib::query::JsonPathQuery;
+//! use rsonpath_lib::result::csv::CsvExtractor;
+//! # use std::error::Error;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let records = JsonPathQuery::parse("$.people.*")?;
+//! let name = JsonPathQuery::parse("$.name")?;
+//! let city = JsonPathQuery::parse("$..city")?;
+//! let mut contents = r#"{"people": [
+//!     {"name": "Smith, \"Jo\"", "address": {"city": "Lyon"}},
+//!     {"name": "Kim", "age": 42}
+//! ]}"#
+//! .to_owned();
+//! let input = Input::new(&mut contents);
+//!
+//! let extractor = CsvExtractor::new(
+//!     RsonpathEngine::compile_query(&records)?,
+//!     vec![
+//!         RsonpathEngine::compile_query(&name)?,
+//!         RsonpathEngine::compile_query(&city)?,
+//!     ],
+//! );
+//! let mut csv = vec![];
+//! let rows = extractor.write_rows(&input, &mut csv)?;
+//!
+//! assert_eq!(rows, 2);
+//! assert_eq!(String::from_utf8(csv)?, "\"Smith, \"\"Jo\"\"\",Lyon\nKim,\n");
+//! # Ok(())
+//! # }
+//! ```
+use super::{IndexResult, QueryResult};
+use crate::engine::{error::EngineError, Engine, Input};
+use crate::{debug, json};
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+use thiserror::Error;
+
+/// Error raised while extracting CSV rows.
+#[derive(Debug, Error)]
+pub enum CsvError {
+    /// One of the queries could not be executed on the input.
+    #[error(transparent)]
+    Engine(#[from] EngineError),
+    /// The rows could not be written.
+    #[error("failed to write a CSV row: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Extractor turning every match of a record query into a CSV row,
+/// with columns given by matches of column queries within the record.
+///
+/// The column queries are executed separately on every matched record, with the record
+/// as the root of the document, so `$` refers to the record itself. The first match of each
+/// column query becomes the value of the column. If a column query has no match
+/// in a record, the column is left empty. In particular, all columns of records that are
+/// not objects or arrays are empty, since the root of an atomic document is never matched.
+///
+/// Strings are written with their quotes removed and escape sequences decoded.
+/// All other values, including objects and arrays, are written as their raw JSON.
+/// Fields containing commas, double quotes, or line breaks are enclosed in double quotes,
+/// with double quotes inside doubled, as described in RFC 4180. Rows are terminated with `\n`.
+#[derive(Debug)]
+pub struct CsvExtractor<E> {
+    records: E,
+    columns: Vec<E>,
+}
+
+impl<E: Engine> CsvExtractor<E> {
+    /// Create an extractor producing a row for every match of the `records` engine,
+    /// with a column for each of the `columns` engines.
+    #[must_use]
+    #[inline(always)]
+    pub fn new(records: E, columns: Vec<E>) -> Self {
+        Self { records, columns }
+    }
+
+    /// Write a row for every record matched in the `input` into the `writer`,
+    /// in the order of the records in the document.
+    ///
+    /// Returns the number of written rows.
+    ///
+    /// # Errors
+    /// [`CsvError::Engine`] if the record or column queries fail on malformed JSON,
+    /// and [`CsvError::Io`] if writing to the `writer` fails.
+    /// Rows written before the error remain in the `writer`.
+    #[inline]
+    pub fn write_rows<W: Write>(&self, input: &Input, writer: &mut W) -> Result<usize, CsvError> {
+        let records = self.records.run::<IndexResult>(input)?;
+
+        for &idx in records.get() {
+            let mut record = json::value_at(input, idx).to_vec();
+            let record_input = Input::new_bytes(&mut record);
+
+            for (i, column) in self.columns.iter().enumerate() {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                let first = column.run::<FirstIndexResult>(&record_input)?;
+                if let Some(first_idx) = first.idx {
+                    write_field(writer, json::value_at(&record_input, first_idx))?;
+                }
+            }
+            writer.write_all(b"\n")?;
+        }
+
+        Ok(records.get().len())
+    }
+}
+
+fn write_field<W: Write>(writer: &mut W, value: &[u8]) -> io::Result<()> {
+    let contents = match value {
+        [b'"', string @ .., b'"'] => json::unescape(string),
+        _ => value.into(),
+    };
+
+    if contents
+        .iter()
+        .any(|b| matches!(b, b',' | b'"' | b'\n' | b'\r'))
+    {
+        writer.write_all(b"\"")?;
+        for (i, part) in contents.split(|&b| b == b'"').enumerate() {
+            if i > 0 {
+                writer.write_all(b"\"\"")?;
+            }
+            writer.write_all(part)?;
+        }
+        writer.write_all(b"\"")
+    } else {
+        writer.write_all(&contents)
+    }
+}
+
+/// Result remembering only the first match, complete as soon as it is reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct FirstIndexResult {
+    idx: Option<usize>,
+}
+
+impl Display for FirstIndexResult {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.idx)
+    }
+}
+
+impl QueryResult for FirstIndexResult {
+    #[inline(always)]
+    fn report(&mut self, _bytes: &[u8], item: usize) {
+        debug!("Reporting result: {item}");
+        self.idx.get_or_insert(item);
+    }
+
+    #[inline(always)]
+    fn is_complete(&self) -> bool {
+        self.idx.is_some()
+    }
+}
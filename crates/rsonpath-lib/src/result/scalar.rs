@@ -0,0 +1,125 @@
+//! Conversion of single matched values into typed Rust values.
+//!
+//! Backs [`Engine::get_scalar`](crate::engine::Engine::get_scalar).
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+//! use rsonpath_lib::query::JsonPathQuery;
+//! use rsonpath_lib::result::scalar::ScalarError;
+//! # use std::error::Error;
+//!
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let mut contents = r#"{"server": {"host": "localhost", "port": 8080, "tls": false}}"#.to_owned();
+//! let input = Input::new(&mut contents);
+//! let host = JsonPathQuery::parse("$.server.host")?;
+//! let port = JsonPathQuery::parse("$.server.port")?;
+//! let client = JsonPathQuery::parse("$.client")?;
+//! let host = RsonpathEngine::compile_query(&host)?;
+//! let port = RsonpathEngine::compile_query(&port)?;
+//! let client = RsonpathEngine::compile_query(&client)?;
+//!
+//! assert_eq!(host.get_scalar::<String>(&input)?, "localhost");
+//! assert_eq!(port.get_scalar::<u64>(&input)?, 8080);
+//!
+//! let error = port.get_scalar::<bool>(&input).unwrap_err();
+//! assert_eq!(error.to_string(), "matched value `8080` at position 41 is not of type bool");
+//! assert!(matches!(client.get_scalar::<i64>(&input), Err(ScalarError::NoMatch)));
+//! # Ok(())
+//! # }
+//! ```
+use crate::engine::error::EngineError;
+use crate::json;
+use std::ops::Range;
+use thiserror::Error;
+
+/// Rust type that a matched JSON scalar can be converted to.
+///
+/// Implemented for [`i64`], [`u64`], and [`f64`] from JSON numbers, [`bool`] from `true`
+/// and `false`, and [`String`] from JSON strings, with all escape sequences decoded.
+/// No other conversions are made, so `"42"` is not an [`i64`], and `null` is none of the types.
+pub trait JsonScalar: Sized {
+    /// Name of the type, used in error messages.
+    const TYPE_NAME: &'static str;
+
+    /// Convert the raw bytes of a matched JSON `value`.
+    ///
+    /// Returns [`None`] if the value does not represent a value of this type.
+    fn from_json(value: &[u8]) -> Option<Self>;
+}
+
+/// Error raised when a single matched value cannot be extracted.
+#[derive(Debug, Error)]
+pub enum ScalarError {
+    /// The query could not be executed on the input.
+    #[error(transparent)]
+    Engine(#[from] EngineError),
+    /// The query has no matches in the input.
+    #[error("the query has no matches")]
+    NoMatch,
+    /// The matched value does not represent a value of the requested type.
+    #[error("matched value `{value}` at position {} is not of type {expected}", .span.start)]
+    TypeMismatch {
+        /// Span of the value in the input.
+        span: Range<usize>,
+        /// Raw JSON of the value, lossily converted to UTF-8.
+        value: String,
+        /// Name of the requested type.
+        expected: &'static str,
+    },
+}
+
+macro_rules! number_scalar {
+    ($ty:ty, $name:literal) => {
+        impl JsonScalar for $ty {
+            const TYPE_NAME: &'static str = $name;
+
+            #[inline(always)]
+            fn from_json(value: &[u8]) -> Option<Self> {
+                json::parse_number(value)
+            }
+        }
+    };
+}
+
+number_scalar!(i64, "i64");
+number_scalar!(u64, "u64");
+number_scalar!(f64, "f64");
+
+impl JsonScalar for bool {
+    const TYPE_NAME: &'static str = "bool";
+
+    #[inline]
+    fn from_json(value: &[u8]) -> Option<Self> {
+        match value {
+            b"true" => Some(true),
+            b"false" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl JsonScalar for String {
+    const TYPE_NAME: &'static str = "String";
+
+    #[inline]
+    fn from_json(value: &[u8]) -> Option<Self> {
+        match value {
+            [b'"', contents @ .., b'"'] => {
+                Self::from_utf8(json::unescape(contents).into_owned()).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Convert the value at `span` in `bytes` to `T`.
+pub(crate) fn convert<T: JsonScalar>(bytes: &[u8], span: Range<usize>) -> Result<T, ScalarError> {
+    let value = &bytes[span.clone()];
+
+    T::from_json(value).ok_or_else(|| ScalarError::TypeMismatch {
+        value: String::from_utf8_lossy(value).into_owned(),
+        span,
+        expected: T::TYPE_NAME,
+    })
+}
@@ -0,0 +1,209 @@
+//! Compact binary format of results, for storing them or sending them to other processes.
+//!
+//! The [`IndexResult`] and [`SpanResult`] can be encoded with their `to_bytes` methods
+//! and decoded with `from_bytes`. The encoding starts with a header:
+//! - the four bytes `RSPR`, identifying the encoding;
+//! - one byte with the [`FORMAT_VERSION`];
+//! - one byte with the kind of the result, `0` for indices and `1` for spans.
+//!
+//! After the header comes the number of matches, followed by the index of every match,
+//! or by the start and the length of every span, all as unsigned LEB128 integers.
+//! LEB128 encodes integers as a sequence of bytes with 7 bits of the integer each,
+//! least significant first, so the encoding does not depend on the endianness or the word size
+//! of the platform, and small integers take up a single byte.
+//!
+//! Decoding fails with a [`WireFormatError`] if the bytes were encoded with a different version
+//! of the format, which will be the case after every change to it, or are not a valid encoding
+//! of the requested kind of result.
+//!
+//! # Examples
+//! ```rust
+//! # use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+//! # use rsonpath_lib::query::JsonPathQuery;
+//! # use rsonpath_lib::result::{IndexResult, SpanResult};
+//! # use rsonpath_lib::result::wire::WireFormatError;
+//! let query = JsonPathQuery::parse("$..a").unwrap();
+//! let mut contents = r#"{"a": 1, "b": {"a": [2]}}"#.to_owned();
+//! let input = Input::new(&mut contents);
+//! let engine = RsonpathEngine::compile_query(&query).unwrap();
+//! let result = engine.run::<IndexResult>(&input).unwrap();
+//!
+//! let bytes = result.to_bytes();
+//!
+//! assert_eq!(IndexResult::from_bytes(&bytes), Ok(result));
+//! assert_eq!(SpanResult::from_bytes(&bytes), Err(WireFormatError::WrongKind));
+//! ```
+use super::{IndexResult, SpanResult};
+use std::ops::Range;
+use thiserror::Error;
+
+/// Version of the format written by `to_bytes`. Only this version can be read by `from_bytes`.
+pub const FORMAT_VERSION: u8 = 1;
+
+const MAGIC: &[u8; 4] = b"RSPR";
+const INDICES_KIND: u8 = 0;
+const SPANS_KIND: u8 = 1;
+
+/// Error raised when decoding a result from bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+pub enum WireFormatError {
+    /// The bytes do not start with the header of the format.
+    #[error("the bytes are not an encoded result")]
+    NotAResult,
+    /// The bytes were encoded with a different version of the format.
+    #[error("the result was encoded with format version {found}, but only version {supported} is supported")]
+    UnsupportedVersion {
+        /// Version of the format of the bytes.
+        found: u8,
+        /// The only version that can be decoded, [`FORMAT_VERSION`].
+        supported: u8,
+    },
+    /// The bytes encode a different kind of result, e.g. spans instead of indices.
+    #[error("the bytes encode a different kind of result")]
+    WrongKind,
+    /// The contents after the header are truncated, have trailing bytes,
+    /// or contain numbers too large for this platform.
+    #[error("the encoded result is malformed")]
+    Malformed,
+}
+
+impl IndexResult {
+    /// Encode the indices in the binary [format](self) of the current [`FORMAT_VERSION`].
+    #[must_use]
+    #[inline]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = header(INDICES_KIND);
+        write_number(&mut bytes, self.indices.len());
+        for &idx in &self.indices {
+            write_number(&mut bytes, idx);
+        }
+        bytes
+    }
+
+    /// Decode indices encoded with [`to_bytes`](IndexResult::to_bytes).
+    ///
+    /// # Errors
+    /// [`WireFormatError`] if the `bytes` are not indices encoded with the current [`FORMAT_VERSION`].
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireFormatError> {
+        let mut reader = Reader::new(bytes, INDICES_KIND)?;
+        let len = reader.number()?;
+        let mut indices = Vec::with_capacity(len.min(bytes.len()));
+        for _ in 0..len {
+            indices.push(reader.number()?);
+        }
+        reader.finish()?;
+
+        Ok(Self { indices })
+    }
+}
+
+impl SpanResult {
+    /// Encode the spans in the binary [format](self) of the current [`FORMAT_VERSION`].
+    #[must_use]
+    #[inline]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = header(SPANS_KIND);
+        write_number(&mut bytes, self.spans.len());
+        for span in &self.spans {
+            write_number(&mut bytes, span.start);
+            write_number(&mut bytes, span.end.saturating_sub(span.start));
+        }
+        bytes
+    }
+
+    /// Decode spans encoded with [`to_bytes`](SpanResult::to_bytes).
+    ///
+    /// # Errors
+    /// [`WireFormatError`] if the `bytes` are not spans encoded with the current [`FORMAT_VERSION`].
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireFormatError> {
+        let mut reader = Reader::new(bytes, SPANS_KIND)?;
+        let len = reader.number()?;
+        let mut spans = Vec::with_capacity(len.min(bytes.len()));
+        for _ in 0..len {
+            let start = reader.number()?;
+            let end = start
+                .checked_add(reader.number()?)
+                .ok_or(WireFormatError::Malformed)?;
+            spans.push(Range { start, end });
+        }
+        reader.finish()?;
+
+        Ok(Self { spans })
+    }
+}
+
+fn header(kind: u8) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(FORMAT_VERSION);
+    bytes.push(kind);
+    bytes
+}
+
+fn write_number(bytes: &mut Vec<u8>, number: usize) {
+    let mut rest = number as u64;
+    while rest >= 0x80 {
+        bytes.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
+    }
+    bytes.push(rest as u8);
+}
+
+/// Decoder of the contents after a validated header.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    idx: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], kind: u8) -> Result<Self, WireFormatError> {
+        let Some(rest) = bytes.strip_prefix(MAGIC.as_slice()) else {
+            return Err(WireFormatError::NotAResult);
+        };
+        match *rest {
+            [version, ..] if version != FORMAT_VERSION => {
+                Err(WireFormatError::UnsupportedVersion {
+                    found: version,
+                    supported: FORMAT_VERSION,
+                })
+            }
+            [_, found_kind, ..] if found_kind != kind => Err(WireFormatError::WrongKind),
+            [_, _, ..] => Ok(Self {
+                bytes,
+                idx: MAGIC.len() + 2,
+            }),
+            _ => Err(WireFormatError::NotAResult),
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, WireFormatError> {
+        let mut number = 0_u64;
+
+        for shift in (0..64).step_by(7) {
+            let &byte = self.bytes.get(self.idx).ok_or(WireFormatError::Malformed)?;
+            self.idx += 1;
+            let bits = u64::from(byte & 0x7F);
+            if bits << shift >> shift != bits {
+                return Err(WireFormatError::Malformed);
+            }
+            number |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                return usize::try_from(number)
+                    .ok()
+                    .ok_or(WireFormatError::Malformed);
+            }
+        }
+
+        Err(WireFormatError::Malformed)
+    }
+
+    fn finish(self) -> Result<(), WireFormatError> {
+        if self.idx == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(WireFormatError::Malformed)
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Stream of structural events of an input, for processing JSON without a query.
+//!
+//! The [`scan`] function runs the same classifiers as the engines over the input
+//! and passes every structural character outside of strings to a [`StructuralSink`],
+//! much like a SAX parser. The sink can ask to skip the subtree of any opening character.
+//! With the `tail-skip` feature the subtree is then passed over with the depth classifier,
+//! the same way the engines skip subtrees that cannot contain matches. Otherwise its events
+//! are still classified, but not passed to the sink.
+//!
+//! The events are only as meaningful as the input, which is not validated &ndash;
+//! see [`quick_check`](crate::validation::quick_check) for a cheap sanity check.
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::classification::structural::BracketType;
+//! use rsonpath_lib::engine::Input;
+//! use rsonpath_lib::scan::{scan, ScanAction, StructuralSink};
+//!
+//! /// Counts the members of the root object, without looking into their values.
+//! struct MemberCounter {
+//!     members: usize,
+//! }
+//!
+//! impl StructuralSink for MemberCounter {
+//!     fn on_opening(&mut self, _bracket: BracketType, idx: usize) -> ScanAction {
+//!         if idx == 0 {
+//!             ScanAction::Continue
+//!         } else {
+//!             ScanAction::SkipSubtree
+//!         }
+//!     }
+//!
+//!     fn on_colon(&mut self, _idx: usize) {
+//!         self.members += 1;
+//!     }
+//! }
+//!
+//! let mut contents = r#"{"a": {"b": 1, "c": 2}, "d": [{"e": 3}], "f": 4}"#.to_owned();
+//! let input = Input::new(&mut contents);
+//! let mut counter = MemberCounter { members: 0 };
+//!
+//! scan(&input, &mut counter);
+//!
+//! assert_eq!(counter.members, 3);
+//! ```
+use crate::classification::quotes::classify_quoted_sequences;
+use crate::classification::structural::{
+    classify_structural_characters, BracketType, Structural, StructuralIterator,
+};
+#[cfg(feature = "tail-skip")]
+use crate::engine::tail_skipping::TailSkip;
+use crate::engine::Input;
+
+/// Decision of a [`StructuralSink`] on how to continue after an opening character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ScanAction {
+    /// Report the events within the opened value.
+    #[default]
+    Continue,
+    /// Skip all events within the opened value and continue with its closing character.
+    SkipSubtree,
+}
+
+/// Receiver of the structural events produced by [`scan`].
+///
+/// Every event carries the position of its character in the input.
+/// All methods do nothing by default, so a sink only needs to implement
+/// those for the events it is interested in.
+pub trait StructuralSink {
+    /// Called on an opening bracket or brace at position `idx`.
+    ///
+    /// Returning [`ScanAction::SkipSubtree`] skips all events until the matching closing character,
+    /// which is still reported with [`on_closing`](StructuralSink::on_closing).
+    #[inline(always)]
+    fn on_opening(&mut self, _bracket: BracketType, _idx: usize) -> ScanAction {
+        ScanAction::Continue
+    }
+
+    /// Called on a closing bracket or brace at position `idx`.
+    #[inline(always)]
+    fn on_closing(&mut self, _bracket: BracketType, _idx: usize) {}
+
+    /// Called on a colon at position `idx`.
+    #[inline(always)]
+    fn on_colon(&mut self, _idx: usize) {}
+
+    /// Called on a comma at position `idx`.
+    #[inline(always)]
+    fn on_comma(&mut self, _idx: usize) {}
+}
+
+/// Pass all structural characters of the `input` that are not within strings
+/// to the `sink`, in the order in which they occur.
+///
+/// If the `sink` skips a subtree that is not closed before the end of the input,
+/// the scan ends without reporting its closing character.
+#[inline]
+pub fn scan<S: StructuralSink>(input: &Input, sink: &mut S) {
+    let bytes = input.relax_alignment();
+    let structural_classifier = classify_structural_characters(classify_quoted_sequences(bytes));
+    #[cfg(feature = "tail-skip")]
+    let mut classifier = TailSkip::new(structural_classifier);
+    #[cfg(not(feature = "tail-skip"))]
+    let mut classifier = structural_classifier;
+    classifier.turn_colons_on(0);
+    classifier.turn_commas_on(0);
+
+    while let Some(event) = classifier.next() {
+        match event {
+            Structural::Opening(bracket, idx) => {
+                if sink.on_opening(bracket, idx) == ScanAction::SkipSubtree {
+                    #[cfg(feature = "tail-skip")]
+                    let closing_idx = {
+                        // The classifier stops right after the closing character.
+                        let closing_idx = classifier.skip(bytes[idx]) - 1;
+                        let closing = match bracket {
+                            BracketType::Square => b']',
+                            BracketType::Curly => b'}',
+                        };
+                        (bytes.get(closing_idx) == Some(&closing)).then_some(closing_idx)
+                    };
+                    #[cfg(not(feature = "tail-skip"))]
+                    let closing_idx = skip_events(&mut classifier);
+
+                    match closing_idx {
+                        Some(closing_idx) => sink.on_closing(bracket, closing_idx),
+                        None => return,
+                    }
+                }
+            }
+            Structural::Closing(bracket, idx) => sink.on_closing(bracket, idx),
+            Structural::Colon(idx) => sink.on_colon(idx),
+            Structural::Comma(idx) => sink.on_comma(idx),
+        }
+    }
+}
+
+/// Consume the events of a value whose opening character was just classified,
+/// returning the position of its closing character, or `None` if the input ends first.
+#[cfg(not(feature = "tail-skip"))]
+fn skip_events<I: Iterator<Item = Structural>>(classifier: &mut I) -> Option<usize> {
+    let mut depth = 1_usize;
+
+    for event in classifier {
+        match event {
+            Structural::Opening(_, _) => depth += 1,
+            Structural::Closing(_, idx) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            Structural::Colon(_) | Structural::Comma(_) => (),
+        }
+    }
+
+    None
+}
@@ -0,0 +1,111 @@
+//! Cheap structural sanity check of an input, independent of any query.
+//!
+//! The engines do not validate their input, and their results on malformed documents
+//! are undefined. The [`quick_check`] function runs only the quote and structural
+//! classifiers over the input to catch the most common corruptions, like truncated
+//! documents, before running any queries. It is not a full JSON validator &ndash;
+//! e.g. `[}` or `{1 2}` pass the check.
+//!
+//! # Examples
+//! ```rust
+//! use rsonpath_lib::engine::Input;
+//! use rsonpath_lib::validation::{quick_check, StructuralError};
+//!
+//! let mut contents = r#"{"a": [1, 2], "b": {"c": "#.to_owned();
+//! let input = Input::new(&mut contents);
+//!
+//! assert_eq!(quick_check(&input), Err(StructuralError::UnclosedOpening(0)));
+//! ```
+use crate::classification::quotes::classify_quoted_sequences;
+use crate::classification::structural::{classify_structural_characters, Structural};
+use crate::engine::Input;
+use thiserror::Error;
+
+/// Anomaly in the structure of an input found by [`quick_check`].
+///
+/// The inner [`usize`] value is the position of the first anomaly in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+pub enum StructuralError {
+    /// A closing character has no matching opening character before it.
+    #[error("Closing character at position {0} does not match any opening character.")]
+    UnmatchedClosing(usize),
+    /// The input ended within a string. The position is the one of the opening double quote.
+    #[error("String starting at position {0} is not terminated.")]
+    UnterminatedString(usize),
+    /// The input ended with opening characters that were never closed.
+    /// The position is the one of the outermost of these characters.
+    #[error("Opening character at position {0} is not closed.")]
+    UnclosedOpening(usize),
+}
+
+/// Check that all brackets in the `input` are balanced and all strings are terminated.
+///
+/// Every closing character must match an earlier opening character and every opening character
+/// must be closed before the end of the input. Brackets within strings are ignored, and the kinds
+/// of the brackets are not compared. This takes a single pass of the SIMD classifiers over the input,
+/// much cheaper than full validation.
+///
+/// # Errors
+/// The [`StructuralError`] describing the first anomaly. If the input ends within a string,
+/// [`UnterminatedString`](StructuralError::UnterminatedString) is reported
+/// even if there are also unclosed opening characters.
+#[inline]
+pub fn quick_check(input: &Input) -> Result<(), StructuralError> {
+    let bytes = input.relax_alignment();
+    let classifier = classify_structural_characters(classify_quoted_sequences(bytes));
+    let mut depth = 0_usize;
+    let mut outermost_opening = 0;
+    let mut tail_start = 0;
+
+    for event in classifier {
+        match event {
+            Structural::Opening(_, idx) => {
+                if depth == 0 {
+                    outermost_opening = idx;
+                }
+                depth += 1;
+                tail_start = idx + 1;
+            }
+            Structural::Closing(_, idx) => {
+                if depth == 0 {
+                    return Err(StructuralError::UnmatchedClosing(idx));
+                }
+                depth -= 1;
+                tail_start = idx + 1;
+            }
+            Structural::Colon(_) | Structural::Comma(_) => (),
+        }
+    }
+
+    if let Some(idx) = unterminated_string_start(&bytes[tail_start..]) {
+        return Err(StructuralError::UnterminatedString(tail_start + idx));
+    }
+    if depth > 0 {
+        return Err(StructuralError::UnclosedOpening(outermost_opening));
+    }
+
+    Ok(())
+}
+
+/// Find the opening quote of a string that is not terminated before the end of `bytes`,
+/// assuming that `bytes` start outside of a string.
+fn unterminated_string_start(bytes: &[u8]) -> Option<usize> {
+    let mut string_start = None;
+    let mut is_escaped = false;
+
+    for (idx, &b) in bytes.iter().enumerate() {
+        if is_escaped {
+            is_escaped = false;
+        } else if string_start.is_some() {
+            match b {
+                b'\\' => is_escaped = true,
+                b'"' => string_start = None,
+                _ => (),
+            }
+        } else if b == b'"' {
+            string_start = Some(idx);
+        }
+    }
+
+    string_start
+}
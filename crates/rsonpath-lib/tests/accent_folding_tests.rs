@@ -0,0 +1,56 @@
+#![cfg(feature = "accent-folding")]
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::CountResult;
+use test_case::test_case;
+
+// The first "Zoë" key is precomposed, the second has a combining diaeresis.
+const DOCUMENT: &str = "{
+  \"data\": {
+    \"cafe\": {\"x\": 1},
+    \"Cr\u{e8}me br\u{fb}l\u{e9}e\": {\"x\": 2},
+    \"na\u{ef}ve\": [{\"Zo\u{eb}\": 3}, {\"Zoe\u{308}\": 4}],
+    \"\u{c6}ther\": 5,
+    \"CAF\u{c9}\": 6,
+    \"\u{17d}ivot\": 7
+  }
+}";
+
+macro_rules! accent_folding_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$.data['caf\u{e9}']" => (0, 1); "accented label matches plain key")]
+        #[test_case("$.data['caf\u{e9}'].x" => (0, 1); "accented label then label")]
+        #[test_case("$.data.cafe" => (1, 1); "ascii label")]
+        #[test_case("$.data['Creme brulee']" => (0, 1); "plain label matches accented key")]
+        #[test_case("$.data.naive.*.Zoe" => (0, 2); "precomposed and decomposed keys")]
+        #[test_case("$..['Zo\u{eb}']" => (1, 2); "accented descendant")]
+        #[test_case("$.data.CAFE" => (0, 1); "uppercase")]
+        #[test_case("$.data.Cafe" => (0, 0); "case is preserved")]
+        #[test_case("$.data.Aether" => (0, 0); "ligature is not folded")]
+        #[test_case("$.data.Zivot" => (0, 1); "latin extended")]
+        fn $test_name(query_string: &str) -> (usize, usize) {
+            let mut raw = DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let exact = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap()
+                .get();
+
+            let query = query.into_accent_insensitive();
+            let folded = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap()
+                .get();
+
+            (exact, folded)
+        }
+    };
+}
+
+accent_folding_test_cases!(main_accent_folding_test, MainEngine);
+accent_folding_test_cases!(recursive_accent_folding_test, RecursiveEngine);
@@ -0,0 +1,116 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::automaton::Automaton;
+use rsonpath_lib::query::error::CompilerError;
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{ExistsResult, IndexResult};
+use std::fs;
+use test_case::test_case;
+
+const ROOT_TEST_DIRECTORY: &str = "./tests/data";
+
+fn get_contents(test_path: &str) -> Input {
+    let path = format!("{ROOT_TEST_DIRECTORY}/{test_path}");
+    let mut raw = fs::read_to_string(path).unwrap();
+    Input::new(&mut raw)
+}
+
+fn parse_all(query_strings: &[&str]) -> Vec<JsonPathQuery> {
+    query_strings
+        .iter()
+        .map(|q| JsonPathQuery::parse(q).unwrap())
+        .collect()
+}
+
+macro_rules! alternation_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("basic/child.json", &["$..a..b.c..d", "$..a"]; "child.json two descendants")]
+        #[test_case("basic/child_hell.json", &["$..x..a.b.a.b.c", "$..x..a.b"]; "child_hell.json shared prefix")]
+        #[test_case("basic/heterogeneous_list.json", &["$.a.*", "$.a"]; "heterogeneous_list.json parent and children")]
+        #[test_case("basic/root.json", &["$", "$.a"]; "root.json root and child")]
+        #[test_case("basic/small.json", &["$..person..phoneNumber..number", "$..phoneNumber..type", "$..number"]; "small.json three queries")]
+        #[test_case("basic/wildcard_object2.json", &["$..a.*.*..b.*.*", "$..a.*", "$.*..b"]; "wildcard_object2.json wildcards")]
+        #[test_case("twitter/twitter.json", &["$..user..entities..url", "$.search_metadata.count", "$..count"]; "twitter.json mixed")]
+        fn $test_name(test_path: &str, query_strings: &[&str]) {
+            let contents = get_contents(test_path);
+            let queries = parse_all(query_strings);
+
+            let mut expected: Vec<usize> = queries
+                .iter()
+                .flat_map(|q| {
+                    let result = $impl::compile_query(q)
+                        .unwrap()
+                        .run::<IndexResult>(&contents)
+                        .unwrap();
+                    Vec::from(result)
+                })
+                .collect();
+            expected.sort_unstable();
+            expected.dedup();
+
+            let automaton = Automaton::new_alternation(&queries).unwrap();
+            let mut result: Vec<usize> = $impl::from_compiled_query(automaton)
+                .run::<IndexResult>(&contents)
+                .unwrap()
+                .into();
+            result.sort_unstable();
+
+            assert_eq!(result, expected);
+        }
+    };
+}
+
+macro_rules! exists_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"a": 1, "b": {"c": 2}}"#, &["$.x", "$..c"] => true; "descendant alternative")]
+        #[test_case(r#"{"a": 1, "b": {"c": 2}}"#, &["$.x", "$.b.d"] => false; "no alternative")]
+        #[test_case(r#"{"a": 1, "b": {"c": 2}}"#, &["$"] => true; "root")]
+        #[test_case(r#"{"a": 1, "b": {"c": 2"#, &["$.a", "$.b.c"] => true; "stops before malformed end")]
+        #[test_case(r#"[{"a": [], "b": {"c": [1, 2, {"x": 3"#, &["$.*.b.c.*"] => true; "stops before malformed end in list")]
+        fn $test_name(json: &str, query_strings: &[&str]) -> bool {
+            let mut raw = json.to_owned();
+            let contents = Input::new(&mut raw);
+            let queries = parse_all(query_strings);
+
+            let automaton = Automaton::new_alternation(&queries).unwrap();
+            let result = $impl::from_compiled_query(automaton)
+                .run::<ExistsResult>(&contents)
+                .unwrap();
+
+            result.get()
+        }
+    };
+}
+
+alternation_test_cases!(main_alternation_test, MainEngine);
+alternation_test_cases!(recursive_alternation_test, RecursiveEngine);
+exists_test_cases!(main_exists_test, MainEngine);
+exists_test_cases!(recursive_exists_test, RecursiveEngine);
+
+#[test]
+fn empty_alternation_is_an_error() {
+    let result = Automaton::new_alternation(&[]);
+
+    assert!(matches!(result, Err(CompilerError::EmptyAlternation)));
+}
+
+#[test]
+fn memory_estimate_accounts_for_labels_of_all_alternatives() {
+    let labels: Vec<String> = (0..20).map(|i| format!("label_number_{i:02}")).collect();
+    let queries: Vec<JsonPathQuery> = labels
+        .iter()
+        .map(|label| JsonPathQuery::parse(&format!("$.{label}")).unwrap())
+        .collect();
+
+    let single_bytes = Automaton::new_alternation(&queries[..1])
+        .unwrap()
+        .approx_memory_bytes();
+    let all_bytes = Automaton::new_alternation(&queries)
+        .unwrap()
+        .approx_memory_bytes();
+
+    // Every label is stored both with and without quotes.
+    let other_label_bytes: usize = labels[1..].iter().map(|label| 2 * label.len() + 2).sum();
+    assert!(all_bytes >= single_bytes + other_label_bytes);
+}
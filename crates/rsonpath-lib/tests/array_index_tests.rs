@@ -0,0 +1,112 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, PathResult, QueryResult, SpanResult};
+use test_case::test_case;
+
+fn run<R: QueryResult, E: Compiler>(json: &str, query_string: &str) -> R {
+    let mut raw = json.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let engine = E::compile_query(&query).unwrap();
+
+    engine.run::<R>(&contents).unwrap()
+}
+
+/// Run the query and return the matched values joined with spaces.
+fn values<E: Compiler>(json: &str, query_string: &str) -> String {
+    let result = run::<SpanResult, E>(json, query_string);
+
+    result
+        .get()
+        .iter()
+        .map(|span| &json[span.clone()])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Array of `count` arrays of the form `[i, "padding", i]`, with padding long enough
+/// for the elements to span many SIMD blocks.
+fn long_array(count: usize) -> String {
+    let elements: Vec<_> = (0..count)
+        .map(|i| format!("[{i}, \"{}\",\n    {i}]", "[,]".repeat(i % 30)))
+        .collect();
+
+    format!("[\n  {}\n]", elements.join(",\n  "))
+}
+
+macro_rules! value_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("[[10,[20,30]]]", "$[0][1][0]" => "20"; "nested arrays")]
+        #[test_case("[ [ 10 , [ 20 , 30 ] ] ]", "$[0][1][0]" => "20"; "nested arrays with whitespace")]
+        #[test_case("[\n  [\n    10,\n    [\n      20,\n      30\n    ]\n  ]\n]", "$[0][1][1]" => "30"; "nested arrays with newlines")]
+        #[test_case("[[10,[20,30]]]", "$[0][1]" => "[20,30]"; "complex element")]
+        #[test_case("[[10,[20,30]]]", "$[0][0]" => "10"; "first atomic element")]
+        #[test_case("[[10,[20,30]]]", "$[0][2]" => ""; "index past the end")]
+        #[test_case("[[10,[20,30]]]", "$[1]" => ""; "index past the end of root")]
+        #[test_case("[[], [[]], [[], [1]]]", "$[2][1][0]" => "1"; "empty arrays before")]
+        #[test_case("[[1, 2], [3, 4], [5, 6]]", "$[2][0]" => "5"; "same index at every level")]
+        #[test_case("[[[1, 2], 3], [[4, 5], 6]]", "$[1][0][1]" => "5"; "counters restored after nested arrays")]
+        #[test_case("[[[0, 1], [2, 3]], [[4, 5], [6, 7]]]", "$[1][1][0]" => "6"; "counters restored after arrays at the same depth")]
+        #[test_case("[{\"a\": [1, 2]}, {\"a\": [3, 4]}]", "$[1].a[1]" => "4"; "objects between indices")]
+        #[test_case("{\"a\": [{\"b\": 1}, {\"b\": 2}]}", "$.a[1].b" => "2"; "index between labels")]
+        #[test_case("{\"a\": [\"x\", \"y\"], \"b\": [\"z\"]}", "$.*[0]" => "\"x\" \"z\""; "index after wildcard")]
+        #[test_case("[[0, 1], [2, 3]]", "$[*][1]" => "1 3"; "index after index wildcard")]
+        #[test_case("[[0, 1], [2, 3]]", "$[1][*]" => "2 3"; "wildcard after index")]
+        #[test_case("{\"a\": [1, 2], \"b\": {\"a\": [3, 4]}}", "$..a[1]" => "2 4"; "index after descendant")]
+        #[test_case("{\"0\": 1, \"1\": 2}", "$[0]" => ""; "object keys are not indices")]
+        #[test_case("[\"[,]\", \",\", \"]\"]", "$[2]" => "\"]\""; "structural characters in strings")]
+        #[test_case("[[1, 2], [3, 4]]", "$[1][?(@ in 4..5)]" => "4"; "filter after index")]
+        fn $test_name(json: &str, query_string: &str) -> String {
+            values::<$impl>(json, query_string)
+        }
+    };
+}
+
+macro_rules! path_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$[37][0]" => "$[37][0]\n"; "first element deep into the document")]
+        #[test_case("$[37][2]" => "$[37][2]\n"; "last element deep into the document")]
+        #[test_case("$[59][1]" => "$[59][1]\n"; "last array")]
+        #[test_case("$[60][0]" => ""; "past the last array")]
+        fn $test_name(query_string: &str) -> String {
+            run::<PathResult, $impl>(&long_array(60), query_string).to_string()
+        }
+    };
+}
+
+macro_rules! count_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("[[10,[20,30]]]", "$[0][1][0]" => 1; "nested arrays")]
+        #[test_case("[1, 2, 3]", "$[0]" => 1; "atomic root elements")]
+        #[test_case("[]", "$[0]" => 0; "empty root")]
+        #[test_case("[42]", "$[0]" => 1; "only element")]
+        #[test_case("[{}, [], 1]", "$[1]" => 1; "empty array element")]
+        #[test_case("{\"a\": 42}", "$[0]" => 0; "object root")]
+        fn $test_name(json: &str, query_string: &str) -> usize {
+            run::<CountResult, $impl>(json, query_string).get()
+        }
+    };
+}
+
+value_test_cases!(main_value_test, MainEngine);
+value_test_cases!(recursive_value_test, RecursiveEngine);
+path_test_cases!(main_path_test, MainEngine);
+path_test_cases!(recursive_path_test, RecursiveEngine);
+count_test_cases!(main_count_test, MainEngine);
+count_test_cases!(recursive_count_test, RecursiveEngine);
+
+#[test]
+fn every_element_of_long_array_is_selected_by_its_index() {
+    let json = long_array(60);
+
+    for i in 0..60 {
+        let query_string = format!("$[{i}][2]");
+        let expected = i.to_string();
+
+        assert_eq!(values::<MainEngine>(&json, &query_string), expected);
+        assert_eq!(values::<RecursiveEngine>(&json, &query_string), expected);
+    }
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "arrow")]
+use arrow_array::builder::{BooleanBuilder, Float64Builder, Int64Builder, StringBuilder};
+use arrow_array::{Array, BooleanArray, Float64Array, Int64Array, StringArray};
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::arrow::{ArrowResult, ArrowTypeMismatchError, JsonArrayBuilder};
+use std::sync::Arc;
+
+const DOCUMENT: &str = r#"{
+  "items": [
+    {"id": 1, "price": 2.5, "name": "first", "active": true},
+    {"id": null, "price": 3, "name": "sec\"ondé", "active": false},
+    {"id": -3, "price": null, "name": null, "active": null}
+  ],
+  "ids": [7, 8, null]
+}"#;
+
+fn run<E: Compiler, B: JsonArrayBuilder>(
+    query_string: &str,
+) -> Result<Arc<dyn Array>, ArrowTypeMismatchError>
+where
+    for<'q> E::E<'q>: Engine,
+{
+    let mut raw = DOCUMENT.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let result = E::compile_query(&query)
+        .unwrap()
+        .run::<ArrowResult<B>>(&contents)
+        .unwrap();
+
+    result.finish()
+}
+
+macro_rules! arrow_test_cases {
+    ($module:ident, $impl:ident) => {
+        mod $module {
+            use super::*;
+
+            #[test]
+            fn int64_with_nulls() {
+                let array = run::<$impl, Int64Builder>("$.items[*].id").unwrap();
+
+                assert_eq!(
+                    array.as_any().downcast_ref::<Int64Array>().unwrap(),
+                    &Int64Array::from(vec![Some(1), None, Some(-3)])
+                );
+            }
+
+            #[test]
+            fn int64_list_items() {
+                let array = run::<$impl, Int64Builder>("$.ids[*]").unwrap();
+
+                assert_eq!(
+                    array.as_any().downcast_ref::<Int64Array>().unwrap(),
+                    &Int64Array::from(vec![Some(7), Some(8), None])
+                );
+            }
+
+            #[test]
+            fn float64_accepts_integers() {
+                let array = run::<$impl, Float64Builder>("$..price").unwrap();
+
+                assert_eq!(
+                    array.as_any().downcast_ref::<Float64Array>().unwrap(),
+                    &Float64Array::from(vec![Some(2.5), Some(3.0), None])
+                );
+            }
+
+            #[test]
+            fn strings_are_unescaped() {
+                let array = run::<$impl, StringBuilder>("$..name").unwrap();
+
+                assert_eq!(
+                    array.as_any().downcast_ref::<StringArray>().unwrap(),
+                    &StringArray::from(vec![Some("first"), Some("sec\"ondé"), None])
+                );
+            }
+
+            #[test]
+            fn booleans() {
+                let array = run::<$impl, BooleanBuilder>("$..active").unwrap();
+
+                assert_eq!(
+                    array.as_any().downcast_ref::<BooleanArray>().unwrap(),
+                    &BooleanArray::from(vec![Some(true), Some(false), None])
+                );
+            }
+
+            #[test]
+            fn float_into_int64_is_an_error() {
+                let error = run::<$impl, Int64Builder>("$..price").unwrap_err();
+
+                assert_eq!(error.value, "2.5");
+                assert_eq!(error.expected, "Int64");
+            }
+
+            #[test]
+            fn number_into_string_is_an_error() {
+                let error = run::<$impl, StringBuilder>("$..id").unwrap_err();
+
+                assert_eq!(error.value, "1");
+                assert_eq!(error.expected, "Utf8");
+            }
+
+            #[test]
+            fn object_into_int64_is_an_error() {
+                let error = run::<$impl, Int64Builder>("$.items[*]").unwrap_err();
+
+                assert!(error.value.starts_with(r#"{"id": 1"#));
+                assert!(error.value.ends_with("true}"));
+            }
+        }
+    };
+}
+
+arrow_test_cases!(main, MainEngine);
+arrow_test_cases!(recursive, RecursiveEngine);
@@ -0,0 +1,173 @@
+#![cfg(feature = "bench")]
+use rsonpath_lib::bench::{Benchmark, Configuration, Measurement, Report};
+use rsonpath_lib::engine::error::EngineError;
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::error::QueryError;
+use rsonpath_lib::query::error::CompilerError;
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::CountResult;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+use test_case::test_case;
+
+const JSON: &str = r#"{"a": [1, {"b": [2, 3]}, {"c": {"b": 4}}], "d": {"b": 5}}"#;
+
+fn quick_benchmark(samples: usize) -> Benchmark {
+    Benchmark::new(JSON)
+        .with_warm_up_runs(1)
+        .with_samples(NonZeroUsize::new(samples).unwrap())
+        .with_min_sample_duration(Duration::ZERO)
+}
+
+fn count<C: Compiler>(query: &JsonPathQuery) -> usize {
+    let mut contents = JSON.to_owned();
+    let input = Input::new(&mut contents);
+
+    C::compile_query(query)
+        .unwrap()
+        .run::<CountResult>(&input)
+        .unwrap()
+        .get()
+}
+
+#[test_case("$..b"; "descendant")]
+#[test_case("$.a[1:]"; "slice")]
+#[test_case("$.*.*"; "wildcard")]
+#[test_case("$.x"; "no matches")]
+fn measured_count_is_the_engine_count(query_string: &str) {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let benchmark = quick_benchmark(3);
+
+    let main = benchmark.measure::<MainEngine>("main", &query).unwrap();
+    let recursive = benchmark
+        .measure::<RecursiveEngine>("recursive", &query)
+        .unwrap();
+
+    assert_eq!(main.count(), count::<MainEngine>(&query));
+    assert_eq!(recursive.count(), count::<RecursiveEngine>(&query));
+}
+
+#[test_case(1; "single sample")]
+#[test_case(4; "even samples")]
+#[test_case(7; "odd samples")]
+fn samples_are_ordered_around_the_median(samples: usize) {
+    let query = JsonPathQuery::parse("$..b").unwrap();
+
+    let measurement = quick_benchmark(samples)
+        .measure::<MainEngine>("main", &query)
+        .unwrap();
+
+    assert_eq!(measurement.samples().len(), samples);
+    assert!(measurement.samples().windows(2).all(|w| w[0] <= w[1]));
+    assert!(measurement.fastest() <= measurement.median());
+    assert!(measurement.median() <= measurement.slowest());
+    assert_eq!(measurement.bytes(), JSON.len());
+    assert!(measurement.throughput() > 0.0);
+}
+
+#[test]
+fn samples_repeat_runs_to_last_the_minimal_duration() {
+    let mut runs = 0;
+
+    let measurement = Benchmark::new(JSON)
+        .with_warm_up_runs(2)
+        .with_samples(NonZeroUsize::new(3).unwrap())
+        .with_min_sample_duration(Duration::from_millis(10))
+        .measure_with("sleep", |_| {
+            runs += 1;
+            std::thread::sleep(Duration::from_micros(100));
+            Ok(runs)
+        })
+        .unwrap();
+
+    assert!(measurement.iterations() > 1);
+    assert_eq!(runs, 2 + 3 * measurement.iterations());
+    assert_eq!(measurement.count(), 2);
+}
+
+#[test]
+fn zero_warm_up_runs_still_run_once() {
+    let mut runs = 0;
+
+    let measurement = quick_benchmark(2)
+        .with_warm_up_runs(0)
+        .measure_with("count", |_| {
+            runs += 1;
+            Ok(runs)
+        })
+        .unwrap();
+
+    assert_eq!(measurement.count(), 1);
+    assert_eq!(runs, 1 + 2 * measurement.iterations());
+}
+
+#[test]
+fn engine_errors_are_returned() {
+    let error = quick_benchmark(1)
+        .measure_with("failing", |_| Err(EngineError::MissingClosingCharacter()))
+        .unwrap_err();
+
+    assert!(matches!(error, EngineError::MissingClosingCharacter()));
+}
+
+#[test]
+fn unsupported_query_is_a_compiler_error() {
+    let query = JsonPathQuery::parse("$.a[last()].b").unwrap();
+
+    let error = quick_benchmark(1)
+        .measure::<MainEngine>("main", &query)
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        QueryError::Compiler(CompilerError::NotSupported(_))
+    ));
+}
+
+#[test]
+fn configuration_is_the_one_of_the_build() {
+    let configuration = Configuration::current();
+
+    assert_eq!(configuration.simd(), cfg!(feature = "simd"));
+    assert_eq!(configuration.head_skip(), cfg!(feature = "head-skip"));
+    assert_eq!(configuration.tail_skip(), cfg!(feature = "tail-skip"));
+    assert_eq!(
+        configuration.unique_labels(),
+        cfg!(feature = "unique-labels")
+    );
+}
+
+#[test]
+fn report_has_a_row_for_every_measurement() {
+    let query = JsonPathQuery::parse("$..b").unwrap();
+    let benchmark = quick_benchmark(3);
+    let measurements: Vec<Measurement> = vec![
+        benchmark.measure::<MainEngine>("main", &query).unwrap(),
+        benchmark
+            .measure::<RecursiveEngine>("recursive", &query)
+            .unwrap(),
+    ];
+    let mut report = Report::new();
+    for measurement in &measurements {
+        report.push(measurement.clone());
+    }
+
+    let table = report.to_string();
+    let lines: Vec<_> = table.lines().collect();
+
+    assert_eq!(report.measurements(), measurements);
+    assert_eq!(
+        lines[0],
+        format!("configuration: {}", Configuration::current())
+    );
+    assert!(lines[1].starts_with("engine"));
+    assert_eq!(lines.len(), 4);
+    for (line, measurement) in lines[2..].iter().zip(&measurements) {
+        let columns: Vec<_> = line.split_whitespace().collect();
+        assert_eq!(columns[0], measurement.name());
+        assert_eq!(columns[1], JSON.len().to_string());
+        assert_eq!(columns[2], measurement.count().to_string());
+    }
+}
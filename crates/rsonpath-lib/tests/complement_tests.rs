@@ -0,0 +1,106 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::automaton::Automaton;
+use rsonpath_lib::query::error::CompilerError;
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::CountResult;
+use std::fs;
+use test_case::test_case;
+
+const ROOT_TEST_DIRECTORY: &str = "./tests/data";
+
+fn get_contents(test_path: &str) -> Input {
+    let path = format!("{ROOT_TEST_DIRECTORY}/{test_path}");
+    let mut raw = fs::read_to_string(path).unwrap();
+    Input::new(&mut raw)
+}
+
+macro_rules! complement_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("basic/small.json", "$..person.phoneNumber", "$..person.*"; "small.json descendant parent")]
+        #[test_case("basic/small.json", "$.person.spouse", "$.person.*"; "small.json object child")]
+        #[test_case("basic/child.json", "$..a.b", "$..a.*"; "child.json nested parents")]
+        #[test_case("basic/heterogeneous_list.json", "$.a.b", "$.a.*"; "heterogeneous_list.json list parent")]
+        #[test_case("basic/wildcard_object2.json", "$..a.*.b", "$..a.*.*"; "wildcard_object2.json wildcard parent")]
+        #[test_case("twitter/twitter.json", "$..user.entities", "$..user.*"; "twitter.json user without entities")]
+        fn $test_name(test_path: &str, query_string: &str, parent_wildcard_string: &str) {
+            let contents = get_contents(test_path);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let parent_wildcard = JsonPathQuery::parse(parent_wildcard_string).unwrap();
+
+            let excluded = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap()
+                .get();
+            let all_children = $impl::compile_query(&parent_wildcard)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap()
+                .get();
+            let expected = all_children - excluded;
+
+            let automaton = Automaton::new_complement(&query).unwrap();
+            let result = $impl::from_compiled_query(automaton)
+                .run::<CountResult>(&contents)
+                .unwrap()
+                .get();
+
+            assert_eq!(result, expected);
+        }
+    };
+}
+
+macro_rules! count_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"config": {"a": 1, "secret": 2, "b": {"c": 3}}}"#, "$.config.secret" => 2; "atomic excluded value")]
+        #[test_case(r#"{"config": {"secret": {"x": 1}, "a": [1]}}"#, "$.config.secret" => 1; "complex excluded value")]
+        #[test_case(r#"{"config": {"a": {"secret": 1}, "b": 2}}"#, "$.config.secret" => 2; "excluded label deeper")]
+        #[test_case(r#"{"config": {"secret": 1}}"#, "$.config.secret" => 0; "only excluded child")]
+        #[test_case(r#"{"config": [1, {"secret": 2}, 3]}"#, "$.config.secret" => 3; "list parent")]
+        #[test_case(r#"{"config": 42}"#, "$.config.secret" => 0; "atomic parent")]
+        #[test_case(r#"{"a": {"config": {"secret": 1, "x": 2}}, "config": {"y": 1}}"#, "$..config.secret" => 2; "descendant parents")]
+        #[test_case(r#"{"config": {"config": {"secret": 1, "x": 2}}}"#, "$..config.secret" => 2; "nested descendant parents")]
+        #[test_case(r#"{"a": {"a": {"c": 1, "d": 2}, "c": 3, "e": 4}}"#, "$.a...a.c" => 3; "descendant or self parents")]
+        fn $test_name(json: &str, query_string: &str) -> usize {
+            let mut raw = json.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let automaton = Automaton::new_complement(&query).unwrap();
+            let result = $impl::from_compiled_query(automaton)
+                .run::<CountResult>(&contents)
+                .unwrap();
+
+            result.get()
+        }
+    };
+}
+
+complement_test_cases!(main_complement_test, MainEngine);
+complement_test_cases!(recursive_complement_test, RecursiveEngine);
+count_test_cases!(main_count_test, MainEngine);
+count_test_cases!(recursive_count_test, RecursiveEngine);
+
+#[test_case("$"; "root")]
+#[test_case("$..secret"; "descendant")]
+#[test_case("$.config..secret"; "descendant after child")]
+fn complement_of_query_not_ending_with_child_is_an_error(query_string: &str) {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let result = Automaton::new_complement(&query);
+
+    assert!(matches!(result, Err(CompilerError::InvalidComplement)));
+}
+
+#[test_case("$.config.*"; "wildcard")]
+#[test_case("$.config[?(@.x == 1)]"; "filter")]
+#[test_case("$.config[0]"; "array index")]
+fn complement_of_non_label_child_is_not_supported(query_string: &str) {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let result = Automaton::new_complement(&query);
+
+    assert!(matches!(result, Err(CompilerError::NotSupported(_))));
+}
@@ -0,0 +1,60 @@
+use rsonpath_lib::engine::error::EngineError;
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::{Compiler, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, ExistsResult, QueryResult};
+use test_case::test_case;
+
+fn run<R: QueryResult>(json: &str, query_string: &str) -> Result<(R, bool), EngineError> {
+    let mut raw = json.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+
+    let consumed = engine.run_with_consumption::<R>(&contents)?;
+    let is_fully_consumed = consumed.is_fully_consumed();
+
+    Ok((consumed.into_result(), is_fully_consumed))
+}
+
+#[test_case(r#"{"a": 1, "b": {"a": 2}}"#, "$..a" => (2, true); "all matches")]
+#[test_case(r#"{"b": {"c": [1, 2]}}"#, "$..a" => (0, true); "no matches")]
+#[test_case(r#"{"b": {"c": [1, 2]}}"#, "$.b.c[1]" => (1, true); "match at the end")]
+#[test_case(r#"{"a": {"b": 1}}"#, "$" => (1, true); "root query")]
+#[test_case("[]", "$.a" => (0, true); "empty array")]
+#[test_case("42", "$.a" => (0, true); "atomic root")]
+#[test_case("", "$.a" => (0, true); "empty document")]
+#[test_case("  {\"a\": 1}  \n", "$.a" => (1, true); "whitespace around the root")]
+fn count(json: &str, query_string: &str) -> (usize, bool) {
+    let (result, is_fully_consumed) = run::<CountResult>(json, query_string).unwrap();
+
+    (result.get(), is_fully_consumed)
+}
+
+#[test_case(r#"{"a": 1, "b": {"a": 2}}"#, "$..a" => (true, false); "stopped at first match")]
+#[test_case(r#"{"b": {"c": [1, 2]}}"#, "$..a" => (false, true); "no matches")]
+#[test_case(r#"{"b": 1, "a": 2}"#, "$.a" => (true, false); "match in the last member")]
+#[test_case(r#"{"a": 1}"#, "$" => (true, false); "root query")]
+fn exists(json: &str, query_string: &str) -> (bool, bool) {
+    let (result, is_fully_consumed) = run::<ExistsResult>(json, query_string).unwrap();
+
+    (result.get(), is_fully_consumed)
+}
+
+#[test]
+fn stopping_early_in_unclosed_document_is_not_an_error() {
+    let (result, is_fully_consumed) = run::<ExistsResult>(r#"{"a": 1, "b": ["#, "$.a").unwrap();
+
+    assert!(result.get());
+    assert!(!is_fully_consumed);
+}
+
+#[test]
+fn unclosed_document_is_an_error() {
+    let result = run::<CountResult>(r#"{"a": 1, "b": ["#, "$.c");
+
+    assert!(matches!(
+        result,
+        Err(EngineError::MissingClosingCharacter())
+    ));
+}
@@ -0,0 +1,57 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, PathResult, QueryResult};
+use test_case::test_case;
+
+const DOCUMENT: &str = r#"{
+  "\u0000": 1,
+  "x\u001Fy": 2,
+  "a": {"line\nbreak": 3, "line\\nbreak": 4, "tab\u0009": 5},
+  "b": {"line\u000abreak": 6}
+}"#;
+
+fn run<R: QueryResult, E: Compiler>(query_string: &str) -> R {
+    let mut raw = DOCUMENT.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let engine = E::compile_query(&query).unwrap();
+
+    engine.run::<R>(&contents).unwrap()
+}
+
+macro_rules! count_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"$['\u0000']"# => 1; "null character")]
+        #[test_case(r#"$['x\u001fy']"# => 1; "lowercase hex in query")]
+        #[test_case(r#"$['x\u001Fy']"# => 1; "uppercase hex in query")]
+        #[test_case(r#"$['\u0001']"# => 0; "different control character")]
+        #[test_case(r#"$.a['line\nbreak']"# => 1; "short escape matches short escape")]
+        #[test_case(r#"$.b['line\nbreak']"# => 1; "short escape matches unicode escape")]
+        #[test_case(r#"$.a['line\u000Abreak']"# => 1; "unicode escape matches short escape")]
+        #[test_case(r#"$.*['line\nbreak']"# => 2; "both spellings")]
+        #[test_case(r#"$.a['line\\nbreak']"# => 1; "escaped backslash is not a control character")]
+        #[test_case(r#"$.a['tab\t']"# => 1; "tab")]
+        #[test_case(r#"$..['line\nbreak']"# => 2; "descendant")]
+        fn $test_name(query_string: &str) -> usize {
+            run::<CountResult, $impl>(query_string).get()
+        }
+    };
+}
+
+macro_rules! path_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"$['\u0000']"# => "$['\u{0}']\n"; "null character")]
+        #[test_case(r#"$.*['line\u000abreak']"# => "$['a']['line\nbreak']\n$['b']['line\nbreak']\n"; "both spellings")]
+        fn $test_name(query_string: &str) -> String {
+            run::<PathResult, $impl>(query_string).to_string()
+        }
+    };
+}
+
+count_test_cases!(main_count_test, MainEngine);
+count_test_cases!(recursive_count_test, RecursiveEngine);
+path_test_cases!(main_path_test, MainEngine);
+path_test_cases!(recursive_path_test, RecursiveEngine);
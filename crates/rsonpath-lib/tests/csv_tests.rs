@@ -0,0 +1,83 @@
+#![cfg(feature = "csv")]
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::csv::{CsvError, CsvExtractor};
+use std::io::{self, Write};
+use test_case::test_case;
+
+const DOCUMENT: &str = r#"{
+  "people": [
+    {"name": "Ann", "age": 31, "city": "Oslo"},
+    {"name": "Smith, John", "age": 45},
+    {"name": "The \"Boss\"", "city": "New\nYork", "tags": ["a", "b"]},
+    {"age": null, "address": {"city": "Rome, IT"}},
+    {"name": "Café", "meta": {"k": "v"}}
+  ]
+}"#;
+
+macro_rules! csv_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$.people.*", &["$.name", "$.age"] => "Ann,31\n\"Smith, John\",45\n\"The \"\"Boss\"\"\",\n,null\nCafé,\n"; "escaping and missing fields")]
+        #[test_case("$.people.*", &["$.city"] => "Oslo\n\n\"New\nYork\"\n\n\n"; "newlines in values")]
+        #[test_case("$.people.*", &["$..city"] => "Oslo\n\n\"New\nYork\"\n\"Rome, IT\"\n\n"; "descendant column")]
+        #[test_case("$.people.*", &["$.tags", "$.meta"] => ",\n,\n\"[\"\"a\"\", \"\"b\"\"]\",\n,\n,\"{\"\"k\"\": \"\"v\"\"}\"\n"; "complex values")]
+        #[test_case("$..address", &["$", "$.city"] => "\"{\"\"city\"\": \"\"Rome, IT\"\"}\",\"Rome, IT\"\n"; "record as column")]
+        #[test_case("$..city", &["$"] => "Oslo\n\"New\nYork\"\n\"Rome, IT\"\n"; "scalar records")]
+        #[test_case("$.people.*", &[] => "\n\n\n\n\n"; "no columns")]
+        #[test_case("$.nobody.*", &["$.name"] => ""; "no records")]
+        fn $test_name(record_query: &str, column_queries: &[&str]) -> String {
+            let mut raw = DOCUMENT.to_owned();
+            let input = Input::new(&mut raw);
+            let record_query = JsonPathQuery::parse(record_query).unwrap();
+            let column_queries: Vec<_> = column_queries
+                .iter()
+                .map(|q| JsonPathQuery::parse(q).unwrap())
+                .collect();
+            let extractor = CsvExtractor::new(
+                $impl::compile_query(&record_query).unwrap(),
+                column_queries
+                    .iter()
+                    .map(|q| $impl::compile_query(q).unwrap())
+                    .collect(),
+            );
+
+            let mut csv = vec![];
+            extractor.write_rows(&input, &mut csv).unwrap();
+
+            String::from_utf8(csv).unwrap()
+        }
+    };
+}
+
+csv_test_cases!(main_csv_test, MainEngine);
+csv_test_cases!(recursive_csv_test, RecursiveEngine);
+
+struct FailingWriter;
+
+impl Write for FailingWriter {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_error_is_reported() {
+    let mut raw = DOCUMENT.to_owned();
+    let input = Input::new(&mut raw);
+    let record_query = JsonPathQuery::parse("$.people.*").unwrap();
+    let column_query = JsonPathQuery::parse("$.name").unwrap();
+    let extractor = CsvExtractor::new(
+        MainEngine::compile_query(&record_query).unwrap(),
+        vec![MainEngine::compile_query(&column_query).unwrap()],
+    );
+
+    let result = extractor.write_rows(&input, &mut FailingWriter);
+
+    assert!(matches!(result, Err(CsvError::Io(_))));
+}
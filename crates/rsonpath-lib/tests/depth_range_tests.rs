@@ -0,0 +1,131 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, DepthHistogramResult};
+use std::fs;
+use std::ops::Range;
+use test_case::test_case;
+
+const ROOT_TEST_DIRECTORY: &str = "./tests/data";
+
+const QUERIES: &[&str] = &[
+    "$",
+    "$.*",
+    "$..a",
+    "$..a.b",
+    "$..a..b",
+    "$.*[0]",
+    "$..[1:]",
+    "$..user..entities..url",
+    "$..user.id",
+    "$.statuses[*].user.screen_name",
+    "$..[?(@ is scalar)]",
+];
+
+const RANGES: &[Range<usize>] = &[
+    0..1,
+    0..2,
+    1..2,
+    1..3,
+    2..4,
+    3..6,
+    4..usize::MAX,
+    0..usize::MAX,
+];
+
+fn count(json: &str, query_string: &str, depth_range: Range<usize>) -> usize {
+    let mut raw = json.to_owned();
+    let input = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+
+    engine
+        .run_with_depth_range::<CountResult>(&input, depth_range)
+        .unwrap()
+        .get()
+}
+
+fn assert_depth_range_filters_all_matches(json: &str, description: &str) {
+    let mut raw = json.to_owned();
+    let input = Input::new(&mut raw);
+
+    for query_string in QUERIES {
+        let query = JsonPathQuery::parse(query_string).unwrap();
+        let engine = MainEngine::compile_query(&query).unwrap();
+        let all = engine.run::<DepthHistogramResult>(&input).unwrap();
+
+        for depth_range in RANGES {
+            let mut expected: Vec<usize> = all
+                .get()
+                .iter()
+                .enumerate()
+                .map(|(depth, &count)| {
+                    if depth_range.contains(&depth) {
+                        count
+                    } else {
+                        0
+                    }
+                })
+                .collect();
+            while expected.last() == Some(&0) {
+                expected.pop();
+            }
+            let result = engine
+                .run_with_depth_range::<DepthHistogramResult>(&input, depth_range.clone())
+                .unwrap();
+
+            assert_eq!(
+                result.get(),
+                expected,
+                "{query_string} in {depth_range:?} on {description}"
+            );
+        }
+    }
+}
+
+#[test_case("basic/child_hell.json")]
+#[test_case("basic/heterogeneous_list.json")]
+#[test_case("basic/singletons_and_empties.json")]
+#[test_case("basic/skipping.json")]
+#[test_case("basic/wildcard_list.json")]
+#[test_case("twitter/twitter_urls.json")]
+#[test_case("twitter/twitter.json")]
+fn depth_range_filters_all_matches_on_corpus(path: &str) {
+    let json = fs::read_to_string(format!("{ROOT_TEST_DIRECTORY}/{path}")).unwrap();
+
+    assert_depth_range_filters_all_matches(&json, path);
+}
+
+#[test_case(r#"{"a": {"b": 1, "a": {"b": [2, {"b": 3}]}}}"#; "nested labels")]
+#[test_case(r#"[[1, [2, [3, [4, [5]]]]], [], [[[]]], 6]"#; "nested lists")]
+#[test_case(r#"{"x": "]}", "a": [{"b": "{["}, {"a": {"b": "\""}}]}"#; "brackets in strings")]
+fn depth_range_filters_all_matches(json: &str) {
+    assert_depth_range_filters_all_matches(json, json);
+}
+
+#[test_case(3..6 => 3; "depths three through five")]
+#[test_case(0..3 => 1; "shallower than three")]
+#[test_case(6..usize::MAX => 1; "deeper than five")]
+#[test_case(4..5 => 1; "single depth")]
+#[test_case(5..5 => 0; "empty range")]
+#[test_case(5..3 => 0; "reversed range")]
+#[allow(clippy::reversed_empty_ranges)]
+fn band_of_descendants(depth_range: Range<usize>) -> usize {
+    let json = r#"{"x": 1, "a": {"b": {"x": 3, "c": {"x": 4, "d": {"x": 5, "e": {"x": 6}}}}}}"#;
+
+    count(json, "$..x", depth_range)
+}
+
+#[test_case("$" => 1; "root")]
+#[test_case("$.a" => 0; "child")]
+fn range_ending_at_one_contains_only_the_root(query_string: &str) -> usize {
+    count(r#"{"a": {"a": 1}}"#, query_string, 0..1)
+}
+
+#[test]
+fn empty_range_does_not_read_the_input() {
+    // The input is not valid JSON, so any attempt to run the query on it would fail.
+    let result = count("}}", "$..a", 3..3);
+
+    assert_eq!(result, 0);
+}
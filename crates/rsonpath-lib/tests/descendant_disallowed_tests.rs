@@ -0,0 +1,79 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::error::CompilerError;
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::CountResult;
+use std::io;
+use test_case::test_case;
+
+macro_rules! descendant_disallowed_test_cases {
+    ($rejected_test_name:ident, $accepted_test_name:ident, $impl:ident) => {
+        #[test_case("$..a" => ("..['a']".to_owned(), 1); "descendant")]
+        #[test_case("$.a.b...c" => ("...['c']".to_owned(), 3); "descendant or self")]
+        #[test_case("$[*]..[?(@ is scalar)]" => ("..[?(@ is scalar)]".to_owned(), 2); "descendant filter")]
+        #[test_case("$.a..b..c" => ("..['b']".to_owned(), 2); "first of many")]
+        fn $rejected_test_name(query_string: &str) -> (String, usize) {
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let result = $impl::compile_query_without_descendants(&query);
+
+            match result {
+                Err(CompilerError::DescendantDisallowed { selector, position }) => (selector, position),
+                Err(error) => panic!("unexpected error: {error}"),
+                Ok(_) => panic!("query {query_string} was accepted"),
+            }
+        }
+
+        #[test_case("$" => 1; "root")]
+        #[test_case("$.a" => 1; "child")]
+        #[test_case("$.a[*].b" => 2; "wildcard")]
+        #[test_case("$.a[1]" => 1; "array index")]
+        #[test_case("$.a[?(@.b == 1)]" => 1; "filter")]
+        fn $accepted_test_name(query_string: &str) -> usize {
+            let mut contents = r#"{"a": [{"b": 1}, {"b": 2}]}"#.to_owned();
+            let input = Input::new(&mut contents);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query_without_descendants(&query).unwrap();
+
+            engine.run::<CountResult>(&input).unwrap().get()
+        }
+    };
+}
+
+descendant_disallowed_test_cases!(
+    main_descendant_rejected_test,
+    main_descendant_free_accepted_test,
+    MainEngine
+);
+descendant_disallowed_test_cases!(
+    recursive_descendant_rejected_test,
+    recursive_descendant_free_accepted_test,
+    RecursiveEngine
+);
+
+#[test]
+fn descendant_disallowed_error_names_selector_and_position() {
+    let query = JsonPathQuery::parse("$.a..b").unwrap();
+    let error = MainEngine::compile_query_without_descendants(&query)
+        .err()
+        .unwrap();
+
+    assert_eq!(
+        error.to_string(),
+        "Descendant selector `..['b']` at position 2 of the query is not allowed."
+    );
+    assert_eq!(io::Error::from(error).kind(), io::ErrorKind::InvalidInput);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn pattern_descendant_disallowed() {
+    let query = JsonPathQuery::parse("$.a..[~'^b']").unwrap();
+    let result = RecursiveEngine::compile_query_without_descendants(&query);
+
+    assert!(matches!(
+        result,
+        Err(CompilerError::DescendantDisallowed { selector, position: 2 }) if selector == "..[~'^b']"
+    ));
+}
@@ -0,0 +1,57 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, PathResult, QueryResult};
+use test_case::test_case;
+
+const NESTED_DOCUMENT: &str = r#"{"a": {"a": 1}}"#;
+
+const DOCUMENT: &str = r#"{
+  "a": {"b": {"a": {"c": 1}}, "c": 2},
+  "x": {"a": {"c": 3}}
+}"#;
+
+fn run<R: QueryResult, E: Compiler>(json: &str, query_string: &str) -> R {
+    let mut raw = json.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let engine = E::compile_query(&query).unwrap();
+
+    engine.run::<R>(&contents).unwrap()
+}
+
+macro_rules! nested_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$.a..a" => "$['a']['a']\n"; "descendant excludes self")]
+        #[test_case("$.a...a" => "$['a']\n$['a']['a']\n"; "descendant or self includes self")]
+        #[test_case("$.a...['a']" => "$['a']\n$['a']['a']\n"; "index label")]
+        #[test_case("$...a" => "$['a']\n$['a']['a']\n"; "root has no key")]
+        #[test_case("$..a...a" => "$['a']\n$['a']['a']\n"; "self also matched as descendant")]
+        #[test_case("$.*...a" => "$['a']\n$['a']['a']\n"; "wildcard self")]
+        #[test_case("$.a...b" => ""; "different label")]
+        fn $test_name(query_string: &str) -> String {
+            run::<PathResult, $impl>(NESTED_DOCUMENT, query_string).to_string()
+        }
+    };
+}
+
+macro_rules! count_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$.a...a.c" => 2; "self and deeper descendant")]
+        #[test_case("$..a...a.c" => 3; "every value counted once")]
+        #[test_case("$.x...a" => 1; "parent with different key")]
+        #[test_case("$.*...a" => 3; "wildcard parents")]
+        #[test_case("$.a...b...a" => 1; "chained selectors")]
+        #[test_case("$.a...b.a.c" => 1; "self continued with children")]
+        fn $test_name(query_string: &str) -> usize {
+            run::<CountResult, $impl>(DOCUMENT, query_string).get()
+        }
+    };
+}
+
+nested_test_cases!(main_nested_test, MainEngine);
+nested_test_cases!(recursive_nested_test, RecursiveEngine);
+count_test_cases!(main_count_test, MainEngine);
+count_test_cases!(recursive_count_test, RecursiveEngine);
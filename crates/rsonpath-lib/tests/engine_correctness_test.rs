@@ -34,6 +34,7 @@ macro_rules! count_test_cases {
         #[test_case("basic/root.json", "" => 1; "root.json")]
         #[test_case("basic/singletons_and_empties.json", r#"$.*.*"# => 2; "singletons_and_empties.json")]
         #[test_case("basic/skipping.json", r#"$.a.b"# => 1; "skipping")]
+        #[test_case("basic/skipping_siblings.json", r#"$.x.*.b"# => 3; "skipping siblings")]
         #[test_case("basic/small_no_list.json", "$..person..phoneNumber..number" => 2; "small_no_list.json $..person..phoneNumber..number")]
         #[test_case("basic/small.json", "$..person..phoneNumber..number" => 4; "small.json $..person..phoneNumber..number")]
         #[test_case("basic/spaced_colon.json", r#"$..a..b..label"# => 2; "spaced colon")]
@@ -59,6 +60,7 @@ macro_rules! count_test_cases {
         #[test_case("basic/compressed/quote_escape.json", r#"$['"x']"# => 1; "compressed quote_escape.json with quote")]
         #[test_case("basic/compressed/singletons_and_empties.json", r#"$.*.*"# => 2; "compressed singletons_and_empties.json")]
         #[test_case("basic/compressed/skipping.json", r#"$.a.b"# => 1; "compressed skipping")]
+        #[test_case("basic/compressed/skipping_siblings.json", r#"$.x.*.b"# => 3; "compressed skipping siblings")]
         #[test_case("basic/compressed/small_no_list.json", "$..person..phoneNumber..number" => 2; "compressed small_no_list.json $..person..phoneNumber..number")]
         #[test_case("basic/compressed/small.json", "$..person..phoneNumber..number" => 4; "compressed small.json $..person..phoneNumber..number")]
         #[test_case("twitter/compressed/twitter.json", "$..user..entities..url" => 44; "compressed twitter.json $..user..entities..url (recursive)")]
@@ -163,6 +165,7 @@ macro_rules! indices_test_cases {
         #[test_case("basic/root.json", "" => vec![0]; "root.json")]
         #[test_case("basic/singletons_and_empties.json", r#"$.*.*"# => vec![21, 50]; "singletons_and_empties.json")]
         #[test_case("basic/skipping.json", r#"$.a.b"# => vec![808]; "skipping")]
+        #[test_case("basic/skipping_siblings.json", r#"$.x.*.b"# => vec![38, 98, 215]; "skipping siblings")]
         #[test_case("basic/small_no_list.json", "$..person..phoneNumber..number" => vec![310, 764]; "small_no_list.json $..person..phoneNumber..number")]
         #[test_case("basic/small.json", "$..person..phoneNumber..number" => vec![332, 436, 934, 1070]; "small.json $..person..phoneNumber..number")]
         #[test_case("basic/spaced_colon.json", r#"$..a..b..label"# => vec![106, 213]; "spaced colon")]
@@ -190,6 +193,7 @@ macro_rules! indices_test_cases {
         #[test_case("basic/compressed/quote_escape.json", r#"$['x']"# => vec![13]; "compressed quote_escape.json without quote")]
         #[test_case("basic/compressed/singletons_and_empties.json", r#"$.*.*"# => vec![6, 15]; "compressed singletons_and_empties.json")]
         #[test_case("basic/compressed/skipping.json", r#"$.a.b"# => vec![452]; "compressed skipping")]
+        #[test_case("basic/compressed/skipping_siblings.json", r#"$.x.*.b"# => vec![10, 24, 50]; "compressed skipping siblings")]
         #[test_case("basic/compressed/small_no_list.json", "$..person..phoneNumber..number" => vec![176, 380]; "compressed small_no_list.json $..person..phoneNumber..number")]
         #[test_case("basic/compressed/small.json", "$..person..phoneNumber..number" => vec![177, 219, 425, 467]; "compressed small.json $..person..phoneNumber..number")]
         #[test_case(
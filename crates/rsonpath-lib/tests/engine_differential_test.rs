@@ -0,0 +1,240 @@
+// Differential tests running the same queries through both engines.
+// The engines must give identical results on every input, so any difference is a bug in one of them.
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, IndexResult, QueryResult};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const ROOT_TEST_DIRECTORY: &str = "./tests/data";
+/// Documents larger than this are left out of the corpus to keep the test fast.
+const MAX_DOCUMENT_SIZE: u64 = 1 << 20;
+/// Number of distinct keys of a document used to generate queries for it.
+const KEYS_PER_DOCUMENT: usize = 6;
+
+fn run<R: QueryResult, E: Compiler>(query: &JsonPathQuery, json: &str) -> R {
+    let mut raw = json.to_owned();
+    let contents = Input::new(&mut raw);
+    let engine = E::compile_query(query).unwrap();
+
+    engine.run::<R>(&contents).unwrap()
+}
+
+fn assert_engines_agree(query_string: &str, json: &str, description: &str) {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let main_count = run::<CountResult, MainEngine>(&query, json);
+    let recursive_count = run::<CountResult, RecursiveEngine>(&query, json);
+    assert_eq!(
+        main_count, recursive_count,
+        "count of {query_string} differs on {description}"
+    );
+
+    let main_indices = run::<IndexResult, MainEngine>(&query, json);
+    let recursive_indices = run::<IndexResult, RecursiveEngine>(&query, json);
+    assert_eq!(
+        main_indices, recursive_indices,
+        "indices of {query_string} differ on {description}"
+    );
+}
+
+fn corpus_documents() -> Vec<PathBuf> {
+    fn visit(directory: &Path, documents: &mut Vec<PathBuf>) {
+        for entry in fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                visit(&path, documents);
+            } else if path.extension().map_or(false, |ext| ext == "json")
+                && fs::metadata(&path).unwrap().len() <= MAX_DOCUMENT_SIZE
+            {
+                documents.push(path);
+            }
+        }
+    }
+
+    let mut documents = vec![];
+    visit(Path::new(ROOT_TEST_DIRECTORY), &mut documents);
+    documents.sort();
+    documents
+}
+
+/// Find the first `count` distinct keys in the document that can be put in a quoted label verbatim.
+fn distinct_keys(json: &str, count: usize) -> Vec<String> {
+    let bytes = json.as_bytes();
+    let mut keys: Vec<String> = vec![];
+    let mut idx = 0;
+
+    while idx < bytes.len() && keys.len() < count {
+        if bytes[idx] != b'"' {
+            idx += 1;
+            continue;
+        }
+        let start = idx + 1;
+        idx = start;
+        while idx < bytes.len() && bytes[idx] != b'"' {
+            idx += if bytes[idx] == b'\\' { 2 } else { 1 };
+        }
+        let string = &json[start..idx.min(bytes.len())];
+        idx += 1;
+
+        let is_key = json[idx.min(bytes.len())..].trim_start().starts_with(':');
+        if is_key && !string.contains(['\\', '\'']) && !keys.iter().any(|k| k == string) {
+            keys.push(string.to_owned());
+        }
+    }
+
+    keys
+}
+
+/// Generate queries exercising all kinds of selectors with the given keys.
+fn queries_for_keys(keys: &[String]) -> Vec<String> {
+    let mut queries = vec![
+        "$".to_owned(),
+        "$.*".to_owned(),
+        "$.*.*".to_owned(),
+        "$..[?(@ is scalar)]".to_owned(),
+    ];
+
+    for key in keys {
+        queries.push(format!("$['{key}']"));
+        queries.push(format!("$..['{key}']"));
+        queries.push(format!("$..['{key}'].*"));
+        queries.push(format!("$..['{key}'][*].*"));
+        queries.push(format!("$.*..['{key}']"));
+        queries.push(format!("$...['{key}']"));
+        queries.push(format!("$..['{key}'][?(@ is scalar)]"));
+    }
+    for pair in keys.windows(2) {
+        queries.push(format!("$..['{}']..['{}']", pair[0], pair[1]));
+        queries.push(format!("$..['{}'].*..['{}']", pair[0], pair[1]));
+        queries.push(format!("$..['{}']..['{}']", pair[1], pair[0]));
+    }
+
+    queries
+}
+
+#[test]
+fn engines_agree_on_corpus() {
+    let documents = corpus_documents();
+    assert!(!documents.is_empty());
+
+    for path in documents {
+        let json = fs::read_to_string(&path).unwrap();
+        let description = path.display().to_string();
+
+        for query_string in queries_for_keys(&distinct_keys(&json, KEYS_PER_DOCUMENT)) {
+            assert_engines_agree(&query_string, &json, &description);
+        }
+    }
+}
+
+mod proptests {
+    use super::assert_engines_agree;
+    use proptest::prelude::*;
+
+    /// JSON value generated for differential testing, with keys from a small alphabet
+    /// so that generated queries match often.
+    #[derive(Debug, Clone)]
+    enum Value {
+        Atom(&'static str),
+        Array(Vec<Value>),
+        Object(Vec<(&'static str, Value)>),
+    }
+
+    impl Value {
+        fn write(&self, out: &mut String, whitespace: &str) {
+            match self {
+                Self::Atom(atom) => out.push_str(atom),
+                Self::Array(elements) => {
+                    out.push('[');
+                    for (i, element) in elements.iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        out.push_str(whitespace);
+                        element.write(out, whitespace);
+                    }
+                    out.push_str(whitespace);
+                    out.push(']');
+                }
+                Self::Object(members) => {
+                    out.push('{');
+                    for (i, (key, value)) in members.iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        out.push_str(whitespace);
+                        out.push_str(&format!("\"{key}\":"));
+                        out.push_str(whitespace);
+                        value.write(out, whitespace);
+                    }
+                    out.push_str(whitespace);
+                    out.push('}');
+                }
+            }
+        }
+    }
+
+    fn any_key() -> impl Strategy<Value = &'static str> {
+        prop_oneof![Just("a"), Just("b"), Just("c")]
+    }
+
+    fn any_value() -> impl Strategy<Value = Value> {
+        let atom = prop_oneof![
+            Just("42"),
+            Just("-1.5e3"),
+            Just("null"),
+            Just("true"),
+            Just(r#""a""#),
+            Just(r#""{[,:\"]}""#),
+        ]
+        .prop_map(Value::Atom);
+
+        atom.prop_recursive(5, 64, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+                // Keys are unique, since the engines assume that with the `unique-labels` feature.
+                prop::collection::btree_map(any_key(), inner, 0..4)
+                    .prop_map(|members| Value::Object(members.into_iter().collect())),
+            ]
+        })
+    }
+
+    prop_compose! {
+        fn any_document()(value in any_value(), whitespace in prop_oneof![Just(""), Just(" "), Just("\n  ")]) -> String {
+            let mut json = String::new();
+            value.write(&mut json, whitespace);
+            json
+        }
+    }
+
+    fn any_selector() -> impl Strategy<Value = String> {
+        prop_oneof![
+            any_key().prop_map(|key| format!(".{key}")),
+            any_key().prop_map(|key| format!("..{key}")),
+            any_key().prop_map(|key| format!("...{key}")),
+            Just(".*".to_owned()),
+            Just("[*]".to_owned()),
+            (0..3_usize).prop_map(|index| format!("[{index}]")),
+        ]
+    }
+
+    prop_compose! {
+        fn any_query()(selectors in prop::collection::vec(any_selector(), 1..5), is_filtered in any::<bool>()) -> String {
+            let mut query = format!("${}", selectors.concat());
+            if is_filtered {
+                query += "[?(@ is scalar)]";
+            }
+            query
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn engines_agree_on_generated_documents(json in any_document(), query_string in any_query()) {
+            assert_engines_agree(&query_string, &json, &json);
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::{
+    automaton::Automaton, error::CompilerError, Comparison, FilterExpression, JsonLiteral,
+    JsonPathQuery, Label, NumberSyntax, NumericRange,
+};
+use rsonpath_lib::result::{CountResult, IndexResult, PathResult};
+use test_case::test_case;
+
+const DOCUMENT: &str = r#"{
+  "items": [
+    {"id": 1, "meta": {"k": 1}},
+    {"id": 2, "meta": { "k" : 1 }},
+    {"id": 3, "meta": {"k": 1, "l": [1, 2]}},
+    {"id": 4, "meta": {"l": [1, 2], "k": 1}},
+    {"id": 5, "meta": {"k": "a b"}},
+    {"id": 6, "meta": 42},
+    {"id": 7}
+  ],
+  "byName": {
+    "x": {"tags": ["a", "b"]},
+    "y": {"tags": [ "a" , "b" ]},
+    "z": {"tags": ["b", "a"]}
+  }
+}"#;
+
+const SCALARS_DOCUMENT: &str = r#"{
+  "a": "s",
+  "b": -1.5e3,
+  "c": true,
+  "d": false,
+  "e": null,
+  "f": {"g": [1, {"h": "x"}, [], {}, ["[", "{"]]},
+  "i": []
+}"#;
+
+macro_rules! filter_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"$.items[?(@.meta == {"k":1})]"# => 2; "object literal")]
+        #[test_case(r#"$.items[?(@.meta == {"k": 1, "l": [1, 2]})]"# => 1; "key order matters")]
+        #[test_case(r#"$.items[?(@.meta == {"l":[1,2],"k":1})]"# => 1; "key order matters reversed")]
+        #[test_case(r#"$.items[?(@.meta == {"k": "a b"})]"# => 1; "whitespace in string")]
+        #[test_case(r#"$.items[?(@.meta == {"k": "ab"})]"# => 0; "whitespace in string is significant")]
+        #[test_case(r#"$.items[?(@['meta'] == 42)]"# => 1; "number literal with index path")]
+        #[test_case(r#"$.items[?(@.meta.k == 1)]"# => 4; "nested path")]
+        #[test_case(r#"$.items[?(@.meta.k==1.0)]"# => 0; "number representation matters")]
+        #[test_case(r#"$.byName[?(@.tags == ["a", "b"])]"# => 2; "object members")]
+        #[test_case(r#"$..meta[?(@ == 1)]"# => 4; "current value")]
+        #[test_case(r#"$.items[?(@.meta is scalar)]"# => 1; "scalar member")]
+        #[test_case(r#"$..[?(@.k == 1)]"# => 4; "descendant filter")]
+        fn $test_name(query_string: &str) -> usize {
+            let mut raw = DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap();
+
+            result.get()
+        }
+    };
+}
+
+const KEYWORDS_DOCUMENT: &str = r#"{
+  "flags": {
+    "a": true,
+    "b": false,
+    "c": null,
+    "d": "true",
+    "e": 1,
+    "f": {"g": true, "h": [true, false]},
+    "i": truent,
+    "j": tru,
+    "k": [true]
+  },
+  "other": {"l": true, "m": nullable}
+}"#;
+
+const ELEMENTS_DOCUMENT: &str = r#"{
+  "users": [
+    {"id": 41, "name": "a", "tags": [{"id": 42}]},
+    {"id": 42, "name": "b", "friends": [{"id": 42}]},
+    {"name": "c", "profile": {"x": [1, {"id": 42}]}, "id": 42},
+    {"name": "d", "id": 420},
+    [{"id": 42}],
+    {"id": "42"}
+  ]
+}"#;
+
+const RANGES_DOCUMENT: &str = r#"{
+  "items": [
+    {"x": 0},
+    {"x": 1},
+    {"x": 5.5},
+    {"x": 10},
+    {"x": -3},
+    {"x": "5"},
+    {"x": [5]},
+    {"x": null},
+    {"y": 5},
+    {"x": 1e1},
+    {"x": 9.999}
+  ]
+}"#;
+
+macro_rules! scalar_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"$..[?(@ is scalar)]"# => 9; "all scalars")]
+        #[test_case(r#"$..f..[?(@ is scalar)]"# => 4; "scalars under descendant")]
+        #[test_case(r#"$.f.g[?(@ is scalar)]"# => 1; "scalar elements")]
+        #[test_case(r#"$.f.g.*[?(@ is scalar)]"# => 3; "scalars in nested containers")]
+        #[test_case(r#"$[?(@ is scalar)]"# => 5; "scalar children of root")]
+        #[test_case(r#"$..[?(@.h is scalar)]"# => 1; "containers with scalar member")]
+        #[test_case(r#"$.i[?(@ is scalar)]"# => 0; "empty array")]
+        fn $test_name(query_string: &str) -> usize {
+            let mut raw = SCALARS_DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap();
+
+            result.get()
+        }
+    };
+}
+
+const SETS_DOCUMENT: &str = r#"{
+  "users": [
+    {"status": "active"},
+    {"status": "\u0061ctive"},
+    {"status": "pending"},
+    {"status": "banned"},
+    {"status": "a\"b"},
+    {"status": 1.0},
+    {"status": -0},
+    {"status": null},
+    {"status": ["active"]},
+    {"status": {"k": 1}},
+    {"level": "active"}
+  ]
+}"#;
+
+macro_rules! set_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"$.users[?(@.status in ['active', 'pending'])]"# => 3; "single quoted strings")]
+        #[test_case(r#"$.users[?(@.status in ["active","pending"])]"# => 3; "double quoted strings")]
+        #[test_case(r#"$.users[?(@.status in ["\u0061ctive"])]"# => 2; "escaped literal")]
+        #[test_case(r#"$.users[?(@.status in ['a"b', "a\"b"])]"# => 1; "escaped quotes")]
+        #[test_case(r#"$.users[?(@.status in [1])]"# => 1; "number compared by value")]
+        #[test_case(r#"$.users[?(@.status in [0.0e1, 7])]"# => 1; "negative zero")]
+        #[test_case(r#"$.users[?(@.status in [null, true])]"# => 1; "keywords")]
+        #[test_case(r#"$.users[?(@.status in [ ["active"] , {"k":1} ])]"# => 2; "complex values")]
+        #[test_case(r#"$.users[?(@.status in ["1", "null"])]"# => 0; "strings are not numbers or keywords")]
+        #[test_case(r#"$.users[?(@.status in [])]"# => 0; "empty set")]
+        #[test_case(r#"$.users.*[?(@ in ['active', 1])]"# => 4; "current value")]
+        #[test_case(r#"$..[?(@ in ['active'])]"# => 4; "descendants")]
+        fn $test_name(query_string: &str) -> usize {
+            let mut raw = SETS_DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap();
+
+            result.get()
+        }
+    };
+}
+
+const SIZES_DOCUMENT: &str = r#"{
+  "configs": [
+    {},
+    { },
+    {"a": 1},
+    {"a": {"x": 1, "y": 2, "z": 3}, "b": [{"c": 1, "d": 2}]},
+    {"a:b": "c:d", "e": "}", "f": "{"},
+    [],
+    [1, 2, 3],
+    "{}",
+    null,
+    {"meta": {"k": 1, "l": 2}}
+  ]
+}"#;
+
+macro_rules! size_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"$.configs[?(size(@) == 0)]"# => 2; "empty objects")]
+        #[test_case(r#"$.configs[?(size(@)==1)]"# => 2; "no whitespace")]
+        #[test_case(r#"$.configs[?(size(@) == 2)]"# => 1; "nested members are not counted")]
+        #[test_case(r#"$.configs[?(size(@) == 3)]"# => 1; "colons and brackets in strings")]
+        #[test_case(r#"$.configs[?(size(@) != 0)]"# => 4; "arrays and scalars are not objects")]
+        #[test_case(r#"$.configs[?(size(@) < 2)]"# => 4; "less")]
+        #[test_case(r#"$.configs[?(size(@) <= 2)]"# => 5; "less or equal")]
+        #[test_case(r#"$.configs[?(size(@) > 1)]"# => 2; "greater")]
+        #[test_case(r#"$.configs[?(size(@) >= 0)]"# => 6; "all objects")]
+        #[test_case(r#"$.configs[?(size( @.meta ) == 2)]"# => 1; "size at path")]
+        #[test_case(r#"$.configs[?(size(@.a) == 3)]"# => 1; "size of member")]
+        #[test_case(r#"$..[?(size(@) == 2)]"# => 3; "descendants")]
+        fn $test_name(query_string: &str) -> usize {
+            let mut raw = SIZES_DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap();
+
+            result.get()
+        }
+    };
+}
+
+const SLOPPY_NUMBERS_DOCUMENT: &str = r#"{
+  "items": [
+    {"x": 5},
+    {"x": 05},
+    {"x": +5},
+    {"x": -005.0},
+    {"x": 5.},
+    {"x": +},
+    {"x": +-5},
+    {"x": "+5"}
+  ]
+}"#;
+
+macro_rules! number_syntax_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"$.items[?(@.x in 5..=5)]"# => (1, 4); "range")]
+        #[test_case(r#"$.items.*[?(@ in 5..=5)]"# => (1, 4); "range of current value")]
+        #[test_case(r#"$.items[?(@.x in [-5, 5])]"# => (1, 5); "set")]
+        #[test_case(r#"$.items.*[?(@ in [5, "+5"])]"# => (2, 5); "set with strings")]
+        #[test_case(r#"$.items[?(@.x == 5)]"# => (1, 1); "equality is textual")]
+        fn $test_name(query_string: &str) -> (usize, usize) {
+            let mut raw = SLOPPY_NUMBERS_DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let count = |number_syntax| {
+                let automaton = Automaton::new(&query)
+                    .unwrap()
+                    .with_number_syntax(number_syntax);
+                $impl::from_compiled_query(automaton)
+                    .run::<CountResult>(&contents)
+                    .unwrap()
+                    .get()
+            };
+
+            (count(NumberSyntax::Strict), count(NumberSyntax::Lenient))
+        }
+    };
+}
+
+macro_rules! range_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"$.items[?(@.x in 1..10)]"# => 3; "exclusive end")]
+        #[test_case(r#"$.items[?(@.x in 1..=10)]"# => 5; "inclusive end")]
+        #[test_case(r#"$.items[?(@.x in -5..0)]"# => 1; "negative start")]
+        #[test_case(r#"$.items[?(@.x in -5..=0)]"# => 2; "inclusive zero")]
+        #[test_case(r#"$.items[?(@.x in 10..1)]"# => 0; "empty range")]
+        #[test_case(r#"$.items.*[?(@ in 5..6)]"# => 2; "strings and arrays are not numbers")]
+        #[test_case(r#"$..[?(@ in 0..=10)]"# => 8; "descendant numbers")]
+        fn $test_name(query_string: &str) -> usize {
+            let mut raw = RANGES_DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap();
+
+            result.get()
+        }
+    };
+}
+
+macro_rules! keyword_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"$.flags[?(@ == true)]"# => 1; "true member")]
+        #[test_case(r#"$.flags[?(@ == false)]"# => 1; "false member")]
+        #[test_case(r#"$.flags[?(@ == null)]"# => 1; "null member")]
+        #[test_case(r#"$.flags[?(@ == "true")]"# => 1; "string is not a keyword")]
+        #[test_case(r#"$.*[?(@ == true)]"# => 2; "wildcard parent")]
+        #[test_case(r#"$.*[?(@==null)]"# => 1; "partial keyword prefix")]
+        #[test_case(r#"$.flags.*[?(@ == true)]"# => 2; "nested elements")]
+        #[test_case(r#"$..[?(@ == true)]"# => 5; "descendants")]
+        #[test_case(r#"$..[?(@ == false)]"# => 2; "false descendants")]
+        #[test_case(r#"$.flags[?(@.g == true)]"# => 1; "keyword at path")]
+        fn $test_name(query_string: &str) -> usize {
+            let mut raw = KEYWORDS_DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap();
+
+            result.get()
+        }
+    };
+}
+
+macro_rules! element_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$.users[?(@.id == 42)]" => "$['users'][1]\n$['users'][2]\n"; "first and last key")]
+        #[test_case("$.users.*[?(@.id == 42)]" => "$['users'][4][0]\n"; "nested elements")]
+        #[test_case("$..[?(@.id == 42)]" => "$['users'][0]['tags'][0]\n$['users'][1]\n$['users'][1]['friends'][0]\n$['users'][2]\n$['users'][2]['profile']['x'][1]\n$['users'][4][0]\n"; "descendants")]
+        #[test_case("$.users[?(@.id == 7)]" => ""; "no matches")]
+        fn $test_name(query_string: &str) -> String {
+            let mut raw = ELEMENTS_DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let indices = engine.run::<IndexResult>(&contents).unwrap();
+            for &idx in indices.get() {
+                let element = ELEMENTS_DOCUMENT[idx..].trim_start_matches([':', ',', ' ', '\n']);
+                assert!(element.starts_with("{"), "match at {idx} is not an object");
+            }
+
+            engine.run::<PathResult>(&contents).unwrap().to_string()
+        }
+    };
+}
+
+filter_test_cases!(main_filter_test, MainEngine);
+filter_test_cases!(recursive_filter_test, RecursiveEngine);
+scalar_test_cases!(main_scalar_test, MainEngine);
+scalar_test_cases!(recursive_scalar_test, RecursiveEngine);
+set_test_cases!(main_set_test, MainEngine);
+set_test_cases!(recursive_set_test, RecursiveEngine);
+size_test_cases!(main_size_test, MainEngine);
+size_test_cases!(recursive_size_test, RecursiveEngine);
+number_syntax_test_cases!(main_number_syntax_test, MainEngine);
+number_syntax_test_cases!(recursive_number_syntax_test, RecursiveEngine);
+range_test_cases!(main_range_test, MainEngine);
+range_test_cases!(recursive_range_test, RecursiveEngine);
+keyword_test_cases!(main_keyword_test, MainEngine);
+keyword_test_cases!(recursive_keyword_test, RecursiveEngine);
+element_test_cases!(main_element_test, MainEngine);
+element_test_cases!(recursive_element_test, RecursiveEngine);
+
+#[test]
+fn large_set_matches_only_its_members() {
+    let members: Vec<_> = (0..1000).map(|i| format!("'s{i}'")).collect();
+    let query_string = format!("$.*[?(@ in [{}])]", members.join(", "));
+    let mut raw = r#"[["s0", "s999", "s1000", "s-1", 0]]"#.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(&query_string).unwrap();
+
+    let result = MainEngine::compile_query(&query)
+        .unwrap()
+        .run::<CountResult>(&contents)
+        .unwrap();
+
+    assert_eq!(result.get(), 2);
+}
+
+#[test_case(1.0, 10.0 => true; "finite")]
+#[test_case(-0.0, f64::MAX => true; "extreme finite")]
+#[test_case(f64::NAN, 10.0 => false; "nan start")]
+#[test_case(1.0, f64::NAN => false; "nan end")]
+#[test_case(f64::NEG_INFINITY, 10.0 => false; "infinite start")]
+#[test_case(1.0, f64::INFINITY => false; "infinite end")]
+fn numeric_range_requires_finite_bounds(start: f64, end: f64) -> bool {
+    let exclusive = NumericRange::new(start, end);
+    let inclusive = NumericRange::new_inclusive(start, end);
+    assert_eq!(exclusive.is_some(), inclusive.is_some());
+
+    exclusive.is_some()
+}
+
+#[test_case(&["meta"], r#"{"k": 1}"#, r#"{"meta": {"k": 1}, "rest": [[["# => true; "stops after object literal")]
+#[test_case(&["meta", "k"], "1", r#"{"meta": {"k": 1, "l": [[["# => true; "stops at found label")]
+#[test_case(&["meta"], "1", r#"{"meta": [[[["# => false; "rejects array by first byte")]
+#[test_case(&["id", "k"], "1", r#"{"id": 1, "meta": {"k": 1}"# => false; "stops at missing label")]
+fn filter_reads_only_what_comparison_needs(path: &[&str], literal: &str, json: &str) -> bool {
+    let path = path.iter().copied().map(Label::new).collect();
+    let literal = JsonLiteral::parse(literal).unwrap();
+
+    FilterExpression::new(path, Comparison::Equal(literal)).matches(json.as_bytes())
+}
+
+#[test]
+fn non_terminal_filter_is_not_supported() {
+    let query = JsonPathQuery::parse(r#"$.items[?(@.id == 1)].meta"#).unwrap();
+
+    let result = Automaton::new(&query);
+
+    assert!(matches!(result, Err(CompilerError::NotSupported(_))));
+}
+
+#[test]
+fn non_terminal_descendant_filter_is_not_supported() {
+    let query = JsonPathQuery::parse(r#"$..[?(@ is scalar)].meta"#).unwrap();
+
+    let result = Automaton::new(&query);
+
+    assert!(matches!(result, Err(CompilerError::NotSupported(_))));
+}
+
+#[test]
+fn filter_in_alternation_is_not_supported() {
+    let queries = [
+        JsonPathQuery::parse(r#"$.items[?(@.id == 1)]"#).unwrap(),
+        JsonPathQuery::parse("$.byName").unwrap(),
+    ];
+
+    let result = Automaton::new_alternation(&queries);
+
+    assert!(matches!(result, Err(CompilerError::NotSupported(_))));
+}
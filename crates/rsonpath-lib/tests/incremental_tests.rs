@@ -0,0 +1,70 @@
+use rsonpath_lib::engine::incremental::{Edit, IncrementalQuery, UpdateKind};
+use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+use rsonpath_lib::query::{automaton::Automaton, JsonPathQuery};
+use rsonpath_lib::result::IndexResult;
+use test_case::test_case;
+
+const DOCUMENT: &str =
+    r#"{"a": {"price": 1, "name": "x"}, "b": [{"price": 22}, "\\"], "c": {"price": true}}"#;
+
+/// Apply the edit to the document, update the query and check that its matches
+/// are the same as those of a fresh run on the edited document.
+fn edit(
+    document: &str,
+    query_string: &str,
+    range: std::ops::Range<usize>,
+    inserted: &str,
+) -> UpdateKind {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let mut contents = document.to_owned();
+    let mut incremental =
+        IncrementalQuery::new(Automaton::new(&query).unwrap(), &Input::new(&mut contents)).unwrap();
+
+    let mut edited = document.to_owned();
+    edited.replace_range(range.clone(), inserted);
+    let input = Input::new(&mut edited);
+    let edit = Edit {
+        start: range.start,
+        removed: document[range].as_bytes(),
+        inserted_len: inserted.len(),
+    };
+    let update = incremental.update(&input, &edit).unwrap();
+
+    let expected = RsonpathEngine::compile_query(&query)
+        .unwrap()
+        .run::<IndexResult>(&input)
+        .unwrap();
+    assert_eq!(incremental.get(), expected.get());
+
+    update
+}
+
+#[test_case("$..price", 16..17, "100" => UpdateKind::Shifted; "longer number")]
+#[test_case("$..price", 49..51, "3" => UpdateKind::Shifted; "shorter number")]
+#[test_case("$..price", 28..29, "xyz" => UpdateKind::Shifted; "string value")]
+#[test_case("$..price", 5..6, "\n    " => UpdateKind::Shifted; "whitespace before matched value")]
+#[test_case("$..price", 15..16, "" => UpdateKind::Shifted; "whitespace right before match")]
+#[test_case("$.b.*", 39..39, "  " => UpdateKind::Shifted; "whitespace before first element")]
+#[test_case("$..price", 76..80, "false" => UpdateKind::Shifted; "literal")]
+#[test_case("$..price", 8..13, "cost" => UpdateKind::Rerun; "key")]
+#[test_case("$..price", 20..22, "nam" => UpdateKind::Rerun; "unmatched key")]
+#[test_case("$..price", 16..17, "[1]" => UpdateKind::Rerun; "structural characters")]
+#[test_case("$..price", 28..29, "\\\"" => UpdateKind::Rerun; "quote")]
+#[test_case("$..price", 57..57, "x" => UpdateKind::Rerun; "after backslash")]
+#[test_case("$.a[?(@ == 1)]", 16..17, "2" => UpdateKind::Rerun; "filter")]
+fn update(query_string: &str, range: std::ops::Range<usize>, inserted: &str) -> UpdateKind {
+    edit(DOCUMENT, query_string, range, inserted)
+}
+
+#[test_case(r#"{"a": [1]}"#, 7..8, "" => UpdateKind::Rerun; "sole element removed")]
+#[test_case(r#"{"a": []}"#, 7..7, "1" => UpdateKind::Rerun; "sole element inserted")]
+#[test_case(r#"{"a": [ ]}"#, 8..8, "1" => UpdateKind::Rerun; "element inserted after whitespace")]
+#[test_case(r#"{"a": [1]}"#, 7..7, "2" => UpdateKind::Rerun; "sole element extended at start")]
+#[test_case(r#"{"a": [1, 2]}"#, 7..7, " " => UpdateKind::Rerun; "whitespace after opening bracket")]
+#[test_case(r#"{"a": [1, 2]}"#, 10..10, "3" => UpdateKind::Rerun; "element after comma")]
+#[test_case(r#"{"a": [12]}"#, 8..8, "3" => UpdateKind::Shifted; "within sole element")]
+#[test_case(r#"{"a": [ 1]}"#, 8..8, " " => UpdateKind::Shifted; "whitespace before sole element")]
+#[test_case(r#"{"a": [{"b": 1}]}"#, 7..7, " " => UpdateKind::Shifted; "whitespace before object element")]
+fn update_list(document: &str, range: std::ops::Range<usize>, inserted: &str) -> UpdateKind {
+    edit(document, "$.a.*", range, inserted)
+}
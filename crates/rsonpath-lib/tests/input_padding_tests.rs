@@ -0,0 +1,79 @@
+use rsonpath_lib::engine::error::InvalidPaddingError;
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{IndexResult, SpanResult};
+use std::ops::Range;
+use test_case::test_case;
+
+macro_rules! padding_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"a": [{"a": 1}, "x"]}"#, "$..a", b' '; "descendant with space")]
+        #[test_case(r#"{"a": [{"a": 1}, "x"]}"#, "$.a.*", b'\t'; "wildcard with tab")]
+        #[test_case(r#"{"b": {"a": 42}}"#, "$.b.a", b'\n'; "atomic child with newline")]
+        #[test_case(r#"[1, 2, [3]]"#, "$.*", b'\r'; "list with carriage return")]
+        #[test_case(r#"{"a": 1}"#, "$", 0; "root with zero")]
+        fn $test_name(json: &str, query_string: &str, padding: u8) {
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+            let mut raw = json.to_owned();
+            let expected = engine.run::<IndexResult>(&Input::new(&mut raw)).unwrap();
+
+            let mut raw = json.as_bytes().to_vec();
+            let input = Input::new_bytes_with_padding(&mut raw, padding).unwrap();
+            let result = engine.run::<IndexResult>(&input).unwrap();
+
+            assert_eq!(result, expected);
+        }
+    };
+}
+
+padding_test_cases!(main_padding_test, MainEngine);
+padding_test_cases!(recursive_padding_test, RecursiveEngine);
+
+macro_rules! root_padding_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("123", b' ' => vec![0..3]; "root number with space")]
+        #[test_case("true", b'\t' => vec![0..4]; "root literal with tab")]
+        #[test_case(" -1.5 ", b'\n' => vec![1..5]; "root number with whitespace and newline")]
+        #[test_case("\"x\"", 0 => vec![0..3]; "root string with zero")]
+        #[test_case("", b' ' => Vec::<Range<usize>>::new(); "empty with space")]
+        #[test_case("  \n", b'\r' => Vec::<Range<usize>>::new(); "whitespace only with carriage return")]
+        fn $test_name(json: &str, padding: u8) -> Vec<Range<usize>> {
+            let query = JsonPathQuery::parse("$").unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let mut raw = json.as_bytes().to_vec();
+            let input = Input::new_bytes_with_padding(&mut raw, padding).unwrap();
+
+            engine.run::<SpanResult>(&input).unwrap().into()
+        }
+    };
+}
+
+root_padding_test_cases!(main_root_padding_test, MainEngine);
+root_padding_test_cases!(recursive_root_padding_test, RecursiveEngine);
+
+#[test_case(b'{')]
+#[test_case(b'}')]
+#[test_case(b'[')]
+#[test_case(b']')]
+#[test_case(b':')]
+#[test_case(b',')]
+#[test_case(b'"')]
+#[test_case(b'\\')]
+#[test_case(b'#')]
+#[test_case(b'0')]
+#[test_case(b'1')]
+#[test_case(b't')]
+#[test_case(b'a')]
+#[test_case(0xFF)]
+fn non_whitespace_padding_is_an_error(padding: u8) {
+    let mut raw = br#"{"a": 1}"#.to_vec();
+
+    let result = Input::new_bytes_with_padding(&mut raw, padding);
+
+    assert!(matches!(result, Err(InvalidPaddingError(byte)) if byte == padding));
+    assert_eq!(raw, br#"{"a": 1}"#);
+}
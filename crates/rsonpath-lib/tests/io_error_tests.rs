@@ -0,0 +1,69 @@
+use rsonpath_lib::engine::error::EngineError;
+use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+use rsonpath_lib::query::automaton::Automaton;
+use rsonpath_lib::query::error::CompilerError;
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::CountResult;
+use std::io;
+
+fn count(query_string: &str, json: &str) -> io::Result<usize> {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+    let engine = RsonpathEngine::compile_query(&query)?;
+
+    Ok(engine.run::<CountResult>(&input)?.get())
+}
+
+#[test]
+fn engine_error_is_invalid_data() {
+    let error = count("$..a", r#"{"a": [1, 2"#).unwrap_err();
+
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(
+        error.to_string(),
+        EngineError::MissingClosingCharacter().to_string()
+    );
+    assert!(matches!(
+        error
+            .into_inner()
+            .unwrap()
+            .downcast::<EngineError>()
+            .as_deref(),
+        Ok(EngineError::MissingClosingCharacter())
+    ));
+}
+
+#[test]
+fn compiler_error_is_invalid_input() {
+    let query = JsonPathQuery::parse("$..a").unwrap();
+    let compiler_error = Automaton::new_complement(&query).unwrap_err();
+    let message = compiler_error.to_string();
+
+    let error = io::Error::from(compiler_error);
+
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(error.to_string(), message);
+    assert!(matches!(
+        error
+            .into_inner()
+            .unwrap()
+            .downcast::<CompilerError>()
+            .as_deref(),
+        Ok(CompilerError::InvalidComplement)
+    ));
+}
+
+#[test]
+fn unsupported_feature_is_unsupported() {
+    let error = count("$.a[last()].b", "{}").unwrap_err();
+
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn successful_run_is_ok() {
+    let result = count("$..a", r#"{"a": [1, {"a": 2}]}"#);
+
+    assert_eq!(result.unwrap(), 2);
+}
@@ -0,0 +1,107 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::{automaton::Automaton, error::CompilerError, JsonPathQuery};
+use rsonpath_lib::result::{CountResult, PathResult, QueryResult};
+use test_case::test_case;
+
+const DOCUMENT: &str = r#"{
+  "a": {"x": 1, "y": [1, 2], "z": {"w": 3}},
+  "b": {},
+  "c": [1, {"v": 2}, 3],
+  "d": {"only": true}
+}"#;
+
+const NESTED_DOCUMENT: &str = r#"{"a": {"p": 1, "a": {"q": 2, "r": [3]}}}"#;
+
+fn run<R: QueryResult, E: Compiler>(json: &str, query_string: &str) -> R {
+    let mut raw = json.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let engine = E::compile_query(&query).unwrap();
+
+    engine.run::<R>(&contents).unwrap()
+}
+
+macro_rules! path_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$.a[last()]" => "$['a']['z']\n"; "last member")]
+        #[test_case("$.a[last(1)]" => "$['a']['z']\n"; "explicit last member")]
+        #[test_case("$.a[last(2)]" => "$['a']['y']\n"; "second to last member")]
+        #[test_case("$.a[last(3)]" => "$['a']['x']\n"; "first member")]
+        #[test_case("$.a[last(4)]" => ""; "position past the first member")]
+        #[test_case("$.b[last()]" => ""; "empty object")]
+        #[test_case("$.c[last()]" => ""; "array elements are not members")]
+        #[test_case("$.d[last()]" => "$['d']['only']\n"; "single member")]
+        #[test_case("$[last()]" => "$['d']\n"; "root members")]
+        #[test_case("$.*[last()]" => "$['a']['z']\n$['d']['only']\n"; "wildcard parents")]
+        #[test_case("$.c.*[last()]" => "$['c'][1]['v']\n"; "object in array")]
+        #[test_case("$..z[last()]" => "$['a']['z']['w']\n"; "descendant parent")]
+        fn $test_name(query_string: &str) -> String {
+            run::<PathResult, $impl>(DOCUMENT, query_string).to_string()
+        }
+    };
+}
+
+macro_rules! nested_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$..a[last()]" => "$['a']['a']['r']\n$['a']['a']\n"; "inner object closes first")]
+        #[test_case("$..a[last(2)]" => "$['a']['a']['q']\n$['a']['p']\n"; "second to last of nested objects")]
+        #[test_case("$..a[last(3)]" => ""; "not enough members")]
+        fn $test_name(query_string: &str) -> String {
+            run::<PathResult, $impl>(NESTED_DOCUMENT, query_string).to_string()
+        }
+    };
+}
+
+macro_rules! count_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"a": {"x": 1, "y": 2}}"#, "$.a[last()]" => 1; "compact")]
+        #[test_case("{\n  \"a\" : {\n    \"x\" : 1 ,\n    \"y\" : 2\n  }\n}", "$.a[last()]" => 1; "whitespace")]
+        #[test_case(r#"{"a": {"x": {}, "y": []}}"#, "$.a[last(2)]" => 1; "complex members")]
+        #[test_case(r#"{"a": {"x": "}", "y": "{"}}"#, "$.a[last()]" => 1; "brackets in strings")]
+        #[test_case(r#"{"a": [{"x": 1}, {"y": 2}, {}]}"#, "$.a.*[last()]" => 2; "objects in array")]
+        #[test_case(r#"{"a": 42}"#, "$.a[last()]" => 0; "atomic parent")]
+        fn $test_name(json: &str, query_string: &str) -> usize {
+            run::<CountResult, $impl>(json, query_string).get()
+        }
+    };
+}
+
+path_test_cases!(main_path_test, MainEngine);
+path_test_cases!(recursive_path_test, RecursiveEngine);
+nested_test_cases!(main_nested_test, MainEngine);
+nested_test_cases!(recursive_nested_test, RecursiveEngine);
+count_test_cases!(main_count_test, MainEngine);
+count_test_cases!(recursive_count_test, RecursiveEngine);
+
+#[test]
+fn non_terminal_member_from_end_is_not_supported() {
+    let query = JsonPathQuery::parse("$.a[last()].w").unwrap();
+
+    let result = Automaton::new(&query);
+
+    assert!(matches!(result, Err(CompilerError::NotSupported(_))));
+}
+
+#[test]
+fn member_from_end_in_alternation_is_not_supported() {
+    let queries = [
+        JsonPathQuery::parse("$.a[last()]").unwrap(),
+        JsonPathQuery::parse("$.b").unwrap(),
+    ];
+
+    let result = Automaton::new_alternation(&queries);
+
+    assert!(matches!(result, Err(CompilerError::NotSupported(_))));
+}
+
+#[test]
+fn complement_of_member_from_end_is_not_supported() {
+    let query = JsonPathQuery::parse("$.a[last()]").unwrap();
+
+    let result = Automaton::new_complement(&query);
+
+    assert!(matches!(result, Err(CompilerError::NotSupported(_))));
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "unicode-normalization")]
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::CountResult;
+use test_case::test_case;
+
+// The first "café" key is precomposed (NFC), the second is decomposed (NFD).
+const DOCUMENT: &str = "{
+  \"data\": {
+    \"caf\u{e9}\": {\"x\": 1},
+    \"cafe\u{301}\": {\"x\": 2},
+    \"cafe\": {\"x\": 3},
+    \"menu\": [{\"caf\u{e9}\": 4}, {\"cafe\u{301}\": 5}]
+  }
+}";
+
+macro_rules! normalization_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$.data['caf\u{e9}']" => (1, 2); "precomposed child")]
+        #[test_case("$.data['cafe\u{301}']" => (1, 2); "decomposed child")]
+        #[test_case("$.data['caf\u{e9}'].x" => (1, 2); "precomposed child then label")]
+        #[test_case("$..['cafe\u{301}']" => (2, 4); "decomposed descendant")]
+        #[test_case("$.data.menu.*['caf\u{e9}']" => (1, 2); "precomposed child after wildcard")]
+        #[test_case("$.data.cafe" => (1, 1); "ascii child")]
+        fn $test_name(query_string: &str) -> (usize, usize) {
+            let mut raw = DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let exact = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap()
+                .get();
+
+            let query = query.into_normalization_insensitive();
+            let normalized = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap()
+                .get();
+
+            (exact, normalized)
+        }
+    };
+}
+
+normalization_test_cases!(main_normalization_test, MainEngine);
+normalization_test_cases!(recursive_normalization_test, RecursiveEngine);
@@ -0,0 +1,65 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::{automaton::Automaton, JsonPathQuery};
+use rsonpath_lib::result::PathResult;
+use test_case::test_case;
+
+const NESTED_DOCUMENT: &str = r#"{"0": [1, [2]], "a": [{"0": 3}], "b": {"1": [4, 5]}}"#;
+
+fn run<E: Compiler>(json: &str, query_string: &str, coerce: bool) -> String {
+    let mut raw = json.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let automaton = if coerce {
+        Automaton::new_with_numeric_key_coercion(&query).unwrap()
+    } else {
+        Automaton::new(&query).unwrap()
+    };
+    let engine = E::from_compiled_query(automaton);
+
+    engine.run::<PathResult>(&contents).unwrap().to_string()
+}
+
+macro_rules! coercion_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"0": "a"}"#, "$['0']" => "$['0']\n"; "key in object")]
+        #[test_case(r#"["a", "b"]"#, "$['0']" => "$[0]\n"; "index in array")]
+        #[test_case(r#"[{"1": "a"}, ["b", "c"], {"01": "d"}]"#, "$.*['1']" => "$[0]['1']\n$[1][1]\n"; "container type decides")]
+        #[test_case(r#"["a", "b"]"#, "$['2']" => ""; "index out of range")]
+        #[test_case(r#"["a", "b"]"#, "$['01']" => ""; "leading zero is not an index")]
+        #[test_case(r#"{"01": "a"}"#, "$['01']" => "$['01']\n"; "leading zero is still a key")]
+        #[test_case(r#"["a", "b"]"#, "$['-1']" => ""; "negative is not an index")]
+        #[test_case(r#"["a", "b"]"#, "$['18446744073709551616']" => ""; "overflow is not an index")]
+        #[test_case(r#"[["a", "b"], {"1": "c"}]"#, "$['0']['1']" => "$[0][1]\n"; "chained")]
+        #[test_case(NESTED_DOCUMENT, "$..['0']" => "$['0']\n$['0'][0]\n$['0'][1][0]\n$['a'][0]\n$['a'][0]['0']\n$['b']['1'][0]\n"; "descendant")]
+        #[test_case(NESTED_DOCUMENT, "$..['1']" => "$['0'][1]\n$['b']['1']\n$['b']['1'][1]\n"; "descendant index and key")]
+        #[test_case(NESTED_DOCUMENT, "$..b['1'][0]" => "$['b']['1'][0]\n"; "mixed with index selector")]
+        fn $test_name(json: &str, query_string: &str) -> String {
+            run::<$impl>(json, query_string, true)
+        }
+    };
+}
+
+macro_rules! default_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"["a", "b"]"#, "$['0']" => ""; "quoted label never matches elements")]
+        #[test_case(r#"{"0": "a"}"#, "$[0]" => ""; "index never matches keys")]
+        #[test_case(r#"{"0": "a"}"#, "$['0']" => "$['0']\n"; "quoted label matches keys")]
+        #[test_case(NESTED_DOCUMENT, "$..['0']" => "$['0']\n$['a'][0]['0']\n"; "descendant matches keys only")]
+        #[test_case(r#"{"0": "a"}"#, r#"$["0"]"# => "$['0']\n"; "double quoted label matches keys")]
+        #[test_case(r#"["a", "b"]"#, "$[0]" => "$[0]\n"; "index matches elements")]
+        #[test_case(r#"[{"0": "a"}, ["b"]]"#, "$.*['0']" => "$[0]['0']\n"; "quoted label in mixed containers")]
+        #[test_case(r#"[{"0": "a"}, ["b"]]"#, "$.*[0]" => "$[1][0]\n"; "index in mixed containers")]
+        #[test_case(r#"{"0": {"0": "a"}, "1": [["b"]]}"#, "$['0']['0']" => "$['0']['0']\n"; "chained quoted labels")]
+        #[test_case(r#"{"0": {"0": "a"}, "1": [["b"]]}"#, "$['1'][0][0]" => "$['1'][0][0]\n"; "quoted label then indices")]
+        fn $test_name(json: &str, query_string: &str) -> String {
+            run::<$impl>(json, query_string, false)
+        }
+    };
+}
+
+coercion_test_cases!(main_coercion_test, MainEngine);
+coercion_test_cases!(recursive_coercion_test, RecursiveEngine);
+default_test_cases!(main_default_test, MainEngine);
+default_test_cases!(recursive_default_test, RecursiveEngine);
@@ -0,0 +1,75 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::error::CompilerError;
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, SpanResult};
+use test_case::test_case;
+
+/// Run the query reporting only outermost matches and return the matched values joined with spaces.
+fn outermost_values<E: Compiler>(json: &str, query_string: &str) -> String {
+    let mut raw = json.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let engine = E::compile_query_outermost(&query).unwrap();
+
+    let result = engine.run::<SpanResult>(&contents).unwrap();
+
+    result
+        .get()
+        .iter()
+        .map(|span| &json[span.clone()])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+macro_rules! outermost_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"a":{"a":{"a":1}}}"#, "$..a" => r#"{"a":{"a":1}}"#; "nested self-matching")]
+        #[test_case(r#"{"b":{"a":1},"c":{"a":2}}"#, "$..a" => "1 2"; "siblings")]
+        #[test_case(r#"{"a":{"a":1},"b":{"a":{"a":2}}}"#, "$..a" => r#"{"a":1} {"a":2}"#; "depth restored after skip")]
+        #[test_case(r#"[{"a":[{"a":1}]},{"a":2}]"#, "$..a" => r#"[{"a":1}] 2"#; "within arrays")]
+        #[test_case(r#"{"x":{"a":{"b":{"a":{"b":1}}}}}"#, "$..a.b" => r#"{"a":{"b":1}}"#; "multi-selector query")]
+        #[test_case(r#"{"a":[[0,[1]],2],"b":[3]}"#, "$..[:]" => "[0,[1]] 2 3"; "every array element")]
+        #[test_case(r#"[[0,[1,2]],3,[4]]"#, "$..[::2]" => "[0,[1,2]] [4]"; "descendant slice")]
+        #[test_case(r#"{"a":{"a":1}}"#, "$.a" => r#"{"a":1}"#; "child")]
+        #[test_case(r#"{"a":{"a":1}}"#, "$" => r#"{"a":{"a":1}}"#; "root")]
+        #[test_case(r#"{"b":1}"#, "$..a" => ""; "no matches")]
+        #[test_case(r#"{"a":1,"b":{"c":{"a":2}}}"#, "$..a" => "1 2"; "atomic matches")]
+        fn $test_name(json: &str, query_string: &str) -> String {
+            outermost_values::<$impl>(json, query_string)
+        }
+    };
+}
+
+macro_rules! unsupported_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$..a[?(@ == 1)]"; "filter")]
+        #[test_case("$.a[last()]"; "member from end")]
+        fn $test_name(query_string: &str) {
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let result = $impl::compile_query_outermost(&query);
+
+            assert!(matches!(result, Err(CompilerError::NotSupported(_))));
+        }
+    };
+}
+
+outermost_test_cases!(main_outermost_test, MainEngine);
+outermost_test_cases!(recursive_outermost_test, RecursiveEngine);
+unsupported_test_cases!(main_unsupported_test, MainEngine);
+unsupported_test_cases!(recursive_unsupported_test, RecursiveEngine);
+
+#[test]
+fn default_compilation_reports_all_matches() {
+    let mut contents = r#"{"a":{"a":{"a":1}}}"#.to_owned();
+    let input = Input::new(&mut contents);
+    let query = JsonPathQuery::parse("$..a").unwrap();
+
+    let all = MainEngine::compile_query(&query).unwrap();
+    let outermost = MainEngine::compile_query_outermost(&query).unwrap();
+
+    assert_eq!(all.run::<CountResult>(&input).unwrap().get(), 3);
+    assert_eq!(outermost.run::<CountResult>(&input).unwrap().get(), 1);
+}
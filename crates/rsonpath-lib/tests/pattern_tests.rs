@@ -0,0 +1,170 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+#[cfg(feature = "regex")]
+use rsonpath_lib::query::automaton::Automaton;
+use rsonpath_lib::query::{builder::JsonPathQueryBuilder, JsonPathQuery, KeyPattern, Label};
+#[cfg(feature = "regex")]
+use rsonpath_lib::result::IndexResult;
+use rsonpath_lib::result::{CountResult, PathResult};
+use test_case::test_case;
+
+const DOCUMENT: &str = r#"{
+  "data": {
+    "item_1": 1,
+    "item_22": {"x": 2, "item_5": {"x": 5}},
+    "other": {"x": 3},
+    "item_3": 4,
+    "items": [{"x": 6}]
+  }
+}"#;
+
+/// Keys starting with `field_` at every level, under keys that do not match,
+/// in arrays, and nested in matching values.
+const LAYERED_DOCUMENT: &str = r#"{
+  "field_a": 1,
+  "other": {
+    "deeper": {
+      "field_b": {"field_c": [1, {"field_d": 2}], "x": 3},
+      "fiel": 4
+    },
+    "list": [[{"nested": {"field_e": null}}], "field_f"]
+  },
+  "field": {"field_g": true},
+  "x_field_h": 5
+}"#;
+
+macro_rules! prefix_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$..field_*" => "$['field_a']\n$['other']['deeper']['field_b']\n$['other']['deeper']['field_b']['field_c']\n$['other']['deeper']['field_b']['field_c'][1]['field_d']\n$['other']['list'][0][0]['nested']['field_e']\n$['field']['field_g']\n"; "descendant prefix")]
+        #[test_case("$.field_*" => "$['field_a']\n"; "child prefix")]
+        #[test_case("$.field*" => "$['field_a']\n$['field']\n"; "child prefix matching label")]
+        #[test_case("$.other..field_*..field_*" => "$['other']['deeper']['field_b']['field_c']\n$['other']['deeper']['field_b']['field_c'][1]['field_d']\n"; "nested descendant prefixes")]
+        #[test_case("$..field_*.x" => "$['other']['deeper']['field_b']['x']\n"; "descendant prefix then label")]
+        #[test_case("$..list..field_*" => "$['other']['list'][0][0]['nested']['field_e']\n"; "descendant prefix in arrays")]
+        #[test_case("$..nothing_*" => ""; "no matches")]
+        fn $test_name(query_string: &str) -> String {
+            let mut raw = LAYERED_DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<PathResult>(&contents)
+                .unwrap();
+
+            result.to_string()
+        }
+    };
+}
+
+#[cfg(feature = "regex")]
+macro_rules! pattern_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r"$.data[~'^item_\d+$']" => 3; "pattern child")]
+        #[test_case(r"$.data[~'item']" => 4; "unanchored pattern child")]
+        #[test_case(r"$.data[~'^item_\d+$'].x" => 1; "pattern child then label")]
+        #[test_case(r"$..[~'^item_\d+$']" => 4; "pattern descendant")]
+        #[test_case(r"$..[~'^item_\d+$']..x" => 2; "pattern descendant then descendant")]
+        #[test_case(r"$.data[~'^(item_22|other)$'].x" => 2; "alternative in pattern")]
+        #[test_case(r"$.data[~'^nothing$']" => 0; "no matches")]
+        #[test_case(r"$..[~'^x$']" => 4; "pattern equivalent to label")]
+        fn $test_name(query_string: &str) -> usize {
+            let mut raw = DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap();
+
+            result.get()
+        }
+    };
+}
+
+#[cfg(feature = "regex")]
+macro_rules! pattern_alternation_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(&[r"$..[~'^item_']", "$..item_22"]; "pattern overlapping label")]
+        #[test_case(&[r"$.data[~'^item_2']", "$.data.item_22.x"]; "pattern overlapping label prefix")]
+        #[test_case(&[r"$..[~'^item_']..x", r"$..[~'2$']"]; "overlapping patterns")]
+        fn $test_name(query_strings: &[&str]) {
+            let queries: Vec<_> = query_strings
+                .iter()
+                .map(|q| JsonPathQuery::parse(q).unwrap())
+                .collect();
+            let mut raw = DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+
+            let mut expected: Vec<usize> = queries
+                .iter()
+                .flat_map(|q| {
+                    let result = $impl::compile_query(q)
+                        .unwrap()
+                        .run::<IndexResult>(&contents)
+                        .unwrap();
+                    Vec::from(result)
+                })
+                .collect();
+            expected.sort_unstable();
+            expected.dedup();
+
+            let automaton = Automaton::new_alternation(&queries).unwrap();
+            let mut result: Vec<usize> = $impl::from_compiled_query(automaton)
+                .run::<IndexResult>(&contents)
+                .unwrap()
+                .into();
+            result.sort_unstable();
+
+            assert_eq!(result, expected);
+        }
+    };
+}
+
+macro_rules! custom_pattern_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(&[("data", false), ("ITEM_1", false)] => 1; "case insensitive child")]
+        #[test_case(&[("data", false), ("item", false)] => 4; "prefix child")]
+        #[test_case(&[("data", false), ("item", true)] => 5; "prefix descendant")]
+        #[test_case(&[("item_", true), ("x", false)] => 2; "prefix descendant then child")]
+        #[test_case(&[("X", true)] => 4; "case insensitive descendant")]
+        fn $test_name(selectors: &[(&str, bool)]) -> usize {
+            let mut builder = JsonPathQueryBuilder::new();
+            for &(label, is_descendant) in selectors {
+                let pattern = KeyPattern::custom(Label::new(label), |key, label| {
+                    key.len() >= label.len() && key[..label.len()].eq_ignore_ascii_case(label)
+                });
+                builder = if is_descendant {
+                    builder.pattern_descendant(pattern)
+                } else {
+                    builder.pattern_child(pattern)
+                };
+            }
+            let query = builder.build();
+            let mut raw = DOCUMENT.to_owned();
+            let contents = Input::new(&mut raw);
+
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<CountResult>(&contents)
+                .unwrap();
+
+            result.get()
+        }
+    };
+}
+
+#[cfg(feature = "regex")]
+pattern_test_cases!(main_pattern_test, MainEngine);
+#[cfg(feature = "regex")]
+pattern_test_cases!(recursive_pattern_test, RecursiveEngine);
+#[cfg(feature = "regex")]
+pattern_alternation_test_cases!(main_pattern_alternation_test, MainEngine);
+#[cfg(feature = "regex")]
+pattern_alternation_test_cases!(recursive_pattern_alternation_test, RecursiveEngine);
+prefix_test_cases!(main_prefix_test, MainEngine);
+prefix_test_cases!(recursive_prefix_test, RecursiveEngine);
+custom_pattern_test_cases!(main_custom_pattern_test, MainEngine);
+custom_pattern_test_cases!(recursive_custom_pattern_test, RecursiveEngine);
@@ -49,6 +49,29 @@ fn indexed_wildcard_child_selector() {
     assert_eq!(result, expected_query);
 }
 
+#[test]
+fn descendant_or_self_selector() {
+    let input = r#"$.a...b..c...['d']"#;
+    let expected_query = JsonPathQueryBuilder::new()
+        .child(Label::new("a"))
+        .descendant_or_self(Label::new("b"))
+        .descendant(Label::new("c"))
+        .descendant_or_self(Label::new("d"))
+        .into();
+
+    let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+    assert_eq!(result, expected_query);
+    assert_eq!(result.to_string(), "$['a']...['b']..['c']...['d']");
+}
+
+#[test]
+fn four_dots_are_invalid() {
+    let result = JsonPathQuery::parse("$....a");
+
+    assert!(result.is_err());
+}
+
 #[test]
 fn escaped_single_quote_in_single_quote_label() {
     let input = r#"['\'']"#;
@@ -243,3 +266,363 @@ mod proptests {
         }
     }
 }
+
+#[cfg(feature = "regex")]
+mod patterns {
+    use pretty_assertions::assert_eq;
+    use rsonpath_lib::query::{
+        builder::JsonPathQueryBuilder, error::ParserError, JsonPathQuery, KeyPattern,
+    };
+
+    #[test]
+    fn pattern_child_and_descendant_selectors() {
+        let input = r#"$[~'^a\d+$']..[~"b|c"]"#;
+        let expected_query = JsonPathQueryBuilder::new()
+            .pattern_child(KeyPattern::regex(r"^a\d+$").unwrap())
+            .pattern_descendant(KeyPattern::regex("b|c").unwrap())
+            .into();
+
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result, expected_query);
+    }
+
+    #[test]
+    fn escaped_quote_in_pattern() {
+        let input = r#"$[~'it\'s']"#;
+        let expected_query = JsonPathQueryBuilder::new()
+            .pattern_child(KeyPattern::regex("it's").unwrap())
+            .into();
+
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result, expected_query);
+    }
+
+    #[test]
+    fn invalid_regex_in_pattern() {
+        let input = "$[~'(']";
+
+        let result = JsonPathQuery::parse(input);
+
+        assert!(matches!(result, Err(ParserError::InvalidRegex(_))));
+    }
+}
+
+mod prefixes {
+    use pretty_assertions::assert_eq;
+    use rsonpath_lib::query::{builder::JsonPathQueryBuilder, JsonPathQuery, KeyPattern, Label};
+    use test_case::test_case;
+
+    #[test]
+    fn prefix_child_and_descendant_selectors() {
+        let input = "$.a_*..field_*.b";
+        let expected_query = JsonPathQueryBuilder::new()
+            .pattern_child(KeyPattern::prefix(Label::new("a_")))
+            .pattern_descendant(KeyPattern::prefix(Label::new("field_")))
+            .child(Label::new("b"))
+            .into();
+
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result, expected_query);
+        assert_eq!(
+            result.to_string(),
+            "$[~prefix('a_')]..[~prefix('field_')]['b']"
+        );
+    }
+
+    #[test_case("$.a_*..field_*.b"; "dot")]
+    #[test_case("$[~prefix('a_')]..[~prefix(\"field_\")]"; "index")]
+    #[test_case(r#"$[~prefix('a b.c')]..[~prefix("")]"#; "index with special characters")]
+    fn displayed_prefix_selectors_parse_to_the_same_query(input: &str) {
+        let query = JsonPathQuery::parse(input).expect("expected Ok");
+
+        let reparsed = JsonPathQuery::parse(&query.to_string()).expect("expected Ok");
+
+        assert_eq!(reparsed, query);
+    }
+
+    #[test]
+    fn prefix_index_selector_with_escaped_quote() {
+        let input = r#"$..[~prefix('a\'b')]"#;
+        let expected_query = JsonPathQueryBuilder::new()
+            .pattern_descendant(KeyPattern::prefix(Label::new("a'b")))
+            .into();
+
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result, expected_query);
+    }
+
+    #[test_case("$..*"; "descendant wildcard")]
+    #[test_case("$.a**"; "double star")]
+    #[test_case("$...a*"; "descendant or self")]
+    #[test_case("$['a']*"; "index")]
+    fn invalid_prefix(input: &str) {
+        let result = JsonPathQuery::parse(input);
+
+        assert!(result.is_err());
+    }
+}
+
+mod filters {
+    use pretty_assertions::assert_eq;
+    use rsonpath_lib::query::{
+        builder::JsonPathQueryBuilder, Comparison, FilterExpression, JsonLiteral, JsonPathQuery,
+        Label,
+    };
+    use test_case::test_case;
+
+    #[test]
+    fn filter_with_object_literal() {
+        let input = r#"$.items[?(@.meta['k'] == { "a" : [1, true, null, "x y"] })]"#;
+        let literal = JsonLiteral::parse(r#"{"a":[1,true,null,"x y"]}"#).unwrap();
+        let expected_query = JsonPathQueryBuilder::new()
+            .child(Label::new("items"))
+            .filter(FilterExpression::new(
+                vec![Label::new("meta"), Label::new("k")],
+                Comparison::Equal(literal),
+            ))
+            .into();
+
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result, expected_query);
+    }
+
+    #[test]
+    fn descendant_scalar_filter() {
+        let input = r#"$.a..[?( @.b  is   scalar )]"#;
+        let expected_query = JsonPathQueryBuilder::new()
+            .child(Label::new("a"))
+            .descendant_filter(FilterExpression::new(
+                vec![Label::new("b")],
+                Comparison::IsScalar,
+            ))
+            .into();
+
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result, expected_query);
+        assert_eq!(result.to_string(), "$['a']..[?(@['b'] is scalar)]");
+    }
+
+    #[test]
+    fn filter_on_current_value() {
+        let input = r#"$[?(@=="a\"b")]"#;
+
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result.to_string(), r#"$[?(@ == "a\"b")]"#);
+    }
+
+    #[test_case("$[?(@ == true)]"; "true")]
+    #[test_case("$[?(@ == false)]"; "false")]
+    #[test_case("$[?(@.a.b == null)]"; "null")]
+    fn keyword_filter(input: &str) {
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result.to_string(), input.replace(".a.b", "['a']['b']"));
+    }
+
+    #[test_case(r#"$[?(@.a in ['x', "y"])]"#, r#"$[?(@['a'] in ["x", "y"])]"#; "mixed quotes")]
+    #[test_case(r#"$[?(@ in [ 1 ,true,{"k": null} ])]"#, r#"$[?(@ in [1, true, {"k":null}])]"#; "whitespace around literals")]
+    #[test_case(r#"$[?(@ in ['it\'s', 'a"b'])]"#, r#"$[?(@ in ["it's", "a\"b"])]"#; "escapes in single quoted strings")]
+    #[test_case(r#"$[?(@ in [])]"#, r#"$[?(@ in [])]"#; "empty set")]
+    fn set_filter(input: &str, expected: &str) {
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result.to_string(), expected);
+    }
+
+    #[test_case("$[?(size(@)==0)]", "$[?(size(@) == 0)]"; "equal")]
+    #[test_case("$[?(size(@.a.b) != 1)]", "$[?(size(@['a']['b']) != 1)]"; "not equal with path")]
+    #[test_case("$[?(size( @ ) < 2)]", "$[?(size(@) < 2)]"; "less")]
+    #[test_case("$[?(size(@) <= 2)]", "$[?(size(@) <= 2)]"; "less or equal")]
+    #[test_case("$[?(size(@) > 10)]", "$[?(size(@) > 10)]"; "greater")]
+    #[test_case("$..[?(size(@)>=3)]", "$..[?(size(@) >= 3)]"; "greater or equal")]
+    fn size_filter(input: &str, expected: &str) {
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result.to_string(), expected);
+    }
+
+    #[test_case(r#"$[?(@.a == {"k":})]"#; "missing value")]
+    #[test_case(r#"$[?(@.a == [1, 2)]"#; "unclosed array")]
+    #[test_case(r#"$[?(@.a == 01)]"#; "leading zero")]
+    #[test_case(r#"$[?(@.a == 'x')]"#; "single quoted string")]
+    #[test_case(r#"$[?(@.a = 1)]"#; "single equals sign")]
+    #[test_case(r#"$[?(@ == truent)]"#; "keyword with trailing characters")]
+    #[test_case(r#"$[?(@ == tru)]"#; "partial keyword")]
+    #[test_case(r#"$[?(@ == True)]"#; "capitalized keyword")]
+    #[test_case(r#"$[?(@ isscalar)]"#; "no space in scalar test")]
+    #[test_case(r#"$[?(@is scalar)]"#; "no space before scalar test")]
+    #[test_case(r#"$[?(@ is number)]"#; "unknown type test")]
+    #[test_case(r#"$...[?(@ is scalar)]"#; "three dots before descendant filter")]
+    #[test_case(r#"$[?(@ in 1...10)]"#; "three dots in range")]
+    #[test_case(r#"$[?(@ in ..10)]"#; "range without start")]
+    #[test_case(r#"$[?(@ in 1..)]"#; "range without end")]
+    #[test_case(r#"$[?(@ in 1 .. 10)]"#; "whitespace in range")]
+    #[test_case(r#"$[?(@ in "a".."b")]"#; "string range")]
+    #[test_case(r#"$[?(@ in1..2)]"#; "no space before range")]
+    #[test_case(r#"$[?(@ in 1e400..2)]"#; "infinite bound")]
+    #[test_case(r#"$[?(@ in [1, 2)]"#; "unclosed set")]
+    #[test_case(r#"$[?(@ in [1,, 2])]"#; "empty set member")]
+    #[test_case(r#"$[?(@ in [1, 2,])]"#; "trailing comma in set")]
+    #[test_case(r#"$[?(@ in [x])]"#; "unquoted string in set")]
+    #[test_case(r#"$[?(@ in[1])]"#; "no space before set")]
+    #[test_case(r#"$[?(size(@) = 1)]"#; "single equals sign in size test")]
+    #[test_case(r#"$[?(size(@) == -1)]"#; "negative size")]
+    #[test_case(r#"$[?(size(@) == 1.5)]"#; "fractional size")]
+    #[test_case(r#"$[?(size(@) == 01)]"#; "leading zero in size")]
+    #[test_case(r#"$[?(size(@))]"#; "size without comparison")]
+    #[test_case(r#"$[?(size(@ == 1)]"#; "unclosed size")]
+    #[test_case(r#"$[?(size(1) == 1)]"#; "size of literal")]
+    fn invalid_filter(input: &str) {
+        let result = JsonPathQuery::parse(input);
+
+        assert!(result.is_err());
+    }
+}
+
+mod members_from_end {
+    use pretty_assertions::assert_eq;
+    use rsonpath_lib::query::{builder::JsonPathQueryBuilder, JsonPathQuery, Label};
+    use std::num::NonZeroUsize;
+    use test_case::test_case;
+
+    #[test]
+    fn last_member() {
+        let input = "$.a[last()]";
+        let expected_query = JsonPathQueryBuilder::new()
+            .child(Label::new("a"))
+            .member_from_end(NonZeroUsize::new(1).unwrap())
+            .into();
+
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result, expected_query);
+        assert_eq!(result.to_string(), "$['a'][last()]");
+    }
+
+    #[test_case("$[last(1)]", "$[last()]"; "explicit last")]
+    #[test_case("$..a[last(12)]", "$..['a'][last(12)]"; "multiple digits")]
+    fn member_from_end(input: &str, expected: &str) {
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result.to_string(), expected);
+    }
+
+    #[test_case("$[last(0)]"; "zero")]
+    #[test_case("$[last(-1)]"; "negative")]
+    #[test_case("$[last(a)]"; "not a number")]
+    #[test_case("$[last( )]"; "whitespace")]
+    #[test_case("$[last]"; "no parentheses")]
+    #[test_case("$.last()"; "dot notation")]
+    fn invalid_member_from_end(input: &str) {
+        let result = JsonPathQuery::parse(input);
+
+        assert!(result.is_err());
+    }
+}
+
+mod array_indices {
+    use pretty_assertions::assert_eq;
+    use rsonpath_lib::query::{builder::JsonPathQueryBuilder, JsonPathQuery, Label};
+    use test_case::test_case;
+
+    #[test]
+    fn chained_indices() {
+        let input = "$.a[0][12]";
+        let expected_query = JsonPathQueryBuilder::new()
+            .child(Label::new("a"))
+            .array_index(0)
+            .array_index(12)
+            .into();
+
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result, expected_query);
+        assert_eq!(result.to_string(), "$['a'][0][12]");
+    }
+
+    #[test_case("$[0]", "$[0]"; "zero")]
+    #[test_case("$[10].b", "$[10]['b']"; "before label")]
+    #[test_case("$..a[3]", "$..['a'][3]"; "after descendant")]
+    #[test_case("$['0']", "$['0']"; "quoted number is a label")]
+    #[test_case(r#"$["0"]"#, "$['0']"; "double quoted number is a label")]
+    #[test_case("$['0'][0]", "$['0'][0]"; "label and index")]
+    fn array_index(input: &str, expected: &str) {
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result.to_string(), expected);
+    }
+
+    #[test_case("$[01]"; "leading zero")]
+    #[test_case("$[-1]"; "negative")]
+    #[test_case("$[ 1]"; "whitespace")]
+    #[test_case("$[1.5]"; "fraction")]
+    #[test_case("$[99999999999999999999999]"; "overflow")]
+    #[test_case("$.0"; "dot notation")]
+    fn invalid_array_index(input: &str) {
+        let result = JsonPathQuery::parse(input);
+
+        assert!(result.is_err());
+    }
+}
+
+mod slices {
+    use pretty_assertions::assert_eq;
+    use rsonpath_lib::query::{
+        builder::JsonPathQueryBuilder, ArraySlice, JsonPathQuery, JsonPathQueryNode,
+    };
+    use std::num::NonZeroUsize;
+    use test_case::test_case;
+
+    #[test]
+    fn descendant_slice() {
+        let input = "$..[1::2]";
+        let slice = ArraySlice::new(1, None, NonZeroUsize::new(2).unwrap());
+        let expected_query = JsonPathQueryBuilder::new().descendant_slice(slice).into();
+
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result, expected_query);
+        assert!(matches!(
+            result.root().child(),
+            Some(JsonPathQueryNode::DescendantSlice(..))
+        ));
+    }
+
+    #[test_case("$[::2]", "$[0::2]"; "only step")]
+    #[test_case("$[1:4]", "$[1:4:1]"; "no step")]
+    #[test_case("$[1:4:]", "$[1:4:1]"; "empty step")]
+    #[test_case("$[:]", "$[0::1]"; "everything")]
+    #[test_case("$[2:]", "$[2::1]"; "only start")]
+    #[test_case("$[:3]", "$[0:3:1]"; "only end")]
+    #[test_case("$..[0::2]", "$..[0::2]"; "descendant")]
+    #[test_case("$.a[1::3].b", "$['a'][1::3]['b']"; "between labels")]
+    fn slice(input: &str, expected: &str) {
+        let result = JsonPathQuery::parse(input).expect("expected Ok");
+
+        assert_eq!(result.to_string(), expected);
+        assert_eq!(
+            JsonPathQuery::parse(&result.to_string()).expect("expected Ok"),
+            result
+        );
+    }
+
+    #[test_case("$[::0]"; "zero step")]
+    #[test_case("$[-1:]"; "negative start")]
+    #[test_case("$[:-1]"; "negative end")]
+    #[test_case("$[::-1]"; "negative step")]
+    #[test_case("$[1:2:3:4]"; "too many parts")]
+    #[test_case("$[01:]"; "leading zero")]
+    #[test_case("$[ 1:2]"; "whitespace")]
+    fn invalid_slice(input: &str) {
+        let result = JsonPathQuery::parse(input);
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, IndexResult};
+use std::fs;
+use std::io::{self, Read};
+use test_case::test_case;
+
+const ROOT_TEST_DIRECTORY: &str = "./tests/data";
+
+/// Reader returning the contents in small chunks and failing at the end.
+struct FailingReader<'a> {
+    contents: &'a [u8],
+}
+
+impl Read for FailingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.contents.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"));
+        }
+        let len = buf.len().min(self.contents.len()).min(7);
+        buf[..len].copy_from_slice(&self.contents[..len]);
+        self.contents = &self.contents[len..];
+        Ok(len)
+    }
+}
+
+macro_rules! reader_input_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("basic/small.json", "$..person..phoneNumber..number"; "small.json")]
+        #[test_case("basic/child.json", "$..a..b.c..d"; "child.json")]
+        #[test_case("basic/empty.json", "$"; "empty.json")]
+        #[test_case("twitter/twitter.json", "$..user..entities..url"; "twitter.json")]
+        fn $test_name(test_path: &str, query_string: &str) {
+            let path = format!("{ROOT_TEST_DIRECTORY}/{test_path}");
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+            let mut raw = fs::read_to_string(&path).unwrap();
+            let expected = engine.run::<IndexResult>(&Input::new(&mut raw)).unwrap();
+
+            let mut file = fs::File::open(&path).unwrap();
+            let input = Input::from_reader(&mut file).unwrap();
+            let result = engine.run::<IndexResult>(&input).unwrap();
+
+            assert_eq!(result, expected);
+        }
+    };
+}
+
+macro_rules! empty_reader_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$"; "root")]
+        #[test_case("$..a"; "descendant")]
+        fn $test_name(query_string: &str) {
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let input = Input::from_reader(&mut io::empty()).unwrap();
+            let result = engine.run::<CountResult>(&input).unwrap();
+
+            assert_eq!(result.get(), 0);
+        }
+    };
+}
+
+reader_input_test_cases!(main_reader_input_test, MainEngine);
+reader_input_test_cases!(recursive_reader_input_test, RecursiveEngine);
+empty_reader_test_cases!(main_empty_reader_test, MainEngine);
+empty_reader_test_cases!(recursive_empty_reader_test, RecursiveEngine);
+
+#[test]
+fn reader_error_is_returned() {
+    let mut reader = FailingReader {
+        contents: br#"{"a": 1}"#,
+    };
+
+    let result = Input::from_reader(&mut reader);
+
+    assert!(matches!(result, Err(err) if err.kind() == io::ErrorKind::ConnectionReset));
+}
@@ -0,0 +1,177 @@
+use rsonpath_lib::engine::error::EngineError;
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{
+    CountResult, DepthHistogramResult, ExistsResult, PathResult, QueryResult, SpanResult,
+    TokenIndexResult,
+};
+use test_case::test_case;
+
+macro_rules! recovery_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$.a", "{\"a\": 1}\n{\"a\": 2}\n" => (vec!["1", "2"], vec![]); "consistent records")]
+        #[test_case("$.a", "{\"a\": 1}\n{\"a\": [2}\n{\"a\": 3}\n" => (vec!["1", "3"], vec!["{\"a\": [2}"]); "mismatched closing")]
+        #[test_case("$.a", "{\"a\": 1}\n{\"a\": 2\n" => (vec!["1"], vec!["{"]); "truncated last record")]
+        #[test_case("$.a", "{\"a\": 1, \"b\": [\n{\"a\": 2}\n{\"a\": 3}\n" => (vec!["2", "3"], vec!["{\"a\": 1, \"b\": ["]); "records within truncated record")]
+        #[test_case("$.a", "{\"a\": 1}]\n{\"a\": 2}\n" => (vec!["1", "2"], vec!["]"]); "stray closing")]
+        #[test_case("$.a", "{\"a\": 1}\n{\"a\": [}\n]\n{\"a\": 3}\n{\"a\": {]\n" => (vec!["1", "3"], vec!["{\"a\": [}\n]", "{\"a\": {]"]); "corrupted parts counted separately")]
+        #[test_case("$.a", "{\"a\": \"}\"}\n{\"a\": \"[\"}\n" => (vec!["\"}\"", "\"[\""], vec![]); "brackets in strings")]
+        #[test_case("$.a", "1 {\"a\": 1} \"x\" [2]\n" => (vec!["1"], vec![]); "top-level atoms ignored")]
+        #[test_case("$..a", "[{\"a\": 1}, {\"a\": [2]}]\n" => (vec!["1", "[2]"], vec![]); "single document")]
+        #[test_case("$.id", "{\"a\": {\"id\": 1}, \"b\": [}\n{\"id\": 2}\n" => (vec!["2"], vec!["{\"a\": {\"id\": 1}, \"b\": [}"]); "values nested in inconsistent record")]
+        #[test_case("$.id", "{\"a\": [{\"id\": 1}, {\"b\": 2]}\n{\"id\": 3}\n" => (vec!["3"], vec!["{\"a\": [{\"id\": 1}, {\"b\": 2]}"]); "mismatched closing within record")]
+        #[test_case("$.id", "{\"a\": {\"id\": 1}, \"b\": [\n{\"id\": 2}\n" => (vec!["2"], vec!["{\"a\": {\"id\": 1}, \"b\": ["]); "values nested in truncated record")]
+        #[test_case("$.a", "{1: 2}\n{\"a\": 3}\n" => (vec!["3"], vec!["{1: 2}"]); "malformed key")]
+        #[test_case("$.a", "{\"a\": 1}\n{a\": 2}\n{\"a\": 3}\n" => (vec!["1"], vec!["{"]); "unterminated string")]
+        #[test_case("$.a", "" => (vec![], vec![]); "empty input")]
+        fn $test_name<'a>(query_string: &str, json: &'a str) -> (Vec<&'a str>, Vec<&'a str>) {
+            let mut contents = json.to_owned();
+            let input = Input::new(&mut contents);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let recovered = engine.run_lenient::<SpanResult>(&input).unwrap();
+            assert_eq!(recovered.skipped_records(), recovered.skipped_spans().len());
+
+            let values = recovered.result().get().iter().map(|span| json[span.clone()].trim()).collect();
+            let skipped = recovered.skipped_spans().iter().map(|span| &json[span.clone()]).collect();
+
+            (values, skipped)
+        }
+    };
+}
+
+recovery_test_cases!(main_recovery_test, MainEngine);
+recovery_test_cases!(recursive_recovery_test, RecursiveEngine);
+
+#[test]
+fn recovery_agrees_with_run_on_consistent_document() {
+    let json = r#"{"a": [{"b": 1}, {"b": [2, {"b": 3}]}], "c": {"b": null}}"#;
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+    let query = JsonPathQuery::parse("$..b").unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+
+    let recovered = engine.run_lenient::<SpanResult>(&input).unwrap();
+
+    assert_eq!(recovered.skipped_records(), 0);
+    assert_eq!(
+        recovered.into_result(),
+        engine.run::<SpanResult>(&input).unwrap()
+    );
+}
+
+#[test]
+fn complete_result_stops_recovery() {
+    let json = "{\"a\": 1}\n{\"a\": [2}\n";
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+    let query = JsonPathQuery::parse("$.a").unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+
+    let recovered = engine.run_lenient::<ExistsResult>(&input).unwrap();
+
+    assert!(recovered.result().get());
+    assert_eq!(recovered.skipped_records(), 0);
+}
+
+#[test]
+fn record_after_string_spanning_blocks() {
+    let long = "x".repeat(200);
+    let json =
+        format!("{{\"a\": \"{long}\"}}\n{{\"a\": [}}\n{{\"a\": \"{long}\"}}\n{{\"a\": 2}}\n");
+    let mut contents = json.clone();
+    let input = Input::new(&mut contents);
+    let query = JsonPathQuery::parse("$.a").unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+
+    let recovered = engine.run_lenient::<SpanResult>(&input).unwrap();
+    let values: Vec<_> = recovered
+        .result()
+        .get()
+        .iter()
+        .map(|span| json[span.clone()].trim())
+        .collect();
+
+    assert_eq!(
+        values,
+        [format!("\"{long}\"").as_str(), &format!("\"{long}\""), "2"]
+    );
+    assert_eq!(recovered.skipped_records(), 1);
+}
+
+#[test]
+fn depths_are_within_records() {
+    let json = "{\"a\": [1]}\n{\"a\": [}\n[{\"a\": 2}, {\"b\": {\"a\": 3}}]\n";
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+    let query = JsonPathQuery::parse("$..a").unwrap();
+
+    let main = MainEngine::compile_query(&query).unwrap();
+    let recursive = RecursiveEngine::compile_query(&query).unwrap();
+    let main_result = main.run_lenient::<DepthHistogramResult>(&input).unwrap();
+    let recursive_result = recursive
+        .run_lenient::<DepthHistogramResult>(&input)
+        .unwrap();
+
+    assert_eq!(main_result.result().get(), &[0, 1, 1, 1]);
+    assert_eq!(main_result, recursive_result);
+}
+
+#[test]
+fn paths_are_within_records() {
+    let json = "[0, {\"a\": 1}]\n{\"a\": [}\n{\"b\": [{\"a\": 2}]}\n";
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+    let query = JsonPathQuery::parse("$..a").unwrap();
+
+    let main = MainEngine::compile_query(&query).unwrap();
+    let recursive = RecursiveEngine::compile_query(&query).unwrap();
+    let main_result = main.run_lenient::<PathResult>(&input).unwrap();
+    let recursive_result = recursive.run_lenient::<PathResult>(&input).unwrap();
+
+    assert_eq!(
+        main_result.result().to_string(),
+        "$[1]['a']\n$['b'][0]['a']\n"
+    );
+    assert_eq!(main_result, recursive_result);
+}
+
+#[test]
+fn token_indices_are_within_records() {
+    let json = "{\"a\": 1}\n{\"a\": [}\n{\"b\": [0, {\"a\": 2}]}\n";
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+    let query = JsonPathQuery::parse("$..a").unwrap();
+
+    let main = MainEngine::compile_query(&query).unwrap();
+    let recursive = RecursiveEngine::compile_query(&query).unwrap();
+    let main_result = main.run_lenient::<TokenIndexResult>(&input).unwrap();
+    let recursive_result = recursive.run_lenient::<TokenIndexResult>(&input).unwrap();
+
+    assert_eq!(main_result.result().get(), &[2, 6]);
+    assert_eq!(main_result, recursive_result);
+}
+
+/// Engine implemented outside of the crate, without recovery.
+struct NoMatchesEngine;
+
+impl Engine for NoMatchesEngine {
+    fn run_into<R: QueryResult>(&self, _input: &Input, _result: &mut R) -> Result<(), EngineError> {
+        Ok(())
+    }
+}
+
+#[test]
+fn lenient_run_is_not_supported_by_default() {
+    let mut contents = "{\"a\": 1}\n".to_owned();
+    let input = Input::new(&mut contents);
+
+    let error = NoMatchesEngine
+        .run_lenient::<CountResult>(&input)
+        .unwrap_err();
+
+    assert!(matches!(error, EngineError::NotSupported(_)));
+}
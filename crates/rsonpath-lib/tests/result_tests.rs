@@ -0,0 +1,488 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{
+    CappedSpanResult, CountResult, DedupResult, DepthHistogramResult, ExistsResult, FanOutResult,
+    IndexResult, KeysResult, NumericStatsResult, OversizedValues, PathResult, QueryResult,
+    SpanResult, SummaryResult, TokenIndexResult,
+};
+use std::fs;
+use test_case::test_case;
+
+const ROOT_TEST_DIRECTORY: &str = "./tests/data";
+
+fn get_contents(test_path: &str) -> Input {
+    let path = format!("{ROOT_TEST_DIRECTORY}/{test_path}");
+    let mut raw = fs::read_to_string(path).unwrap();
+    Input::new(&mut raw)
+}
+
+fn keys(keys: &[Option<&str>]) -> Vec<Option<String>> {
+    keys.iter().map(|key| key.map(str::to_owned)).collect()
+}
+
+fn raw_keys(keys: &[Option<&str>]) -> Vec<Option<Vec<u8>>> {
+    keys.iter()
+        .map(|key| key.map(|key| key.as_bytes().to_vec()))
+        .collect()
+}
+
+macro_rules! summary_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("basic/atomic_descendant.json", "$..a" => (1, Some(9), Some(9)); "atomic_descendant.json $..a")]
+        #[test_case("basic/atomic_descendant.json", "$..a..b" => (0, None, None); "atomic_descendant.json $..a..b")]
+        #[test_case("basic/empty.json", "$" => (0, None, None); "empty.json $")]
+        #[test_case("basic/root.json", "$" => (1, Some(0), Some(0)); "root.json $")]
+        #[test_case("basic/child.json", "$..a..b.c..d" => (3, Some(984), Some(1545)); "child.json $..a..b.c..d")]
+        #[test_case("basic/wildcard_list.json", "$..a.*" => (6, Some(46), Some(287)); "wildcard_list.json $..a.*")]
+        fn $test_name(test_path: &str, query_string: &str) -> (usize, Option<usize>, Option<usize>) {
+            let contents = get_contents(test_path);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<SummaryResult>(&contents)
+                .unwrap();
+
+            (result.count(), result.first(), result.last())
+        }
+    };
+}
+
+type NumericStats = (usize, usize, Option<f64>, Option<f64>, f64, Option<i128>);
+
+macro_rules! numeric_stats_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"[1, 2, 3]"#, "$.*" => (3, 0, Some(1.0), Some(3.0), 6.0, Some(6)); "integers")]
+        #[test_case(r#"{"a": [1, 2.5, "3", null, {"b": 4}, [5]]}"#, "$.a.*" => (2, 4, Some(1.0), Some(2.5), 3.5, None); "non-numbers skipped")]
+        #[test_case(r#"[-2, 3e2, -0.5]"#, "$.*" => (3, 0, Some(-2.0), Some(300.0), 297.5, None); "negative and exponent")]
+        #[test_case(r#"[9007199254740993, 1]"#, "$.*" => (2, 0, Some(1.0), Some(9007199254740992.0), 9007199254740994.0, Some(9007199254740994)); "exact integer sum")]
+        #[test_case(r#"[1e16, 1.5, 1.5, -1e16]"#, "$.*" => (4, 0, Some(-1e16), Some(1e16), 3.0, None); "compensated float sum")]
+        #[test_case(r#"{"a": "x"}"#, "$.a" => (0, 1, None, None, 0.0, Some(0)); "no numbers")]
+        #[test_case(r#"[1, -inf, -infinity, -nan]"#, "$.*" => (1, 3, Some(1.0), Some(1.0), 1.0, Some(1)); "non-finite words skipped")]
+        fn $test_name(json: &str, query_string: &str) -> NumericStats {
+            let mut raw = json.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<NumericStatsResult>(&contents)
+                .unwrap();
+
+            (result.count(), result.skipped(), result.min(), result.max(), result.sum(), result.integer_sum())
+        }
+    };
+}
+
+macro_rules! depth_histogram_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("basic/empty.json", "$" => Vec::<usize>::new(); "empty.json $")]
+        #[test_case("basic/root.json", "$" => vec![1]; "root.json $")]
+        #[test_case("basic/atomic_descendant.json", "$..b" => vec![0, 1, 0, 1]; "atomic_descendant.json $..b")]
+        #[test_case("basic/wildcard_list.json", "$..a.*" => vec![0, 0, 0, 0, 6]; "wildcard_list.json $..a.*")]
+        fn $test_name(test_path: &str, query_string: &str) -> Vec<usize> {
+            let contents = get_contents(test_path);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<DepthHistogramResult>(&contents)
+                .unwrap();
+
+            result.into()
+        }
+    };
+}
+
+macro_rules! span_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"a": "x\"y", "b": 1}"#, "$.a" => vec![r#""x\"y""#]; "string with quotes")]
+        #[test_case(r#"{"a" :   -1.5e3 , "b": 1}"#, "$.a" => vec!["-1.5e3"]; "number without whitespace")]
+        #[test_case(r#"{"a":true}"#, "$.a" => vec!["true"]; "literal at end of object")]
+        #[test_case(r#"[ null , { "k": [1, "]"] } ]"#, "$.*" => vec!["null", r#"{ "k": [1, "]"] }"#]; "array elements")]
+        #[test_case(r#"{"a": {"a": [1]}}"#, "$..a" => vec![r#"{"a": [1]}"#, "[1]"]; "nested matches")]
+        #[test_case(r#" [1, 2] "#, "$" => vec!["[1, 2]"]; "root")]
+        fn $test_name(json: &str, query_string: &str) -> Vec<String> {
+            let mut raw = json.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<SpanResult>(&contents)
+                .unwrap();
+
+            result
+                .get()
+                .iter()
+                .map(|span| json[span.clone()].to_owned())
+                .collect()
+        }
+    };
+}
+
+macro_rules! root_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"a": [1, 2]}"# => (1, vec![0], vec![r#"{"a": [1, 2]}"#.to_owned()]); "object")]
+        #[test_case("  [[], {}]\n" => (1, vec![2], vec!["[[], {}]".to_owned()]); "array with whitespace")]
+        #[test_case("42" => (1, vec![0], vec!["42".to_owned()]); "bare number")]
+        #[test_case(" -1.5e3 " => (1, vec![1], vec!["-1.5e3".to_owned()]); "negative number with whitespace")]
+        #[test_case(r#""{[a]}""# => (1, vec![0], vec![r#""{[a]}""#.to_owned()]); "string with brackets")]
+        #[test_case("true" => (1, vec![0], vec!["true".to_owned()]); "literal")]
+        #[test_case("null" => (1, vec![0], vec!["null".to_owned()]); "null")]
+        #[test_case("" => (0, vec![], vec![]); "empty")]
+        #[test_case(" \n\t " => (0, vec![], vec![]); "only whitespace")]
+        fn $test_name(json: &str) -> (usize, Vec<usize>, Vec<String>) {
+            let mut raw = json.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse("$").unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let count = engine.run::<CountResult>(&contents).unwrap().get();
+            let indices = engine.run::<IndexResult>(&contents).unwrap().into();
+            let spans = engine
+                .run::<SpanResult>(&contents)
+                .unwrap()
+                .get()
+                .iter()
+                .map(|span| json[span.clone()].to_owned())
+                .collect();
+
+            (count, indices, spans)
+        }
+    };
+}
+
+macro_rules! capped_span_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"a": [1, 22, 333]}"#, "$.a.*", 2, OversizedValues::Skip => vec![("1".to_owned(), false), ("22".to_owned(), false)]; "skip long number")]
+        #[test_case(r#"{"a": [1, 22, 333]}"#, "$.a.*", 2, OversizedValues::Truncate => vec![("1".to_owned(), false), ("22".to_owned(), false), ("33".to_owned(), true)]; "truncate long number")]
+        #[test_case(r#"{"a": {"b": "xyz"}}"#, "$.a.b", 3, OversizedValues::Truncate => vec![(r#""xy"#.to_owned(), true)]; "truncate string")]
+        #[test_case(r#"{"a": {"a": [1]}}"#, "$..a", 3, OversizedValues::Skip => vec![("[1]".to_owned(), false)]; "skip outer match")]
+        #[test_case(r#"{"a": {"a": [1]}}"#, "$..a", 0, OversizedValues::Truncate => vec![(String::new(), true), (String::new(), true)]; "zero limit")]
+        #[test_case(r#"{"a": "x"}"#, "$", usize::MAX, OversizedValues::Skip => vec![(r#"{"a": "x"}"#.to_owned(), false)]; "no limit")]
+        fn $test_name(json: &str, query_string: &str, max_value_bytes: usize, oversized: OversizedValues) -> Vec<(String, bool)> {
+            let mut raw = json.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let mut result = CappedSpanResult::new(max_value_bytes, oversized);
+            $impl::compile_query(&query)
+                .unwrap()
+                .run_into(&contents, &mut result)
+                .unwrap();
+
+            result
+                .get()
+                .iter()
+                .map(|capped| (json[capped.span.clone()].to_owned(), capped.is_truncated))
+                .collect()
+        }
+    };
+}
+
+macro_rules! path_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"a": 1}"#, "$" => "$\n"; "root")]
+        #[test_case(r#"{"a": [1, [2, 3], {"b": 4}]}"#, "$.a.*" => "$['a'][0]\n$['a'][1]\n$['a'][2]\n"; "list elements")]
+        #[test_case(r#"[ {"x": 1}, [], {"x": [{"x": 2}]} ]"#, "$..x" => "$[0]['x']\n$[2]['x']\n$[2]['x'][0]['x']\n"; "nested descendants")]
+        #[test_case(r#"{"s": ",[", "l": ["]", ",", {"k": 1}]}"#, "$..k" => "$['l'][2]['k']\n"; "structural characters in strings")]
+        #[test_case(r#"{"a\"b": {"it's": {"k": 1}}}"#, "$..k" => "$['a\"b']['it\\'s']['k']\n"; "escaped keys")]
+        #[test_case(r#"[[0, {"x": 1}], {"y": [0, 1, {"x": [2, {"x": 3}]}]}]"#, "$..x" => "$[0][1]['x']\n$[1]['y'][2]['x']\n$[1]['y'][2]['x'][1]['x']\n"; "lists with unreported elements")]
+        #[test_case(r#"{"a": [{"b": 1}, {"b": 2}, {"c": 3, "b": 4}]}"#, "$.a[2].b" => "$['a'][2]['b']\n"; "index selector")]
+        fn $test_name(json: &str, query_string: &str) -> String {
+            let mut raw = json.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<PathResult>(&contents)
+                .unwrap();
+
+            result.to_string()
+        }
+    };
+}
+
+macro_rules! keys_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"person": {"name": "Ann", "age": 42, "tags": ["a"]}}"#, "$.person.*" => keys(&[Some("name"), Some("age"), Some("tags")]); "object members")]
+        #[test_case(r#"{"a": [1, {"b": 2}]}"#, "$.a.*" => keys(&[None, None]); "array elements")]
+        #[test_case(r#"{"a": [1, {"b": 2}], "c": 3}"#, "$..[?(@ is scalar)]" => keys(&[None, Some("b"), Some("c")]); "descendants")]
+        #[test_case(r#"{ "x\"y" :1, "\u0041\n": [] }"#, "$.*" => keys(&[Some("x\"y"), Some("A\n")]); "escapes are decoded")]
+        #[test_case(r#"{"a": {"a": {"a": 1}}}"#, "$..a" => keys(&[Some("a"), Some("a"), Some("a")]); "nested matches")]
+        #[test_case(r#"{"a": 1}"#, "$" => keys(&[None]); "root")]
+        fn $test_name(json: &str, query_string: &str) -> Vec<Option<String>> {
+            let mut raw = json.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<KeysResult>(&contents)
+                .unwrap();
+
+            result.into()
+        }
+    };
+}
+
+macro_rules! raw_keys_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"person": {"name": "Ann", "age": 42}}"#, "$.person.*" => raw_keys(&[Some("name"), Some("age")]); "no escapes")]
+        #[test_case(r#"{"a": [1, {"b": 2}]}"#, "$.a.*" => raw_keys(&[None, None]); "array elements")]
+        #[test_case(r#"{ "x\"y" :1, "\u0041\n": [] }"#, "$.*" => raw_keys(&[Some(r#"x\"y"#), Some(r"\u0041\n")]); "escapes are kept")]
+        #[test_case(r#"{"\u00E9": 1, "\u00e9": 2, "é": 3}"#, "$.*" => raw_keys(&[Some(r"\u00E9"), Some(r"\u00e9"), Some("é")]); "equal keys written differently")]
+        #[test_case(r#"{"\ud83d\ude00": 1, "\ud800": 2}"#, "$.*" => raw_keys(&[Some(r"\ud83d\ude00"), Some(r"\ud800")]); "surrogates")]
+        fn $test_name(json: &str, query_string: &str) -> Vec<Option<Vec<u8>>> {
+            let mut raw = json.to_owned();
+            let contents = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let result = $impl::compile_query(&query)
+                .unwrap()
+                .run::<KeysResult>(&contents)
+                .unwrap();
+
+            assert_eq!(result.keys_unescaped(), result.get());
+            assert_eq!(result.keys_raw().len(), result.get().len());
+
+            result.keys_raw().to_vec()
+        }
+    };
+}
+
+macro_rules! token_index_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"a":[1,{"b":2}]}"#, "{ \"a\" : [ 1 , { \"b\" : 2 } ] }", "$.a.*" => vec![3, 4]; "spaces")]
+        #[test_case(r#"[{"x":"{,:"},[]]"#, "[\n  {\n    \"x\": \"{,:\"\n  },\n  []\n]", "$..x" => vec![3]; "structural characters in strings")]
+        #[test_case(r#"{"a":{"a":[true]}}"#, "{\r\n\t\"a\":\t{\r\n\t\t\"a\":\t[\r\n\t\t\ttrue\r\n\t\t]\r\n\t}\r\n}", "$..a" => vec![2, 4]; "nested matches")]
+        #[test_case("[1]", " [ 1 ] ", "$" => vec![0]; "root")]
+        #[test_case(r#"{"a":{"x":{"a":{"y":1}},"z":2}}"#, "{ \"a\": { \"x\": { \"a\": { \"y\": 1 } }, \"z\": 2 } }", "$..a[last()]" => vec![8, 12]; "members reported at closing")]
+        fn $test_name(minified: &str, pretty: &str, query_string: &str) -> Vec<usize> {
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+            let mut minified = minified.to_owned();
+            let mut pretty = pretty.to_owned();
+
+            let minified_result = engine
+                .run::<TokenIndexResult>(&Input::new(&mut minified))
+                .unwrap();
+            let pretty_result = engine
+                .run::<TokenIndexResult>(&Input::new(&mut pretty))
+                .unwrap();
+
+            assert_eq!(minified_result, pretty_result);
+            minified_result.into()
+        }
+    };
+}
+
+macro_rules! accumulated_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("basic/small.json", &["$..person..phoneNumber..number", "$..phoneNumber..type", "$..number"]; "small.json three queries")]
+        #[test_case("basic/child.json", &["$..a..b.c..d", "$..a"]; "child.json two queries")]
+        #[test_case("basic/root.json", &["$", "$.a", "$"]; "root.json repeated root")]
+        #[test_case("basic/wildcard_list.json", &[]; "wildcard_list.json no queries")]
+        fn $test_name(test_path: &str, query_strings: &[&str]) {
+            let contents = get_contents(test_path);
+            let queries: Vec<_> = query_strings
+                .iter()
+                .map(|q| JsonPathQuery::parse(q).unwrap())
+                .collect();
+
+            let expected: Vec<usize> = queries
+                .iter()
+                .flat_map(|q| {
+                    let result = $impl::compile_query(q)
+                        .unwrap()
+                        .run::<IndexResult>(&contents)
+                        .unwrap();
+                    Vec::from(result)
+                })
+                .collect();
+
+            let mut result = IndexResult::default();
+            for query in &queries {
+                $impl::compile_query(query)
+                    .unwrap()
+                    .run_into(&contents, &mut result)
+                    .unwrap();
+            }
+
+            assert_eq!(result.get(), expected);
+        }
+    };
+}
+
+macro_rules! reset_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test]
+        fn $test_name() {
+            let contents = get_contents("basic/small.json");
+            let query = JsonPathQuery::parse("$..number").unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+            let mut result = CountResult::default();
+
+            engine.run_into(&contents, &mut result).unwrap();
+            engine.run_into(&contents, &mut result).unwrap();
+            assert_eq!(result.get(), 8);
+
+            result.reset();
+            engine.run_into(&contents, &mut result).unwrap();
+            assert_eq!(result.get(), 4);
+        }
+    };
+}
+
+macro_rules! complete_accumulator_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test]
+        fn $test_name() {
+            let mut raw = r#"{"a": 1, "b": {"c": 2"#.to_owned();
+            let contents = Input::new(&mut raw);
+            let first = JsonPathQuery::parse("$.a").unwrap();
+            let second = JsonPathQuery::parse("$..c").unwrap();
+            let mut result = ExistsResult::default();
+
+            $impl::compile_query(&first)
+                .unwrap()
+                .run_into(&contents, &mut result)
+                .unwrap();
+            // The document is malformed, but it is not processed again once the result is complete.
+            $impl::compile_query(&second)
+                .unwrap()
+                .run_into(&contents, &mut result)
+                .unwrap();
+
+            assert!(result.get());
+        }
+    };
+}
+
+macro_rules! dedup_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("basic/small.json", "$..person..phoneNumber..type"; "small.json")]
+        #[test_case("basic/child.json", "$..a..b.c..d"; "child.json")]
+        #[test_case("basic/wildcard_list.json", "$..a.*"; "wildcard_list.json")]
+        #[test_case("basic/empty.json", "$"; "empty.json")]
+        fn $test_name(test_path: &str, query_string: &str) {
+            let contents = get_contents(test_path);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let result = engine.run::<DedupResult<SpanResult>>(&contents).unwrap();
+            let all_spans: Vec<_> = engine.run::<SpanResult>(&contents).unwrap().into();
+
+            let mut expected: Vec<std::ops::Range<usize>> = vec![];
+            for span in all_spans {
+                if !expected
+                    .iter()
+                    .any(|other| contents[other.clone()] == contents[span.clone()])
+                {
+                    expected.push(span);
+                }
+            }
+            assert_eq!(result.distinct_values(), expected.len());
+            assert_eq!(result.get().get(), expected);
+        }
+    };
+}
+
+macro_rules! fan_out_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("basic/small.json", "$..person..phoneNumber..number"; "small.json")]
+        #[test_case("basic/child.json", "$..a..b.c..d"; "child.json")]
+        #[test_case("basic/wildcard_list.json", "$..a.*"; "wildcard_list.json")]
+        #[test_case("basic/empty.json", "$"; "empty.json")]
+        fn $test_name(test_path: &str, query_string: &str) {
+            let contents = get_contents(test_path);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let result = engine
+                .run::<FanOutResult<(ExistsResult, CountResult, IndexResult, SpanResult)>>(
+                    &contents,
+                )
+                .unwrap();
+            let (exists, count, indices, spans) = result.into_inner();
+
+            // The exists result is complete after the first match, but the others are not.
+            assert_eq!(exists, engine.run::<ExistsResult>(&contents).unwrap());
+            assert_eq!(count, engine.run::<CountResult>(&contents).unwrap());
+            assert_eq!(indices, engine.run::<IndexResult>(&contents).unwrap());
+            assert_eq!(spans, engine.run::<SpanResult>(&contents).unwrap());
+        }
+    };
+}
+
+summary_test_cases!(main_summary_test, MainEngine);
+summary_test_cases!(recursive_summary_test, RecursiveEngine);
+numeric_stats_test_cases!(main_numeric_stats_test, MainEngine);
+numeric_stats_test_cases!(recursive_numeric_stats_test, RecursiveEngine);
+depth_histogram_test_cases!(main_depth_histogram_test, MainEngine);
+depth_histogram_test_cases!(recursive_depth_histogram_test, RecursiveEngine);
+span_test_cases!(main_span_test, MainEngine);
+span_test_cases!(recursive_span_test, RecursiveEngine);
+root_test_cases!(main_root_test, MainEngine);
+root_test_cases!(recursive_root_test, RecursiveEngine);
+capped_span_test_cases!(main_capped_span_test, MainEngine);
+capped_span_test_cases!(recursive_capped_span_test, RecursiveEngine);
+path_test_cases!(main_path_test, MainEngine);
+path_test_cases!(recursive_path_test, RecursiveEngine);
+keys_test_cases!(main_keys_test, MainEngine);
+keys_test_cases!(recursive_keys_test, RecursiveEngine);
+raw_keys_test_cases!(main_raw_keys_test, MainEngine);
+raw_keys_test_cases!(recursive_raw_keys_test, RecursiveEngine);
+token_index_test_cases!(main_token_index_test, MainEngine);
+token_index_test_cases!(recursive_token_index_test, RecursiveEngine);
+accumulated_test_cases!(main_accumulated_test, MainEngine);
+accumulated_test_cases!(recursive_accumulated_test, RecursiveEngine);
+reset_test_cases!(main_reset_test, MainEngine);
+reset_test_cases!(recursive_reset_test, RecursiveEngine);
+complete_accumulator_test_cases!(main_complete_accumulator_test, MainEngine);
+complete_accumulator_test_cases!(recursive_complete_accumulator_test, RecursiveEngine);
+dedup_test_cases!(main_dedup_test, MainEngine);
+dedup_test_cases!(recursive_dedup_test, RecursiveEngine);
+fan_out_test_cases!(main_fan_out_test, MainEngine);
+fan_out_test_cases!(recursive_fan_out_test, RecursiveEngine);
+
+#[test]
+fn fan_out_result_displays_components_in_order() {
+    let mut raw = r#"{"a": {"x": 1}, "b": [{"x": 2}]}"#.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse("$..x").unwrap();
+
+    let result = MainEngine::compile_query(&query)
+        .unwrap()
+        .run::<FanOutResult<(CountResult, FanOutResult<(ExistsResult, IndexResult)>)>>(&contents)
+        .unwrap();
+
+    assert_eq!(result.to_string(), "2\ntrue\n[10, 26]");
+}
+
+#[test]
+fn capped_span_result_keeps_limit_after_reset() {
+    let mut raw = r#"{"a": [1, 22, 333]}"#.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse("$.a.*").unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+    let mut result = CappedSpanResult::new(2, OversizedValues::Truncate);
+
+    engine.run_into(&contents, &mut result).unwrap();
+    result.reset();
+    engine.run_into(&contents, &mut result).unwrap();
+
+    assert_eq!(result.to_string(), "[7..8, 10..12, 14..16 (truncated)]");
+}
+
+#[test]
+fn dedup_result_compares_raw_bytes_and_forgets_values_on_reset() {
+    let mut raw = r#"{"a": [1, 1.0, "x", "\u0078", {"k": 1}, {"k":1}, 1, "x"]}"#.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse("$.a.*").unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+    let mut result = DedupResult::<CountResult>::default();
+
+    engine.run_into(&contents, &mut result).unwrap();
+    assert_eq!(result.get().get(), 6);
+
+    result.reset();
+    engine.run_into(&contents, &mut result).unwrap();
+    assert_eq!(result.get().get(), 6);
+}
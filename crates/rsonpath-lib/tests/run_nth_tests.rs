@@ -0,0 +1,49 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::CountResult;
+use test_case::test_case;
+
+const DOCUMENT: &str = r#"{"a": [1, {"a": "x"}, [2, 3]], "b": {"a": null}}"#;
+
+macro_rules! nth_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$..a", 0 => Some(r#"[1, {"a": "x"}, [2, 3]]"#.to_owned()); "first match")]
+        #[test_case("$..a", 1 => Some(r#""x""#.to_owned()); "nested match")]
+        #[test_case("$..a", 2 => Some("null".to_owned()); "last match")]
+        #[test_case("$..a", 3 => None; "one past the last match")]
+        #[test_case("$..a", usize::MAX => None; "far out of range")]
+        #[test_case("$.a.*", 2 => Some("[2, 3]".to_owned()); "array element")]
+        #[test_case("$.c", 0 => None; "no matches")]
+        #[test_case("$", 0 => Some(DOCUMENT.to_owned()); "root")]
+        fn $test_name(query_string: &str, n: usize) -> Option<String> {
+            let mut raw = DOCUMENT.to_owned();
+            let input = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let span = engine.run_nth(&input, n).unwrap();
+
+            span.map(|span| DOCUMENT[span].to_owned())
+        }
+    };
+}
+
+nth_test_cases!(main_nth_test, MainEngine);
+nth_test_cases!(recursive_nth_test, RecursiveEngine);
+
+#[test]
+fn stops_after_the_selected_match() {
+    let json = r#"[1, 2, 3, {"#;
+    let mut raw = json.to_owned();
+    let input = Input::new(&mut raw);
+    let query = JsonPathQuery::parse("$.*").unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+
+    // The unclosed object is only detected if the whole document is processed.
+    assert!(engine.run::<CountResult>(&input).is_err());
+    let span = engine.run_nth(&input, 1).unwrap().unwrap();
+
+    assert_eq!(&json[span], "2");
+}
@@ -0,0 +1,113 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::scalar::{JsonScalar, ScalarError};
+use std::fmt::Debug;
+use test_case::test_case;
+
+const CONFIG: &str = r#"{
+    "name": "svc \"main\" é",
+    "port": 8080,
+    "offset": -12,
+    "ratio": 0.25,
+    "big": 18446744073709551615,
+    "enabled": true,
+    "features": [{"on": false}, {"on": true}],
+    "missing": null,
+    "port_text": "8080"
+}"#;
+
+fn get<C: Compiler, T: JsonScalar>(query_string: &str) -> Result<T, ScalarError> {
+    let mut contents = CONFIG.to_owned();
+    let input = Input::new(&mut contents);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let engine = C::compile_query(&query).unwrap();
+
+    engine.get_scalar::<T>(&input)
+}
+
+fn mismatch<T: Debug>(result: Result<T, ScalarError>) -> (String, &'static str) {
+    match result {
+        Err(ScalarError::TypeMismatch {
+            span,
+            value,
+            expected,
+        }) => {
+            assert_eq!(CONFIG[span], value);
+            (value, expected)
+        }
+        other => panic!("expected a type mismatch, got {other:?}"),
+    }
+}
+
+macro_rules! scalar_test_cases {
+    ($values_test_name:ident, $mismatch_test_name:ident, $no_match_test_name:ident, $impl:ident) => {
+        #[test]
+        fn $values_test_name() {
+            assert_eq!(get::<$impl, String>("$.name").unwrap(), "svc \"main\" é");
+            assert_eq!(get::<$impl, String>("$.port_text").unwrap(), "8080");
+            assert_eq!(get::<$impl, i64>("$.port").unwrap(), 8080);
+            assert_eq!(get::<$impl, u64>("$.port").unwrap(), 8080);
+            assert_eq!(get::<$impl, f64>("$.port").unwrap(), 8080.0);
+            assert_eq!(get::<$impl, i64>("$.offset").unwrap(), -12);
+            assert_eq!(get::<$impl, f64>("$.ratio").unwrap(), 0.25);
+            assert_eq!(get::<$impl, u64>("$.big").unwrap(), u64::MAX);
+            assert!(get::<$impl, bool>("$.enabled").unwrap());
+            assert!(!get::<$impl, bool>("$.features.*.on").unwrap());
+            assert!(!get::<$impl, bool>("$..on").unwrap());
+        }
+
+        #[test_case("$.offset", "u64" => ("-12".to_owned(), "u64"); "negative as unsigned")]
+        #[test_case("$.ratio", "i64" => ("0.25".to_owned(), "i64"); "fraction as integer")]
+        #[test_case("$.big", "i64" => ("18446744073709551615".to_owned(), "i64"); "overflow")]
+        #[test_case("$.port_text", "i64" => (r#""8080""#.to_owned(), "i64"); "string as number")]
+        #[test_case("$.port", "String" => ("8080".to_owned(), "String"); "number as string")]
+        #[test_case("$.port", "bool" => ("8080".to_owned(), "bool"); "number as bool")]
+        #[test_case("$.missing", "f64" => ("null".to_owned(), "f64"); "null")]
+        #[test_case("$.features", "String" => (r#"[{"on": false}, {"on": true}]"#.to_owned(), "String"); "array")]
+        fn $mismatch_test_name(query_string: &str, type_name: &str) -> (String, &'static str) {
+            match type_name {
+                "i64" => mismatch(get::<$impl, i64>(query_string)),
+                "u64" => mismatch(get::<$impl, u64>(query_string)),
+                "f64" => mismatch(get::<$impl, f64>(query_string)),
+                "bool" => mismatch(get::<$impl, bool>(query_string)),
+                "String" => mismatch(get::<$impl, String>(query_string)),
+                _ => unreachable!(),
+            }
+        }
+
+        #[test_case("$.absent"; "absent key")]
+        #[test_case("$.features[5].on"; "index out of bounds")]
+        #[test_case("$..deep"; "descendant")]
+        fn $no_match_test_name(query_string: &str) {
+            assert!(matches!(get::<$impl, i64>(query_string), Err(ScalarError::NoMatch)));
+        }
+    };
+}
+
+scalar_test_cases!(
+    main_scalar_values_test,
+    main_scalar_mismatch_test,
+    main_scalar_no_match_test,
+    MainEngine
+);
+scalar_test_cases!(
+    recursive_scalar_values_test,
+    recursive_scalar_mismatch_test,
+    recursive_scalar_no_match_test,
+    RecursiveEngine
+);
+
+#[test]
+fn scalar_engine_error_is_propagated() {
+    let mut contents = "{1: 2}".to_owned();
+    let input = Input::new(&mut contents);
+    let query = JsonPathQuery::parse("$.a").unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+
+    assert!(matches!(
+        engine.get_scalar::<i64>(&input),
+        Err(ScalarError::Engine(_))
+    ));
+}
@@ -0,0 +1,181 @@
+use rsonpath_lib::classification::structural::BracketType;
+use rsonpath_lib::engine::Input;
+use rsonpath_lib::scan::{scan, ScanAction, StructuralSink};
+use test_case::test_case;
+
+/// Records all events as their characters followed by their positions,
+/// skipping the subtrees of openings nested deeper than `max_depth`.
+struct Recorder {
+    max_depth: usize,
+    depth: usize,
+    events: Vec<(char, usize)>,
+}
+
+impl StructuralSink for Recorder {
+    fn on_opening(&mut self, bracket: BracketType, idx: usize) -> ScanAction {
+        let character = match bracket {
+            BracketType::Square => '[',
+            BracketType::Curly => '{',
+        };
+        self.events.push((character, idx));
+        self.depth += 1;
+
+        if self.depth > self.max_depth {
+            ScanAction::SkipSubtree
+        } else {
+            ScanAction::Continue
+        }
+    }
+
+    fn on_closing(&mut self, bracket: BracketType, idx: usize) {
+        let character = match bracket {
+            BracketType::Square => ']',
+            BracketType::Curly => '}',
+        };
+        self.events.push((character, idx));
+        self.depth -= 1;
+    }
+
+    fn on_colon(&mut self, idx: usize) {
+        self.events.push((':', idx));
+    }
+
+    fn on_comma(&mut self, idx: usize) {
+        self.events.push((',', idx));
+    }
+}
+
+fn record(json: &str, max_depth: usize) -> Vec<(char, usize)> {
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+    let mut recorder = Recorder {
+        max_depth,
+        depth: 0,
+        events: vec![],
+    };
+
+    scan(&input, &mut recorder);
+
+    recorder.events
+}
+
+/// Straightforward byte-by-byte computation of the events that [`scan`] should report.
+fn expected_events(json: &str, max_depth: usize) -> Vec<(char, usize)> {
+    let mut events = vec![];
+    let mut depth = 0;
+    let mut is_in_string = false;
+    let mut is_escaped = false;
+
+    for (idx, b) in json.bytes().enumerate() {
+        if is_escaped {
+            is_escaped = false;
+        } else if is_in_string {
+            match b {
+                b'\\' => is_escaped = true,
+                b'"' => is_in_string = false,
+                _ => (),
+            }
+        } else {
+            match b {
+                b'"' => is_in_string = true,
+                b'[' | b'{' => {
+                    depth += 1;
+                    if depth <= max_depth.saturating_add(1) {
+                        events.push((b as char, idx));
+                    }
+                }
+                b']' | b'}' => {
+                    if depth <= max_depth.saturating_add(1) {
+                        events.push((b as char, idx));
+                    }
+                    depth -= 1;
+                }
+                b':' | b',' if depth <= max_depth => events.push((b as char, idx)),
+                _ => (),
+            }
+        }
+    }
+
+    events
+}
+
+#[test]
+fn all_events_are_reported() {
+    let json = r#"{"a": [1, {"b": "]:,"}], "c\"": {}}"#;
+
+    assert_eq!(
+        record(json, usize::MAX),
+        vec![
+            ('{', 0),
+            (':', 4),
+            ('[', 6),
+            (',', 8),
+            ('{', 10),
+            (':', 14),
+            ('}', 21),
+            (']', 22),
+            (',', 23),
+            (':', 30),
+            ('{', 32),
+            ('}', 33),
+            ('}', 34),
+        ]
+    );
+}
+
+#[test]
+fn skipped_subtree_reports_only_its_brackets() {
+    let json = r#"{"a": [1, {"b": 2}], "c": 3}"#;
+
+    assert_eq!(
+        record(json, 1),
+        vec![
+            ('{', 0),
+            (':', 4),
+            ('[', 6),
+            (']', 18),
+            (',', 19),
+            (':', 24),
+            ('}', 27),
+        ]
+    );
+}
+
+#[test]
+fn skipping_the_root_reports_only_its_brackets() {
+    let json = r#"[{"a": [1, 2]}, 3] "#;
+
+    assert_eq!(record(json, 0), vec![('[', 0), (']', 17)]);
+}
+
+#[test]
+fn unclosed_skipped_subtree_ends_the_scan() {
+    let json = r#"{"a": [1, 2"#;
+
+    assert_eq!(record(json, 1), vec![('{', 0), (':', 4), ('[', 6)]);
+}
+
+#[test]
+fn unclosed_skipped_subtree_with_closed_children_ends_the_scan() {
+    let json = r#"{"a": [1, [2]"#;
+
+    assert_eq!(record(json, 1), vec![('{', 0), (':', 4), ('[', 6)]);
+}
+
+#[test]
+fn atomic_root_reports_nothing() {
+    assert_eq!(record(r#""{[:,]}""#, usize::MAX), vec![]);
+}
+
+#[test_case(0; "skip root")]
+#[test_case(1; "skip depth two")]
+#[test_case(2; "skip depth three")]
+#[test_case(3; "skip depth four")]
+#[test_case(usize::MAX; "no skipping")]
+fn events_across_blocks_are_correct(max_depth: usize) {
+    let json = r#"{"items": [{"id": 1, "tags": ["a", "b,c", "{d}"], "nested": {"x": [1, [2, [3]]], "y": "\"]"}},
+        {"id": 2, "tags": [], "nested": {"x": [], "y": {"z": {"w": [[[]]]}}}}],
+        "total": 2, "meta": {"pages": [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20]}}"#;
+
+    assert_eq!(record(json, max_depth), expected_events(json, max_depth));
+}
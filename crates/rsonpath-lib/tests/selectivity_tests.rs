@@ -0,0 +1,54 @@
+use rsonpath_lib::query::automaton::{Automaton, Selectivity};
+use rsonpath_lib::query::JsonPathQuery;
+use test_case::test_case;
+
+#[test_case("$" => Selectivity::Anchored; "root")]
+#[test_case("$.a.b" => Selectivity::Anchored; "labels")]
+#[test_case("$.*.a[*]" => Selectivity::Anchored; "wildcards")]
+#[test_case("$.a[?(@.b == 1)]" => Selectivity::Anchored; "filter")]
+#[test_case("$.a..b" => Selectivity::SubtreeScan; "descendant after label")]
+#[test_case("$.a..[?(@ is scalar)]" => Selectivity::SubtreeScan; "descendant filter after label")]
+#[test_case("$.a.*..b" => Selectivity::SubtreeScan; "descendant after label and wildcard")]
+#[test_case("$.a...b" => Selectivity::SubtreeScan; "descendant or self after label")]
+#[test_case("$..[?(@ is scalar)]" => Selectivity::FullScan; "descendant filter")]
+#[test_case("$.*..a" => Selectivity::FullScan; "descendant after wildcard")]
+#[test_case("$.*.*..a.b" => Selectivity::FullScan; "descendant after wildcards")]
+fn selectivity_of_query(query_string: &str) -> Selectivity {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let automaton = Automaton::new(&query).unwrap();
+
+    automaton.selectivity()
+}
+
+#[cfg(feature = "head-skip")]
+#[test_case("$..a" => Selectivity::LabelSearch; "descendant label")]
+#[test_case("$..a.b.c" => Selectivity::LabelSearch; "descendant label before labels")]
+#[test_case("$..a..b" => Selectivity::LabelSearch; "nested descendant labels")]
+#[test_case("$..a.*" => Selectivity::LabelSearch; "descendant label before wildcard")]
+fn selectivity_of_head_skipped_query(query_string: &str) -> Selectivity {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let automaton = Automaton::new(&query).unwrap();
+
+    automaton.selectivity()
+}
+
+#[test]
+fn alternation_of_descendant_labels_is_full_scan() {
+    let queries = [
+        JsonPathQuery::parse("$..a").unwrap(),
+        JsonPathQuery::parse("$..b").unwrap(),
+    ];
+
+    let automaton = Automaton::new_alternation(&queries).unwrap();
+
+    assert_eq!(automaton.selectivity(), Selectivity::FullScan);
+}
+
+#[test]
+fn selectivity_is_ordered_from_most_selective() {
+    assert!(Selectivity::Anchored < Selectivity::LabelSearch);
+    assert!(Selectivity::LabelSearch < Selectivity::SubtreeScan);
+    assert!(Selectivity::SubtreeScan < Selectivity::FullScan);
+}
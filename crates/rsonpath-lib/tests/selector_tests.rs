@@ -0,0 +1,147 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::automaton::Automaton;
+use rsonpath_lib::query::error::CompilerError;
+use rsonpath_lib::query::{ArraySlice, JsonPathQuery, JsonPathQueryNode, Label, Selector};
+use rsonpath_lib::result::IndexResult;
+use std::num::NonZeroUsize;
+use test_case::test_case;
+
+const JSON: &str =
+    r#"{"a": [1, {"b": [2, 3]}, {"a": {"b": 4}}], "b": {"c": 5, "b": [6]}, "field_x": 7}"#;
+
+/// The selectors of a parsed query, following the root.
+fn selectors_of(query: &JsonPathQuery) -> Vec<Selector> {
+    query
+        .root()
+        .iter()
+        .filter_map(|node| match node {
+            JsonPathQueryNode::Root(_) => None,
+            JsonPathQueryNode::Child(label, _) => Some(Selector::Child(label.clone())),
+            JsonPathQueryNode::AnyChild(_) => Some(Selector::AnyChild),
+            JsonPathQueryNode::Descendant(label, _) => Some(Selector::Descendant(label.clone())),
+            JsonPathQueryNode::DescendantOrSelf(label, _) => {
+                Some(Selector::DescendantOrSelf(label.clone()))
+            }
+            JsonPathQueryNode::PatternChild(pattern, _) => {
+                Some(Selector::PatternChild(pattern.clone()))
+            }
+            JsonPathQueryNode::PatternDescendant(pattern, _) => {
+                Some(Selector::PatternDescendant(pattern.clone()))
+            }
+            JsonPathQueryNode::Filter(filter, _) => Some(Selector::Filter(filter.clone())),
+            JsonPathQueryNode::DescendantFilter(filter, _) => {
+                Some(Selector::DescendantFilter(filter.clone()))
+            }
+            JsonPathQueryNode::MemberFromEnd(position, _) => {
+                Some(Selector::MemberFromEnd(*position))
+            }
+            JsonPathQueryNode::ArrayIndex(index, _) => Some(Selector::ArrayIndex(*index)),
+            JsonPathQueryNode::Slice(slice, _) => Some(Selector::Slice(*slice)),
+            JsonPathQueryNode::DescendantSlice(slice, _) => Some(Selector::DescendantSlice(*slice)),
+        })
+        .collect()
+}
+
+#[test_case("$"; "root")]
+#[test_case("$.a"; "child")]
+#[test_case("$.*"; "wildcard")]
+#[test_case("$..b"; "descendant")]
+#[test_case("$.a...b"; "descendant or self")]
+#[test_case("$...a...b"; "many descendant or self")]
+#[test_case("$.a[1].b[0]"; "indices")]
+#[test_case("$..a[1:3]"; "slice")]
+#[test_case("$..[1:]"; "descendant slice")]
+#[test_case("$..field_*"; "prefix pattern")]
+#[test_case("$.b[?(@.c == 5)]"; "filter")]
+#[test_case("$..[?(@ == 4)]"; "descendant filter")]
+#[test_case("$.b[last()]"; "member from end")]
+#[test_case("$..a.*..b[*]"; "mixed")]
+fn selectors_compile_like_the_query(query_string: &str) {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let selectors = selectors_of(&query);
+
+    let expected = Automaton::new(&query).unwrap();
+    let automaton = Automaton::from_selectors(&selectors).unwrap();
+
+    assert_eq!(automaton, expected);
+    assert_eq!(automaton.filter(), expected.filter());
+    assert_eq!(automaton.member_from_end(), expected.member_from_end());
+}
+
+macro_rules! engine_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$..b"; "descendant")]
+        #[test_case("$.a[1:]..b"; "slice and descendant")]
+        #[test_case("$..field_*"; "prefix pattern")]
+        #[test_case("$.a[?(@.b == 4)]"; "filter")]
+        #[test_case("$.*[last()]"; "member from end")]
+        fn $test_name(query_string: &str) {
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let selectors = selectors_of(&query);
+            let mut contents = JSON.to_owned();
+            let input = Input::new(&mut contents);
+
+            let expected: Vec<usize> = $impl::compile_query(&query)
+                .unwrap()
+                .run::<IndexResult>(&input)
+                .unwrap()
+                .into();
+            let automaton = Automaton::from_selectors(&selectors).unwrap();
+            let result: Vec<usize> = $impl::from_compiled_query(automaton)
+                .run::<IndexResult>(&input)
+                .unwrap()
+                .into();
+
+            assert_eq!(result, expected);
+        }
+    };
+}
+
+engine_test_cases!(selectors_give_the_results_of_the_query_main, MainEngine);
+engine_test_cases!(
+    selectors_give_the_results_of_the_query_recursive,
+    RecursiveEngine
+);
+
+#[test]
+fn handwritten_selectors_compile_like_the_query() {
+    let selectors = [
+        Selector::Descendant(Label::new("a")),
+        Selector::AnyChild,
+        Selector::Slice(ArraySlice::new(1, Some(3), NonZeroUsize::new(1).unwrap())),
+    ];
+    let query = JsonPathQuery::parse("$..a.*[1:3]").unwrap();
+
+    assert_eq!(
+        Automaton::from_selectors(&selectors).unwrap(),
+        Automaton::new(&query).unwrap()
+    );
+}
+
+#[test_case("$.b[?(@.c == 5)].c"; "non-terminal filter")]
+#[test_case("$..[?(@ == 4)].a"; "non-terminal descendant filter")]
+#[test_case("$.a[last()].b"; "non-terminal member from end")]
+fn unsupported_selectors_fail_like_the_query(query_string: &str) {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let selectors = selectors_of(&query);
+
+    let expected = Automaton::new(&query).unwrap_err();
+    let error = Automaton::from_selectors(&selectors).unwrap_err();
+
+    assert!(matches!(error, CompilerError::NotSupported(_)));
+    assert_eq!(error.to_string(), expected.to_string());
+}
+
+#[test]
+fn too_many_descendant_or_self_selectors_fail_like_the_query() {
+    let query = JsonPathQuery::parse(&format!("${}", "...a".repeat(20))).unwrap();
+    let selectors = selectors_of(&query);
+
+    let expected = Automaton::new(&query).unwrap_err();
+    let error = Automaton::from_selectors(&selectors).unwrap_err();
+
+    assert!(matches!(error, CompilerError::QueryTooComplex(_)));
+    assert_eq!(error.to_string(), expected.to_string());
+}
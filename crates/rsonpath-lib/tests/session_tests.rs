@@ -0,0 +1,137 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::session::Session;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::automaton::Automaton;
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, IndexResult, SpanResult};
+use std::fs;
+use test_case::test_case;
+
+const ROOT_TEST_DIRECTORY: &str = "./tests/data";
+
+const QUERIES: &[&str] = &[
+    "$",
+    "$.*",
+    "$..a",
+    "$..a.b",
+    "$..a..b",
+    "$.a..b",
+    "$..user..entities..url",
+    "$..user.id",
+    "$..entities.urls[0]",
+    "$.statuses[*].user.screen_name",
+    "$.search_metadata.count",
+    "$..[?(@ is scalar)]",
+    "$.a.b",
+    "$[1].a",
+    "$..a[1]",
+];
+
+fn assert_session_agrees_with_engine(json: &str, description: &str) {
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+    let session = Session::new(&input);
+    let profiled_session = Session::new_with_depth_profile(&input);
+
+    for query_string in QUERIES {
+        let query = JsonPathQuery::parse(query_string).unwrap();
+        let automaton = Automaton::new(&query).unwrap();
+        let expected = MainEngine::compile_query(&query)
+            .unwrap()
+            .run::<IndexResult>(&input)
+            .unwrap();
+
+        assert_eq!(
+            session.run::<IndexResult>(&automaton).unwrap(),
+            expected,
+            "{query_string} on {description}"
+        );
+        assert_eq!(
+            profiled_session.run::<IndexResult>(&automaton).unwrap(),
+            expected,
+            "{query_string} on {description} with the depth profile"
+        );
+    }
+}
+
+#[test_case("basic/escapes.json")]
+#[test_case("basic/quote_escape.json")]
+#[test_case("basic/skipping.json")]
+#[test_case("basic/child_hell.json")]
+#[test_case("basic/heterogeneous_list.json")]
+#[test_case("basic/spaced_colon.json")]
+#[test_case("twitter/twitter_urls.json")]
+#[test_case("twitter/twitter.json")]
+fn session_agrees_with_engine_on_corpus(path: &str) {
+    let json = fs::read_to_string(format!("{ROOT_TEST_DIRECTORY}/{path}")).unwrap();
+
+    assert_session_agrees_with_engine(&json, path);
+}
+
+#[test_case(r#"{"a": {"b": 1}}"#; "small")]
+#[test_case(r#"{"x": "a long string with \"a\": {\"b\": 1} inside it, spanning more than one block of the input", "a": {"b": 2}}"#; "quoted keys across blocks")]
+#[test_case(r#"{"x": "\\", "a": [{"b": "]}"}, {"b": "\"{"}]}"#; "escapes and brackets in strings")]
+#[test_case(r#"[{"a": {"a": {"b": 1}}}, {"c": [{"a": {"b": 2}}]}, "a", {"b": 3}]"#; "nested labels")]
+#[test_case(&format!(r#"{{"a": [{}, {{"b": [1, {{"a": [2, 3]}}]}}], "b": 4}}"#, r#"{"c": [[], {}], "d": "]}"}, "#.repeat(20)); "skipping across blocks")]
+#[test_case(r#"[{"a": [1, 2]}, {"a": {"b": [[1], {"a": [4, 5]}]}}, [{"a": {"b": 6}}]]"#; "skipping nested labels")]
+#[test_case(""; "empty input")]
+#[test_case("42"; "atomic root")]
+fn session_agrees_with_engine(json: &str) {
+    assert_session_agrees_with_engine(json, json);
+}
+
+#[test]
+fn session_runs_many_queries_on_the_same_input() {
+    let json = r#"{"a": [1, {"b": "]"}], "b": {"a": 2}}"#;
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+    let session = Session::new(&input);
+    let run = |query_string: &str| {
+        let query = JsonPathQuery::parse(query_string).unwrap();
+        let automaton = Automaton::new(&query).unwrap();
+        let spans = session.run::<SpanResult>(&automaton).unwrap();
+
+        spans
+            .get()
+            .iter()
+            .map(|span| json[span.clone()].trim().to_owned())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(run("$..a"), vec![r#"[1, {"b": "]"}]"#, "2"]);
+    assert_eq!(run("$.a[1].b"), vec![r#""]""#]);
+    assert_eq!(run("$.b"), vec![r#"{"a": 2}"#]);
+    assert_eq!(run("$..a"), vec![r#"[1, {"b": "]"}]"#, "2"]);
+}
+
+#[test]
+fn session_memory_is_one_bit_per_input_byte() {
+    let json = r#"{"a": 1}"#.repeat(1000);
+    let mut contents = format!("[{}]", json.replace("}{", "},{"));
+    let input = Input::new(&mut contents);
+    let session = Session::new(&input);
+
+    assert_eq!(session.approx_memory_bytes(), input.len() / 8);
+
+    let query = JsonPathQuery::parse("$[*].a").unwrap();
+    let automaton = Automaton::new(&query).unwrap();
+    assert_eq!(session.run::<CountResult>(&automaton).unwrap().get(), 1000);
+}
+
+#[test]
+fn depth_profile_takes_memory_proportional_to_brackets() {
+    let json = r#"{"a": 1}"#.repeat(1000);
+    let mut contents = format!("[{}]", json.replace("}{", "},{"));
+    let input = Input::new(&mut contents);
+    let session = Session::new_with_depth_profile(&input);
+    let brackets = 2 * 1000 + 2;
+
+    assert_eq!(
+        session.approx_memory_bytes(),
+        input.len() / 8 + brackets * 2 * std::mem::size_of::<usize>()
+    );
+
+    let query = JsonPathQuery::parse("$[*].a").unwrap();
+    let automaton = Automaton::new(&query).unwrap();
+    assert_eq!(session.run::<CountResult>(&automaton).unwrap().get(), 1000);
+}
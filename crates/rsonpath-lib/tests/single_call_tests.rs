@@ -0,0 +1,103 @@
+use rsonpath_lib::engine::error::EngineError;
+use rsonpath_lib::engine::{Compiler, Engine, Input, RsonpathEngine};
+use rsonpath_lib::error::QueryError;
+use rsonpath_lib::query::error::CompilerError;
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{IndexResult, SpanResult};
+use std::error::Error;
+use test_case::test_case;
+
+const JSON: &str = r#"{"a": [1, {"b": "]"}], "b": {"c": 2, "b": [true, null]}, "d": "x"}"#;
+
+#[test_case("$..b" => 3; "descendants")]
+#[test_case("$.*" => 3; "wildcard")]
+#[test_case("$.b.b[1]" => 1; "index")]
+#[test_case("$" => 1; "root")]
+#[test_case("$.e" => 0; "no matches")]
+fn count(query: &str) -> usize {
+    rsonpath_lib::count(query, JSON).unwrap()
+}
+
+#[test_case("$..b"; "descendants")]
+#[test_case("$.*"; "wildcard")]
+#[test_case("$.b.*"; "nested wildcard")]
+#[test_case("$.e"; "no matches")]
+fn indices_agree_with_engine(query_string: &str) {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let mut contents = JSON.to_owned();
+    let input = Input::new(&mut contents);
+    let expected: Vec<usize> = RsonpathEngine::compile_query(&query)
+        .unwrap()
+        .run::<IndexResult>(&input)
+        .unwrap()
+        .into();
+
+    assert_eq!(rsonpath_lib::indices(query_string, JSON).unwrap(), expected);
+}
+
+#[test_case("$..b" => vec![r#""]""#, r#"{"c": 2, "b": [true, null]}"#, "[true, null]"]; "descendants")]
+#[test_case("$.b.b[*]" => vec!["true", "null"]; "elements")]
+#[test_case("$.d" => vec![r#""x""#]; "string")]
+#[test_case("$.e" => Vec::<&str>::new(); "no matches")]
+fn values(query: &str) -> Vec<&'static str> {
+    rsonpath_lib::values(query, JSON).unwrap()
+}
+
+#[test_case("$..b"; "descendants")]
+#[test_case("$..[:]"; "all elements")]
+#[test_case("$.*"; "wildcard")]
+fn values_agree_with_spans(query_string: &str) {
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let mut contents = JSON.to_owned();
+    let input = Input::new(&mut contents);
+    let spans = RsonpathEngine::compile_query(&query)
+        .unwrap()
+        .run::<SpanResult>(&input)
+        .unwrap();
+    let expected: Vec<_> = spans.get().iter().map(|span| &JSON[span.clone()]).collect();
+
+    assert_eq!(rsonpath_lib::values(query_string, JSON).unwrap(), expected);
+}
+
+#[test]
+fn repeated_calls_give_the_same_results() {
+    let first = rsonpath_lib::values("$..b", JSON).unwrap();
+
+    for _ in 0..100 {
+        assert_eq!(rsonpath_lib::values("$..b", JSON).unwrap(), first);
+    }
+}
+
+#[test]
+fn invalid_query_is_a_parser_error() {
+    let error = rsonpath_lib::count("$..", JSON).unwrap_err();
+    let expected = JsonPathQuery::parse("$..").unwrap_err();
+
+    assert!(matches!(error, QueryError::Parser(_)));
+    assert_eq!(error.to_string(), expected.to_string());
+}
+
+#[test]
+fn unsupported_query_is_a_compiler_error() {
+    let error = rsonpath_lib::count("$.a[last()].w", JSON).unwrap_err();
+
+    assert!(matches!(
+        error,
+        QueryError::Compiler(CompilerError::NotSupported(_))
+    ));
+}
+
+#[test]
+fn malformed_document_is_an_engine_error() {
+    let error = rsonpath_lib::values("$.a", r#"{"a": [1, 2"#).unwrap_err();
+
+    assert!(matches!(
+        error,
+        QueryError::Engine(EngineError::MissingClosingCharacter())
+    ));
+    assert_eq!(
+        error.to_string(),
+        EngineError::MissingClosingCharacter().to_string()
+    );
+    assert!(error.source().is_none());
+}
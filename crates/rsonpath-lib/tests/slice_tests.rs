@@ -0,0 +1,89 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, QueryResult, SpanResult};
+use test_case::test_case;
+
+fn run<R: QueryResult, E: Compiler>(json: &str, query_string: &str) -> R {
+    let mut raw = json.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let engine = E::compile_query(&query).unwrap();
+
+    engine.run::<R>(&contents).unwrap()
+}
+
+/// Run the query and return the matched values joined with spaces.
+fn values<E: Compiler>(json: &str, query_string: &str) -> String {
+    let result = run::<SpanResult, E>(json, query_string);
+
+    result
+        .get()
+        .iter()
+        .map(|span| &json[span.clone()])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+macro_rules! value_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("[0, 1, 2, 3, 4]", "$[::2]" => "0 2 4"; "every second element")]
+        #[test_case("[0, 1, 2, 3, 4, 5]", "$[::2]" => "0 2 4"; "even length")]
+        #[test_case("[0, 1, 2, 3, 4, 5]", "$[1::2]" => "1 3 5"; "offset")]
+        #[test_case("[0, 1, 2, 3, 4, 5, 6]", "$[1:5]" => "1 2 3 4"; "range")]
+        #[test_case("[0, 1, 2, 3, 4, 5, 6]", "$[1::3]" => "1 4"; "stride and offset")]
+        #[test_case("[0, 1, 2, 3, 4, 5, 6]", "$[2:6:3]" => "2 5"; "stride within range")]
+        #[test_case("[0, 1]", "$[5:]" => ""; "start past the end")]
+        #[test_case("[0, 1, 2]", "$[2:2]" => ""; "empty range")]
+        #[test_case("[]", "$[:]" => ""; "empty array")]
+        #[test_case("[7]", "$[::2]" => "7"; "only element")]
+        #[test_case("{\"0\": 1, \"1\": 2}", "$[:]" => ""; "object keys are not indices")]
+        #[test_case("[[0, 1, 2], [3, 4, 5]]", "$[1][::2]" => "3 5"; "slice after index")]
+        #[test_case("[[0, 1], [2, 3], [4, 5]]", "$[::2][1]" => "1 5"; "index after slice")]
+        #[test_case("[[0, 1], [2, 3], [4, 5]]", "$[1:][:1]" => "2 4"; "chained slices")]
+        #[test_case("[{\"a\": 1}, {\"a\": 2}, {\"a\": 3}]", "$[::2].a" => "1 3"; "objects in array")]
+        #[test_case("[0, 1, 2, 3]", "$[:2][1]" => ""; "no index within atomic elements")]
+        #[test_case("[0, 1, 2, 3]", "$[1]" => "1"; "plain index still works")]
+        fn $test_name(json: &str, query_string: &str) -> String {
+            values::<$impl>(json, query_string)
+        }
+    };
+}
+
+macro_rules! descendant_value_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("[0, 1, 2, 3, 4]", "$..[::2]" => "0 2 4"; "flat array")]
+        #[test_case("[[0, 1, 2], 3, [4, 5]]", "$..[::2]" => "[0, 1, 2] 0 2 [4, 5] 4"; "nested arrays count from zero")]
+        #[test_case("[1, [10, 11, 12], 2, 3]", "$..[::2]" => "1 10 12 2"; "nested array does not shift outer indices")]
+        #[test_case("[[1, 2], 3, 4]", "$..[::2]" => "[1, 2] 1 4"; "documented example")]
+        #[test_case("[[[0, 1, 2]]]", "$..[1:]" => "1 2"; "deeply nested")]
+        #[test_case("{\"a\": [0, 1, 2], \"b\": {\"c\": [3, 4, 5, 6]}}", "$..[1::2]" => "1 4 6"; "arrays within objects")]
+        #[test_case("{\"a\": [], \"b\": [0]}", "$..[::2]" => "0"; "empty and single element arrays")]
+        #[test_case("{\"a\": [{\"b\": [0, 1, 2]}, {\"b\": [3, 4]}]}", "$.a..[1:]" => "1 2 {\"b\": [3, 4]} 4"; "descendant slice after label")]
+        #[test_case("[[0, 1], [2, 3], [4, 5]]", "$..[::2][1]" => "1 5"; "index after descendant slice")]
+        fn $test_name(json: &str, query_string: &str) -> String {
+            values::<$impl>(json, query_string)
+        }
+    };
+}
+
+macro_rules! count_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("[0, 1, 2, 3, 4]", "$[::2]" => 3; "odd length")]
+        #[test_case("[0, 1, 2, 3]", "$[::2]" => 2; "even length")]
+        #[test_case("[[], [[]], [[], []]]", "$..[::2]" => 4; "empty arrays")]
+        #[test_case("[0, 1, 2, 3]", "$[1:3]" => 2; "range")]
+        fn $test_name(json: &str, query_string: &str) -> usize {
+            run::<CountResult, $impl>(json, query_string).get()
+        }
+    };
+}
+
+value_test_cases!(main_value_test, MainEngine);
+value_test_cases!(recursive_value_test, RecursiveEngine);
+descendant_value_test_cases!(main_descendant_value_test, MainEngine);
+descendant_value_test_cases!(recursive_descendant_value_test, RecursiveEngine);
+count_test_cases!(main_count_test, MainEngine);
+count_test_cases!(recursive_count_test, RecursiveEngine);
@@ -0,0 +1,69 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, IndexResult, Tagged};
+use test_case::test_case;
+
+macro_rules! tagged_test_cases {
+    ($test_name:ident, $generic_tag_test_name:ident, $impl:ident) => {
+        #[test_case(r#"{"a": [1, 2]}"#, "$.a.*" => (3, Some(2)); "matches")]
+        #[test_case(r#"{"a": [1, 2]}"#, "$.b" => (3, Some(0)); "no matches")]
+        #[test_case("{1: 2}", "$.a" => (3, None); "error")]
+        fn $test_name(json: &str, query_string: &str) -> (u64, Option<usize>) {
+            let mut raw = json.to_owned();
+            let input = Input::new(&mut raw);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let (tag, result) = engine.run_tagged::<CountResult, _>(&input, 3).into_parts();
+
+            (tag, result.ok().map(|count| count.get()))
+        }
+
+        #[test]
+        fn $generic_tag_test_name() {
+            let mut raw = r#"{"a": 1}"#.to_owned();
+            let input = Input::new(&mut raw);
+            let query = JsonPathQuery::parse("$.a").unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let tagged = engine.run_tagged::<IndexResult, _>(&input, ("doc-1", 1_700_000_000_u64));
+
+            assert_eq!(tagged.tag(), &("doc-1", 1_700_000_000));
+            assert_eq!(tagged.value().as_ref().unwrap().get(), &[4]);
+        }
+    };
+}
+
+tagged_test_cases!(main_tagged_test, main_tagged_generic_tag_test, MainEngine);
+tagged_test_cases!(
+    recursive_tagged_test,
+    recursive_tagged_generic_tag_test,
+    RecursiveEngine
+);
+
+#[test]
+fn tagged_runs_can_be_reordered() {
+    let query = JsonPathQuery::parse("$..x").unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+    let documents = [r#"{"x": 1}"#, r#"[{"x": 1}, {"x": 2}]"#, "[]"];
+
+    let mut outcomes: Vec<Tagged<usize, usize>> = documents
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, json)| {
+            let mut raw = (*json).to_owned();
+            let input = Input::new(&mut raw);
+            let (tag, result) = engine.run_tagged::<CountResult, _>(&input, i).into_parts();
+            Tagged::new(tag, result.unwrap().get())
+        })
+        .collect();
+    outcomes.sort_by_key(|outcome| *outcome.tag());
+
+    assert_eq!(
+        outcomes,
+        vec![Tagged::new(0, 1), Tagged::new(1, 2), Tagged::new(2, 0)]
+    );
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "timing")]
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::timing::RunStats;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::CountResult;
+use test_case::test_case;
+
+const DOCUMENT: &str = r#"{"a": {"b": 42, "c": [1, {"b": 2}]}, "b": {"x": [3, 4]}}"#;
+
+fn run_timed(query_string: &str, stats: &mut RunStats) -> usize {
+    let mut raw = DOCUMENT.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+
+    engine
+        .run_timed::<CountResult>(&contents, stats)
+        .unwrap()
+        .get()
+}
+
+#[test_case("$..b"; "descendant")]
+#[test_case("$.a.b"; "child")]
+#[test_case("$.*.*"; "wildcard")]
+#[test_case("$.x"; "no matches")]
+fn timed_run_matches_untimed_run(query_string: &str) {
+    let mut raw = DOCUMENT.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let expected = MainEngine::compile_query(&query)
+        .unwrap()
+        .run::<CountResult>(&contents)
+        .unwrap()
+        .get();
+    let mut stats = RunStats::new();
+
+    let count = run_timed(query_string, &mut stats);
+
+    assert_eq!(count, expected);
+}
+
+#[test]
+fn classification_time_is_recorded() {
+    let mut stats = RunStats::new();
+
+    run_timed("$..b", &mut stats);
+
+    assert!(stats.classification_nanos() > 0);
+    assert_eq!(
+        stats.total_nanos(),
+        stats.classification_nanos() + stats.handling_nanos()
+    );
+}
+
+#[test]
+fn times_accumulate_over_runs() {
+    let mut stats = RunStats::new();
+    run_timed("$..b", &mut stats);
+    let first = stats;
+
+    run_timed("$..b", &mut stats);
+
+    assert!(stats.classification_nanos() > first.classification_nanos());
+    assert!(stats.handling_nanos() >= first.handling_nanos());
+}
+
+#[test]
+fn reset_clears_times() {
+    let mut stats = RunStats::new();
+    run_timed("$..b", &mut stats);
+
+    stats.reset();
+
+    assert_eq!(stats, RunStats::new());
+}
+
+#[test]
+fn empty_query_is_not_timed() {
+    let mut stats = RunStats::new();
+
+    run_timed("$", &mut stats);
+
+    assert_eq!(stats.total_nanos(), 0);
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "trace")]
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::trace::{Trace, TraceAction};
+use rsonpath_lib::engine::{Compiler, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::IndexResult;
+
+fn run_traced(json: &str, query_string: &str) -> (Option<IndexResult>, Trace) {
+    let mut raw = json.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+    let engine = MainEngine::compile_query(&query).unwrap();
+    let mut trace = Trace::new();
+    let result = engine.run_traced(&contents, &mut trace).ok();
+
+    (result, trace)
+}
+
+fn reports(trace: &Trace) -> Vec<usize> {
+    trace
+        .entries()
+        .iter()
+        .filter_map(|entry| match entry.action {
+            TraceAction::Report(idx) => Some(idx),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn reports_in_trace_match_the_result() {
+    let (result, trace) = run_traced(r#"{"a": {"b": 42}, "c": [{"b": 1}, 2]}"#, "$..b");
+
+    assert_eq!(reports(&trace), result.unwrap().get());
+}
+
+#[test]
+fn every_event_is_traced() {
+    let (_, trace) = run_traced(r#"{"a": [1, {"b": 2}]}"#, "$..x");
+    let events: Vec<_> = trace
+        .entries()
+        .iter()
+        .map(|entry| entry.event.idx())
+        .collect();
+
+    assert_eq!(events, vec![0, 4, 6, 10, 14, 17, 18, 19]);
+}
+
+#[test]
+fn transitions_and_returns_are_traced() {
+    let (_, trace) = run_traced(r#"{"a": {"b": 42}}"#, "$.a.b");
+    let descriptions: Vec<_> = trace
+        .entries()
+        .iter()
+        .take(5)
+        .map(|entry| entry.to_string())
+        .collect();
+
+    assert_eq!(
+        descriptions,
+        vec![
+            "Opening(Curly, 0) depth=0 state=DFA(1): none",
+            "Opening(Curly, 6) depth=1 state=DFA(1): goto DFA(2)",
+            "Colon(10) depth=2 state=DFA(2): report 10",
+            "Closing(Curly, 14) depth=2 state=DFA(2): return to DFA(1)",
+            "Closing(Curly, 14) depth=2 state=DFA(2): skip",
+        ]
+    );
+}
+
+#[test]
+fn root_report_is_traced() {
+    let (_, trace) = run_traced(r#"  {"a": 1}"#, "$");
+
+    assert_eq!(reports(&trace), vec![2]);
+}
+
+#[test]
+fn trace_is_kept_on_error() {
+    let (result, trace) = run_traced(r#"{"a": {"b": 42}"#, "$..b");
+
+    assert!(result.is_none());
+    assert_eq!(reports(&trace), vec![10]);
+}
+
+#[test]
+fn element_matched_by_filter_is_skipped() {
+    let json = r#"{"a": [{"id": 1, "b": {"c": [2]}}, {"id": 2}]}"#;
+    let (result, trace) = run_traced(json, "$.a[?(@.id == 1)]");
+    let events: Vec<_> = trace
+        .entries()
+        .iter()
+        .map(|entry| entry.event.idx())
+        .collect();
+
+    assert_eq!(result.unwrap().get(), &[7]);
+    assert!(trace
+        .entries()
+        .iter()
+        .any(|entry| entry.event.idx() == 7 && entry.action == TraceAction::Skip));
+    assert!(!events.contains(&json.find("{\"c\"").unwrap()));
+}
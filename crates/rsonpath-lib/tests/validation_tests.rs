@@ -0,0 +1,48 @@
+use rsonpath_lib::engine::Input;
+use rsonpath_lib::validation::{quick_check, StructuralError};
+use test_case::test_case;
+
+fn check(json: &str) -> Result<(), StructuralError> {
+    let mut contents = json.to_owned();
+    let input = Input::new(&mut contents);
+
+    quick_check(&input)
+}
+
+#[test_case(r#"{"a": [1, {"b": null}], "c": "x"}"#; "object")]
+#[test_case("[]"; "empty array")]
+#[test_case("42"; "atomic root")]
+#[test_case(r#""[{""#; "string root")]
+#[test_case(r#"{"a": "}]", "b\"": "\\"}"#; "brackets and escapes in strings")]
+#[test_case(""; "empty input")]
+fn well_formed(json: &str) {
+    assert_eq!(check(json), Ok(()));
+}
+
+#[test_case(r#"{"a": 1}}"# => Err(StructuralError::UnmatchedClosing(8)); "trailing closing")]
+#[test_case(r#"]{"a": 1}"# => Err(StructuralError::UnmatchedClosing(0)); "leading closing")]
+#[test_case(r#"[1, 2]] [3"# => Err(StructuralError::UnmatchedClosing(6)); "first anomaly reported")]
+#[test_case(r#"{"a": [1, 2], "b": {"c": 3"# => Err(StructuralError::UnclosedOpening(0)); "truncated document")]
+#[test_case(r#"[] [{"a": []"# => Err(StructuralError::UnclosedOpening(3)); "outermost unclosed opening")]
+#[test_case(r#"{"a": "xyz"# => Err(StructuralError::UnterminatedString(6)); "truncated string")]
+#[test_case(r#"{"a": "x\""# => Err(StructuralError::UnterminatedString(6)); "escaped closing quote")]
+#[test_case(r#"{"a": "}"# => Err(StructuralError::UnterminatedString(6)); "closing within unterminated string")]
+fn malformed(json: &str) -> Result<(), StructuralError> {
+    check(json)
+}
+
+#[test]
+fn anomaly_past_first_blocks() {
+    let elements = vec![r#"{"k": "[v]"}"#; 50].join(", ");
+    let json = format!("[{elements}]]");
+
+    assert_eq!(
+        check(&format!("[{elements}]")),
+        Ok(()),
+        "well-formed long document"
+    );
+    assert_eq!(
+        check(&json),
+        Err(StructuralError::UnmatchedClosing(json.len() - 1))
+    );
+}
@@ -0,0 +1,107 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::{CountResult, SpanResult};
+use test_case::test_case;
+
+const NESTED_DOCUMENT: &str = r#"{
+    "a": {"x": {"id": 1}, "y": {"id": 2, "n": {"id": 6}}},
+    "b": [{"id": 3}, [{"id": 7}], {"c": {"id": 8}}],
+    "c": [[{"id": 9}], {"id": 4}, {"id": 5}],
+    "id": 0
+}"#;
+
+/// Object with `fanout` arrays of `fanout` objects each, all of them with an `id`
+/// and a nested object with another `id`.
+fn uniform_document(fanout: usize) -> String {
+    let leaf = r#"{"id": 1, "nested": {"id": 2}}"#;
+    let level = |inner: &str, is_list: bool| {
+        let members: Vec<String> = (0..fanout)
+            .map(|i| {
+                if is_list {
+                    inner.to_owned()
+                } else {
+                    format!(r#""k{i}": {inner}"#)
+                }
+            })
+            .collect();
+        if is_list {
+            format!("[{}]", members.join(", "))
+        } else {
+            format!("{{{}}}", members.join(", "))
+        }
+    };
+
+    level(&level(leaf, true), false)
+}
+
+macro_rules! wildcard_test_cases {
+    ($count_test_name:ident, $values_test_name:ident, $uniform_test_name:ident, $impl:ident) => {
+        #[test_case("$.*.*.id" => 5; "two wildcards")]
+        #[test_case("$[*][*].id" => 5; "two array wildcards")]
+        #[test_case("$.*[*].id" => 5; "mixed wildcards")]
+        #[test_case("$.*.id" => 0; "one wildcard")]
+        #[test_case("$.*.*.*.id" => 4; "three wildcards")]
+        #[test_case("$.*.*.*.*.id" => 0; "four wildcards")]
+        #[test_case("$.*.*" => 8; "trailing wildcards")]
+        fn $count_test_name(query_string: &str) -> usize {
+            let mut contents = NESTED_DOCUMENT.to_owned();
+            let input = Input::new(&mut contents);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            engine.run::<CountResult>(&input).unwrap().get()
+        }
+
+        #[test_case("$.*.*.id" => vec!["1", "2", "3", "4", "5"]; "two wildcards")]
+        #[test_case("$.*.*.*.id" => vec!["6", "7", "8", "9"]; "three wildcards")]
+        #[test_case("$.b.*.*.id" => vec!["7", "8"]; "wildcards after label")]
+        #[test_case("$.*.*.c.id" => vec!["8"]; "label after wildcards")]
+        fn $values_test_name(query_string: &str) -> Vec<&'static str> {
+            let mut contents = NESTED_DOCUMENT.to_owned();
+            let input = Input::new(&mut contents);
+            let query = JsonPathQuery::parse(query_string).unwrap();
+            let engine = $impl::compile_query(&query).unwrap();
+
+            let result = engine.run::<SpanResult>(&input).unwrap();
+
+            result
+                .get()
+                .iter()
+                .map(|span| NESTED_DOCUMENT[span.clone()].trim())
+                .collect()
+        }
+
+        #[test_case(1; "fanout 1")]
+        #[test_case(3; "fanout 3")]
+        #[test_case(10; "fanout 10")]
+        fn $uniform_test_name(fanout: usize) {
+            let mut contents = uniform_document(fanout);
+            let input = Input::new(&mut contents);
+            let count = |query_string: &str| {
+                let query = JsonPathQuery::parse(query_string).unwrap();
+                let engine = $impl::compile_query(&query).unwrap();
+                engine.run::<CountResult>(&input).unwrap().get()
+            };
+
+            assert_eq!(count("$.*.*.id"), fanout * fanout);
+            assert_eq!(count("$.*.*.*.id"), fanout * fanout);
+            assert_eq!(count("$.*.*.*"), 2 * fanout * fanout);
+            assert_eq!(count("$.*.*.*.*"), fanout * fanout);
+        }
+    };
+}
+
+wildcard_test_cases!(
+    main_wildcard_count_test,
+    main_wildcard_values_test,
+    main_wildcard_uniform_test,
+    MainEngine
+);
+wildcard_test_cases!(
+    recursive_wildcard_count_test,
+    recursive_wildcard_values_test,
+    recursive_wildcard_uniform_test,
+    RecursiveEngine
+);
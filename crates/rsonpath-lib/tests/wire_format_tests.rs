@@ -0,0 +1,80 @@
+use rsonpath_lib::engine::main::MainEngine;
+use rsonpath_lib::engine::recursive::RecursiveEngine;
+use rsonpath_lib::engine::{Compiler, Engine, Input};
+use rsonpath_lib::query::JsonPathQuery;
+use rsonpath_lib::result::wire::{WireFormatError, FORMAT_VERSION};
+use rsonpath_lib::result::{IndexResult, QueryResult, SpanResult};
+use test_case::test_case;
+
+const DOCUMENT: &str = r#"{"a": 1, "b": {"a": [2, "xyz"]}, "c": [{"a": null}]}"#;
+
+fn run<R: QueryResult, E: Compiler>(json: &str, query_string: &str) -> R {
+    let mut raw = json.to_owned();
+    let contents = Input::new(&mut raw);
+    let query = JsonPathQuery::parse(query_string).unwrap();
+
+    let engine = E::compile_query(&query).unwrap();
+
+    engine.run::<R>(&contents).unwrap()
+}
+
+macro_rules! round_trip_test_cases {
+    ($test_name:ident, $impl:ident) => {
+        #[test_case("$..a"; "descendants")]
+        #[test_case("$.b.a.*"; "elements")]
+        #[test_case("$.x"; "no matches")]
+        fn $test_name(query_string: &str) {
+            let indices = run::<IndexResult, $impl>(DOCUMENT, query_string);
+            let spans = run::<SpanResult, $impl>(DOCUMENT, query_string);
+
+            assert_eq!(IndexResult::from_bytes(&indices.to_bytes()), Ok(indices));
+            assert_eq!(SpanResult::from_bytes(&spans.to_bytes()), Ok(spans));
+        }
+    };
+}
+
+round_trip_test_cases!(main_round_trip_test, MainEngine);
+round_trip_test_cases!(recursive_round_trip_test, RecursiveEngine);
+
+#[test]
+fn large_indices_round_trip() {
+    let json = format!(r#"{{"pad": "{}", "a": [1, 2]}}"#, " ".repeat(100_000));
+    let result = run::<SpanResult, MainEngine>(&json, "$.a.*");
+
+    assert_eq!(SpanResult::from_bytes(&result.to_bytes()), Ok(result));
+}
+
+#[test]
+fn encoding_is_fixed() {
+    let result = run::<SpanResult, MainEngine>(DOCUMENT, "$.b.a");
+    let mut expected = b"RSPR".to_vec();
+    expected.extend([FORMAT_VERSION, 1, 1, 20, 10]);
+
+    assert_eq!(result.to_bytes(), expected);
+}
+
+#[test_case(b"" => WireFormatError::NotAResult; "empty")]
+#[test_case(b"RSP" => WireFormatError::NotAResult; "partial magic")]
+#[test_case(b"XSPR\x01\x00\x00" => WireFormatError::NotAResult; "wrong magic")]
+#[test_case(b"RSPR\x01" => WireFormatError::NotAResult; "missing kind")]
+#[test_case(b"RSPR\x02\x00\x00" => WireFormatError::UnsupportedVersion { found: 2, supported: FORMAT_VERSION }; "future version")]
+#[test_case(b"RSPR\x00\x00\x00" => WireFormatError::UnsupportedVersion { found: 0, supported: FORMAT_VERSION }; "past version")]
+#[test_case(b"RSPR\x01\x01\x00" => WireFormatError::WrongKind; "spans")]
+#[test_case(b"RSPR\x01\x00" => WireFormatError::Malformed; "missing length")]
+#[test_case(b"RSPR\x01\x00\x02\x05" => WireFormatError::Malformed; "truncated")]
+#[test_case(b"RSPR\x01\x00\x01\x85" => WireFormatError::Malformed; "truncated number")]
+#[test_case(b"RSPR\x01\x00\x01\x05\x06" => WireFormatError::Malformed; "trailing bytes")]
+#[test_case(b"RSPR\x01\x00\x01\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x7F" => WireFormatError::Malformed; "overflow")]
+fn invalid_indices(bytes: &[u8]) -> WireFormatError {
+    IndexResult::from_bytes(bytes).unwrap_err()
+}
+
+#[test]
+fn version_mismatch_has_clear_message() {
+    let error = SpanResult::from_bytes(b"RSPR\x07\x01\x00").unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        format!("the result was encoded with format version 7, but only version {FORMAT_VERSION} is supported")
+    );
+}
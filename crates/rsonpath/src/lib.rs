@@ -19,6 +19,7 @@ pub fn report_parser_error(query_string: &str, error: ParserError) -> eyre::Repo
     match error {
         ParserError::SyntaxError { report } => report_query_syntax_error(query_string, report),
         ParserError::InternalNomError { .. } => eyre::Report::new(error),
+        _ => eyre::Report::new(error),
     }
 }
 
@@ -40,6 +41,7 @@ pub fn report_compiler_error(query: &JsonPathQuery, error: CompilerError) -> eyr
             }
             add_unsupported_context(report, UnsupportedFeatureError::large_automaton_queries())
         }
+        _ => eyre::Report::new(error),
     }
 }
 
@@ -10,7 +10,7 @@ use rsonpath_lib::engine::recursive::RecursiveEngine;
 use rsonpath_lib::engine::{Compiler, Engine, Input};
 use rsonpath_lib::query::automaton::Automaton;
 use rsonpath_lib::query::JsonPathQuery;
-use rsonpath_lib::result::{CountResult, IndexResult, QueryResult};
+use rsonpath_lib::result::{CountResult, IndexResult, KeysResult, QueryResult, TokenIndexResult};
 use simple_logger::SimpleLogger;
 
 #[derive(Parser, Debug)]
@@ -30,6 +30,8 @@ struct Args {
     engine: EngineArg,
     /// Only compile the query and output the automaton, do not run the engine.
     ///
+    /// The automaton is preceded by a comment with its estimated selectivity.
+    ///
     /// Cannot be used with --engine or FILE_PATH.
     #[clap(short, long)]
     #[arg(conflicts_with = "engine")]
@@ -58,6 +60,10 @@ enum ResultArg {
     Bytes,
     /// Return only the number of matches.
     Count,
+    /// Return a list of token indices of all matches, independent of whitespace.
+    Tokens,
+    /// Return a list of the unescaped keys of all matches, if they are object members.
+    Keys,
 }
 
 fn main() -> Result<()> {
@@ -84,6 +90,8 @@ fn run_with_args(args: &Args) -> Result<()> {
         match args.result {
             ResultArg::Bytes => run::<IndexResult>(&query, &input, args.engine),
             ResultArg::Count => run::<CountResult>(&query, &input, args.engine),
+            ResultArg::Tokens => run::<TokenIndexResult>(&query, &input, args.engine),
+            ResultArg::Keys => run::<KeysResult>(&query, &input, args.engine),
         }
     }
 }
@@ -92,6 +100,7 @@ fn compile(query: &JsonPathQuery) -> Result<()> {
     let automaton = Automaton::new(query)
         .map_err(|err| report_compiler_error(query, err).wrap_err("Error compiling the query."))?;
     info!("Automaton: {automaton}");
+    info!("Selectivity: {}", automaton.selectivity());
     println!("{automaton}");
     Ok(())
 }